@@ -0,0 +1,61 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Error, Expr, ExprLit, ExprUnary, Lit, Result, UnOp};
+use variant::{self, Direction};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => return Err(Error::new_spanned(name, "only enums without fields can derive `Export`")),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "enums deriving `Export` can't be generic"));
+    }
+    if let Some(variant) = data.variants.iter().find(|variant| !variant.fields.is_empty()) {
+        return Err(Error::new_spanned(variant, "only enums without fields can derive `Export`"));
+    }
+
+    // The editor maps the entries of the hint to their index, unless they are
+    // written `Name:value`.
+    let mut values = Vec::new();
+    let mut next = 0;
+    for variant in data.variants.iter() {
+        let value = match variant.discriminant {
+            Some((_, ref expr)) => discriminant(expr)?,
+            None => next,
+        };
+        let entry = if value == values.len() as i64 {
+            variant.ident.to_string()
+        } else {
+            format!("{}:{}", variant.ident, value)
+        };
+        values.push(entry);
+        next = value + 1;
+    }
+
+    let to_variant = variant::derive(input.clone(), Direction::To)?;
+    let from_variant = variant::derive(input.clone(), Direction::From)?;
+
+    Ok(quote! {
+        #to_variant
+        #from_variant
+
+        godot_derive_variant_impl! {
+            impl __gdnative::init::Export for #name {
+                fn export_hint() -> __gdnative::init::PropertyHint<'static> {
+                    __gdnative::init::PropertyHint::Enum { values: &[#(#values),*] }
+                }
+            }
+        }
+    })
+}
+
+// The hint is built at compile time, so the discriminants must be literals.
+fn discriminant(expr: &Expr) -> Result<i64> {
+    match *expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(ref int), .. }) => int.base10_parse(),
+        Expr::Unary(ExprUnary { op: UnOp::Neg(_), ref expr, .. }) => discriminant(expr).map(|value| -value),
+        _ => Err(Error::new_spanned(expr, "the discriminants of enums deriving `Export` must be integer literals")),
+    }
+}
@@ -0,0 +1,267 @@
+//! Procedural macros to declare native classes, re-exported by the `gdnative` crate.
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate gdnative;
+//!
+//! use gdnative::*;
+//!
+//! #[derive(NativeClass)]
+//! #[inherit(Node)]
+//! struct Player {
+//!     header: NativeInstanceHeader,
+//!
+//!     #[property(default = 10.0, hint = "PropertyHint::Range { range: 0.0..100.0, step: 1.0, slider: true }")]
+//!     speed: f64,
+//! }
+//!
+//! #[methods]
+//! impl Player {
+//!     fn _init(header: NativeInstanceHeader) -> Self {
+//!         Player { header, speed: 10.0 }
+//!     }
+//!
+//!     #[export]
+//!     fn _ready(&mut self) {
+//!         godot_print!("ready");
+//!     }
+//! }
+//!
+//! fn init(handle: init::InitHandle) {
+//!     handle.add_native_class::<Player>();
+//! }
+//! ```
+//!
+//! The generated code invokes the `gdnative` macros, so the crate must be imported
+//! with `#[macro_use]`.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+mod export;
+mod methods;
+mod native_class;
+mod variant;
+
+use proc_macro::TokenStream;
+use syn::{Attribute, DeriveInput, Error, Expr, ExprLit, ItemImpl, Lit, Meta};
+
+/// Implements `NativeClass` and `init::NativeClassInit` for a struct.
+///
+/// The struct must have a field of type `NativeInstanceHeader` and an
+/// `#[inherit(BaseClass)]` attribute. Instances are created with an associated
+/// function `fn _init(header: NativeInstanceHeader) -> Self`.
+///
+/// The instances are stored in a `LocalCellData`, another wrapper can be selected
+/// with an attribute like `#[user_data(MutexData<Player>)]`, see the `user_data`
+/// module of `gdnative`.
+///
+/// Fields marked with `#[property]` are registered as properties. The attribute
+/// accepts the following options:
+///
+/// - `path = "group/name"`: the name of the property, defaults to the name of the field,
+/// - `default = expr`: the default value, defaults to `Default::default()`,
+/// - `hint = expr`: a `PropertyHint`, also accepted as a string containing the expression,
+/// - `usage = expr`: the `PropertyUsage`, defaults to `PropertyUsage::DEFAULT`.
+///
+/// The type of the field must implement `GodotType`, and `Default` if no default value
+/// is given.
+///
+/// With `#[inspector_category("AI/Steering")]` on the struct, the properties are shown
+/// under that category in the inspector. A bare `#[inspector_category]` names the
+/// category after the module declaring the struct, for example `Ai/Steering` for a
+/// struct in `my_game::ai::steering`.
+///
+/// A field marked `#[base]` stores the state of an abstract class implementing
+/// `init::BaseClass`: the class extends it instead of the `#[inherit]` class, and
+/// registers its members before its own.
+///
+/// The exported methods returning an `Err` return it as a dictionary, with
+/// `#[method_errors(Log)]` they print it and return nil instead, see `MethodErrors`.
+///
+/// The doc comments of the struct and of its properties are registered as their
+/// documentation, see `ClassBuilder::set_class_documentation`.
+#[proc_macro_derive(NativeClass, attributes(inherit, base, property, user_data, method_errors, inspector_category))]
+pub fn derive_native_class(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match native_class::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
+
+/// Implements `init::NativeClassMethods` for the type of an impl block, registering
+/// the methods marked with `#[export]`.
+///
+/// Exported methods must take `&mut self` or `&self`, their arguments must implement
+/// `GodotType` and their return value `ReturnValue`. Their doc comments are
+/// registered as their documentation.
+///
+/// A method is exported under its rust name, or under the name given with
+/// `#[export(name = "...")]`, for example to keep the snake_case name of a
+/// GDScript API implemented by a method named after a rust convention. With the
+/// `check_names` feature of `gdnative`, the names not written in snake_case are
+/// reported with `godot_warn!` when the class is registered.
+///
+/// The last arguments can be marked `#[opt]`, the callers can then omit them and
+/// they take their `Default::default()`, or the value given with
+/// `#[opt(default = expr)]`, which is evaluated without the instance:
+///
+/// ```ignore
+/// #[export]
+/// fn jump(&mut self, height: f64, #[opt(default = 1.0)] speed: f64, #[opt] silent: bool) {
+/// }
+/// ```
+///
+/// The last arguments of type `Option<T>` are optional without `#[opt]`, the
+/// callers can omit them like passing `null`. An `Option` followed by a required
+/// argument is an error.
+///
+/// The last argument can be marked `#[rest]` to receive the arguments after the
+/// others, as a `&[Variant]` or a `Vec<Variant>`, see `Varargs`:
+///
+/// ```ignore
+/// #[export]
+/// fn log(&self, format: String, #[rest] values: &[Variant]) {
+/// }
+/// ```
+///
+/// The names of the arguments and the default values are described to the editor,
+/// see `ClassBuilder::set_method_arguments`.
+///
+/// Methods taking `&self` only borrow the instance immutably, they can be called back
+/// by the engine while another `&self` method of the instance is running.
+#[proc_macro_attribute]
+pub fn methods(_meta: TokenStream, input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ItemImpl);
+    match methods::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
+
+/// Implements `ToVariant` for a struct or an enum, see `derive_from_variant` for
+/// the conversion.
+#[proc_macro_derive(ToVariant)]
+pub fn derive_to_variant(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match variant::derive(input, variant::Direction::To) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
+
+/// Implements `FromVariant` for a struct or an enum. Types deriving both
+/// `ToVariant` and `FromVariant` implement `GodotType`, so they can be used as
+/// method arguments and properties:
+///
+/// ```ignore
+/// #[derive(ToVariant, FromVariant)]
+/// struct Hit {
+///     damage: i64,
+///     position: Vector2,
+///     critical: Option<bool>,
+/// }
+///
+/// #[derive(ToVariant, FromVariant)]
+/// enum Command {
+///     Move(Vector2),
+///     Attack { target: NodePath },
+///     Stop,
+/// }
+/// ```
+///
+/// - Structs with named fields become a dictionary mapping the field names to their
+///   values. Missing fields are converted from nil, so `Option` fields can be left
+///   out.
+/// - Structs with a single unnamed field become the value of the field, the other
+///   tuple structs an array of their fields and unit structs nil.
+/// - Enums without fields become their discriminant, an int.
+/// - The other enums become a dictionary with the name of the variant as only key,
+///   and its fields as value following the rules of the structs, like
+///   `{"Move": Vector2(1, 0)}` or `{"Stop": null}`.
+///
+/// The fields must implement `ToVariant` and `FromVariant` respectively, as well as
+/// the type parameters.
+///
+/// `try_from_variant` reports the field that failed to convert, like
+/// `at ["Attack"].target: expected NodePath, found Nil`.
+#[proc_macro_derive(FromVariant)]
+pub fn derive_from_variant(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match variant::derive(input, variant::Direction::From) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
+
+/// Implements `ToVariant`, `FromVariant` and `init::Export` for an enum without
+/// fields, so that its properties are shown as a dropdown of its variants in the
+/// inspector:
+///
+/// ```ignore
+/// #[derive(Clone, Copy, Default, Export)]
+/// enum Team {
+///     #[default]
+///     Neutral,
+///     Red,
+///     Blue,
+/// }
+///
+/// #[derive(NativeClass)]
+/// #[inherit(Node)]
+/// struct Unit {
+///     header: NativeInstanceHeader,
+///
+///     #[property]
+///     team: Team,
+/// }
+/// ```
+///
+/// The enum is converted to its discriminant, like with `#[derive(ToVariant)]`.
+/// Explicit discriminants must be integer literals.
+#[proc_macro_derive(Export)]
+pub fn derive_export(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match export::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
+
+// Joins the `///` comments of an item, without the space following the slashes.
+fn doc_string(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs.iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match attr.meta {
+            Meta::NameValue(ref meta) => match meta.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(ref s), .. }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| if line.starts_with(' ') { line[1..].to_owned() } else { line })
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+// `Error::to_compile_error` expands to `::core::compile_error!`, which doesn't resolve
+// in crates using the 2015 edition.
+fn compile_error(err: Error) -> TokenStream {
+    err.into_iter()
+        .map(|err| {
+            let msg = err.to_string();
+            quote_spanned!(err.span()=> compile_error!(#msg);)
+        })
+        .collect::<proc_macro2::TokenStream>()
+        .into()
+}
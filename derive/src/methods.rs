@@ -0,0 +1,172 @@
+use doc_string;
+use proc_macro2::TokenStream;
+use syn::{Attribute, Error, Expr, FnArg, ImplItem, ItemImpl, LitStr, Meta, Pat, ReceiverKind, Result, ReturnType, Signature, Type};
+
+pub fn expand(mut input: ItemImpl) -> Result<TokenStream> {
+    if input.trait_.is_some() {
+        return Err(Error::new_spanned(&input.self_ty, "`#[methods]` can't be used on trait impls"));
+    }
+
+    let mut exports = Vec::new();
+    for item in input.items.iter_mut() {
+        if let ImplItem::Fn(ref mut method) = *item {
+            let export = method.attrs.iter().position(|attr| attr.path().is_ident("export"));
+            if let Some(idx) = export {
+                let export = method.attrs.remove(idx);
+                let name = exported_name(&export)?
+                    .unwrap_or_else(|| LitStr::new(&method.sig.ident.to_string(), method.sig.ident.span()));
+                let doc = doc_string(&method.attrs).map(|doc| quote!(#[doc = #doc]));
+                let sig = exported_signature(&method.sig, &name)?;
+                exports.push(quote!(#doc #sig));
+            }
+            // The `#[opt]` and `#[rest]` attributes are only read by the exports.
+            for arg in method.sig.inputs.iter_mut() {
+                if let FnArg::Typed(ref mut arg) = *arg {
+                    arg.attrs.retain(|attr| !attr.path().is_ident("opt") && !attr.path().is_ident("rest"));
+                }
+            }
+        }
+    }
+
+    let self_ty = &input.self_ty;
+    Ok(quote! {
+        #input
+
+        godot_derive_methods! {
+            #self_ty;
+            #(#exports)*
+        }
+    })
+}
+
+// The name given with `#[export(name = "...")]`, the engine calls the method with it.
+fn exported_name(attr: &Attribute) -> Result<Option<LitStr>> {
+    if let Meta::Path(_) = attr.meta {
+        return Ok(None);
+    }
+
+    let mut name = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse::<LitStr>()?);
+            Ok(())
+        } else {
+            Err(meta.error("unknown `export` option, expected `name`"))
+        }
+    })?;
+    match name {
+        Some(ref lit) if lit.value().is_empty() => Err(Error::new_spanned(lit, "the exported name can't be empty")),
+        Some(lit) => Ok(Some(lit)),
+        None => Err(Error::new_spanned(attr, "expected `#[export]` or `#[export(name = \"...\")]`")),
+    }
+}
+
+fn exported_signature(sig: &Signature, exported_name: &LitStr) -> Result<TokenStream> {
+    let name = &sig.ident;
+    let mut inputs = sig.inputs.iter();
+
+    let receiver = match inputs.next() {
+        Some(&FnArg::Receiver(ref receiver)) => match receiver.kind {
+            ReceiverKind::Reference(_, _, Some(_)) => quote!(&mut self),
+            ReceiverKind::Reference(_, _, None) => quote!(&self),
+            _ => return Err(Error::new_spanned(receiver, "exported methods must take `&mut self` or `&self`")),
+        },
+        _ => return Err(Error::new_spanned(name, "exported methods must take `&mut self` or `&self`")),
+    };
+
+    let mut args = Vec::new();
+    let mut optional = false;
+    let mut optional_option = None;
+    let mut rest = false;
+    for arg in inputs {
+        match *arg {
+            FnArg::Typed(ref arg) => match *arg.pat {
+                Pat::Ident(ref pat) => {
+                    let ident = &pat.ident;
+                    let ty = &arg.ty;
+                    if rest {
+                        return Err(Error::new_spanned(ident, "the `#[rest]` argument must be the last one"));
+                    }
+                    // Not described to the editor, which has no variadic arguments.
+                    if arg.attrs.iter().any(|attr| attr.path().is_ident("rest")) {
+                        rest = true;
+                        args.push(quote!(, #[rest] #ident : #ty));
+                        continue;
+                    }
+                    let default = match optional_default(&arg.attrs, ty)? {
+                        // The `Option`s at the end can be omitted without `#[opt]`.
+                        None if is_option(ty) => {
+                            optional_option = Some(ident);
+                            Some(parse_quote!(::std::option::Option::None))
+                        }
+                        default => default,
+                    };
+                    match default {
+                        Some(default) => {
+                            optional = true;
+                            args.push(quote!(, #[opt] #ident : #ty = #default));
+                        }
+                        None if optional_option.is_some() => {
+                            return Err(Error::new_spanned(
+                                optional_option,
+                                "`Option` arguments are optional and must come after the required ones",
+                            ));
+                        }
+                        None if optional => {
+                            return Err(Error::new_spanned(ident, "`#[opt]` arguments must come after the required ones"));
+                        }
+                        None => {
+                            args.push(quote!(, #ident : #ty));
+                        }
+                    }
+                }
+                _ => return Err(Error::new_spanned(&arg.pat, "arguments of exported methods must be identifiers")),
+            },
+            FnArg::Receiver(ref receiver) => return Err(Error::new_spanned(receiver, "unexpected receiver")),
+        }
+    }
+
+    let ret = match sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ref ty) => quote!(#ty),
+    };
+
+    Ok(quote! {
+        fn #name(#receiver #(#args)*) -> #ret;
+        name(#exported_name);
+    })
+}
+
+// Whether the type is written `Option<T>`, with or without its path.
+fn is_option(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref path) if path.qself.is_none() => path.path.segments.last()
+            .map_or(false, |segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+// The default value of an argument marked `#[opt]` or `#[opt(default = expr)]`.
+fn optional_default(attrs: &[Attribute], ty: &Type) -> Result<Option<Expr>> {
+    let attr = match attrs.iter().find(|attr| attr.path().is_ident("opt")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    if let Meta::Path(_) = attr.meta {
+        return Ok(Some(parse_quote!(<#ty as ::std::default::Default>::default())));
+    }
+
+    let mut default = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("default") {
+            default = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unknown `opt` option, expected `default`"))
+        }
+    })?;
+    match default {
+        Some(default) => Ok(Some(default)),
+        None => Err(Error::new_spanned(attr, "expected `#[opt]` or `#[opt(default = expr)]`")),
+    }
+}
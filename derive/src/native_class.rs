@@ -0,0 +1,157 @@
+use proc_macro2::TokenStream;
+use syn::parse::ParseStream;
+use doc_string;
+use syn::{Data, DeriveInput, Error, Expr, ExprLit, Fields, Ident, Lit, LitStr, Meta, Result, Type};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "native classes can't be generic"));
+    }
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(name, "native classes must have named fields")),
+        },
+        _ => return Err(Error::new_spanned(name, "native classes must be structs")),
+    };
+
+    let base = match input.attrs.iter().find(|attr| attr.path().is_ident("inherit")) {
+        Some(attr) => attr.parse_args::<Type>()?,
+        None => return Err(Error::new_spanned(
+            name, "missing the base class, add an attribute like `#[inherit(Node)]`"
+        )),
+    };
+
+    let user_data = match input.attrs.iter().find(|attr| attr.path().is_ident("user_data")) {
+        Some(attr) => {
+            let user_data = attr.parse_args::<Type>()?;
+            Some(quote!(, user_data: #user_data))
+        }
+        None => None,
+    };
+
+    let method_errors = match input.attrs.iter().find(|attr| attr.path().is_ident("method_errors")) {
+        Some(attr) => {
+            let method_errors = attr.parse_args::<Ident>()?;
+            Some(quote!(, method_errors: #method_errors))
+        }
+        None => None,
+    };
+
+    let header = fields.iter()
+        .find(|field| is_header(&field.ty))
+        .and_then(|field| field.ident.as_ref())
+        .ok_or_else(|| Error::new_spanned(name, "missing a field of type `NativeInstanceHeader`"))?;
+
+    let mut base_fields = fields.iter().filter(|field| field.attrs.iter().any(|attr| attr.path().is_ident("base")));
+    let extends = match (base_fields.next(), base_fields.next()) {
+        (_, Some(field)) => return Err(Error::new_spanned(field, "a class can only extend one `#[base]` field")),
+        (Some(field), None) => {
+            let field_name = field.ident.as_ref().unwrap();
+            let ty = &field.ty;
+            Some(quote!(, extends: #field_name : #ty))
+        }
+        (None, None) => None,
+    };
+
+    let mut properties = Vec::new();
+    for field in fields.iter() {
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("property")) {
+            let field_name = field.ident.as_ref().unwrap();
+            let ty = &field.ty;
+            let property = Property::parse(attr, field_name.to_string())?;
+
+            let path = property.path;
+            let default = property.default.map(|default| quote!(, default: #default));
+            let hint = property.hint.map(|hint| quote!(, hint: #hint));
+            let usage = property.usage.map(|usage| quote!(, usage: #usage));
+            let doc = doc_string(&field.attrs).map(|doc| quote!(, doc: #doc));
+
+            properties.push(quote! {
+                #field_name : #ty { path: #path #default #hint #usage #doc }
+            });
+        }
+    }
+
+    let doc = doc_string(&input.attrs).map(|doc| quote!(, doc: #doc));
+
+    // A bare `#[inspector_category]` names the category after the module.
+    let category = match input.attrs.iter().find(|attr| attr.path().is_ident("inspector_category")) {
+        Some(_) if properties.is_empty() => None,
+        Some(attr) => match attr.meta {
+            Meta::Path(_) => Some(quote!(, module_category: module_path!())),
+            _ => {
+                let category = attr.parse_args::<LitStr>()?;
+                Some(quote!(, category: #category))
+            }
+        },
+        None => None,
+    };
+
+    Ok(quote! {
+        godot_derive_native_class! {
+            #name : #base, header: #header #extends #user_data #method_errors #category #doc;
+            #(#properties)*
+        }
+    })
+}
+
+fn is_header(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref path) => path.path.segments.last()
+            .map_or(false, |segment| segment.ident == "NativeInstanceHeader"),
+        _ => false,
+    }
+}
+
+struct Property {
+    path: LitStr,
+    default: Option<Expr>,
+    hint: Option<Expr>,
+    usage: Option<Expr>,
+}
+
+impl Property {
+    fn parse(attr: &syn::Attribute, field_name: String) -> Result<Self> {
+        let mut property = Property {
+            path: LitStr::new(&field_name, attr.path().segments[0].ident.span()),
+            default: None,
+            hint: None,
+            usage: None,
+        };
+
+        // A bare `#[property]` uses the default options.
+        if let Meta::Path(_) = attr.meta {
+            return Ok(property);
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                property.path = meta.value()?.parse()?;
+            } else if meta.path.is_ident("default") {
+                property.default = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("hint") {
+                property.hint = Some(parse_expr_or_str(meta.value()?)?);
+            } else if meta.path.is_ident("usage") {
+                property.usage = Some(parse_expr_or_str(meta.value()?)?);
+            } else {
+                return Err(meta.error("unknown property option, expected `path`, `default`, `hint` or `usage`"));
+            }
+            Ok(())
+        })?;
+
+        Ok(property)
+    }
+}
+
+// Expressions can also be given as string literals, like in `hint = "PropertyHint::None"`.
+fn parse_expr_or_str(input: ParseStream) -> Result<Expr> {
+    let expr: Expr = input.parse()?;
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Str(ref s), .. }) => s.parse(),
+        _ => Ok(expr),
+    }
+}
@@ -0,0 +1,278 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Error, Fields, GenericParam, Generics, Ident, Index, Result};
+
+#[derive(Copy, Clone)]
+pub enum Direction {
+    To,
+    From,
+}
+
+impl Direction {
+    fn trait_name(self) -> TokenStream {
+        match self {
+            Direction::To => quote!(__gdnative::ToVariant),
+            Direction::From => quote!(__gdnative::FromVariant),
+        }
+    }
+}
+
+pub fn derive(input: DeriveInput, direction: Direction) -> Result<TokenStream> {
+    let name = &input.ident;
+    let trait_name = direction.trait_name();
+    let generics = add_bounds(input.generics.clone(), &trait_name);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match (direction, &input.data) {
+        (Direction::To, &Data::Struct(ref data)) => {
+            let to = fields_to_variant(&data.fields, |member| quote!(&self.#member));
+            quote! {
+                fn to_variant(&self) -> __gdnative::Variant {
+                    #to
+                }
+            }
+        }
+        (Direction::From, &Data::Struct(ref data)) => {
+            from_variant_fns(fields_from_variant(&data.fields, quote!(#name), quote!(variant)))
+        }
+        (direction, &Data::Enum(ref data)) => {
+            if data.variants.is_empty() {
+                return Err(Error::new_spanned(name, "enums without variants can't be converted to variants"));
+            }
+
+            let fieldless = data.variants.iter().all(|variant| variant.fields.is_empty());
+            let variants = data.variants.iter().map(|variant| (&variant.ident, &variant.fields));
+            match (direction, fieldless) {
+                (Direction::To, true) => int_enum_to_variant(name, variants),
+                (Direction::From, true) => int_enum_from_variant(name, variants),
+                (Direction::To, false) => tagged_enum_to_variant(name, variants),
+                (Direction::From, false) => tagged_enum_from_variant(name, variants),
+            }
+        }
+        (_, &Data::Union(_)) => return Err(Error::new_spanned(name, "unions can't be converted to variants")),
+    };
+
+    Ok(quote! {
+        godot_derive_variant_impl! {
+            impl #impl_generics #trait_name for #name #ty_generics #where_clause {
+                #body
+            }
+        }
+    })
+}
+
+// Every type parameter must be convertible too.
+fn add_bounds(mut generics: Generics, trait_name: &TokenStream) -> Generics {
+    for param in generics.params.iter_mut() {
+        if let GenericParam::Type(ref mut param) = *param {
+            param.bounds.push(parse_quote!(#trait_name));
+        }
+    }
+    generics
+}
+
+// Named fields become a dictionary, a single unnamed field its value, several unnamed
+// fields an array and no fields nil. The locals are prefixed since the fields of enum
+// variants are bound to their names.
+fn fields_to_variant<F>(fields: &Fields, access: F) -> TokenStream
+where F: Fn(TokenStream) -> TokenStream {
+    match *fields {
+        Fields::Named(ref fields) => {
+            let sets = fields.named.iter().map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                let key = ident.to_string();
+                let value = access(quote!(#ident));
+                quote! {
+                    __dictionary.set(
+                        &__gdnative::Variant::from_str(#key),
+                        &__gdnative::ToVariant::to_variant(#value),
+                    );
+                }
+            });
+            quote! {
+                let mut __dictionary = __gdnative::Dictionary::new();
+                #(#sets)*
+                __gdnative::Variant::from_dictionary(&__dictionary)
+            }
+        }
+        Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            let value = access(quote!(0));
+            quote!(__gdnative::ToVariant::to_variant(#value))
+        }
+        Fields::Unnamed(ref fields) => {
+            let pushes = (0..fields.unnamed.len()).map(|idx| {
+                let value = access(member(idx));
+                quote!(__array.push(&__gdnative::ToVariant::to_variant(#value));)
+            });
+            quote! {
+                let mut __array = __gdnative::VariantArray::new();
+                #(#pushes)*
+                __gdnative::Variant::from_array(&__array)
+            }
+        }
+        Fields::Unit => quote!(__gdnative::Variant::new()),
+    }
+}
+
+// The reverse of `fields_to_variant`, fails if any field has the wrong type. A missing
+// field of a dictionary is converted from nil, so that optional fields can be left out.
+fn fields_from_variant(fields: &Fields, path: TokenStream, variant: TokenStream) -> TokenStream {
+    let invalid_type = quote!(__gdnative::FromVariantError::invalid_type::<Self>(#variant));
+    match *fields {
+        Fields::Named(ref fields) => {
+            let inits = fields.named.iter().map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                let key = ident.to_string();
+                quote!(#ident: __gdnative::dictionary_field(&dictionary, #key)?)
+            });
+            quote! {
+                let dictionary = #variant.try_to_dictionary().ok_or_else(|| #invalid_type)?;
+                Ok(#path { #(#inits),* })
+            }
+        }
+        Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            quote!(Ok(#path(__gdnative::FromVariant::try_from_variant(#variant)?)))
+        }
+        Fields::Unnamed(ref fields) => {
+            let count = fields.unnamed.len();
+            let len = count as i32;
+            let inits = (0..count).map(|idx| {
+                let idx = idx as i32;
+                quote!(__gdnative::array_field(&array, #idx)?)
+            });
+            quote! {
+                let array = #variant.try_to_array().ok_or_else(|| #invalid_type)?;
+                if array.len() != #len {
+                    return Err(__gdnative::FromVariantError::new(__gdnative::FromVariantErrorKind::InvalidLength {
+                        expected: #count,
+                        actual: array.len() as usize,
+                    }));
+                }
+                Ok(#path(#(#inits),*))
+            }
+        }
+        Fields::Unit => quote! {
+            if #variant.is_nil() {
+                Ok(#path)
+            } else {
+                Err(#invalid_type)
+            }
+        },
+    }
+}
+
+// `from_variant` is implemented with `try_from_variant`.
+fn from_variant_fns(body: TokenStream) -> TokenStream {
+    quote! {
+        fn from_variant(variant: &__gdnative::Variant) -> Option<Self> {
+            <Self as __gdnative::FromVariant>::try_from_variant(variant).ok()
+        }
+
+        fn try_from_variant(variant: &__gdnative::Variant) -> Result<Self, __gdnative::FromVariantError> {
+            #body
+        }
+    }
+}
+
+// Enums without fields become their discriminant.
+fn int_enum_to_variant<'a, I>(name: &Ident, variants: I) -> TokenStream
+where I: Iterator<Item = (&'a Ident, &'a Fields)> {
+    let arms = variants.map(|(variant, _)| quote!(#name::#variant => #name::#variant as i64));
+    quote! {
+        fn to_variant(&self) -> __gdnative::Variant {
+            __gdnative::Variant::from_i64(match *self {
+                #(#arms,)*
+            })
+        }
+    }
+}
+
+fn int_enum_from_variant<'a, I>(name: &Ident, variants: I) -> TokenStream
+where I: Iterator<Item = (&'a Ident, &'a Fields)> {
+    let checks = variants.map(|(variant, _)| quote! {
+        if value == #name::#variant as i64 {
+            return Ok(#name::#variant);
+        }
+    });
+    from_variant_fns(quote! {
+        let value = <i64 as __gdnative::FromVariant>::from_variant(variant)
+            .ok_or_else(|| __gdnative::FromVariantError::invalid_type::<Self>(variant))?;
+        #(#checks)*
+        Err(__gdnative::FromVariantError::new(__gdnative::FromVariantErrorKind::UnknownVariant {
+            value: value.to_string(),
+        }))
+    })
+}
+
+// The other enums become a dictionary with the name of the variant as only key and
+// its fields as value.
+fn tagged_enum_to_variant<'a, I>(name: &Ident, variants: I) -> TokenStream
+where I: Iterator<Item = (&'a Ident, &'a Fields)> {
+    let arms = variants.map(|(variant, fields)| {
+        let tag = variant.to_string();
+        let bindings = bindings(fields);
+        let pattern = match *fields {
+            Fields::Named(_) => quote!(#name::#variant { #(ref #bindings),* }),
+            Fields::Unnamed(_) => quote!(#name::#variant(#(ref #bindings),*)),
+            Fields::Unit => quote!(#name::#variant),
+        };
+        let payload = fields_to_variant(fields, |member| {
+            let binding = binding(fields, &member);
+            quote!(#binding)
+        });
+        quote! {
+            #pattern => __gdnative::tagged_variant(#tag, { #payload })
+        }
+    });
+    quote! {
+        fn to_variant(&self) -> __gdnative::Variant {
+            match *self {
+                #(#arms,)*
+            }
+        }
+    }
+}
+
+fn tagged_enum_from_variant<'a, I>(name: &Ident, variants: I) -> TokenStream
+where I: Iterator<Item = (&'a Ident, &'a Fields)> {
+    let arms = variants.map(|(variant, fields)| {
+        let tag = variant.to_string();
+        let from = fields_from_variant(fields, quote!(#name::#variant), quote!(payload));
+        quote! {
+            #tag => (|| -> Result<Self, __gdnative::FromVariantError> { #from })(),
+        }
+    });
+    from_variant_fns(quote! {
+        let (tag, payload) = __gdnative::variant_tag::<Self>(variant)?;
+        let payload = &payload;
+        let value = match &*tag {
+            #(#arms)*
+            _ => return Err(__gdnative::FromVariantError::new(__gdnative::FromVariantErrorKind::UnknownVariant {
+                value: format!("{:?}", tag),
+            })),
+        };
+        value.map_err(|err| err.at_key(&__gdnative::Variant::from_str(&*tag)))
+    })
+}
+
+fn member(idx: usize) -> TokenStream {
+    let idx = Index::from(idx);
+    quote!(#idx)
+}
+
+// The names the fields of an enum variant are bound to in a pattern: the field names,
+// or `field_0`, `field_1`... for unnamed fields.
+fn bindings(fields: &Fields) -> Vec<Ident> {
+    fields.iter().enumerate()
+        .map(|(idx, field)| match field.ident {
+            Some(ref ident) => ident.clone(),
+            None => Ident::new(&format!("field_{}", idx), Span::call_site()),
+        })
+        .collect()
+}
+
+fn binding(fields: &Fields, member: &TokenStream) -> Ident {
+    match *fields {
+        Fields::Named(_) => syn::parse2(member.clone()).unwrap(),
+        _ => Ident::new(&format!("field_{}", member), Span::call_site()),
+    }
+}
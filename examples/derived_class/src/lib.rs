@@ -0,0 +1,128 @@
+#[macro_use]
+extern crate gdnative as godot;
+
+use godot::prelude::*;
+
+#[derive(Clone, Copy, Default, Export)]
+enum Axis {
+    X,
+    #[default]
+    Y,
+    Z,
+}
+
+/// The time spent animating a node, stored by the classes extending `Animated`.
+struct Animated {
+    elapsed: f64,
+}
+
+impl BaseClass for Animated {
+    fn class_name() -> &'static str {
+        "Animated"
+    }
+
+    fn base_class() -> &'static str {
+        "Spatial"
+    }
+
+    fn register_members<C: Extends<Self>>(builder: &ClassBuilder<'_, C>) {
+        builder.add_typed_method("elapsed", |this: &mut C, _owner: &mut Spatial, (): ()| this.base().elapsed);
+    }
+}
+
+/// Rotates its node around one of its axes.
+#[derive(NativeClass)]
+#[inherit(Spatial)]
+#[inspector_category("Motion/Rotation")]
+struct Rotator {
+    header: NativeInstanceHeader,
+
+    /// The rotation speed, in radians per second.
+    #[property(
+        path = "rotation/speed",
+        default = 1.0,
+        hint = "PropertyHint::Range { range: 0.0..10.0, step: 0.1, slider: true }"
+    )]
+    speed: f64,
+
+    /// Shown as a dropdown of the variants.
+    #[property(path = "rotation/axis")]
+    axis: Axis,
+
+    #[property(usage = PropertyUsage::DEFAULT)]
+    label: GodotString,
+
+    #[base]
+    animated: Animated,
+}
+
+#[methods]
+impl Rotator {
+    fn _init(header: NativeInstanceHeader) -> Self {
+        Rotator {
+            header,
+            speed: 1.0,
+            axis: Axis::Y,
+            label: GodotString::new(),
+            animated: Animated { elapsed: 0.0 },
+        }
+    }
+
+    #[export]
+    fn _ready(&mut self) {
+        let mut owner = self.owner();
+        owner.set_process(true);
+        godot_print!("Rotator ready: {:?}", self.label);
+    }
+
+    #[export]
+    fn _process(&mut self, delta: f64) {
+        self.animated.elapsed += delta;
+        let axis = match self.axis {
+            Axis::X => Vector3::new(1.0, 0.0, 0.0),
+            Axis::Y => Vector3::new(0.0, 1.0, 0.0),
+            Axis::Z => Vector3::new(0.0, 0.0, 1.0),
+        };
+        let speed = self.speed;
+        self.owner().rotate_object_local(axis, speed * delta);
+    }
+
+    /// Restarts the rotation from `elapsed` seconds, at `speed` radians per second.
+    #[export]
+    fn restart(&mut self, #[opt] elapsed: f64, #[opt(default = 1.0)] speed: f64) {
+        self.animated.elapsed = elapsed;
+        self.speed = speed;
+    }
+
+    /// Rotates around `axis` from now on, 0 for X to 2 for Z, or stops rotating
+    /// without it.
+    #[export]
+    fn set_axis(&mut self, axis: Option<i64>) {
+        match axis {
+            Some(0) => self.axis = Axis::X,
+            Some(2) => self.axis = Axis::Z,
+            Some(_) => self.axis = Axis::Y,
+            None => self.speed = 0.0,
+        }
+    }
+
+    /// Prints the label followed by any number of values.
+    #[export(name = "log")]
+    fn print_values(&self, #[rest] values: &[Variant]) {
+        let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        godot_print!("{}: {}", self.label.to_string(), values.join(" "));
+    }
+
+    fn owner(&self) -> Spatial {
+        unsafe { GodotObject::from_sys(self.header.this) }
+    }
+}
+
+fn init(handle: InitHandle) {
+    handle.add_base_class::<Animated>();
+    handle.add_native_class::<Rotator>();
+}
+
+godot_gdnative_init!();
+godot_nativescript_init!(init);
+godot_gdnative_terminate!();
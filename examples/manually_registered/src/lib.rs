@@ -20,8 +20,8 @@ impl MyClass {
         }
     }
 
-    fn _ready(&mut self) {
-        godot_print!("Hello World!");
+    fn _ready(&mut self, owner: Node) {
+        godot_print!("Hello World from {}!", owner.get_name().to_string());
     }
 
     fn _process(&mut self, delta: f64) {
@@ -37,6 +37,8 @@ impl MyClass {
 }
 
 impl NativeClass for MyClass {
+    type UserData = LocalCellData<MyClass>;
+
     fn class_name() -> &'static str {
         "MyClass"
     }
@@ -54,15 +56,14 @@ fn init(gdnative_init: init::InitHandle) {
 
     let ready_method = godot_wrap_method!(
         MyClass,
-        fn _ready(&mut self) -> ()
+        fn _ready(&mut self, #[owner] owner: Node) -> ()
     );
 
     let ready_method = ScriptMethod {
         name: "_ready",
         method_ptr: Some(ready_method),
-        attributes: ScriptMethodAttributes {
-            rpc_mode: RpcMode::Disabled
-        },
+        attributes: ScriptMethodAttributes::from(RpcMode::Disabled),
+        documentation: Some("Prints the name of the node the script is attached to."),
         method_data: std::ptr::null_mut(),
         free_func: None
     };
@@ -85,9 +86,33 @@ fn init(gdnative_init: init::InitHandle) {
             destructor: Some(destructor),
         }
     );
+    class.set_class_documentation("Counts the time elapsed since the node was ready.");
     class.add_method_advanced(ready_method);
     class.add_method("_process", process_method);
     class.add_method("_exit_tree", exit_tree_method);
+    class.add_typed_method("add_time", |this: &mut MyClass, _owner: &mut Node, (seconds,): (f64,)| {
+        this.elapsed_time += seconds;
+        this.elapsed_time
+    });
+    class.add_signal(Signal {
+        name: "tick",
+        args: &[SignalArgument {
+            name: "elapsed_time",
+            default: 0.0f64.to_variant(),
+            hint: PropertyHint::None,
+            usage: PropertyUsage::DEFAULT,
+        }],
+        documentation: Some("Carries the time elapsed since the node was ready."),
+    });
+    class.add_enum("Speed", &[("SLOW", 0), ("NORMAL", 1), ("FAST", 2)]);
+    // Keep counting from the same value when the editor reloads the library.
+    class.set_reload_hooks(
+        |this: &MyClass| this.elapsed_time.to_variant(),
+        |this: &mut MyClass, state| this.elapsed_time = state.to_f64(),
+    );
+    class.add_to_string(|this| format!("MyClass (running for {:.1}s)", this.elapsed_time));
+
+    godot_gdnative_info!(gdnative_init);
 }
 
 godot_gdnative_init!();
@@ -12,21 +12,19 @@ godot_class! {
             rotate_speed: f64,
         }
         setup(builder) {
-            builder.add_property(
-                Property {
-                    name: "base/rotate_speed",
-                    default: 0.05,
-                    hint: PropertyHint::Range {
-                        range: 0.05..1.0,
-                        step: 0.01,
-                        slider: true
-                    },
-                    getter: |this: &mut RustTest| this.rotate_speed,
-                    setter: |this: &mut RustTest, v| this.rotate_speed = v,
-                    usage: PropertyUsage::DEFAULT,
-                }
-            );
+            builder
+                .property::<f64>("base/rotate_speed")
+                .default(0.05)
+                .hint(PropertyHint::Range {
+                    range: 0.05..1.0,
+                    step: 0.01,
+                    slider: true
+                })
+                .with_field(|this: &RustTest| &this.rotate_speed, |this: &mut RustTest| &mut this.rotate_speed)
+                .documentation("The rotation speed of the cube, in radians per frame.")
+                .done();
 
+            builder.add_property_category("Tests");
             builder.add_property(
                 Property {
                     name: "test/test_enum",
@@ -41,6 +39,7 @@ godot_class! {
                     getter: |_: &mut RustTest| { GodotString::from_str("Hello") },
                     setter: (),
                     usage: PropertyUsage::DEFAULT,
+                    documentation: None,
                 }
             );
 
@@ -54,6 +53,7 @@ godot_class! {
                     getter: |_: &mut RustTest| 0,
                     setter: (),
                     usage: PropertyUsage::DEFAULT,
+                    documentation: None,
                 }
             );
         }
@@ -0,0 +1,11 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate gdnative;
+
+fuzz_target!(|data: &[u8]| {
+    unsafe {
+        gdnative::install_mock_engine();
+    }
+    gdnative::fuzz_variant(data);
+});
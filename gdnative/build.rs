@@ -7,13 +7,14 @@ extern crate heck;
 
 use heck::{CamelCase, SnakeCase};
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::env;
 use std::path::PathBuf;
 use std::io::Write;
 use std::fmt;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
 
 #[derive(Clone)]
 enum Ty {
@@ -170,11 +171,31 @@ fn main() {
 
     let mut output = File::create(out_path.join("types.rs")).unwrap();
 
+    // With the `dynamic_dispatch` feature the methods go through a single shared
+    // (variant based) call path instead of generating ptrcall code for each one.
+    let dynamic_dispatch = env::var("CARGO_FEATURE_DYNAMIC_DISPATCH").is_ok();
+
+    let generated = generated_classes(&classes);
+    emit_helper_cfgs(&generated);
+    let deprecations = find_deprecations(&classes);
+
     writeln!(output, "use std::ptr;").unwrap();
     writeln!(output, "use std::mem;").unwrap();
     writeln!(output, "use object;").unwrap();
 
     for class in classes {
+        if !generated.contains(&class.name) {
+            writeln!(output, r#"
+/// `{name}` is not generated because it is not in the class whitelist, see the
+/// `GDNATIVE_CLASS_WHITELIST` environment variable.
+#[deprecated(note = "the class {name} is not generated, add it to the file set in the GDNATIVE_CLASS_WHITELIST environment variable")]
+#[allow(non_camel_case_types)]
+pub enum {name} {{}}"#,
+                name = class.name
+            ).unwrap();
+            continue;
+        }
+
         let has_parent = class.base_class != "";
         let singleton_str = if class.singleton { "singleton " } else { "" } ;
         let ownership_type = if class.is_reference { "reference counted" } else { "manually managed" };
@@ -319,7 +340,9 @@ impl {name}MethodTable {{
             let table = Self::get_mut();
             static INIT: Once = ONCE_INIT;
             INIT.call_once(|| {{
-                {name}MethodTable::init(table, api);
+                ::timing::time(::timing::TimingKind::Bindings, || "{name}".into(), || {{
+                    {name}MethodTable::init(table, api);
+                }});
             }});
 
             table
@@ -374,6 +397,16 @@ unsafe impl GodotObject for {name} {{
             addref_if_reference = if class.is_reference { "object::add_ref(obj);" } else { "" }
         ).unwrap();
 
+        writeln!(output, r#"
+impl ::std::fmt::Debug for {name} {{
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+        object::debug_object(self.this, f)
+    }}
+}}
+"#,
+            name = class.name
+        ).unwrap();
+
         if class.base_class != "" {
             writeln!(output, r#"
 impl Deref for {name} {{
@@ -506,14 +539,23 @@ r#"
                 continue 'method;
             }
 
-            let rust_ret_type = if let Some(ty) = method.get_return_type().to_rust() {
-                ty
-            } else {
-                continue
+            let rust_ret_type = match method.get_return_type() {
+                Ty::Object(ref name) if !generated.contains(name) => continue,
+                ty => if let Some(ty) = ty.to_rust() {
+                    ty
+                } else {
+                    continue
+                },
             };
 
             let mut params = String::new();
             for argument in &method.arguments {
+                if let Ty::Object(ref name) = argument.get_type() {
+                    if !generated.contains(name) {
+                        continue 'method;
+                    }
+                }
+
                 if let Some(ty) = argument.get_type().to_rust() {
                     fmt::Write::write_fmt(&mut params, format_args!(", {}: {}", rust_safe_name(&argument.name), ty)).unwrap();
                 } else {
@@ -531,22 +573,43 @@ r#"
                 "&mut self"
             };
 
-            writeln!(output, r#"
+            let deprecation = deprecations.get(&(class.name.clone(), method.name.clone()));
+            writeln!(output).unwrap();
+            if let Some(deprecation) = deprecation {
+                writeln!(output, r#"    /// {doc}"#, doc = deprecation.doc()).unwrap();
+            }
 
-    pub fn {name}({self_param}{params}) -> {rust_ret_type} {{
-        unsafe {{
+            writeln!(output, r#"    pub fn {name}({self_param}{params}) -> {rust_ret_type} {{"#,
+                name = method_name,
+                rust_ret_type = rust_ret_type,
+                params = params,
+                self_param = self_param,
+            ).unwrap();
+
+            if let Some(deprecation) = deprecation {
+                writeln!(output, r#"        static DEPRECATION: ::deprecation::Deprecation = ::deprecation::Deprecation::new(
+            "{cname}.{method}", {major}, {minor}, ::deprecation::DeprecationKind::{kind:?}, {note:?},
+        );
+        DEPRECATION.check();"#,
+                    cname = class.name,
+                    method = method.name,
+                    major = deprecation.since.0,
+                    minor = deprecation.since.1,
+                    kind = deprecation.kind,
+                    note = deprecation.note
+                ).unwrap();
+            }
+
+            writeln!(output, r#"        unsafe {{
             let api = ::get_api();
 
             let method_bind: *mut sys::godot_method_bind = {cname}MethodTable::get(api).{name};"#,
                 cname = class.name,
                 name = method_name,
-                rust_ret_type = rust_ret_type,
-                params = params,
-                self_param = self_param,
             ).unwrap();
             if method.has_varargs {
                 writeln!(output,
-r#"            let mut argument_buffer: Vec<*const sys::godot_variant> = Vec::with_capacity({arg_count} + varargs.len());"#,
+r#"            let mut argument_buffer = ::arg_buffer::ArgBuffer::with_capacity({arg_count} + varargs.len());"#,
                     arg_count = method.arguments.len()
                 ).unwrap();
 
@@ -580,7 +643,7 @@ r#"            argument_buffer.push(&{name}.0); "#,
             for arg in varargs {{
                 argument_buffer.push(&arg.0 as *const _);
             }}
-            let ret = Variant((api.godot_method_bind_call)(method_bind, self.this, argument_buffer.as_mut_ptr(), argument_buffer.len() as _, ptr::null_mut()));"#
+            let ret = Variant::from_sys((api.godot_method_bind_call)(method_bind, self.this, argument_buffer.as_mut_ptr(), argument_buffer.len() as _, ptr::null_mut()));"#
                 ).unwrap();
 
                 if rust_ret_type.starts_with("Option") {
@@ -593,6 +656,20 @@ r#"                ret.into()"#
                     ).unwrap();
                 }
 
+            } else if dynamic_dispatch {
+                writeln!(output, r#"
+            let ret = object::dynamic_call(method_bind, self.this, &["#
+                ).unwrap();
+
+                for argument in &method.arguments {
+                    godot_handle_dynamic_argument(&mut output, &argument.get_type(), rust_safe_name(&argument.name));
+                }
+
+                writeln!(output, r#"
+            ]);"#
+                ).unwrap();
+
+                godot_handle_dynamic_return(&mut output, &method.get_return_type());
             } else {
                 writeln!(output, r#"
             let mut argument_buffer = [ptr::null() as *const libc::c_void; {arg_count}];
@@ -616,6 +693,27 @@ r#"        }}
     }}"#).unwrap();
         }
 
+        for signal in &class.signals {
+            let arguments: Vec<String> = signal.arguments.iter()
+                .map(|argument| format!("{}: {}", argument.name, argument.ty))
+                .collect();
+            let arguments = if arguments.is_empty() {
+                String::new()
+            } else {
+                format!(" The arguments are `({})`.", arguments.join(", "))
+            };
+            writeln!(output, r#"
+    /// Calls `callback` each time the `{signal}` signal is emitted, see
+    /// `ConnectSignal::connect_fn`.{arguments}
+    pub fn connect_{signal}<F>(&self, callback: F) -> GodotResult
+    where F: FnMut(&[&Variant]) + 'static {{
+        ::signal_relay::connect_fn(self, "{signal}", ConnectFlags::empty(), callback)
+    }}"#,
+                signal = signal.name,
+                arguments = arguments
+            ).unwrap();
+        }
+
         writeln!(output,
 r#"
     pub fn cast<T: GodotObject>(&self) -> Option<T> {{
@@ -644,6 +742,216 @@ impl Drop for {name} {{
     }
 }
 
+// The classes used by the crate itself, always generated.
+const CORE_CLASSES: &[&str] = &[
+    "Object",
+    "Reference",
+    "Node",
+    "Resource",
+    "NativeScript",
+    "ProjectSettings",
+    "GDNativeLibrary",
+    "_Engine",
+    "_OS",
+    "_ResourceLoader",
+    "ResourceInteractiveLoader",
+];
+
+// The hand written helper modules and the classes they need. A module is only
+// compiled if all of its classes are generated.
+const HELPER_MODULES: &[(&str, &[&str])] = &[
+    ("curve", &["Curve", "Curve2D", "Curve3D", "Gradient", "PathFollow", "PathFollow2D"]),
+    ("grid_map", &["GridMap", "MeshLibrary"]),
+    ("particles", &["Particles", "Particles2D", "ParticlesMaterial", "Texture"]),
+    ("lighting", &["Light", "DirectionalLight", "Environment", "ProceduralSky"]),
+    ("texture_atlas", &["AtlasTexture", "CanvasItem", "Texture"]),
+    ("script_templates", &["EditorPlugin", "EditorInterface", "EditorFileSystem", "EditorFileSystemDirectory", "_ResourceSaver"]),
+    ("memory_report", &["_OS", "Performance"]),
+    ("body_state", &["PhysicsDirectBodyState", "PhysicsDirectSpaceState", "PhysicsShapeQueryParameters"]),
+    ("tasks", &["SceneTree", "SceneTreeTimer", "Viewport"]),
+    ("downloader", &[
+        "SceneTree", "SceneTreeTimer", "Viewport", "HTTPRequest", "HTTPClient", "_File", "_Directory",
+        "_ResourceLoader",
+    ]),
+    ("singleton", &["SceneTree", "Viewport"]),
+    ("input_map", &[
+        "InputMap", "InputEventKey", "InputEventMouseButton", "InputEventJoypadButton", "InputEventJoypadMotion",
+        "ConfigFile",
+    ]),
+];
+
+/// Returns the classes to generate.
+///
+/// If the `GDNATIVE_CLASS_WHITELIST` environment variable is set to the path of a
+/// file listing class names (one per line, `#` starts a comment), only these classes,
+/// the classes used by the crate itself and their base classes are generated.
+/// Otherwise all the classes are.
+fn generated_classes(classes: &[GodotClass]) -> HashSet<String> {
+    println!("cargo:rerun-if-env-changed=GDNATIVE_CLASS_WHITELIST");
+
+    let path = match env::var("GDNATIVE_CLASS_WHITELIST") {
+        Ok(path) => path,
+        Err(_) => return classes.iter().map(|class| class.name.clone()).collect(),
+    };
+    println!("cargo:rerun-if-changed={}", path);
+
+    let mut whitelist = String::new();
+    File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut whitelist))
+        .unwrap_or_else(|err| panic!("Failed to read the class whitelist {}: {}", path, err));
+
+    let base_classes: HashMap<&str, &str> = classes.iter()
+        .map(|class| (class.name.as_str(), class.base_class.as_str()))
+        .collect();
+
+    let requested = whitelist.lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|name| !name.is_empty())
+        .chain(CORE_CLASSES.iter().cloned());
+
+    let mut generated = HashSet::new();
+    for name in requested {
+        if !base_classes.contains_key(name) {
+            panic!("Unknown class `{}` in the class whitelist {}", name, path);
+        }
+
+        let mut name = name;
+        while name != "" && generated.insert(name.to_string()) {
+            name = base_classes[name];
+        }
+    }
+
+    generated
+}
+
+fn emit_helper_cfgs(generated: &HashSet<String>) {
+    let modules: Vec<String> = HELPER_MODULES.iter()
+        .map(|&(module, _)| format!("\"{}\"", module))
+        .collect();
+    println!("cargo:rustc-check-cfg=cfg(gdnative_helper, values({}))", modules.join(", "));
+
+    for &(module, classes) in HELPER_MODULES {
+        if classes.iter().all(|class| generated.contains(*class)) {
+            println!("cargo:rustc-cfg=gdnative_helper=\"{}\"", module);
+        }
+    }
+}
+
+/// Compares `api.json` with the APIs of the newer engine versions, the
+/// `api-<major>.<minor>.json` files next to it, and returns the methods removed or
+/// behaving differently in these versions, by class and method name. The wrappers
+/// of these methods warn once when they are called, see the `deprecation` module.
+fn find_deprecations(classes: &[GodotClass]) -> HashMap<(String, String), Deprecation> {
+    println!("cargo:rerun-if-changed=api.json");
+
+    let mut newer_apis = Vec::new();
+    for entry in fs::read_dir(".").unwrap() {
+        let file_name = entry.unwrap().file_name().into_string().unwrap_or_default();
+        let version = match api_version(&file_name) {
+            Some(version) => version,
+            None => continue,
+        };
+        println!("cargo:rerun-if-changed={}", file_name);
+        let api: Vec<ApiClass> = serde_json::from_reader(File::open(&file_name).unwrap())
+            .unwrap_or_else(|err| panic!("Failed to parse {}: {}", file_name, err));
+        newer_apis.push((version, api));
+    }
+    newer_apis.sort_by_key(|&(version, _)| version);
+
+    let mut deprecations = HashMap::new();
+    for &(version, ref api) in &newer_apis {
+        let api: HashMap<&str, &ApiClass> = api.iter().map(|class| (class.name.as_str(), class)).collect();
+        let is_class = |name: &str| api.contains_key(name) || classes.iter().any(|class| class.name == name);
+
+        for class in classes {
+            for method in &class.methods {
+                let key = (class.name.clone(), method.name.clone());
+                if deprecations.contains_key(&key) {
+                    continue;
+                }
+
+                let deprecation = match find_method(&api, &class.name, &method.name) {
+                    None if !api.contains_key(class.name.as_str()) => Deprecation {
+                        since: version,
+                        kind: DeprecationKind::Removed,
+                        note: format!("the class {} doesn't exist anymore", class.name),
+                    },
+                    None => Deprecation {
+                        since: version,
+                        kind: DeprecationKind::Removed,
+                        note: replacement_note(&api[class.name.as_str()], class, &method.name),
+                    },
+                    Some(newer) if newer.signature(&is_class) != method.signature(&is_class) => Deprecation {
+                        since: version,
+                        kind: DeprecationKind::Changed,
+                        note: format!("the engine method is now `{}`", newer.display_signature()),
+                    },
+                    Some(_) => continue,
+                };
+                deprecations.insert(key, deprecation);
+            }
+        }
+    }
+
+    deprecations
+}
+
+// The version of the engine API in `api-<major>.<minor>.json`.
+fn api_version(file_name: &str) -> Option<(u32, u32)> {
+    let version = file_name.trim_start_matches("api-");
+    if version.len() == file_name.len() || !version.ends_with(".json") {
+        return None;
+    }
+    let mut parts = version.trim_end_matches(".json").split('.').map(|part| part.parse::<u32>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), None) => Some((major, minor)),
+        _ => None,
+    }
+}
+
+// Looks the method up in the class and its base classes, like the engine does.
+fn find_method<'a>(api: &HashMap<&str, &'a ApiClass>, class: &str, method: &str) -> Option<&'a GodotMethod> {
+    let mut class = api.get(class);
+    while let Some(current) = class {
+        if let Some(found) = current.methods.iter().find(|m| m.name == method) {
+            return Some(found);
+        }
+        class = api.get(current.base_class.as_str());
+    }
+    None
+}
+
+// Suggests the methods added to the class by the newer version with a close name,
+// like `set_wrap_enabled` for `set_wrap`.
+fn replacement_note(newer: &ApiClass, class: &GodotClass, method: &str) -> String {
+    let candidates: Vec<String> = newer.methods.iter()
+        .filter(|m| !class.methods.iter().any(|old| old.name == m.name))
+        .filter(|m| m.name.starts_with(&format!("{}_", method)) || edit_distance(&m.name, method) <= 2)
+        .map(|m| format!("`{}`", m.name))
+        .collect();
+
+    if candidates.is_empty() {
+        "the engine has no such method anymore".to_string()
+    } else {
+        format!("it may be replaced by {}", candidates.join(" or "))
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..b.len() + 1).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for j in 0..b.len() {
+            let substitution = previous + if ca == b[j] { 0 } else { 1 };
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
 fn skip_method(name: &str) -> bool {
     name == "free"
 }
@@ -712,6 +1020,60 @@ r#"            argument_buffer[{arg}] = (&{name}.0) as *const _ as *const _;"#,
     }
 }
 
+fn godot_handle_dynamic_argument<W: Write>(w: &mut W, ty: &Ty, name: &str) {
+    match ty {
+        &Ty::Object(_) => {
+            writeln!(w,
+r#"                object::object_to_variant({name}.as_ref().map_or(ptr::null_mut(), |arg| arg.this)),"#,
+                name = name
+            ).unwrap();
+        },
+        &Ty::Result
+        | &Ty::VariantType
+        | &Ty::Enum(_)
+        | &Ty::Void
+        => {},
+        _ => {
+            writeln!(w,
+r#"                ToVariant::to_variant(&{name}),"#,
+                name = name
+            ).unwrap();
+        },
+    }
+}
+
+fn godot_handle_dynamic_return<W: Write>(w: &mut W, ty: &Ty) {
+    match ty {
+        &Ty::Void => {
+            writeln!(w,
+r#"            drop(ret);"#
+            ).unwrap();
+        },
+        &Ty::Object(ref name) => {
+            writeln!(w,
+r#"            ret.try_to_object::<{}>()"#,
+                name
+            ).unwrap();
+        },
+        &Ty::Result => {
+            writeln!(w,
+r#"            result_from_sys(mem::transmute(ret.to_i64() as u32))"#
+            ).unwrap();
+        },
+        &Ty::VariantType => {
+            writeln!(w,
+r#"            VariantType::from_sys(mem::transmute(ret.to_i64() as u32))"#
+            ).unwrap();
+        },
+        &Ty::Enum(_) => {},
+        _ => {
+            writeln!(w,
+r#"            object::variant_to_return(&ret)"#
+            ).unwrap();
+        },
+    }
+}
+
 fn godot_handle_return_pre<W: Write>(w: &mut W, ty: &Ty) {
     match ty {
         &Ty::Void => {
@@ -834,12 +1196,16 @@ r#"            let mut rid = Rid::default();
         | &Ty::ColorArray
         | &Ty::Int32Array
         | &Ty::Float32Array
-        | &Ty::Variant
         => {
             writeln!(w,
 r#"            {rust_ty}(ret)"#, rust_ty = ty.to_rust().unwrap()
             ).unwrap();
         }
+        &Ty::Variant => {
+            writeln!(w,
+r#"            Variant::from_sys(ret)"#
+            ).unwrap();
+        }
         &Ty::Object(ref name) => {
             writeln!(w, r#"
             if ret.is_null() {{
@@ -875,6 +1241,20 @@ struct GodotClass {
 
     methods: Vec<GodotMethod>,
     enums: Vec<Enum>,
+    signals: Vec<GodotSignal>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GodotSignal {
+    name: String,
+    arguments: Vec<GodotSignalArgument>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GodotSignalArgument {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -899,6 +1279,32 @@ struct GodotMethod {
 }
 
 impl GodotMethod {
+    // The argument and return types, as far as they matter to the generated code:
+    // enums are integers, and the objects are passed as pointers whatever their class.
+    fn signature<F>(&self, is_class: &F) -> (String, Vec<String>, bool)
+    where F: Fn(&str) -> bool {
+        let normalize = |ty: &str| if ty.starts_with("enum.") {
+            "int".to_string()
+        } else if is_class(ty) {
+            "Object".to_string()
+        } else {
+            ty.to_string()
+        };
+        let arguments = self.arguments.iter().map(|argument| normalize(&argument.ty)).collect();
+        (normalize(&self.return_type), arguments, self.has_varargs)
+    }
+
+    fn display_signature(&self) -> String {
+        let display = |ty: &str| ty.trim_start_matches("enum.").to_string();
+        let mut arguments: Vec<String> = self.arguments.iter()
+            .map(|argument| format!("{}: {}", argument.name, display(&argument.ty)))
+            .collect();
+        if self.has_varargs {
+            arguments.push("...".to_string());
+        }
+        format!("{}({}) -> {}", self.name, arguments.join(", "), display(&self.return_type))
+    }
+
     fn get_name(&self) -> &str {
         // GDScript and NativeScript have ::new methods but we want to reserve
         // the name for the constructors.
@@ -914,6 +1320,37 @@ impl GodotMethod {
     }
 }
 
+// The classes of the newer APIs, only their methods are compared.
+#[derive(Deserialize, Debug)]
+struct ApiClass {
+    name: String,
+    base_class: String,
+    methods: Vec<GodotMethod>,
+}
+
+struct Deprecation {
+    since: (u32, u32),
+    kind: DeprecationKind,
+    note: String,
+}
+
+// Matches `deprecation::DeprecationKind`.
+#[derive(Debug, Copy, Clone)]
+enum DeprecationKind {
+    Removed,
+    Changed,
+}
+
+impl Deprecation {
+    fn doc(&self) -> String {
+        let what = match self.kind {
+            DeprecationKind::Removed => "Removed in",
+            DeprecationKind::Changed => "Behaves differently since",
+        };
+        format!("{} Godot {}.{}: {}.", what, self.since.0, self.since.1, self.note)
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct GodotArgument {
     name: String,
@@ -0,0 +1,332 @@
+//! Announcements for screen reader users, spoken by a text to speech backend.
+//!
+//! UI code built in rust announces the changes a sighted player would notice, like
+//! the focused button or a new objective, through `Accessibility`. The announcements
+//! are spoken by the backend the game installs, usually when the player enables the
+//! option in the settings:
+//!
+//! ```ignore
+//! if settings.narration {
+//!     if let Some(backend) = default_speech_backend() {
+//!         Accessibility::set_backend(backend);
+//!     }
+//! }
+//!
+//! // In the focus handler.
+//! Accessibility::announce(&format!("{}, button", button.get_text()));
+//! ```
+//!
+//! Engines with text to speech (`OS.tts_speak`) are used through `EngineSpeech`.
+//! With the `tts_command` feature, `CommandSpeech` runs the speech command of the
+//! platform instead: `say` on macOS, `spd-say` on Linux and the
+//! speech synthesizer of PowerShell on Windows. Other backends, for example a
+//! screen reader library, implement `SpeechBackend`.
+//!
+//! Without a backend the announcements are dropped.
+
+#[cfg(feature = "tts_command")]
+use std::io::Write;
+#[cfg(feature = "tts_command")]
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use GodotObject;
+use GodotString;
+use Object;
+use ToVariant;
+use Variant;
+use _OS;
+
+/// Speaks the announcements of `Accessibility`.
+pub trait SpeechBackend {
+    /// Speaks `text`. With `interrupt` the announcement being spoken is stopped
+    /// first, else `text` is spoken after it when the backend queues announcements.
+    fn speak(&mut self, text: &str, interrupt: bool);
+
+    /// Stops the announcement being spoken.
+    fn stop(&mut self);
+}
+
+static BACKEND: Mutex<Option<Box<dyn SpeechBackend + Send>>> = Mutex::new(None);
+
+/// Sends announcements to the installed `SpeechBackend`, see the `accessibility`
+/// module.
+pub struct Accessibility;
+
+impl Accessibility {
+    /// Installs the backend speaking the announcements, replacing the previous one.
+    pub fn set_backend<B: SpeechBackend + Send + 'static>(backend: B) {
+        Self::set_boxed_backend(Some(Box::new(backend)));
+    }
+
+    /// Removes the backend, the next announcements are dropped.
+    pub fn clear_backend() {
+        Self::set_boxed_backend(None);
+    }
+
+    fn set_boxed_backend(backend: Option<Box<dyn SpeechBackend + Send>>) {
+        if let Ok(mut current) = BACKEND.lock() {
+            if let Some(ref mut current) = *current {
+                current.stop();
+            }
+            *current = backend;
+        }
+    }
+
+    pub fn has_backend() -> bool {
+        BACKEND.lock().map(|backend| backend.is_some()).unwrap_or(false)
+    }
+
+    /// Speaks `text` after the current announcement, for changes which can wait.
+    pub fn announce(text: &str) {
+        Self::speak(text, false);
+    }
+
+    /// Speaks `text` at once, for changes the player must know about immediately,
+    /// like the focused control.
+    pub fn announce_now(text: &str) {
+        Self::speak(text, true);
+    }
+
+    /// Stops the announcement being spoken.
+    pub fn stop() {
+        if let Ok(mut backend) = BACKEND.lock() {
+            if let Some(ref mut backend) = *backend {
+                backend.stop();
+            }
+        }
+    }
+
+    fn speak(text: &str, interrupt: bool) {
+        if text.is_empty() {
+            return;
+        }
+        if let Ok(mut backend) = BACKEND.lock() {
+            if let Some(ref mut backend) = *backend {
+                backend.speak(text, interrupt);
+            }
+        }
+    }
+}
+
+/// The text to speech of the engine, for the engines which have one.
+pub struct EngineSpeech {
+    voice: String,
+}
+
+impl EngineSpeech {
+    /// The backend, or `None` if the engine has no text to speech.
+    pub fn new() -> Option<Self> {
+        let mut os = os_object();
+        if !os.has_method(GodotString::from_str("tts_speak")) {
+            return None;
+        }
+
+        // The first voice of the language of the player, else the default one.
+        let language = os.call(GodotString::from_str("get_locale_language"), &[]);
+        let voices = os.call(GodotString::from_str("tts_get_voices_for_language"), &[language]);
+        let voice = voices.try_to_string_array()
+            .filter(|voices| voices.len() > 0)
+            .map(|voices| voices.get(0).to_string())
+            .unwrap_or_default();
+        Some(EngineSpeech { voice })
+    }
+}
+
+impl SpeechBackend for EngineSpeech {
+    fn speak(&mut self, text: &str, interrupt: bool) {
+        os_object().call(GodotString::from_str("tts_speak"), &[
+            Variant::from_str(text),
+            Variant::from_str(&self.voice),
+            Variant::from_i64(50),
+            1.0.to_variant(),
+            1.0.to_variant(),
+            Variant::from_i64(0),
+            Variant::from_bool(interrupt),
+        ]);
+    }
+
+    fn stop(&mut self) {
+        os_object().call(GodotString::from_str("tts_stop"), &[]);
+    }
+}
+
+fn os_object() -> Object {
+    unsafe { Object::from_sys(_OS::godot_singleton().to_sys()) }
+}
+
+/// The speech command of the platform. Only available with the `tts_command`
+/// feature.
+///
+/// The announced text often comes from the players (names, chat messages), so it
+/// is never part of a script: PowerShell reads it from its standard input, and
+/// `say` and `spd-say` receive it after `--`, where it can't be parsed as an option.
+#[cfg(feature = "tts_command")]
+pub struct CommandSpeech {
+    os_name: String,
+    // The processes speaking, stopped by the next interrupting announcement.
+    children: Vec<Child>,
+}
+
+#[cfg(feature = "tts_command")]
+impl CommandSpeech {
+    /// The backend, or `None` on the platforms without a known speech command.
+    pub fn new() -> Option<Self> {
+        let os_name = _OS::godot_singleton().get_name().to_string();
+        speech_command(&os_name, "")?;
+        Some(CommandSpeech { os_name, children: Vec::new() })
+    }
+}
+
+#[cfg(feature = "tts_command")]
+impl SpeechBackend for CommandSpeech {
+    fn speak(&mut self, text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+        }
+        // Reap the processes which finished speaking.
+        self.children = self.children
+            .drain(..)
+            .filter_map(|mut child| match child.try_wait() {
+                Ok(None) => Some(child),
+                _ => None,
+            })
+            .collect();
+
+        let command = match speech_command(&self.os_name, text) {
+            Some(command) => command,
+            None => return,
+        };
+        let mut process = Command::new(command.program);
+        process.args(&command.args).stdout(Stdio::null()).stderr(Stdio::null());
+        process.stdin(if command.stdin.is_some() { Stdio::piped() } else { Stdio::null() });
+
+        let mut child = match process.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                godot_error!("can't run {}: {}", command.program, err);
+                return;
+            }
+        };
+        if let (Some(input), Some(mut stdin)) = (command.stdin, child.stdin.take()) {
+            // Dropping `stdin` closes it, which ends the text.
+            if let Err(err) = stdin.write_all(input.as_bytes()) {
+                godot_error!("can't send the text to {}: {}", command.program, err);
+            }
+        }
+        self.children.push(child);
+    }
+
+    fn stop(&mut self) {
+        for mut child in self.children.drain(..) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// The program speaking the text on the platform, its arguments, and what it reads
+// from its standard input.
+#[cfg(feature = "tts_command")]
+#[derive(Debug, PartialEq)]
+struct SpeechCommand {
+    program: &'static str,
+    args: Vec<String>,
+    stdin: Option<String>,
+}
+
+#[cfg(feature = "tts_command")]
+fn speech_command(os_name: &str, text: &str) -> Option<SpeechCommand> {
+    let (program, args, stdin) = match os_name {
+        "OSX" => ("say", vec!["--".to_string(), text.to_string()], None),
+        "X11" => ("spd-say", vec!["--wait".to_string(), "--".to_string(), text.to_string()], None),
+        "Windows" => ("powershell", vec![
+            "-NoProfile".to_string(),
+            "-NonInteractive".to_string(),
+            "-Command".to_string(),
+            "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+             Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak([Console]::In.ReadToEnd())"
+                .to_string(),
+        ], Some(text.to_string())),
+        _ => return None,
+    };
+    Some(SpeechCommand { program, args, stdin })
+}
+
+/// The text to speech of the engine if it has one, else, with the `tts_command`
+/// feature, the speech command of the platform.
+pub fn default_speech_backend() -> Option<Box<dyn SpeechBackend + Send>> {
+    if let Some(engine) = EngineSpeech::new() {
+        return Some(Box::new(engine));
+    }
+
+    #[cfg(feature = "tts_command")]
+    {
+        if let Some(command) = CommandSpeech::new() {
+            return Some(Box::new(command));
+        }
+    }
+
+    None
+}
+
+impl SpeechBackend for Box<dyn SpeechBackend + Send> {
+    fn speak(&mut self, text: &str, interrupt: bool) {
+        (**self).speak(text, interrupt);
+    }
+
+    fn stop(&mut self) {
+        (**self).stop();
+    }
+}
+
+#[test]
+fn announcements() {
+    use std::sync::Arc;
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl SpeechBackend for Recorder {
+        fn speak(&mut self, text: &str, interrupt: bool) {
+            self.0.lock().unwrap().push(format!("{}{}", if interrupt { "!" } else { "" }, text));
+        }
+
+        fn stop(&mut self) {
+            self.0.lock().unwrap().push("stop".to_string());
+        }
+    }
+
+    Accessibility::announce("dropped");
+    assert!(!Accessibility::has_backend());
+
+    let spoken = Arc::new(Mutex::new(Vec::new()));
+    Accessibility::set_backend(Recorder(spoken.clone()));
+    Accessibility::announce("Objective: find the key");
+    Accessibility::announce("");
+    Accessibility::announce_now("Play, button");
+    Accessibility::clear_backend();
+    Accessibility::announce("dropped");
+
+    assert_eq!(*spoken.lock().unwrap(), vec!["Objective: find the key", "!Play, button", "stop"]);
+}
+
+#[cfg(feature = "tts_command")]
+#[test]
+fn speech_commands() {
+    let texts = ["Hi", "It's", "\u{2018}); Remove-Item -Recurse ~ #\u{2019}", "say \"hi\"", "-o file", "--help"];
+    for &text in &texts {
+        let say = speech_command("OSX", text).unwrap();
+        assert_eq!(say.program, "say");
+        assert_eq!(say.args, vec!["--".to_string(), text.to_string()]);
+
+        let spd_say = speech_command("X11", text).unwrap();
+        assert_eq!(spd_say.args[1..], ["--".to_string(), text.to_string()]);
+
+        // The script is the same whatever the text, which only goes to stdin.
+        let powershell = speech_command("Windows", text).unwrap();
+        assert_eq!(powershell.program, "powershell");
+        assert_eq!(powershell.args, speech_command("Windows", "").unwrap().args);
+        assert_eq!(powershell.stdin, Some(text.to_string()));
+    }
+    assert_eq!(speech_command("Android", "Hi"), None);
+}
@@ -0,0 +1,216 @@
+//! Scripts made of rust closures, created at runtime.
+//!
+//! Declaring a struct and registering its class is overkill for a few callbacks in
+//! tooling, tests or glue code. `AdhocScript` registers a new class from a set of
+//! closures and attaches a script of that class to an existing object:
+//!
+//! ```ignore
+//! let presses = Rc::new(Cell::new(0));
+//! let counter = presses.clone();
+//!
+//! AdhocScript::new()
+//!     .method("on_pressed", move |_args| counter.set(counter.get() + 1))
+//!     .attach_to(&button)
+//!     .expect("The library is not initialized");
+//!
+//! button.connect("pressed".into(), Some(button.as_object()), "on_pressed".into(), VariantArray::new(), 0)
+//!     .unwrap();
+//! ```
+//!
+//! NativeScript can't unregister classes: each call to `attach_to` registers a class
+//! that lives until the library is unloaded. Ad-hoc scripts shouldn't be created for
+//! objects spawned every frame.
+//!
+//! The closures don't need to be `Send`, so they only run on the thread which called
+//! `attach_to`. A call from another thread prints an error and returns nil.
+
+use std::cell::RefCell;
+use std::slice;
+use std::thread::{self, ThreadId};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use diagnostics::catch_panic;
+use init::{register_after_init, ClassDescriptor, RegisterError, ScriptMethod, ScriptMethodAttributes};
+use info;
+use libc;
+use sys;
+use GodotObject;
+use GodotString;
+use NativeClass;
+use LocalCellData;
+use NativeInstanceHeader;
+use NativeScript;
+use Object;
+use ReturnValue;
+use Variant;
+use {alloc_boxed, free_boxed};
+
+type AdhocMethod = dyn FnMut(&[&Variant]) -> Variant;
+
+static NEXT_CLASS_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A set of closures registered as a script class at runtime, see the
+/// `adhoc_script` module.
+pub struct AdhocScript {
+    methods: Vec<(String, Box<AdhocMethod>)>,
+}
+
+impl AdhocScript {
+    pub fn new() -> Self {
+        AdhocScript { methods: Vec::new() }
+    }
+
+    /// Adds a method to the script. The closure receives the arguments of the call.
+    pub fn method<F, R>(mut self, name: &str, mut func: F) -> Self
+    where
+        F: FnMut(&[&Variant]) -> R + 'static,
+        R: ReturnValue,
+    {
+        let func = move |args: &[&Variant]| func(args).to_return_variant();
+        self.methods.push((name.to_string(), Box::new(func)));
+        self
+    }
+
+    /// Registers the class of the script and attaches a script of this class to
+    /// `owner`, replacing its current script.
+    ///
+    /// Returns the script, or `None` if the library is not initialized. The script
+    /// can also be attached to other objects, which then share the closures.
+    pub fn attach_to<O: GodotObject>(self, owner: &O) -> Option<NativeScript> {
+        let library = info::library_resource()?;
+        let class_name = format!("AdhocScript{}", NEXT_CLASS_ID.fetch_add(1, Ordering::Relaxed));
+        self.register(&class_name).ok()?;
+
+        let mut script = NativeScript::new();
+        script.set_class_name(GodotString::from_str(&class_name));
+        script.set_library(Some(library));
+
+        let mut owner = unsafe { Object::from_sys(owner.to_sys()) };
+        owner.set_script(script.cast());
+
+        Some(script)
+    }
+
+    fn register(self, class_name: &str) -> Result<(), RegisterError> {
+        register_after_init(|handle| {
+            let class = handle.add_class::<AdhocInstance>(ClassDescriptor {
+                name: class_name,
+                base_class: "Object",
+                constructor: Some(godot_wrap_constructor!(AdhocInstance, AdhocInstance::new)),
+                destructor: Some(godot_wrap_destructor!(AdhocInstance)),
+            });
+
+            for (name, func) in self.methods {
+                let method = MethodData {
+                    name: format!("{}::{}", class_name, name),
+                    thread: thread::current().id(),
+                    func: RefCell::new(func),
+                };
+
+                class.add_method_advanced(ScriptMethod {
+                    name: &name,
+                    method_ptr: Some(invoke),
+                    attributes: ScriptMethodAttributes::default(),
+                    documentation: None,
+                    method_data: alloc_boxed(method) as *mut _,
+                    free_func: Some(free_func),
+                });
+            }
+        })
+    }
+}
+
+struct AdhocInstance {
+    header: NativeInstanceHeader,
+}
+
+impl AdhocInstance {
+    fn new(header: NativeInstanceHeader) -> Self {
+        AdhocInstance { header }
+    }
+}
+
+impl NativeClass for AdhocInstance {
+    type UserData = LocalCellData<AdhocInstance>;
+
+    fn class_name() -> &'static str {
+        "AdhocScript"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+struct MethodData {
+    name: String,
+    // The closure is neither `Send` nor `Sync`, it is only called on this thread.
+    thread: ThreadId,
+    func: RefCell<Box<AdhocMethod>>,
+}
+
+extern "C" fn invoke(
+    _this: *mut sys::godot_object,
+    method: *mut libc::c_void,
+    _class: *mut libc::c_void,
+    num_args: libc::c_int,
+    args: *mut *mut sys::godot_variant
+) -> sys::godot_variant {
+    unsafe {
+        let method = &*(method as *mut MethodData);
+        if thread::current().id() != method.thread {
+            godot_error!("{}: the method can only be called from the thread which created the script", method.name);
+            return Variant::new().forget();
+        }
+
+        let args: Vec<&Variant> = if args.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(args, num_args as usize)
+                .iter()
+                .map(|&arg| Variant::cast_ref(arg))
+                .collect()
+        };
+
+        let mut func = match method.func.try_borrow_mut() {
+            Ok(func) => func,
+            Err(_) => {
+                godot_error!("{}: the method can't be called recursively", method.name);
+                return Variant::new().forget();
+            }
+        };
+
+        catch_panic(&method.name, || (&mut **func)(&args))
+            .unwrap_or_else(Variant::new)
+            .forget()
+    }
+}
+
+extern "C" fn free_func(data: *mut libc::c_void) {
+    unsafe {
+        free_boxed(data as *mut MethodData);
+    }
+}
+
+godot_test!(test_adhoc_script {
+    use Reference;
+
+    let object = Reference::new();
+    let script = AdhocScript::new()
+        .method("double", |args| args[0].to_i64() * 2)
+        .attach_to(&object);
+    assert!(script.is_some());
+
+    let mut object = object.as_object();
+    let ret = object.call(GodotString::from_str("double"), &[Variant::from_i64(21)]);
+    assert_eq!(ret.to_i64(), 42);
+
+    // The objects aren't `Send`: the thread gets the pointer, the `Reference` created
+    // above keeps the object alive.
+    let this = unsafe { object.to_sys() } as usize;
+    let ret = thread::spawn(move || {
+        let mut object = unsafe { Object::from_sys(this as *mut sys::godot_object) };
+        let ret = object.call(GodotString::from_str("double"), &[Variant::from_i64(21)]);
+        ret.is_nil()
+    }).join();
+    assert_eq!(ret.ok(), Some(true));
+});
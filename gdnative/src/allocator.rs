@@ -0,0 +1,163 @@
+//! Allocations tracked by the engine.
+//!
+//! Memory allocated with `godot_alloc` shows up in Godot's memory monitors (in debug
+//! builds of the engine). `GodotAllocator` can be used as a global allocator, and
+//! with the `engine_allocator` feature the allocations made by this crate (instance
+//! user data, boxed property accessors) are routed through it as well.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use libc;
+use try_get_api;
+
+// Alignment of the pointers returned by `godot_alloc`.
+const ENGINE_ALIGN: usize = 16;
+
+// Size of the header placed before each allocation. It stores whether the
+// memory comes from the engine or from the system allocator.
+const HEADER_SIZE: usize = 16;
+
+const FROM_SYSTEM: usize = 0;
+const FROM_ENGINE: usize = 1;
+
+/// An allocator that uses the engine's `godot_alloc` and `godot_free`.
+///
+/// Before the API is bound by `godot_gdnative_init` (and after
+/// `godot_gdnative_terminate`), and for alignments the engine does not guarantee,
+/// memory is obtained from the system allocator instead. Each allocation remembers
+/// where it comes from so it is always released with the right function.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: gdnative::GodotAllocator = gdnative::GodotAllocator;
+/// ```
+pub struct GodotAllocator;
+
+unsafe impl GlobalAlloc for GodotAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let total = layout.size() + header_size(layout);
+
+        if let Some(api) = try_get_api() {
+            if layout.align() <= ENGINE_ALIGN && total <= libc::c_int::max_value() as usize {
+                let base = (api.godot_alloc)(total as libc::c_int) as *mut u8;
+                if base.is_null() {
+                    return base;
+                }
+                return write_header(base, layout, FROM_ENGINE);
+            }
+        }
+
+        let base = System.alloc(system_layout(layout));
+        if base.is_null() {
+            return base;
+        }
+        write_header(base, layout, FROM_SYSTEM)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let base = ptr.offset(-(header_size(layout) as isize));
+        let tag = *(ptr.offset(-(mem::size_of::<usize>() as isize)) as *const usize);
+
+        if tag == FROM_ENGINE {
+            if let Some(api) = try_get_api() {
+                (api.godot_free)(base as *mut _);
+            }
+            // Otherwise the library is being unloaded and the engine already
+            // released its allocator, there is nothing left to free.
+        } else {
+            System.dealloc(base, system_layout(layout));
+        }
+    }
+}
+
+fn header_size(layout: Layout) -> usize {
+    if layout.align() > HEADER_SIZE { layout.align() } else { HEADER_SIZE }
+}
+
+fn system_layout(layout: Layout) -> Layout {
+    let align = if layout.align() > ENGINE_ALIGN { layout.align() } else { ENGINE_ALIGN };
+    Layout::from_size_align(layout.size() + header_size(layout), align)
+        .expect("Allocation too large")
+}
+
+unsafe fn write_header(base: *mut u8, layout: Layout, tag: usize) -> *mut u8 {
+    let ptr = base.offset(header_size(layout) as isize);
+    *(ptr.offset(-(mem::size_of::<usize>() as isize)) as *mut usize) = tag;
+    ptr
+}
+
+// The number and size of the values allocated with `alloc_boxed`, see `MemoryReport`.
+static LIVE_BOXES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BOX_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg_attr(not(gdnative_helper = "memory_report"), allow(dead_code))]
+pub(crate) fn live_boxes() -> (usize, usize) {
+    (LIVE_BOXES.load(Ordering::Relaxed), LIVE_BOX_BYTES.load(Ordering::Relaxed))
+}
+
+/// Moves a value to the heap and returns a raw pointer to it, like
+/// `Box::into_raw(Box::new(val))`.
+///
+/// With the `engine_allocator` feature the memory is obtained from `GodotAllocator`.
+/// The pointer must be released with `free_boxed`.
+#[doc(hidden)]
+pub fn alloc_boxed<T>(val: T) -> *mut T {
+    LIVE_BOXES.fetch_add(1, Ordering::Relaxed);
+    LIVE_BOX_BYTES.fetch_add(mem::size_of::<T>(), Ordering::Relaxed);
+
+    if !use_engine_allocator::<T>() {
+        return Box::into_raw(Box::new(val));
+    }
+
+    unsafe {
+        let layout = Layout::new::<T>();
+        let ptr = GodotAllocator.alloc(layout) as *mut T;
+        if ptr.is_null() {
+            ::std::alloc::handle_alloc_error(layout);
+        }
+        ptr::write(ptr, val);
+        ptr
+    }
+}
+
+/// Drops and deallocates a value created with `alloc_boxed`.
+#[doc(hidden)]
+pub unsafe fn free_boxed<T>(ptr: *mut T) {
+    LIVE_BOXES.fetch_sub(1, Ordering::Relaxed);
+    LIVE_BOX_BYTES.fetch_sub(mem::size_of::<T>(), Ordering::Relaxed);
+
+    if !use_engine_allocator::<T>() {
+        drop(Box::from_raw(ptr));
+        return;
+    }
+
+    ptr::drop_in_place(ptr);
+    GodotAllocator.dealloc(ptr as *mut u8, Layout::new::<T>());
+}
+
+fn use_engine_allocator<T>() -> bool {
+    cfg!(feature = "engine_allocator") && mem::size_of::<T>() != 0
+}
+
+#[test]
+fn system_fallback_alloc() {
+    // The API is not bound in unit tests so this exercises the system path.
+    for &(size, align) in &[(1, 1), (24, 8), (100, 16), (64, 64), (3, 4096)] {
+        let layout = Layout::from_size_align(size, align).unwrap();
+        unsafe {
+            let ptr = GodotAllocator.alloc(layout);
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % align, 0);
+            ptr::write_bytes(ptr, 0xAB, size);
+            GodotAllocator.dealloc(ptr, layout);
+        }
+    }
+
+    let boxed = alloc_boxed(String::from("hello"));
+    unsafe {
+        assert_eq!(&*boxed, "hello");
+        free_boxed(boxed);
+    }
+}
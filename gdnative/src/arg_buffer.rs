@@ -0,0 +1,105 @@
+// The arrays of pointers to the arguments of the variadic calls, like `emit_signal`
+// and `call`, are taken from a pool of the thread instead of being allocated by
+// every call. The calls can be nested, a signal handler emitting another signal for
+// example, so the pool holds one array per level of nesting.
+
+use std::cell::RefCell;
+use std::ops::Deref;
+use sys;
+use Variant;
+
+// The arrays kept by a thread, deeper nestings allocate.
+const MAX_POOLED: usize = 8;
+// The larger arrays are freed, a call with many arguments shouldn't keep them alive.
+const MAX_POOLED_CAPACITY: usize = 64;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<*const sys::godot_variant>>> = RefCell::new(Vec::new());
+}
+
+/// The pointers to the arguments of a call, returned to the pool when dropped.
+pub(crate) struct ArgBuffer {
+    args: Vec<*const sys::godot_variant>,
+}
+
+impl ArgBuffer {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let mut args = POOL.try_with(|pool| pool.borrow_mut().pop())
+            .ok()
+            .and_then(|args| args)
+            .unwrap_or_else(Vec::new);
+        args.reserve(capacity);
+        ArgBuffer { args }
+    }
+
+    /// The pointers to `args`, which must outlive the buffer.
+    pub(crate) fn from_variants(args: &[Variant]) -> Self {
+        let mut buffer = ArgBuffer::with_capacity(args.len());
+        for arg in args {
+            buffer.push(&arg.0);
+        }
+        buffer
+    }
+
+    pub(crate) fn push(&mut self, arg: *const sys::godot_variant) {
+        self.args.push(arg);
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut *const sys::godot_variant {
+        self.args.as_mut_ptr()
+    }
+}
+
+impl Deref for ArgBuffer {
+    type Target = [*const sys::godot_variant];
+
+    fn deref(&self) -> &Self::Target {
+        &self.args
+    }
+}
+
+impl Drop for ArgBuffer {
+    fn drop(&mut self) {
+        if self.args.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+
+        let mut args = ::std::mem::replace(&mut self.args, Vec::new());
+        args.clear();
+        // The pool is gone while the thread exits.
+        let _ = POOL.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_POOLED {
+                pool.push(args);
+            }
+        });
+    }
+}
+
+#[test]
+fn arg_buffer_reuse() {
+    let values = [0u8; 4];
+    let ptr = |idx: usize| &values[idx] as *const u8 as *const sys::godot_variant;
+
+    let first = {
+        let mut buffer = ArgBuffer::with_capacity(2);
+        buffer.push(ptr(0));
+        buffer.push(ptr(1));
+        assert_eq!(&*buffer, &[ptr(0), ptr(1)]);
+        buffer.as_mut_ptr() as usize
+    };
+
+    // The nested buffers don't share an array.
+    let mut outer = ArgBuffer::with_capacity(1);
+    let mut inner = ArgBuffer::with_capacity(1);
+    assert!(outer.is_empty());
+    assert_eq!(outer.as_mut_ptr() as usize, first);
+    assert_ne!(inner.as_mut_ptr() as usize, first);
+    drop(inner);
+    drop(outer);
+
+    let pooled = || POOL.with(|pool| pool.borrow().len());
+    assert_eq!(pooled(), 2);
+    drop(ArgBuffer::with_capacity(MAX_POOLED_CAPACITY + 1));
+    assert_eq!(pooled(), 1);
+}
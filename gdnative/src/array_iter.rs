@@ -0,0 +1,240 @@
+//! Iteration over `VariantArray` and the pool arrays.
+//!
+//! The arrays can be iterated by value or by reference, collected from iterators
+//! and extended:
+//!
+//! ```ignore
+//! let names: StringArray = nodes.iter().map(|node| node.get_name()).collect();
+//! let values: VariantArray = (0..10i64).collect();
+//! let total: i32 = scores.iter().sum();
+//!
+//! for value in &array {
+//!     godot_print!("{:?}", value);
+//! }
+//! ```
+//!
+//! Iterating a `VariantArray` by reference yields references to its elements, the
+//! other iterators yield copies. Iterators walk the elements the array had when they
+//! were created, they stop early if the array shrinks through another reference.
+
+use ByteArray;
+use Color;
+use ColorArray;
+use Float32Array;
+use GodotString;
+use Int32Array;
+use StringArray;
+use ToVariant;
+use Variant;
+use VariantArray;
+use Vector2;
+use Vector2Array;
+use Vector3;
+use Vector3Array;
+use std::iter::FromIterator;
+
+/// Access to the elements of an array, implemented for the arrays and references to
+/// them.
+#[doc(hidden)]
+pub trait ArrayElements {
+    type Element;
+
+    fn element_count(&self) -> i32;
+
+    fn element(&self, idx: i32) -> Self::Element;
+}
+
+/// An iterator over the elements of a `VariantArray` or a pool array.
+pub struct ArrayIter<A> {
+    array: A,
+    next: i32,
+    end: i32,
+}
+
+impl<A: ArrayElements> ArrayIter<A> {
+    fn new(array: A) -> Self {
+        let end = array.element_count();
+        ArrayIter { array, next: 0, end }
+    }
+
+    // The array can shrink through another reference while it is iterated.
+    fn end(&self) -> i32 {
+        self.end.min(self.array.element_count())
+    }
+}
+
+impl<A: ArrayElements> Iterator for ArrayIter<A> {
+    type Item = A::Element;
+
+    fn next(&mut self) -> Option<A::Element> {
+        if self.next >= self.end() {
+            return None;
+        }
+        let element = self.array.element(self.next);
+        self.next += 1;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.end() - self.next).max(0) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<A: ArrayElements> DoubleEndedIterator for ArrayIter<A> {
+    fn next_back(&mut self) -> Option<A::Element> {
+        self.end = self.end();
+        if self.next >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.array.element(self.end))
+    }
+}
+
+impl<A: ArrayElements> ExactSizeIterator for ArrayIter<A> {}
+
+macro_rules! array_iter_impl {
+    (
+        $Array:ident, $Element:ty, $RefElement:ty,
+        get($get_array:ident, $get_idx:ident) $get:expr,
+        get_ref($ref_array:ident, $ref_idx:ident) $get_ref:expr
+    ) => (
+        impl $Array {
+            /// Returns an iterator over the elements of the array.
+            pub fn iter(&self) -> ArrayIter<&$Array> {
+                ArrayIter::new(self)
+            }
+        }
+
+        impl ArrayElements for $Array {
+            type Element = $Element;
+
+            fn element_count(&self) -> i32 {
+                self.len()
+            }
+
+            fn element(&self, idx: i32) -> $Element {
+                let $get_array = self;
+                let $get_idx = idx;
+                $get
+            }
+        }
+
+        impl<'a> ArrayElements for &'a $Array {
+            type Element = $RefElement;
+
+            fn element_count(&self) -> i32 {
+                self.len()
+            }
+
+            fn element(&self, idx: i32) -> $RefElement {
+                let $ref_array: &'a $Array = *self;
+                let $ref_idx = idx;
+                $get_ref
+            }
+        }
+
+        impl IntoIterator for $Array {
+            type Item = $Element;
+            type IntoIter = ArrayIter<$Array>;
+
+            fn into_iter(self) -> ArrayIter<$Array> {
+                ArrayIter::new(self)
+            }
+        }
+
+        impl<'a> IntoIterator for &'a $Array {
+            type Item = $RefElement;
+            type IntoIter = ArrayIter<&'a $Array>;
+
+            fn into_iter(self) -> ArrayIter<&'a $Array> {
+                ArrayIter::new(self)
+            }
+        }
+    );
+    (
+        $Array:ident, $Element:ty,
+        push($push_array:ident, $push_value:ident) $push:expr
+    ) => (
+        array_iter_impl!(
+            $Array, $Element, $Element,
+            get(array, idx) array.get(idx),
+            get_ref(array, idx) array.get(idx)
+        );
+
+        impl Extend<$Element> for $Array {
+            fn extend<I: IntoIterator<Item = $Element>>(&mut self, iter: I) {
+                for value in iter {
+                    let $push_array = &mut *self;
+                    let $push_value = value;
+                    $push;
+                }
+            }
+        }
+
+        impl FromIterator<$Element> for $Array {
+            fn from_iter<I: IntoIterator<Item = $Element>>(iter: I) -> Self {
+                let mut array = $Array::new();
+                array.extend(iter);
+                array
+            }
+        }
+    )
+}
+
+array_iter_impl!(
+    VariantArray, Variant, &'a Variant,
+    get(array, idx) array.get_ref(idx).clone(),
+    get_ref(array, idx) array.get_ref(idx)
+);
+
+/// Converts the values with `ToVariant::to_variant`.
+impl<T: ToVariant> Extend<T> for VariantArray {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(&value.to_variant());
+        }
+    }
+}
+
+/// Converts the values with `ToVariant::to_variant`.
+impl<T: ToVariant> FromIterator<T> for VariantArray {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut array = VariantArray::new();
+        array.extend(iter);
+        array
+    }
+}
+
+array_iter_impl!(ByteArray, u8, push(array, value) array.push(value));
+array_iter_impl!(Int32Array, i32, push(array, value) array.push(value));
+array_iter_impl!(Float32Array, f32, push(array, value) array.push(value));
+array_iter_impl!(StringArray, GodotString, push(array, value) array.push(&value));
+array_iter_impl!(Vector2Array, Vector2, push(array, value) array.push(&value));
+array_iter_impl!(Vector3Array, Vector3, push(array, value) array.push(&value));
+array_iter_impl!(ColorArray, Color, push(array, value) array.push(&value));
+
+godot_test!(test_array_iter {
+    let bytes: ByteArray = (1..4u8).collect();
+    assert_eq!(bytes.len(), 3);
+    assert_eq!(bytes.iter().map(u32::from).sum::<u32>(), 6);
+    assert_eq!(bytes.iter().rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+
+    let mut ints: Int32Array = vec![1, 2].into_iter().collect();
+    ints.extend(vec![3, 4]);
+    assert_eq!(ints.into_iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+    let strings: StringArray = vec!["a", "b"].into_iter().map(GodotString::from_str).collect();
+    assert_eq!((&strings).into_iter().len(), 2);
+
+    let array: VariantArray = vec![1i64, 2, 3].into_iter().collect();
+    let mut sum = 0;
+    for value in &array {
+        sum += value.to_i64();
+    }
+    assert_eq!(sum, 6);
+
+    let copy: VariantArray = array.iter().cloned().collect();
+    assert_eq!(copy.len(), 3);
+});
@@ -0,0 +1,360 @@
+//! The direct state of a rigid body, for custom integrators.
+//!
+//! The engine calls `_integrate_forces` on the scripts of `RigidBody` nodes during
+//! the physics step, with a `PhysicsDirectBodyState` only valid during that call.
+//! Declaring the argument as a `DirectBodyState` ties it to the call, so that it
+//! can't be stored in the instance, and gives typed access to the state:
+//!
+//! ```ignore
+//! export fn _integrate_forces(&mut self, mut state: DirectBodyState) {
+//!     let grounded = state.contacts().any(|contact| contact.local_normal().y > 0.7);
+//!     if grounded && self.jump {
+//!         state.apply_central_impulse(Vector3::new(0.0, self.jump_impulse, 0.0));
+//!     }
+//!
+//!     let mut velocity = state.linear_velocity();
+//!     velocity.x = velocity.x.max(-self.max_speed).min(self.max_speed);
+//!     state.set_linear_velocity(velocity);
+//! }
+//! ```
+//!
+//! Without `custom_integrator` set on the body, the engine integrates the forces
+//! and the velocities after the call. The contacts are only reported when
+//! `contact_monitor` is set and `contacts_reported` is above 0.
+//!
+//! Each accessor of a `Contact` is a call to the engine. Code going over the
+//! contacts several times, or sorting them, reads them once with
+//! `DirectBodyState::read_contacts` into a buffer kept in the instance:
+//!
+//! ```ignore
+//! state.read_contacts(&mut self.contacts);
+//! let ground = self.contacts.iter().filter(|contact| contact.local_normal.y > 0.7).count();
+//! let walls = self.contacts.len() - ground;
+//! ```
+//!
+//! The engine has no call returning all the contacts, so reading them still costs
+//! a call per field, but only once per step. The query results of
+//! `PhysicsDirectSpaceState` are arrays of dictionaries, they are converted the same
+//! way by `intersect_shape_into` and `collide_shape_into`.
+
+use std::marker::PhantomData;
+use FromVariant;
+use FromVariantError;
+use Basis;
+use Object;
+use PhysicsDirectBodyState;
+use PhysicsDirectSpaceState;
+use PhysicsShapeQueryParameters;
+use Rid;
+use Transform;
+use Variant;
+use Vector3;
+
+/// The argument of `_integrate_forces`, see the `body_state` module.
+///
+/// The lifetime is the one of the call, it is inferred when the argument is
+/// declared as `DirectBodyState`.
+pub struct DirectBodyState<'a> {
+    state: PhysicsDirectBodyState,
+    _call: PhantomData<&'a ()>,
+}
+
+impl<'a> DirectBodyState<'a> {
+    /// The duration of the physics step, in seconds.
+    pub fn step(&self) -> f64 {
+        self.state.get_step()
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.state.get_transform()
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.state.set_transform(transform);
+    }
+
+    pub fn linear_velocity(&self) -> Vector3 {
+        self.state.get_linear_velocity()
+    }
+
+    pub fn set_linear_velocity(&mut self, velocity: Vector3) {
+        self.state.set_linear_velocity(velocity);
+    }
+
+    pub fn angular_velocity(&self) -> Vector3 {
+        self.state.get_angular_velocity()
+    }
+
+    pub fn set_angular_velocity(&mut self, velocity: Vector3) {
+        self.state.set_angular_velocity(velocity);
+    }
+
+    /// The sum of the gravity of the world and of the areas the body is in.
+    pub fn total_gravity(&self) -> Vector3 {
+        self.state.get_total_gravity()
+    }
+
+    pub fn total_linear_damp(&self) -> f64 {
+        self.state.get_total_linear_damp()
+    }
+
+    pub fn total_angular_damp(&self) -> f64 {
+        self.state.get_total_angular_damp()
+    }
+
+    pub fn center_of_mass(&self) -> Vector3 {
+        self.state.get_center_of_mass()
+    }
+
+    pub fn principal_inertia_axes(&self) -> Basis {
+        self.state.get_principal_inertia_axes()
+    }
+
+    /// `1 / mass`, 0 for static and kinematic bodies.
+    pub fn inverse_mass(&self) -> f64 {
+        self.state.get_inverse_mass()
+    }
+
+    pub fn inverse_inertia(&self) -> Vector3 {
+        self.state.get_inverse_inertia()
+    }
+
+    /// Applies a force during the step, at a position relative to the center of
+    /// mass, in global orientation.
+    pub fn add_force(&mut self, force: Vector3, position: Vector3) {
+        self.state.add_force(force, position);
+    }
+
+    pub fn add_central_force(&mut self, force: Vector3) {
+        self.add_force(force, Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    /// Changes the velocities at once, at a position relative to the center of mass,
+    /// in global orientation.
+    pub fn apply_impulse(&mut self, position: Vector3, impulse: Vector3) {
+        self.state.apply_impulse(position, impulse);
+    }
+
+    pub fn apply_central_impulse(&mut self, impulse: Vector3) {
+        self.apply_impulse(Vector3::new(0.0, 0.0, 0.0), impulse);
+    }
+
+    pub fn apply_torque_impulse(&mut self, impulse: Vector3) {
+        self.state.apply_torqe_impulse(impulse);
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.state.is_sleeping()
+    }
+
+    pub fn set_sleeping(&mut self, sleeping: bool) {
+        self.state.set_sleep_state(sleeping);
+    }
+
+    /// Runs the default integration of the forces, for custom integrators only
+    /// adjusting its result.
+    pub fn integrate_forces(&mut self) {
+        self.state.integrate_forces();
+    }
+
+    /// The contacts of the body during the step.
+    pub fn contacts(&self) -> Contacts<'_> {
+        Contacts {
+            state: &self.state,
+            next: 0,
+            count: self.state.get_contact_count().max(0),
+        }
+    }
+
+    /// Reads all the contacts of the body into `contacts`, replacing its content.
+    /// The buffer can be reused from one step to the next.
+    pub fn read_contacts(&self, contacts: &mut Vec<ContactData>) {
+        contacts.clear();
+        contacts.extend(self.contacts().map(|contact| contact.read()));
+    }
+
+    /// The space of the body, for ray casts and shape queries during the step.
+    pub fn space_state(&mut self) -> Option<PhysicsDirectSpaceState> {
+        self.state.get_space_state()
+    }
+
+    /// The engine object, for the methods without a typed equivalent.
+    pub fn as_raw(&self) -> &PhysicsDirectBodyState {
+        &self.state
+    }
+}
+
+impl<'a> FromVariant for DirectBodyState<'a> {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        Self::try_from_variant(variant).ok()
+    }
+
+    fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+        let state = variant.try_to_object::<PhysicsDirectBodyState>()
+            .ok_or_else(|| FromVariantError::invalid_type::<PhysicsDirectBodyState>(variant))?;
+        Ok(DirectBodyState { state, _call: PhantomData })
+    }
+}
+
+/// The contacts of a body, see `DirectBodyState::contacts`.
+pub struct Contacts<'s> {
+    state: &'s PhysicsDirectBodyState,
+    next: i64,
+    count: i64,
+}
+
+impl<'s> Iterator for Contacts<'s> {
+    type Item = Contact<'s>;
+
+    fn next(&mut self) -> Option<Contact<'s>> {
+        if self.next >= self.count {
+            return None;
+        }
+
+        let contact = Contact { state: self.state, idx: self.next };
+        self.next += 1;
+        Some(contact)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.count - self.next) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<'s> ExactSizeIterator for Contacts<'s> {}
+
+/// A contact of a body with another object. The values are read from the engine
+/// when requested.
+#[derive(Copy, Clone)]
+pub struct Contact<'s> {
+    state: &'s PhysicsDirectBodyState,
+    idx: i64,
+}
+
+impl<'s> Contact<'s> {
+    /// The index of the contact, for the methods of `PhysicsDirectBodyState`.
+    pub fn index(&self) -> i64 {
+        self.idx
+    }
+
+    pub fn local_position(&self) -> Vector3 {
+        self.state.get_contact_local_position(self.idx)
+    }
+
+    pub fn local_normal(&self) -> Vector3 {
+        self.state.get_contact_local_normal(self.idx)
+    }
+
+    /// The index of the shape of the body in contact.
+    pub fn local_shape(&self) -> i64 {
+        self.state.get_contact_local_shape(self.idx)
+    }
+
+    pub fn collider(&self) -> Rid {
+        self.state.get_contact_collider(self.idx)
+    }
+
+    pub fn collider_id(&self) -> i64 {
+        self.state.get_contact_collider_id(self.idx)
+    }
+
+    pub fn collider_object(&self) -> Option<Object> {
+        self.state.get_contact_collider_object(self.idx)
+    }
+
+    pub fn collider_position(&self) -> Vector3 {
+        self.state.get_contact_collider_position(self.idx)
+    }
+
+    /// The index of the shape of the collider in contact.
+    pub fn collider_shape(&self) -> i64 {
+        self.state.get_contact_collider_shape(self.idx)
+    }
+
+    pub fn collider_velocity(&self) -> Vector3 {
+        self.state.get_contact_collider_velocity_at_position(self.idx)
+    }
+
+    /// Reads all the fields of the contact but the collider object.
+    pub fn read(&self) -> ContactData {
+        ContactData {
+            local_position: self.local_position(),
+            local_normal: self.local_normal(),
+            local_shape: self.local_shape(),
+            collider: self.collider(),
+            collider_id: self.collider_id(),
+            collider_position: self.collider_position(),
+            collider_shape: self.collider_shape(),
+            collider_velocity: self.collider_velocity(),
+        }
+    }
+}
+
+/// The fields of a `Contact`, read at once.
+#[derive(Copy, Clone, Debug)]
+pub struct ContactData {
+    pub local_position: Vector3,
+    pub local_normal: Vector3,
+    pub local_shape: i64,
+    pub collider: Rid,
+    /// The instance id of the collider, for `instance_from_id`.
+    pub collider_id: i64,
+    pub collider_position: Vector3,
+    pub collider_shape: i64,
+    pub collider_velocity: Vector3,
+}
+
+/// A shape found by `PhysicsDirectSpaceState::intersect_shape_into`.
+#[derive(Copy, Clone, Debug)]
+pub struct ShapeIntersection {
+    pub collider: Rid,
+    /// The instance id of the collider, for `instance_from_id`.
+    pub collider_id: i64,
+    /// The index of the shape of the collider.
+    pub shape: i64,
+}
+
+impl PhysicsDirectSpaceState {
+    /// Finds the shapes intersecting the query shape, at most `max_results`, and
+    /// replaces the content of `results` with them.
+    pub fn intersect_shape_into(
+        &mut self,
+        query: &PhysicsShapeQueryParameters,
+        max_results: i64,
+        results: &mut Vec<ShapeIntersection>,
+    ) {
+        let found = self.intersect_shape(Some(query.new_ref()), max_results);
+
+        let rid = Variant::from_str("rid");
+        let collider_id = Variant::from_str("collider_id");
+        let shape = Variant::from_str("shape");
+
+        results.clear();
+        results.extend((0..found.len())
+            .filter_map(|idx| found.get_ref(idx).try_to_dictionary())
+            .map(|result| ShapeIntersection {
+                collider: result.get_ref(&rid).try_to_rid().unwrap_or_default(),
+                collider_id: result.get_ref(&collider_id).try_to_i64().unwrap_or(0),
+                shape: result.get_ref(&shape).try_to_i64().unwrap_or(0),
+            }));
+    }
+
+    /// Finds the points where the query shape touches other shapes, at most
+    /// `max_results` pairs, and replaces the content of `points` with them. Each pair
+    /// is a point of the query shape and a point of the other shape.
+    pub fn collide_shape_into(
+        &mut self,
+        query: &PhysicsShapeQueryParameters,
+        max_results: i64,
+        points: &mut Vec<(Vector3, Vector3)>,
+    ) {
+        let found = self.collide_shape(Some(query.new_ref()), max_results);
+        let found: Vec<Vector3> = (0..found.len())
+            .filter_map(|idx| found.get_ref(idx).try_to_vector3())
+            .collect();
+
+        points.clear();
+        points.extend(found.chunks(2).filter(|pair| pair.len() == 2).map(|pair| (pair[0], pair[1])));
+    }
+}
@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 
 /// A reference-counted vector of bytes that uses Godot's pool allocator.
@@ -22,6 +23,13 @@ impl ByteArray {
         }
     }
 
+    /// Creates an array containing a copy of the given bytes.
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut array = ByteArray::new();
+        array.extend_from_slice(bytes);
+        array
+    }
+
     /// Appends a byte to the end of the array.
     pub fn push(&mut self, byte: u8) {
         unsafe {
@@ -100,7 +108,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for ByteArray {
+impl_sys_access!(for ByteArray as godot_pool_byte_array);
+
+impl ToVariant for ByteArray {
     fn to_variant(&self) -> Variant { Variant::from_byte_array(self) }
+}
+
+impl FromVariant for ByteArray {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_byte_array() }
 }
@@ -0,0 +1,132 @@
+//! Calls of methods known by name, with typed arguments and return values.
+//!
+//! `Object::call` takes the arguments as variants, returns nil both for the methods
+//! returning nothing and for the failed calls, and only reports the failures in the
+//! console. `call!` converts the arguments, and returns the converted result or the
+//! reason the call failed:
+//!
+//! ```ignore
+//! let health: i64 = call!(enemy, "take_damage", 10, true)?;
+//! call!(hud, "show_message", "Level up")?;
+//! ```
+//!
+//! The type of the result is inferred, or given to `call`, which takes the arguments
+//! already converted:
+//!
+//! ```ignore
+//! let name = call::<GodotString>(&node, "get_display_name", &[])?;
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use arg_buffer::ArgBuffer;
+use get_api;
+use sys;
+use sys::godot_variant_call_error_error::*;
+use FromVariant;
+use FromVariantError;
+use GodotObject;
+use GodotString;
+use Variant;
+use VariantType;
+
+/// Why a call with `call!`, `call` or `Variant::try_call` failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    /// The object has no method of this name.
+    InvalidMethod { method: String },
+    /// The argument at `index` can't be converted to the type of the parameter.
+    InvalidArgument { index: i32, expected: VariantType },
+    /// The method takes at most `expected` arguments.
+    TooManyArguments { expected: i32 },
+    /// The method takes at least `expected` arguments.
+    TooFewArguments { expected: i32 },
+    /// The variant holds a null or freed object.
+    InstanceIsNull,
+    /// The returned value can't be converted to the expected type.
+    InvalidReturn(FromVariantError),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CallError::InvalidMethod { ref method } => write!(f, "the method {:?} doesn't exist", method),
+            CallError::InvalidArgument { index, expected } => {
+                write!(f, "the argument {} can't be converted to {:?}", index, expected)
+            }
+            CallError::TooManyArguments { expected } => write!(f, "too many arguments, expected at most {}", expected),
+            CallError::TooFewArguments { expected } => write!(f, "too few arguments, expected at least {}", expected),
+            CallError::InstanceIsNull => write!(f, "the object is null"),
+            CallError::InvalidReturn(ref err) => write!(f, "invalid return value: {}", err),
+        }
+    }
+}
+
+impl Error for CallError {}
+
+impl Variant {
+    /// Calls the method `method` of the value, an object or a builtin type, and
+    /// returns its result or the reason the call failed.
+    pub fn try_call(&mut self, method: &GodotString, args: &[Variant]) -> Result<Variant, CallError> {
+        unsafe {
+            let mut err = sys::godot_variant_call_error::default();
+            let mut argument_buffer = ArgBuffer::from_variants(args);
+            let ret = Variant::from_sys((get_api().godot_variant_call)(
+                &mut self.0,
+                &method.0,
+                argument_buffer.as_mut_ptr(),
+                args.len() as i32,
+                &mut err,
+            ));
+
+            match err.error {
+                GODOT_CALL_ERROR_CALL_OK => Ok(ret),
+                GODOT_CALL_ERROR_CALL_ERROR_INVALID_METHOD => Err(CallError::InvalidMethod { method: method.to_string() }),
+                GODOT_CALL_ERROR_CALL_ERROR_INVALID_ARGUMENT => Err(CallError::InvalidArgument {
+                    index: err.argument,
+                    expected: VariantType::from_sys(err.expected),
+                }),
+                GODOT_CALL_ERROR_CALL_ERROR_TOO_MANY_ARGUMENTS => Err(CallError::TooManyArguments { expected: err.argument }),
+                GODOT_CALL_ERROR_CALL_ERROR_TOO_FEW_ARGUMENTS => Err(CallError::TooFewArguments { expected: err.argument }),
+                GODOT_CALL_ERROR_CALL_ERROR_INSTANCE_IS_NULL => Err(CallError::InstanceIsNull),
+            }
+        }
+    }
+}
+
+/// Calls the method `method` of `object` and converts its result to `R`, see the
+/// `call` module and `call!`.
+pub fn call<R: FromVariant>(object: &impl GodotObject, method: &str, args: &[Variant]) -> Result<R, CallError> {
+    let mut variant = unsafe {
+        let mut dest = sys::godot_variant::default();
+        (get_api().godot_variant_new_object)(&mut dest, object.to_sys());
+        Variant::from_sys(dest)
+    };
+    let ret = variant.try_call(&GodotString::from_str(method), args)?;
+    R::try_from_variant(&ret).map_err(CallError::InvalidReturn)
+}
+
+godot_test!(test_call {
+    use Reference;
+    use ToVariant;
+
+    let object = Reference::new();
+    let class: GodotString = call(&object, "get_class", &[]).unwrap();
+    assert_eq!(class.to_string(), "Reference");
+    assert_eq!(call!(object, "is_class", "Object"), Ok(true));
+    assert_eq!(call!(object, "has_method", "get_class",), Ok(true));
+
+    assert_eq!(
+        call::<()>(&object, "missing_method", &[]),
+        Err(CallError::InvalidMethod { method: "missing_method".to_string() })
+    );
+    assert_eq!(call::<bool>(&object, "is_class", &[]), Err(CallError::TooFewArguments { expected: 1 }));
+    assert!(match call::<i64>(&object, "get_class", &[]) {
+        Err(CallError::InvalidReturn(_)) => true,
+        _ => false,
+    });
+
+    let mut string = "hello".to_variant();
+    let upper = string.try_call(&GodotString::from_str("to_upper"), &[]).unwrap();
+    assert_eq!(upper.to_string(), "HELLO");
+});
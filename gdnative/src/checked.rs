@@ -0,0 +1,216 @@
+//! Checked variants of engine calls that otherwise fail silently.
+//!
+//! Calls such as `Node::get_node`, `_ResourceLoader::load` or `Object::connect` only
+//! report failures by printing an error in the engine's console and returning `None`
+//! or an error code. The `try_*` methods in this module (enabled with the
+//! `checked_calls` feature) return an `EngineError` describing what went wrong
+//! instead.
+//!
+//! GDNative can't intercept the messages the engine prints: they still go to the
+//! console, and aren't part of the `EngineError`. The error holds the error code the
+//! engine returned, when it returns one, and a description built on the rust side
+//! by querying the engine about the failure.
+
+use std::error::Error;
+use std::fmt;
+use GodotError;
+use GodotObject;
+use GodotString;
+use NodePath;
+use Node;
+use Object;
+use Resource;
+use Variant;
+use VariantArray;
+use _ResourceLoader;
+
+/// The description of a failed engine call.
+///
+/// The message the engine printed in the console isn't captured, see the `checked`
+/// module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineError {
+    /// The name of the failed call, for example `"Node::get_node"`.
+    pub call: &'static str,
+    /// A human readable description of the failure, built by the rust side.
+    pub description: String,
+    /// The error code returned by the engine. `None` for the calls which don't
+    /// return one, like `get_node`, and for the failures found by the rust side
+    /// before calling the engine.
+    pub error: Option<GodotError>,
+}
+
+impl EngineError {
+    fn new(call: &'static str, description: String, error: Option<GodotError>) -> Self {
+        EngineError { call, description, error }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} failed: {}", self.call, self.description)?;
+        if let Some(err) = self.error {
+            write!(f, " ({:?})", err)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for EngineError {
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Node {
+    /// Checked version of `get_node`.
+    ///
+    /// Returns an error naming the missing path (and the path of this node if it is
+    /// inside the tree) instead of printing "Node not found" in the console.
+    pub fn try_get_node(&self, path: NodePath) -> Result<Node, EngineError> {
+        if self.has_node(path.new_ref()) {
+            if let Some(node) = self.get_node(path.new_ref()) {
+                return Ok(node);
+            }
+        }
+
+        let description = if self.is_inside_tree() {
+            format!("node not found: {:?} (relative to {:?})", path, self.get_path())
+        } else {
+            format!("node not found: {:?} (relative to a node outside of the scene tree)", path)
+        };
+
+        Err(EngineError::new("Node::get_node", description, None))
+    }
+}
+
+impl _ResourceLoader {
+    /// Checked version of `load`.
+    ///
+    /// `load` doesn't return the error code of the loader. When it fails, the
+    /// resource is loaded again with `load_interactive` to find out why: either no
+    /// loader recognizes the path, because the file is missing or of an unknown type,
+    /// or the loader failed and `error` is its error code, like `FileCorrupt`. The
+    /// engine prints its error for both attempts.
+    pub fn try_load(&mut self, path: GodotString, type_hint: GodotString, no_cache: bool) -> Result<Resource, EngineError> {
+        if let Some(resource) = self.load(path.new_ref(), type_hint.new_ref(), no_cache) {
+            return Ok(resource);
+        }
+
+        let resource = if type_hint.is_empty() {
+            format!("{:?}", path)
+        } else {
+            format!("{:?} as {:?}", path, type_hint)
+        };
+
+        let mut loader = match self.load_interactive(path, type_hint) {
+            Some(loader) => loader,
+            None => {
+                let description = format!("no resource {}, the file is missing or no loader recognizes it", resource);
+                return Err(EngineError::new("ResourceLoader::load", description, None));
+            }
+        };
+
+        // `wait` returns `FileEof` once the resource is loaded.
+        match loader.wait() {
+            Err(GodotError::FileEof) => loader.get_resource().ok_or_else(|| {
+                let description = format!("the loader returned no resource for {}", resource);
+                EngineError::new("ResourceLoader::load", description, None)
+            }),
+            Err(err) => {
+                let description = format!("could not load the resource {}", resource);
+                Err(EngineError::new("ResourceLoader::load", description, Some(err)))
+            }
+            Ok(()) => {
+                let description = format!("the loader of {} stopped before the end", resource);
+                Err(EngineError::new("ResourceLoader::load", description, None))
+            }
+        }
+    }
+}
+
+impl Object {
+    /// Checked version of `connect`.
+    ///
+    /// Unlike `connect`, this checks that the target actually has the method, which
+    /// the engine only reports when the signal is emitted. The errors of `connect`
+    /// keep its error code, and describe the connection and whether the signal
+    /// exists or is already connected to the method.
+    pub fn try_connect(
+        &mut self,
+        signal: GodotString,
+        target: &Object,
+        method: GodotString,
+        binds: VariantArray,
+        flags: i64,
+    ) -> Result<(), EngineError> {
+        let connection = format!("signal {:?} of {:?} to method {:?} of {:?}",
+            signal, self.get_class(), method, target.get_class()
+        );
+
+        if !target.has_method(method.new_ref()) {
+            let description = format!("the target has no such method ({})", connection);
+            return Err(EngineError::new("Object::connect", description, None));
+        }
+
+        let err = match self.connect(signal.new_ref(), Some(alias(target)), method.new_ref(), binds, flags) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        let description = if !has_signal(self, &signal) {
+            format!("the object has no such signal ({})", connection)
+        } else if self.is_connected(signal, Some(alias(target)), method) {
+            format!("already connected ({})", connection)
+        } else {
+            format!("could not connect {}", connection)
+        };
+        Err(EngineError::new("Object::connect", description, Some(err)))
+    }
+}
+
+// The signals of the class and the user signals.
+fn has_signal(obj: &Object, signal: &GodotString) -> bool {
+    let name = Variant::from_str("name");
+    let signals = obj.get_signal_list();
+    (0..signals.len()).any(|idx| {
+        signals.get_ref(idx).try_to_dictionary()
+            .map_or(false, |info| info.get(&name).to_godot_string() == *signal)
+    })
+}
+
+// `Object` is not reference counted so creating a second handle does not affect
+// the lifetime of the object.
+fn alias(obj: &Object) -> Object {
+    unsafe { Object::from_sys(obj.to_sys()) }
+}
+
+godot_test!(test_checked_get_node {
+    let node = Node::new();
+
+    let err = node.try_get_node(NodePath::from_str("Missing")).err().expect("Missing node should be an error");
+    assert_eq!(err.call, "Node::get_node");
+    assert!(err.description.contains("Missing"));
+
+    unsafe { node.free(); }
+});
+
+godot_test!(test_checked_connect {
+    let mut object = Node::new().as_object();
+    let target = Node::new().as_object();
+
+    let err = object.try_connect(
+        GodotString::from_str("missing_signal"), &target, GodotString::from_str("queue_free"), VariantArray::new(), 0,
+    ).err().expect("a missing signal should be an error");
+    assert!(err.description.contains("no such signal"));
+    assert!(err.error.is_some());
+
+    let err = object.try_connect(
+        GodotString::from_str("ready"), &target, GodotString::from_str("missing_method"), VariantArray::new(), 0,
+    ).err().expect("a missing method should be an error");
+    assert_eq!(err.error, None);
+
+    unsafe {
+        object.free();
+        target.free();
+    }
+});
@@ -1,6 +1,5 @@
 use std::ops::Deref;
 use std::marker::PhantomData;
-use std::cell::RefCell;
 use std::mem;
 use sys;
 use GodotString;
@@ -9,6 +8,8 @@ use Object;
 use NativeScript;
 use object;
 use get_api;
+use type_tag::{self, TypeTag};
+use user_data::{AccessError, UserData};
 
 /// Godot native class implementation detail that must be stored
 /// in each instance.
@@ -17,16 +18,47 @@ pub struct NativeInstanceHeader {
     pub this: *mut sys::godot_object,
 }
 
-pub trait NativeClass {
+pub trait NativeClass: Sized {
+    /// The wrapper the instances are stored in, usually `LocalCellData<Self>`. See
+    /// the `user_data` module.
+    type UserData: UserData<Target = Self>;
+
     fn class_name() -> &'static str;
 
     fn get_header(&self) -> &NativeInstanceHeader;
 
+    /// The tag identifying this class when an instance is accessed from another
+    /// library, see the `type_tag` module.
+    fn type_tag() -> TypeTag where Self: Sized {
+        TypeTag::of::<Self>()
+    }
+
     fn as_object(&self) -> &Object {
         unsafe {
             mem::transmute(self.get_header())
         }
     }
+
+    /// How the exported methods returning a `Result` pass their errors to the
+    /// engine, `MethodErrors::Dictionary` by default.
+    fn method_errors() -> MethodErrors where Self: Sized {
+        MethodErrors::Dictionary
+    }
+}
+
+/// How the exported methods returning `Result<T, E>` pass an `Err` to the engine.
+/// `Ok` is always converted to its value.
+///
+/// Selected per class by `NativeClass::method_errors`, or with an attribute like
+/// `#[method_errors(Log)]` with `#[derive(NativeClass)]`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MethodErrors {
+    /// The method returns a dictionary `{"error": err}`, for callers handling the
+    /// errors. See the `ToVariant` implementation of `Result`.
+    Dictionary,
+    /// The error is printed with `godot_error!` and the method returns nil, like
+    /// the methods of the engine failing.
+    Log,
 }
 
 /// A reference to a rust native script.
@@ -87,17 +119,17 @@ impl<T: NativeClass> NativeRef<T> {
         }
     }
 
-    fn get_impl(&self) -> &RefCell<T> {
+    fn get_impl(&self) -> &T::UserData {
         unsafe {
             let api = get_api();
             let ud = (api.godot_nativescript_get_userdata)(self.this);
-            &*(ud as *const _ as *const RefCell<T>)
+            &*(ud as *const _ as *const T::UserData)
         }
     }
 }
 
 impl<T: NativeClass> Deref for NativeRef<T> {
-    type Target = RefCell<T>;
+    type Target = T::UserData;
     fn deref(&self) -> &Self::Target {
         self.get_impl()
     }
@@ -113,6 +145,121 @@ impl <T: NativeClass> Drop for NativeRef<T> {
     }
 }
 
+/// A handle to an engine object with a rust `NativeClass` script attached, giving
+/// access to the rust instance of the script.
+///
+/// ```ignore
+/// let inventory = Instance::<Inventory>::try_from_base(&node)
+///     .expect("The node has no Inventory script");
+///
+/// let count = inventory.map(|inventory| inventory.items.len()).unwrap();
+/// inventory.map_mut(|inventory| inventory.add_item(item)).unwrap();
+/// ```
+///
+/// The instance can also come from another GDNative library, if that library
+/// registered the class with the same type tag (see `NativeClass::type_tag`).
+///
+/// If the owner is reference counted, the handle keeps it alive. Otherwise the
+/// handle must not be used after the owner is freed.
+pub struct Instance<T: NativeClass> {
+    this: *mut sys::godot_object,
+    user_data: *const T::UserData,
+    is_reference: bool,
+}
+
+impl<T: NativeClass> Instance<T> {
+    /// Returns the instance of the `T` script attached to `owner`, or `None` if
+    /// `owner` has no script or a script of another class.
+    pub fn try_from_base<O: GodotObject>(owner: &O) -> Option<Self> {
+        unsafe { Self::try_from_sys(owner.to_sys()) }
+    }
+
+    /// Returns the instance of the `T` script attached to the object, or `None` if
+    /// the object has no script or a script of another class.
+    ///
+    /// The object pointer must be valid.
+    pub unsafe fn try_from_sys(this: *mut sys::godot_object) -> Option<Self> {
+        if this.is_null() {
+            return None;
+        }
+
+        let owner = Object::from_sys(this);
+        let script = owner.get_script().and_then(|script| script.cast::<NativeScript>())?;
+        if script.get_class_name() != GodotString::from_str(T::class_name()) {
+            return None;
+        }
+
+        let user_data = type_tag::user_data::<T>(this, &script);
+        if user_data.is_null() {
+            return None;
+        }
+
+        let is_reference = object::is_class(this, "Reference");
+        if is_reference {
+            object::add_ref(this);
+        }
+
+        Some(Instance { this, user_data, is_reference })
+    }
+
+    /// Calls `f` with a shared reference to the rust instance.
+    ///
+    /// Returns an error if the instance is already mutably borrowed, for example
+    /// because one of its methods is running.
+    pub fn map<U, F>(&self, f: F) -> Result<U, AccessError>
+    where F: FnOnce(&T) -> U {
+        self.user_data().map(f)
+    }
+
+    /// Calls `f` with a mutable reference to the rust instance.
+    ///
+    /// Returns an error if the instance is already borrowed, for example because one
+    /// of its methods is running.
+    pub fn map_mut<U, F>(&self, f: F) -> Result<U, AccessError>
+    where F: FnOnce(&mut T) -> U {
+        self.user_data().map_mut(f)
+    }
+
+    /// Returns the object the script is attached to.
+    pub fn owner(&self) -> Object {
+        unsafe { Object::from_sys(self.this) }
+    }
+
+    /// Try to cast the owner into a godot object reference.
+    pub fn cast<O>(&self) -> Option<O> where O: GodotObject {
+        object::godot_cast::<O>(self.this)
+    }
+
+    /// Creates a new handle to the same instance.
+    pub fn new_ref(&self) -> Self {
+        unsafe {
+            if self.is_reference {
+                object::add_ref(self.this);
+            }
+        }
+
+        Instance {
+            this: self.this,
+            user_data: self.user_data,
+            is_reference: self.is_reference,
+        }
+    }
+
+    fn user_data(&self) -> &T::UserData {
+        unsafe { &*self.user_data }
+    }
+}
+
+impl<T: NativeClass> Drop for Instance<T> {
+    fn drop(&mut self) {
+        unsafe {
+            if self.is_reference && object::unref(self.this) {
+                (get_api().godot_object_destroy)(self.this);
+            }
+        }
+    }
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! godot_class_build_export_methods {
@@ -203,6 +350,7 @@ macro_rules! godot_class_build_methods {
 ///                    getter: |this: &mut RustTest| this.x,
 ///                    setter: |this: &mut RustTest, v| this.x = v,
 ///                    usage: PropertyUsage::DEFAULT,
+///                    documentation: Some("The x coordinate."),
 ///                }
 ///            );
 ///        }
@@ -260,18 +408,15 @@ class $name:ident: $parent:ty {
                 }
 
                 extern "C" fn godot_create(this: *mut sys::godot_object, _data: *mut $crate::libc::c_void) -> *mut $crate::libc::c_void {
-                    use std::cell::RefCell;
+                    use $crate::UserData;
 
                     let val = constructor($crate::NativeInstanceHeader {
                         this: this,
                     });
-                    let wrapper = Box::new(RefCell::new(val));
-                    Box::into_raw(wrapper) as *mut _
+                    $crate::alloc_boxed($crate::LocalCellData::new(val)) as *mut _
                 }
                 extern "C" fn godot_free(_this: *mut sys::godot_object, _data: *mut $crate::libc::c_void, ud: *mut $crate::libc::c_void) {
-                    use std::cell::RefCell;
-                    let wrapper: Box<RefCell<$name>> = unsafe { Box::from_raw(ud as *mut _) };
-                    drop(wrapper);
+                    unsafe { $crate::free_boxed(ud as *mut $crate::LocalCellData<$name>) };
                 }
 
                 let $builder = init_handle.add_class::<Self>(
@@ -290,6 +435,7 @@ class $name:ident: $parent:ty {
         }
 
         impl $crate::NativeClass for $name {
+            type UserData = $crate::LocalCellData<$name>;
             fn class_name() -> &'static str { stringify!($name) }
             fn get_header(&self) -> &$crate::NativeInstanceHeader { &self.header }
         }
@@ -0,0 +1,240 @@
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+use GodotString;
+use ProjectSettings;
+
+/// Number of physics layers available in the engine.
+pub const COLLISION_LAYER_COUNT: u32 = 20;
+
+/// A set of physics layers, as used by the `collision_layer` and `collision_mask`
+/// properties of physics bodies and areas.
+///
+/// Layers are numbered from 1 to `COLLISION_LAYER_COUNT` like in the editor.
+///
+/// ```ignore
+/// let mask = CollisionLayers::none().with_layer(1).with_layer(3);
+/// body.set_collision_mask(mask.to_mask());
+///
+/// let names = LayerNames::physics_2d();
+/// let mask = CollisionLayers::none().with_named(&names, "walls").unwrap();
+/// ```
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CollisionLayers(u32);
+
+impl CollisionLayers {
+    /// The empty set.
+    pub fn none() -> Self {
+        CollisionLayers(0)
+    }
+
+    /// All the layers.
+    pub fn all() -> Self {
+        CollisionLayers((1 << COLLISION_LAYER_COUNT) - 1)
+    }
+
+    /// Creates a set containing a single layer.
+    ///
+    /// Panics if the layer is not between 1 and `COLLISION_LAYER_COUNT`.
+    pub fn layer(layer: u32) -> Self {
+        CollisionLayers(bit(layer))
+    }
+
+    /// Creates a set from the bits of a mask. Bits above the last layer are ignored.
+    pub fn from_bits(bits: u32) -> Self {
+        CollisionLayers(bits & Self::all().0)
+    }
+
+    /// Creates a set from a mask returned by the physics API.
+    pub fn from_mask(mask: i64) -> Self {
+        Self::from_bits(mask as u32)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the mask expected by the physics API.
+    pub fn to_mask(&self) -> i64 {
+        self.0 as i64
+    }
+
+    /// Adds a layer to the set.
+    pub fn with_layer(self, layer: u32) -> Self {
+        CollisionLayers(self.0 | bit(layer))
+    }
+
+    /// Removes a layer from the set.
+    pub fn without_layer(self, layer: u32) -> Self {
+        CollisionLayers(self.0 & !bit(layer))
+    }
+
+    /// Adds a layer named in the project settings to the set, or returns `None` if no
+    /// layer has this name.
+    pub fn with_named(self, names: &LayerNames, name: &str) -> Option<Self> {
+        names.find(name).map(|layer| self.with_layer(layer))
+    }
+
+    pub fn contains(&self, layer: u32) -> bool {
+        self.0 & bit(layer) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if the two sets have at least one layer in common.
+    pub fn intersects(&self, other: CollisionLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Iterates over the layers of the set, in increasing order.
+    pub fn layers(&self) -> impl Iterator<Item = u32> {
+        let bits = self.0;
+        (1..COLLISION_LAYER_COUNT + 1).filter(move |&layer| bits & bit(layer) != 0)
+    }
+}
+
+fn bit(layer: u32) -> u32 {
+    assert!(
+        layer >= 1 && layer <= COLLISION_LAYER_COUNT,
+        "Collision layers are numbered from 1 to {}, got {}", COLLISION_LAYER_COUNT, layer
+    );
+    1 << (layer - 1)
+}
+
+impl BitOr for CollisionLayers {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        CollisionLayers(self.0 | other.0)
+    }
+}
+
+impl BitAnd for CollisionLayers {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        CollisionLayers(self.0 & other.0)
+    }
+}
+
+impl BitXor for CollisionLayers {
+    type Output = Self;
+    fn bitxor(self, other: Self) -> Self {
+        CollisionLayers(self.0 ^ other.0)
+    }
+}
+
+impl Not for CollisionLayers {
+    type Output = Self;
+    fn not(self) -> Self {
+        CollisionLayers(!self.0 & Self::all().0)
+    }
+}
+
+impl From<CollisionLayers> for i64 {
+    fn from(layers: CollisionLayers) -> i64 {
+        layers.to_mask()
+    }
+}
+
+impl fmt::Debug for CollisionLayers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.layers()).finish()
+    }
+}
+
+/// The names given to the physics layers in the project settings
+/// (`layer_names/2d_physics/layer_N` and `layer_names/3d_physics/layer_N`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayerNames {
+    names: Vec<Option<String>>,
+}
+
+impl LayerNames {
+    /// Reads the names of the 2D physics layers from the project settings.
+    pub fn physics_2d() -> Self {
+        Self::from_project_settings("2d_physics")
+    }
+
+    /// Reads the names of the 3D physics layers from the project settings.
+    pub fn physics_3d() -> Self {
+        Self::from_project_settings("3d_physics")
+    }
+
+    fn from_project_settings(category: &str) -> Self {
+        let settings = ProjectSettings::godot_singleton();
+        let names = (1..COLLISION_LAYER_COUNT + 1).map(|layer| {
+            let setting = GodotString::from_str(format!("layer_names/{}/layer_{}", category, layer));
+            if !settings.has_setting(setting.new_ref()) {
+                return None;
+            }
+            settings.get_setting(setting).try_to_string().filter(|name| !name.is_empty())
+        }).collect();
+
+        LayerNames { names }
+    }
+
+    /// Creates the names from a list, starting with the name of the first layer.
+    pub fn from_names<S: AsRef<str>>(names: &[Option<S>]) -> Self {
+        LayerNames {
+            names: names.iter()
+                .take(COLLISION_LAYER_COUNT as usize)
+                .map(|name| name.as_ref().map(|name| name.as_ref().to_string()))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of the layer with the given name.
+    pub fn find(&self, name: &str) -> Option<u32> {
+        self.names.iter()
+            .position(|layer_name| layer_name.as_ref().map_or(false, |n| n == name))
+            .map(|idx| idx as u32 + 1)
+    }
+
+    /// Returns the name of a layer, if it has one.
+    pub fn name(&self, layer: u32) -> Option<&str> {
+        if layer == 0 {
+            return None;
+        }
+        self.names.get(layer as usize - 1).and_then(|name| name.as_ref().map(|n| &n[..]))
+    }
+
+    /// Returns the set of layers with the given names, or `None` if one of the names
+    /// is unknown.
+    pub fn layers(&self, names: &[&str]) -> Option<CollisionLayers> {
+        names.iter().fold(Some(CollisionLayers::none()), |layers, name| {
+            layers.and_then(|layers| layers.with_named(self, name))
+        })
+    }
+}
+
+#[test]
+fn collision_layers_bits() {
+    let layers = CollisionLayers::none().with_layer(1).with_layer(3);
+    assert_eq!(layers.bits(), 0b101);
+    assert_eq!(layers.to_mask(), 5);
+    assert!(layers.contains(3));
+    assert!(!layers.contains(2));
+    assert_eq!(layers.layers().collect::<Vec<_>>(), vec![1, 3]);
+    assert_eq!(layers.without_layer(1), CollisionLayers::layer(3));
+    assert_eq!(!CollisionLayers::all(), CollisionLayers::none());
+    assert_eq!(CollisionLayers::from_mask(-1), CollisionLayers::all());
+    assert_eq!(CollisionLayers::layer(2) | CollisionLayers::layer(4), CollisionLayers::from_bits(0b1010));
+    assert!(layers.intersects(CollisionLayers::layer(1)));
+}
+
+#[test]
+#[should_panic]
+fn collision_layers_out_of_range() {
+    CollisionLayers::none().with_layer(0);
+}
+
+#[test]
+fn collision_layer_names() {
+    let names = LayerNames::from_names(&[Some("player"), None, Some("walls")]);
+    assert_eq!(names.find("walls"), Some(3));
+    assert_eq!(names.find("enemies"), None);
+    assert_eq!(names.name(1), Some("player"));
+    assert_eq!(names.name(2), None);
+    assert_eq!(names.layers(&["player", "walls"]), Some(CollisionLayers::from_bits(0b101)));
+    assert_eq!(names.layers(&["player", "enemies"]), None);
+}
@@ -5,6 +5,7 @@ use std::mem::transmute;
 /// RGBA color with 32 bits floating point components.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -21,6 +22,122 @@ impl Color {
         Color { r, g, b, a: 1.0 }
     }
 
+    /// The color of the given hue, saturation and value, from 0 to 1.
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Color {
+        if s == 0.0 {
+            return Color::rgba(v, v, v, a);
+        }
+
+        let h = (h * 6.0) % 6.0;
+        let sector = h.floor();
+        let f = h - sector;
+        let p = v * (1.0 - s);
+        let q = v * (1.0 - s * f);
+        let t = v * (1.0 - s * (1.0 - f));
+        match sector as i32 {
+            0 => Color::rgba(v, t, p, a),
+            1 => Color::rgba(q, v, p, a),
+            2 => Color::rgba(p, v, t, a),
+            3 => Color::rgba(p, q, v, a),
+            4 => Color::rgba(t, p, v, a),
+            _ => Color::rgba(v, p, q, a),
+        }
+    }
+
+    /// Parses a hexadecimal color like `"#ff8000"`, with 3, 4, 6 or 8 digits and an
+    /// optional `#`. The alpha comes last, like in CSS: `"#ff800080"` is a half
+    /// transparent orange. The 8 digits colors of the engine are written
+    /// `aarrggbb` instead.
+    pub fn from_html(html: &str) -> Option<Color> {
+        let digits = if html.starts_with('#') { &html[1..] } else { html };
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        // The short forms repeat every digit, `"f80"` is `"ff8800"`.
+        let component = |idx: usize, len: usize| {
+            let value = u8::from_str_radix(&digits[idx * len..(idx + 1) * len], 16).ok()?;
+            Some(if len == 1 { value * 17 } else { value } as f32 / 255.0)
+        };
+        let (count, len) = match digits.len() {
+            3 => (3, 1),
+            4 => (4, 1),
+            6 => (3, 2),
+            8 => (4, 2),
+            _ => return None,
+        };
+
+        let a = if count == 4 { component(3, len)? } else { 1.0 };
+        Some(Color::rgba(component(0, len)?, component(1, len)?, component(2, len)?, a))
+    }
+
+    /// The color in lowercase hexadecimal, `rrggbbaa` with the alpha or `rrggbb`
+    /// without it, see `from_html`.
+    pub fn to_html(&self, with_alpha: bool) -> String {
+        let component = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+        let mut html = format!("{:02x}{:02x}{:02x}", component(self.r), component(self.g), component(self.b));
+        if with_alpha {
+            html.push_str(&format!("{:02x}", component(self.a)));
+        }
+        html
+    }
+
+    /// The color moved towards white by `amount`, from 0 to 1.
+    pub fn lightened(&self, amount: f32) -> Color {
+        Color::rgba(
+            self.r + (1.0 - self.r) * amount,
+            self.g + (1.0 - self.g) * amount,
+            self.b + (1.0 - self.b) * amount,
+            self.a,
+        )
+    }
+
+    /// The color moved towards black by `amount`, from 0 to 1.
+    pub fn darkened(&self, amount: f32) -> Color {
+        Color::rgba(self.r * (1.0 - amount), self.g * (1.0 - amount), self.b * (1.0 - amount), self.a)
+    }
+
+    /// The linear interpolation of every component, alpha included, to `to` by `t`.
+    pub fn linear_interpolate(&self, to: &Color, t: f32) -> Color {
+        Color::rgba(
+            self.r + (to.r - self.r) * t,
+            self.g + (to.g - self.g) * t,
+            self.b + (to.b - self.b) * t,
+            self.a + (to.a - self.a) * t,
+        )
+    }
+
+    /// The color `over` drawn over this one, with their alpha.
+    pub fn blend(&self, over: &Color) -> Color {
+        let sa = 1.0 - over.a;
+        let a = self.a * sa + over.a;
+        if a == 0.0 {
+            return Color::rgba(0.0, 0.0, 0.0, 0.0);
+        }
+        Color::rgba(
+            (self.r * self.a * sa + over.r * over.a) / a,
+            (self.g * self.a * sa + over.g * over.a) / a,
+            (self.b * self.a * sa + over.b * over.a) / a,
+            a,
+        )
+    }
+
+    /// The color with every component shifted by half its range, like the
+    /// `contrasted` of the engine.
+    pub fn contrasted(&self) -> Color {
+        Color::rgba((self.r + 0.5) % 1.0, (self.g + 0.5) % 1.0, (self.b + 0.5) % 1.0, self.a)
+    }
+
+    /// The negative of the color, with the same alpha.
+    pub fn inverted(&self) -> Color {
+        Color::rgba(1.0 - self.r, 1.0 - self.g, 1.0 - self.b, self.a)
+    }
+
+    /// The average of the red, green and blue components.
+    pub fn gray(&self) -> f32 {
+        (self.r + self.g + self.b) / 3.0
+    }
+
     fn as_sys_color(&self) -> &sys::godot_color {
         unsafe { transmute(self) }
     }
@@ -49,3 +166,25 @@ fn color_repr() {
     use std::mem::size_of;
     assert_eq!(size_of::<Color>(), size_of::<sys::godot_color>());
 }
+
+#[test]
+fn color_conversions() {
+    let orange = Color::rgba(1.0, 0.5, 0.0, 1.0);
+    assert_eq!(Color::from_hsv(30.0 / 360.0, 1.0, 1.0, 1.0), orange);
+    assert_eq!(Color::from_hsv(0.5, 0.0, 0.25, 0.5), Color::rgba(0.25, 0.25, 0.25, 0.5));
+
+    assert_eq!(Color::from_html("#ff8000").map(|color| color.to_html(false)), Some("ff8000".to_string()));
+    assert_eq!(Color::from_html("f80").map(|color| color.to_html(true)), Some("ff8800ff".to_string()));
+    assert_eq!(Color::from_html("#00000080").map(|color| color.to_html(true)), Some("00000080".to_string()));
+    assert_eq!(Color::from_html("#ff80"), Some(Color::rgba(1.0, 1.0, 8.0 * 17.0 / 255.0, 0.0)));
+    assert_eq!(Color::from_html("#ff800"), None);
+    assert_eq!(Color::from_html("#gg8000"), None);
+
+    assert_eq!(orange.lightened(0.5), Color::rgba(1.0, 0.75, 0.5, 1.0));
+    assert_eq!(orange.darkened(0.5), Color::rgba(0.5, 0.25, 0.0, 1.0));
+    assert_eq!(orange.inverted(), Color::rgba(0.0, 0.5, 1.0, 1.0));
+    assert_eq!(orange.contrasted(), Color::rgba(0.5, 0.0, 0.5, 1.0));
+    assert_eq!(orange.linear_interpolate(&Color::rgba(0.0, 0.5, 1.0, 0.0), 0.5), Color::rgba(0.5, 0.5, 0.5, 0.5));
+    assert_eq!(orange.blend(&Color::rgba(0.0, 0.0, 1.0, 0.5)), Color::rgba(0.5, 0.25, 0.5, 1.0));
+    assert_eq!(Color::rgba(0.0, 0.0, 0.0, 0.0).blend(&Color::rgba(1.0, 1.0, 1.0, 0.0)), Color::rgba(0.0, 0.0, 0.0, 0.0));
+}
@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 use Color;
 
@@ -103,7 +104,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for ColorArray {
+impl_sys_access!(for ColorArray as godot_pool_color_array);
+
+impl ToVariant for ColorArray {
     fn to_variant(&self) -> Variant { Variant::from_color_array(self) }
+}
+
+impl FromVariant for ColorArray {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_color_array() }
 }
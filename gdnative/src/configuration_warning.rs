@@ -0,0 +1,134 @@
+//! Configuration warnings of rust nodes, shown in the scene dock of the editor.
+//!
+//! Like the built-in nodes, a node can describe what is missing for it to work, for
+//! example an unassigned node path. The editor shows a warning icon next to the
+//! node, with the description as tooltip:
+//!
+//! ```ignore
+//! builder.add_configuration_warnings(|this: &Turret, owner, warnings| {
+//!     warnings.check_node_path::<Spatial>(owner, "target", &this.target);
+//!     warnings.check_sibling::<AnimationPlayer>(owner, "AnimationPlayer");
+//!     warnings.check(this.range > 0.0, "The range must be positive.");
+//! });
+//! ```
+//!
+//! The editor only calls the scripts of tool classes, see `InitHandle::add_tool_class`.
+//! It asks for the warnings again when the scene changes; a setter changing the
+//! result calls `Node::update_configuration_warning` to refresh the icon at once.
+
+use std::fmt;
+use features::is_editor_hint;
+use init::ClassBuilder;
+use GodotObject;
+use GodotString;
+use NativeClass;
+use Node;
+use NodePathProperty;
+use Object;
+
+/// The problems found in the configuration of a node, see the
+/// `configuration_warning` module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigurationWarnings {
+    messages: Vec<String>,
+}
+
+impl ConfigurationWarnings {
+    pub fn new() -> Self {
+        ConfigurationWarnings { messages: Vec::new() }
+    }
+
+    pub fn add<S: Into<String>>(&mut self, message: S) {
+        self.messages.push(message.into());
+    }
+
+    /// Adds `message` if `ok` is `false`.
+    pub fn check(&mut self, ok: bool, message: &str) {
+        if !ok {
+            self.add(message);
+        }
+    }
+
+    /// Adds a warning if the node path stored in `property` doesn't lead to a `T`.
+    pub fn check_node_path<T: GodotObject>(&mut self, owner: &Node, property: &str, path: &NodePathProperty) {
+        if let Err(err) = path.resolve::<T>(owner) {
+            self.add(format!("{}: {}.", property, err));
+        }
+    }
+
+    /// Adds a warning if the parent of the node has no child `name` of class `T`.
+    pub fn check_sibling<T: GodotObject>(&mut self, owner: &Node, name: &str) {
+        let path = NodePathProperty::new(&format!("../{}", name));
+        if path.resolve::<T>(owner).is_err() {
+            self.add(format!("A sibling {} named {:?} is required.", T::class_name(), name));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+}
+
+/// One warning per line, the format shown by the editor.
+impl fmt::Display for ConfigurationWarnings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.messages.join("\n"))
+    }
+}
+
+impl<'a, C: NativeClass> ClassBuilder<'a, C> {
+    /// Implements `_get_configuration_warning` with the checks of `check`, which
+    /// receives the instance and its owner. See the `configuration_warning` module.
+    pub fn add_configuration_warnings<F>(&self, check: F) -> &Self
+    where F: Fn(&C, &Node, &mut ConfigurationWarnings) + 'static
+    {
+        self.add_configuration_warning(move |this| {
+            let owner = unsafe { Node::from_sys(this.get_header().this) };
+            let mut warnings = ConfigurationWarnings::new();
+            check(this, &owner, &mut warnings);
+            warnings.to_string()
+        })
+    }
+}
+
+impl Node {
+    /// Asks the editor to update the configuration warning of the node, after a
+    /// change of the result of `_get_configuration_warning`. Does nothing outside of
+    /// the editor.
+    pub fn update_configuration_warning(&mut self) {
+        if !is_editor_hint() {
+            return;
+        }
+
+        let mut object = unsafe { Object::from_sys(self.to_sys()) };
+        let method = GodotString::from_str("update_configuration_warning");
+        if object.has_method(method.new_ref()) {
+            object.call(method, &[]);
+            return;
+        }
+
+        // Older editors redraw the scene dock, warnings included, when the tree
+        // changes.
+        let tree = object.call(GodotString::from_str("get_tree"), &[]);
+        if let Some(mut tree) = tree.try_to_object::<Object>() {
+            tree.emit_signal(GodotString::from_str("tree_changed"), &[]);
+        }
+    }
+}
+
+#[test]
+fn warnings_display() {
+    let mut warnings = ConfigurationWarnings::new();
+    assert!(warnings.is_empty());
+    assert_eq!(warnings.to_string(), "");
+
+    warnings.check(true, "not shown");
+    warnings.check(false, "The range must be positive.");
+    warnings.add("target: no node path was assigned.");
+    assert_eq!(warnings.messages().len(), 2);
+    assert_eq!(warnings.to_string(), "The range must be positive.\ntarget: no node path was assigned.");
+}
@@ -0,0 +1,226 @@
+//! Crash reports for the fatal errors of the rust code, with the `crash_dump`
+//! feature.
+//!
+//! When a library is built with `panic = "abort"`, a panic closes the game without
+//! any message the player could send. Once installed, the crash dump writes a report
+//! to a file before the process is aborted:
+//!
+//! ```ignore
+//! fn init(_info: &gdnative::InitializeInfo) {
+//!     gdnative::install_crash_dump(gdnative::CrashDumpOptions::default());
+//! }
+//!
+//! godot_gdnative_init!(init);
+//! ```
+//!
+//! The report contains the panic message and location, the rust backtrace, the
+//! version of the engine, the classes registered by the library and the last
+//! messages printed with `godot_print!`, `godot_warn!` and `godot_error!`. Reports
+//! are written to `user://crash_reports` by default, as
+//! `crash-<timestamp>-<pid>-<n>.txt` where `n` counts the reports of the process.
+//!
+//! Reports are also written when the library detects that it is used in a way that
+//! can't be recovered from, like calling the engine after the library was unloaded.
+//! Panics caught by the method wrappers are only reported as errors to the engine,
+//! unless `report_caught_panics` is set.
+//!
+//! The directory, the engine version and the class list are resolved when the crash
+//! dump is installed, writing the report doesn't call the engine.
+
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use info;
+use GodotString;
+use ProjectSettings;
+use Variant;
+use _Engine;
+
+/// Configuration of the crash dump, see `install_crash_dump`.
+#[derive(Clone, Debug)]
+pub struct CrashDumpOptions {
+    /// The directory the reports are written to, can start with `user://` or `res://`.
+    pub directory: String,
+    /// The number of printed messages included in the reports.
+    pub max_messages: usize,
+    /// Writes a report for every panic, even when it is caught.
+    pub report_caught_panics: bool,
+}
+
+impl Default for CrashDumpOptions {
+    fn default() -> Self {
+        CrashDumpOptions {
+            directory: "user://crash_reports".to_string(),
+            max_messages: 50,
+            report_caught_panics: false,
+        }
+    }
+}
+
+struct CrashDump {
+    directory: PathBuf,
+    engine_version: String,
+    classes: Vec<String>,
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static FATAL_ERROR: AtomicBool = AtomicBool::new(false);
+static REPORT_CAUGHT_PANICS: AtomicBool = AtomicBool::new(false);
+static MAX_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+static REPORT_COUNT: AtomicUsize = AtomicUsize::new(0);
+static CRASH_DUMP: Mutex<Option<CrashDump>> = Mutex::new(None);
+static MESSAGES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Installs a panic hook writing crash reports, see the `crash_dump` module.
+///
+/// Should be called after the classes are registered so that they are listed in
+/// the reports, installing the crash dump again updates the list and the options.
+/// The previous panic hook is still called.
+pub fn install_crash_dump(options: CrashDumpOptions) {
+    let directory = ProjectSettings::godot_singleton()
+        .globalize_path(GodotString::from_str(&options.directory))
+        .to_string();
+
+    let version = _Engine::godot_singleton().get_version_info();
+    let engine_version = version.get_ref(&Variant::from_str("string")).to_string();
+
+    let dump = CrashDump {
+        directory: PathBuf::from(directory),
+        engine_version,
+        classes: info::class_names(),
+    };
+
+    if let Ok(mut crash_dump) = CRASH_DUMP.lock() {
+        *crash_dump = Some(dump);
+    }
+    MAX_MESSAGES.store(options.max_messages, Ordering::Release);
+    REPORT_CAUGHT_PANICS.store(options.report_caught_panics, Ordering::Release);
+
+    if INSTALLED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        previous(panic_info);
+
+        let fatal = FATAL_ERROR.swap(false, Ordering::AcqRel);
+        if fatal || REPORT_CAUGHT_PANICS.load(Ordering::Acquire) || cfg!(panic = "abort") {
+            write_crash_report(&panic_info.to_string());
+        }
+    }));
+}
+
+/// Writes a crash report with the given reason and returns its path, or `None` if
+/// the crash dump isn't installed or the report couldn't be written.
+pub fn write_crash_report(reason: &str) -> Option<PathBuf> {
+    // The lock can be held by the thread that panicked.
+    let crash_dump = CRASH_DUMP.try_lock().ok()?;
+    let crash_dump = crash_dump.as_ref()?;
+
+    let messages: Vec<String> = match MESSAGES.try_lock() {
+        Ok(messages) => messages.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let report = format_report(
+        reason,
+        &info::format_timestamp(timestamp),
+        crash_dump,
+        &Backtrace::force_capture().to_string(),
+        &messages,
+    );
+
+    fs::create_dir_all(&crash_dump.directory).ok()?;
+    let index = REPORT_COUNT.fetch_add(1, Ordering::AcqRel);
+    let path = crash_dump.directory.join(report_file_name(timestamp, process::id(), index));
+    fs::write(&path, report).ok()?;
+    eprintln!("crash report written to {}", path.display());
+    Some(path)
+}
+
+/// Marks the next panic as fatal, a report is written for it even when it is caught.
+pub(crate) fn fatal_error_detected() {
+    FATAL_ERROR.store(true, Ordering::Release);
+}
+
+/// Keeps a printed message for the reports.
+pub(crate) fn keep_message(msg: &str) {
+    let max_messages = MAX_MESSAGES.load(Ordering::Acquire);
+    if max_messages == 0 {
+        return;
+    }
+
+    if let Ok(mut messages) = MESSAGES.lock() {
+        messages.push_back(msg.to_string());
+        while messages.len() > max_messages {
+            messages.pop_front();
+        }
+    }
+}
+
+// Several reports can be written in the same second, by the same process or by
+// several instances of the game.
+fn report_file_name(timestamp: u64, pid: u32, index: usize) -> String {
+    format!("crash-{}-{}-{}.txt", timestamp, pid, index)
+}
+
+fn format_report(reason: &str, time: &str, crash_dump: &CrashDump, backtrace: &str, messages: &[String]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Crash report, {}", time);
+    let _ = writeln!(out, "{}", reason);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "engine: {}", crash_dump.engine_version);
+    let _ = writeln!(out, "gdnative: {}", env!("CARGO_PKG_VERSION"));
+    if let Some(path) = info::library_path() {
+        let _ = writeln!(out, "library: {}", path);
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "registered classes ({}):", crash_dump.classes.len());
+    for class in &crash_dump.classes {
+        let _ = writeln!(out, "  {}", class);
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "last messages ({}):", messages.len());
+    for msg in messages {
+        let _ = writeln!(out, "  {}", msg.replace('\n', "\n  "));
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "backtrace:");
+    let _ = write!(out, "{}", backtrace);
+    out
+}
+
+#[test]
+fn crash_report_format() {
+    let crash_dump = CrashDump {
+        directory: PathBuf::new(),
+        engine_version: "3.0.6.stable".to_string(),
+        classes: vec!["Player".to_string(), "Enemy".to_string()],
+    };
+    let messages = vec!["loading level".to_string(), "ERROR: no spawn\nsecond line".to_string()];
+
+    let report = format_report("panicked at 'boom'", "2018-06-26 08:00:00 UTC", &crash_dump, "  0: main\n", &messages);
+    assert!(report.starts_with("Crash report, 2018-06-26 08:00:00 UTC\npanicked at 'boom'\n"));
+    assert!(report.contains("engine: 3.0.6.stable\n"));
+    assert!(report.contains("registered classes (2):\n  Player\n  Enemy\n"));
+    assert!(report.contains("last messages (2):\n  loading level\n  ERROR: no spawn\n  second line\n"));
+    assert!(report.ends_with("backtrace:\n  0: main\n"));
+}
+
+#[test]
+fn crash_report_file_name() {
+    assert_eq!(report_file_name(1530000000, 4242, 0), "crash-1530000000-4242-0.txt");
+    assert_ne!(report_file_name(1530000000, 4242, 0), report_file_name(1530000000, 4242, 1));
+}
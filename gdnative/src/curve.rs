@@ -0,0 +1,356 @@
+//! Helpers for `Curve`, `Curve2D`, `Curve3D` and the path followers.
+//!
+//! Evaluating a curve through the engine costs a method call per sample. For
+//! spline-driven movement or procedural generation that samples a curve many times
+//! per frame, `Curve2D::baked` and `Curve3D::baked` copy the baked points once
+//! into a `BakedCurve2D`/`BakedCurve3D` which can then be sampled in rust.
+//!
+//! The same goes for the `Curve` and `Gradient` resources designers author in the
+//! inspector, for example the falloff of an explosion or the color of a particle
+//! over its lifetime. `Curve::table` and `Gradient::table` sample them once into a
+//! `CurveTable`/`GradientTable`:
+//!
+//! ```ignore
+//! // In _ready, or when the resource changes.
+//! self.falloff = falloff_curve.table(64);
+//!
+//! // For each body in range, each frame.
+//! let force = self.strength * self.falloff.sample(distance / self.radius);
+//! ```
+
+use Color;
+use Curve;
+use CurveTangentMode;
+use Curve2D;
+use Curve3D;
+use Float32Array;
+use Gradient;
+use PathFollow;
+use PathFollow2D;
+use Vector2;
+use Vector2Array;
+use Vector3;
+use Vector3Array;
+
+impl Curve {
+    /// Adds a point with the given tangents and tangent modes.
+    pub fn add_point_with_modes(
+        &mut self,
+        position: Vector2,
+        left_tangent: f64,
+        right_tangent: f64,
+        left_mode: CurveTangentMode,
+        right_mode: CurveTangentMode,
+    ) {
+        self.add_point(position, left_tangent, right_tangent, left_mode as i64, right_mode as i64);
+    }
+}
+
+impl Vector2Array {
+    /// Copies the content of the array into a `Vec`.
+    pub fn to_vec(&self) -> Vec<Vector2> {
+        (0..self.len()).map(|idx| self.get(idx)).collect()
+    }
+}
+
+impl Vector3Array {
+    /// Copies the content of the array into a `Vec`.
+    pub fn to_vec(&self) -> Vec<Vector3> {
+        (0..self.len()).map(|idx| self.get(idx)).collect()
+    }
+}
+
+impl Float32Array {
+    /// Copies the content of the array into a `Vec`.
+    pub fn to_vec(&self) -> Vec<f32> {
+        (0..self.len()).map(|idx| self.get(idx)).collect()
+    }
+}
+
+macro_rules! baked_curve {
+    ($(#[$attr:meta])* $name:ident, $vector:ty) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name {
+            points: Vec<$vector>,
+            // Distance along the curve of each point.
+            offsets: Vec<f32>,
+        }
+
+        impl $name {
+            /// Creates a baked curve from a list of points.
+            pub fn from_points(points: Vec<$vector>) -> Self {
+                let mut offsets = Vec::with_capacity(points.len());
+                let mut length = 0.0;
+                for (idx, point) in points.iter().enumerate() {
+                    if idx > 0 {
+                        length += (*point - points[idx - 1]).length();
+                    }
+                    offsets.push(length);
+                }
+
+                $name { points, offsets }
+            }
+
+            pub fn points(&self) -> &[$vector] {
+                &self.points
+            }
+
+            /// Returns the length of the curve.
+            pub fn length(&self) -> f32 {
+                self.offsets.last().cloned().unwrap_or(0.0)
+            }
+
+            /// Returns the point at the given distance along the curve, like the
+            /// engine's `interpolate_baked` with linear interpolation.
+            ///
+            /// The offset is clamped to the length of the curve. Returns `None` if
+            /// the curve has no points.
+            pub fn interpolate(&self, offset: f32) -> Option<$vector> {
+                let (idx, t) = self.segment_at(offset)?;
+                if idx + 1 >= self.points.len() {
+                    return Some(self.points[idx]);
+                }
+                Some(self.points[idx].lerp(self.points[idx + 1], t))
+            }
+
+            /// Returns the distance along the curve of the point of the curve that is
+            /// the closest to `to`, or `None` if the curve has no points.
+            pub fn closest_offset(&self, to: $vector) -> Option<f32> {
+                if self.points.len() < 2 {
+                    return self.offsets.first().cloned();
+                }
+
+                let mut best_offset = 0.0;
+                let mut best_dist = ::std::f32::INFINITY;
+                for idx in 0..self.points.len() - 1 {
+                    let a = self.points[idx];
+                    let segment = self.points[idx + 1] - a;
+                    let segment_len = self.offsets[idx + 1] - self.offsets[idx];
+
+                    let t = if segment_len > 0.0 {
+                        ((to - a).dot(segment) / (segment_len * segment_len)).max(0.0).min(1.0)
+                    } else {
+                        0.0
+                    };
+
+                    let dist = (a + segment * t - to).square_length();
+                    if dist < best_dist {
+                        best_dist = dist;
+                        best_offset = self.offsets[idx] + segment_len * t;
+                    }
+                }
+
+                Some(best_offset)
+            }
+
+            /// Returns the point of the curve that is the closest to `to`.
+            pub fn closest_point(&self, to: $vector) -> Option<$vector> {
+                self.closest_offset(to).and_then(|offset| self.interpolate(offset))
+            }
+
+            // Returns the index of the segment containing the offset and the position
+            // in that segment.
+            fn segment_at(&self, offset: f32) -> Option<(usize, f32)> {
+                if self.points.is_empty() {
+                    return None;
+                }
+
+                let offset = offset.max(0.0).min(self.length());
+                let idx = match self.offsets.binary_search_by(|o| o.partial_cmp(&offset).unwrap()) {
+                    Ok(idx) => return Some((idx, 0.0)),
+                    Err(idx) => idx - 1,
+                };
+
+                let segment_len = self.offsets[idx + 1] - self.offsets[idx];
+                Some((idx, (offset - self.offsets[idx]) / segment_len))
+            }
+        }
+    }
+}
+
+baked_curve!(
+    /// The baked points of a `Curve2D`, sampled in rust.
+    BakedCurve2D, Vector2
+);
+
+baked_curve!(
+    /// The baked points of a `Curve3D`, sampled in rust.
+    BakedCurve3D, Vector3
+);
+
+impl Curve2D {
+    /// Copies the baked points of the curve.
+    pub fn baked(&self) -> BakedCurve2D {
+        BakedCurve2D::from_points(self.get_baked_points().to_vec())
+    }
+}
+
+impl Curve3D {
+    /// Copies the baked points of the curve.
+    pub fn baked(&self) -> BakedCurve3D {
+        BakedCurve3D::from_points(self.get_baked_points().to_vec())
+    }
+
+    /// Copies the baked tilts of the curve, one per baked point.
+    pub fn baked_tilts(&self) -> Vec<f32> {
+        self.get_baked_tilts().to_vec()
+    }
+}
+
+/// The values of a `Curve` sampled at regular offsets, see `Curve::table`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurveTable {
+    values: Vec<f32>,
+}
+
+impl CurveTable {
+    /// Creates a table from values sampled at regular offsets from 0 to 1.
+    pub fn from_values(values: Vec<f32>) -> Self {
+        CurveTable { values }
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Returns the value at the given offset, interpolating linearly between the
+    /// samples. The offset is clamped to `[0, 1]`; an empty table returns 0.
+    pub fn sample(&self, offset: f32) -> f32 {
+        match table_position(self.values.len(), offset) {
+            Some((idx, 0.0)) => self.values[idx],
+            Some((idx, t)) => self.values[idx] + (self.values[idx + 1] - self.values[idx]) * t,
+            None => 0.0,
+        }
+    }
+}
+
+/// The colors of a `Gradient` sampled at regular offsets, see `Gradient::table`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradientTable {
+    colors: Vec<Color>,
+}
+
+impl GradientTable {
+    /// Creates a table from colors sampled at regular offsets from 0 to 1.
+    pub fn from_colors(colors: Vec<Color>) -> Self {
+        GradientTable { colors }
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Returns the color at the given offset, interpolating linearly between the
+    /// samples. The offset is clamped to `[0, 1]`; an empty table returns
+    /// transparent black.
+    pub fn sample(&self, offset: f32) -> Color {
+        match table_position(self.colors.len(), offset) {
+            Some((idx, 0.0)) => self.colors[idx],
+            Some((idx, t)) => {
+                let (a, b) = (self.colors[idx], self.colors[idx + 1]);
+                Color::rgba(
+                    a.r + (b.r - a.r) * t,
+                    a.g + (b.g - a.g) * t,
+                    a.b + (b.b - a.b) * t,
+                    a.a + (b.a - a.a) * t,
+                )
+            }
+            None => Color::rgba(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// Returns the index of the sample before the offset in a table of `len` samples,
+// and the position between that sample and the next one.
+fn table_position(len: usize, offset: f32) -> Option<(usize, f32)> {
+    if len == 0 {
+        return None;
+    }
+
+    let position = offset.max(0.0).min(1.0) * (len - 1) as f32;
+    let idx = position.floor() as usize;
+    if idx + 1 >= len {
+        return Some((len - 1, 0.0));
+    }
+    Some((idx, position - idx as f32))
+}
+
+// The offsets from 0 to 1 of `resolution` samples, at least 2.
+fn sample_offsets(resolution: usize) -> impl Iterator<Item = f64> {
+    let last = resolution.max(2) - 1;
+    (0..=last).map(move |idx| idx as f64 / last as f64)
+}
+
+impl Curve {
+    /// Samples the curve at `resolution` regular offsets from 0 to 1. The table is
+    /// a copy, it isn't updated when the curve is edited.
+    pub fn table(&self, resolution: usize) -> CurveTable {
+        CurveTable::from_values(sample_offsets(resolution).map(|offset| self.interpolate(offset) as f32).collect())
+    }
+}
+
+impl Gradient {
+    /// Samples the gradient at `resolution` regular offsets from 0 to 1. The table
+    /// is a copy, it isn't updated when the gradient is edited.
+    pub fn table(&mut self, resolution: usize) -> GradientTable {
+        GradientTable::from_colors(sample_offsets(resolution).map(|offset| self.interpolate(offset)).collect())
+    }
+}
+
+impl PathFollow {
+    /// Moves the follower along the path by the given distance.
+    pub fn advance(&mut self, distance: f64) {
+        let offset = self.get_offset();
+        self.set_offset(offset + distance);
+    }
+}
+
+impl PathFollow2D {
+    /// Moves the follower along the path by the given distance.
+    pub fn advance(&mut self, distance: f64) {
+        let offset = self.get_offset();
+        self.set_offset(offset + distance);
+    }
+}
+
+#[test]
+fn baked_curve_sampling() {
+    let curve = BakedCurve2D::from_points(vec![
+        Vector2::new(0.0, 0.0),
+        Vector2::new(10.0, 0.0),
+        Vector2::new(10.0, 5.0),
+    ]);
+
+    assert_eq!(curve.length(), 15.0);
+    assert_eq!(curve.interpolate(5.0), Some(Vector2::new(5.0, 0.0)));
+    assert_eq!(curve.interpolate(12.0), Some(Vector2::new(10.0, 2.0)));
+    assert_eq!(curve.interpolate(100.0), Some(Vector2::new(10.0, 5.0)));
+    assert_eq!(curve.interpolate(-1.0), Some(Vector2::new(0.0, 0.0)));
+
+    assert_eq!(curve.closest_offset(Vector2::new(3.0, -2.0)), Some(3.0));
+    assert_eq!(curve.closest_offset(Vector2::new(12.0, 4.0)), Some(14.0));
+    assert_eq!(curve.closest_point(Vector2::new(20.0, 1.0)), Some(Vector2::new(10.0, 1.0)));
+
+    assert_eq!(BakedCurve3D::from_points(Vec::new()).interpolate(1.0), None);
+}
+
+#[test]
+fn lookup_tables() {
+    let curve = CurveTable::from_values(vec![0.0, 1.0, 0.5]);
+    assert_eq!(curve.sample(0.0), 0.0);
+    assert_eq!(curve.sample(0.25), 0.5);
+    assert_eq!(curve.sample(0.75), 0.75);
+    assert_eq!(curve.sample(1.0), 0.5);
+    assert_eq!(curve.sample(2.0), 0.5);
+    assert_eq!(curve.sample(-1.0), 0.0);
+    assert_eq!(CurveTable::from_values(Vec::new()).sample(0.5), 0.0);
+    assert_eq!(CurveTable::from_values(vec![3.0]).sample(0.5), 3.0);
+
+    let gradient = GradientTable::from_colors(vec![Color::rgb(0.0, 0.0, 0.0), Color::rgb(1.0, 0.5, 0.0)]);
+    assert_eq!(gradient.sample(0.5), Color::rgb(0.5, 0.25, 0.0));
+    assert_eq!(gradient.sample(1.0), Color::rgb(1.0, 0.5, 0.0));
+
+    assert_eq!(sample_offsets(3).collect::<Vec<_>>(), vec![0.0, 0.5, 1.0]);
+    assert_eq!(sample_offsets(0).collect::<Vec<_>>(), vec![0.0, 1.0]);
+}
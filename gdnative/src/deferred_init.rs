@@ -0,0 +1,231 @@
+//! Initialization waiting for the nodes a class depends on.
+//!
+//! The `_ready` of a node runs before the `_ready` of the nodes after it in the
+//! tree, and an autoload added by a plugin or a node instanced by a GDScript parent
+//! can be missing when it runs. Instead of reordering the scene or yielding frames
+//! until it works, a class declares what it needs with `DeferredInit`, and its
+//! real initialization runs once all of it is available:
+//!
+//! ```ignore
+//! #[export]
+//! fn _ready(&mut self, owner: Node) {
+//!     DeferredInit::new(&owner)
+//!         .autoload("GameState")
+//!         .node("../HUD")
+//!         .run(move |deps| {
+//!             let hud = deps.get("../HUD").unwrap();
+//!             let player = Instance::<Player>::try_from_base(&owner).unwrap();
+//!             player.map_mut(|player| player.init(deps.get("GameState").unwrap(), hud));
+//!         })
+//!         .expect("the tasks can't run");
+//! }
+//! ```
+//!
+//! The dependencies are checked by a task of the `tasks` module once per frame,
+//! starting with the frame after `run`, so the callback never runs while the method
+//! calling `run` borrows the instance. A dependency still missing after the timeout
+//! is reported with `godot_error!`, and the task keeps waiting for it. The task
+//! stops without calling the callback if the owner is freed first.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tasks::{freed_flag, spawn};
+use GodotError;
+use GodotObject;
+use Node;
+use NodePath;
+use _OS;
+
+/// The seconds after which the missing dependencies are reported, see
+/// `DeferredInit::timeout`.
+pub const DEFAULT_DEPENDENCY_TIMEOUT: f64 = 5.0;
+
+/// The dependencies of an initialization, see the `deferred_init` module.
+pub struct DeferredInit {
+    owner: Node,
+    dependencies: Vec<Dependency>,
+    timeout: f64,
+}
+
+struct Dependency {
+    name: String,
+    path: String,
+}
+
+impl DeferredInit {
+    /// An initialization of `owner`, the node of the class, without dependencies.
+    pub fn new(owner: &Node) -> Self {
+        DeferredInit {
+            owner: unsafe { Node::from_sys(owner.to_sys()) },
+            dependencies: Vec::new(),
+            timeout: DEFAULT_DEPENDENCY_TIMEOUT,
+        }
+    }
+
+    /// Waits for the autoload `name`, which is available once the owner is inside
+    /// the tree.
+    pub fn autoload(mut self, name: &str) -> Self {
+        self.dependencies.push(Dependency {
+            name: name.to_string(),
+            path: format!("/root/{}", name),
+        });
+        self
+    }
+
+    /// Waits for the node at `path`, relative to the owner or absolute.
+    pub fn node(mut self, path: &str) -> Self {
+        self.dependencies.push(Dependency {
+            name: path.to_string(),
+            path: path.to_string(),
+        });
+        self
+    }
+
+    /// The seconds after which the missing dependencies are reported, 5 by default.
+    pub fn timeout(mut self, seconds: f64) -> Self {
+        self.timeout = seconds;
+        self
+    }
+
+    /// Calls `callback` with the dependencies once they are all available. Must be
+    /// called from the main thread.
+    ///
+    /// Fails if the library is not initialized.
+    pub fn run<F>(self, callback: F) -> Result<(), GodotError>
+    where F: FnOnce(Dependencies) + 'static {
+        let freed = freed_flag(&self.owner)?;
+        spawn(WaitDependencies {
+            init: self,
+            callback: Some(Box::new(callback)),
+            freed,
+            started: ticks_msec(),
+            reported: false,
+        });
+        Ok(())
+    }
+}
+
+/// The dependencies passed to the callback of `DeferredInit::run`.
+pub struct Dependencies {
+    nodes: Vec<(String, Node)>,
+}
+
+impl Dependencies {
+    /// The dependency added with the name of the autoload or the path of the node.
+    pub fn get(&self, name: &str) -> Option<Node> {
+        self.nodes.iter()
+            .find(|&&(ref dep, _)| dep == name)
+            .map(|&(_, ref node)| unsafe { Node::from_sys(node.to_sys()) })
+    }
+
+    /// The dependency cast to `T`, `None` if it has another class.
+    pub fn get_as<T: GodotObject>(&self, name: &str) -> Option<T> {
+        self.get(name).and_then(|node| node.cast::<T>())
+    }
+}
+
+// The task checking the dependencies once per frame.
+struct WaitDependencies {
+    init: DeferredInit,
+    callback: Option<Box<dyn FnOnce(Dependencies)>>,
+    freed: Rc<Cell<bool>>,
+    started: i64,
+    reported: bool,
+}
+
+impl WaitDependencies {
+    fn find(&self, dependency: &Dependency) -> Option<Node> {
+        let owner = &self.init.owner;
+        // Absolute paths are an error outside the tree, which the engine would
+        // report every frame.
+        if dependency.path.starts_with('/') && !owner.is_inside_tree() {
+            return None;
+        }
+        if !owner.has_node(NodePath::from_str(&dependency.path)) {
+            return None;
+        }
+        owner.get_node(NodePath::from_str(&dependency.path))
+    }
+}
+
+impl Future for WaitDependencies {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = &mut *self;
+        if this.freed.get() {
+            return Poll::Ready(());
+        }
+
+        let mut nodes = Vec::new();
+        let mut missing = Vec::new();
+        for dependency in &this.init.dependencies {
+            match this.find(dependency) {
+                Some(node) => nodes.push((dependency.name.clone(), node)),
+                None => missing.push(dependency.name.as_str()),
+            }
+        }
+
+        if missing.is_empty() {
+            if let Some(callback) = this.callback.take() {
+                callback(Dependencies { nodes });
+            }
+            return Poll::Ready(());
+        }
+
+        if !this.reported && (ticks_msec() - this.started) as f64 >= this.init.timeout * 1000.0 {
+            this.reported = true;
+            godot_error!(
+                "{}: still waiting for {} after {} seconds",
+                this.init.owner.get_name().to_string(),
+                missing.join(", "),
+                this.init.timeout,
+            );
+        }
+
+        // Checked again by the next frame.
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+fn ticks_msec() -> i64 {
+    _OS::godot_singleton().get_ticks_msec()
+}
+
+godot_test!(test_deferred_init {
+    use std::cell::RefCell;
+    use tasks::{pending_tasks, run_tasks};
+    use GodotString;
+
+    let mut owner = Node::new();
+    let found = Rc::new(RefCell::new(None));
+
+    let result = found.clone();
+    DeferredInit::new(&owner)
+        .node("Child")
+        .run(move |deps| *result.borrow_mut() = Some(deps.get("Child").map(|node| node.get_name().to_string())))
+        .unwrap();
+    run_tasks();
+    assert!(found.borrow().is_none());
+
+    let mut child = Node::new();
+    child.set_name(GodotString::from_str("Child"));
+    owner.add_child(Some(child.as_object()), false);
+    run_tasks();
+    assert_eq!(*found.borrow(), Some(Some("Child".to_string())));
+
+    // Freeing the owner stops the task waiting for the autoload outside the tree.
+    let called = Rc::new(Cell::new(false));
+    let flag = called.clone();
+    DeferredInit::new(&owner).autoload("Missing").run(move |_| flag.set(true)).unwrap();
+    run_tasks();
+    let tasks = pending_tasks();
+    unsafe { owner.free(); }
+    run_tasks();
+    assert_eq!(pending_tasks(), tasks - 1);
+    assert!(!called.get());
+});
@@ -0,0 +1,99 @@
+//! Warnings about the engine methods removed, or behaving differently, in the
+//! version of the running engine.
+//!
+//! The bindings are generated from the API of one engine version, `api.json`, and
+//! the library can be loaded by a newer one. The bindings generator compares
+//! `api.json` with the APIs of the newer versions, the `api-<major>.<minor>.json`
+//! files dumped by these engines (`godot --gdnative-generate-json-api`), and finds
+//! the methods which were removed, or whose arguments or return type changed:
+//!
+//! ```ignore
+//! /// Behaves differently since Godot 3.1: the engine method is now `move_and_slide(
+//! /// linear_velocity: Vector2, floor_normal: Vector2, stop_on_slope: bool, ...) -> Vector2`.
+//! pub fn move_and_slide(&mut self, ...) -> Vector2
+//! ```
+//!
+//! The generated wrappers of these methods warn once per method when the running
+//! engine is at least the version of the change, with the new signature of the
+//! method, or the methods which may replace a removed one. Supporting a new engine
+//! version only takes adding the dump of its API.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use Variant;
+use _Engine;
+
+// `major << 16 | minor`, or `UNKNOWN` until the first check.
+static ENGINE_VERSION: AtomicU32 = AtomicU32::new(UNKNOWN);
+const UNKNOWN: u32 = 0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DeprecationKind {
+    Removed,
+    Changed,
+}
+
+/// A method found by the bindings generator, declared by the generated wrapper.
+pub(crate) struct Deprecation {
+    method: &'static str,
+    since: (u32, u32),
+    kind: DeprecationKind,
+    note: &'static str,
+    checked: AtomicBool,
+}
+
+impl Deprecation {
+    pub(crate) const fn new(
+        method: &'static str,
+        major: u32,
+        minor: u32,
+        kind: DeprecationKind,
+        note: &'static str,
+    ) -> Self {
+        Deprecation { method, since: (major, minor), kind, note, checked: AtomicBool::new(false) }
+    }
+
+    /// Warns if the running engine is at least the `since` version, on the first
+    /// call only.
+    pub(crate) fn check(&self) {
+        if self.checked.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        if engine_version() >= self.since {
+            godot_warn!("{}", self.message());
+        }
+    }
+
+    fn message(&self) -> String {
+        let what = match self.kind {
+            DeprecationKind::Removed => "was removed in",
+            DeprecationKind::Changed => "behaves differently since",
+        };
+        format!("{} {} Godot {}.{}: {}", self.method, what, self.since.0, self.since.1, self.note)
+    }
+}
+
+fn engine_version() -> (u32, u32) {
+    let mut version = ENGINE_VERSION.load(Ordering::Acquire);
+    if version == UNKNOWN {
+        let info = _Engine::godot_singleton().get_version_info();
+        let field = |name: &str| info.get(&Variant::from_str(name)).try_to_i64().unwrap_or(0) as u32;
+        version = field("major") << 16 | field("minor");
+        ENGINE_VERSION.store(version, Ordering::Release);
+    }
+    (version >> 16, version & 0xffff)
+}
+
+#[test]
+fn deprecation_messages() {
+    let changed = Deprecation::new("KinematicBody2D.test_move", 3, 1, DeprecationKind::Changed, "see the docs");
+    assert_eq!(changed.message(), "KinematicBody2D.test_move behaves differently since Godot 3.1: see the docs");
+
+    let removed = Deprecation::new("TextEdit.set_wrap", 3, 1, DeprecationKind::Removed, "it may be replaced by `set_wrap_enabled`");
+    assert_eq!(removed.message(), "TextEdit.set_wrap was removed in Godot 3.1: it may be replaced by `set_wrap_enabled`");
+
+    ENGINE_VERSION.store(3 << 16, Ordering::Release);
+    assert_eq!(engine_version(), (3, 0));
+    // Older engines don't warn, and the method isn't checked again.
+    changed.check();
+    assert!(changed.checked.load(Ordering::Acquire));
+}
@@ -0,0 +1,239 @@
+//! Implementation of the `godot_warn!` and `godot_error!` macros, and reports of the
+//! panics of the rust code.
+
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::ffi::CString;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use try_get_api;
+
+// Maximum number of frames included in the compact backtraces.
+const MAX_FRAMES: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[doc(hidden)]
+pub enum MessageKind {
+    Warning,
+    Error,
+}
+
+/// Reports a warning or an error to the engine, with the module path used as the
+/// function name.
+///
+/// A compact backtrace of the rust frames is appended to the message when backtraces
+/// are enabled with the `RUST_BACKTRACE` (or `RUST_LIB_BACKTRACE`) environment
+/// variable.
+#[doc(hidden)]
+pub fn print_message(kind: MessageKind, msg: &str, module_path: &str, file: &str, line: u32) {
+    print_with_backtrace(kind, msg, compact_backtrace(), module_path, file, line);
+}
+
+fn print_with_backtrace(kind: MessageKind, msg: &str, backtrace: Option<String>, module_path: &str, file: &str, line: u32) {
+    let mut msg = msg.to_string();
+    record_message(&format!("{}: {}", match kind {
+        MessageKind::Warning => "WARNING",
+        MessageKind::Error => "ERROR",
+    }, msg));
+
+    if let Some(backtrace) = backtrace {
+        msg.push_str(&backtrace);
+    }
+
+    let api = match try_get_api() {
+        Some(api) => api,
+        None => {
+            let prefix = match kind {
+                MessageKind::Warning => "WARNING",
+                MessageKind::Error => "ERROR",
+            };
+            eprintln!("{}: {}: {}\n   At: {}:{}", prefix, module_path, msg, file, line);
+            return;
+        }
+    };
+
+    let msg = c_string(msg);
+    let func = c_string(module_path.to_string());
+    let file = c_string(file.to_string());
+
+    let print = match kind {
+        MessageKind::Warning => api.godot_print_warning,
+        MessageKind::Error => api.godot_print_error,
+    };
+
+    unsafe {
+        print(
+            msg.as_ptr() as *const _,
+            func.as_ptr() as *const _,
+            file.as_ptr() as *const _,
+            line as _,
+        );
+    }
+}
+
+/// Keeps a printed message for the crash reports, see the `crash_dump` module.
+#[doc(hidden)]
+#[inline]
+pub fn record_message(msg: &str) {
+    #[cfg(feature = "crash_dump")]
+    ::crash_dump::keep_message(msg);
+    #[cfg(not(feature = "crash_dump"))]
+    let _ = msg;
+}
+
+fn c_string(s: String) -> CString {
+    CString::new(s.replace('\0', "\\0")).unwrap()
+}
+
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Reports the panics of the rust code with `godot_error!`, with the location of the
+/// panic and a compact backtrace of the rust frames. Installed by
+/// `godot_gdnative_init!`; the hook previously installed still runs after the report.
+pub fn install_panic_hook() {
+    if PANIC_HOOK_INSTALLED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        let (file, line) = panic_info.location()
+            .map(|location| (location.file(), location.line()))
+            .unwrap_or(("<unknown>", 0));
+        let backtrace = compact_frames(&Backtrace::force_capture().to_string());
+        print_with_backtrace(
+            MessageKind::Error,
+            &format!("panicked: {}", panic_message(panic_info.payload())),
+            Some(backtrace),
+            "rust",
+            file,
+            line,
+        );
+
+        previous(panic_info);
+    }));
+}
+
+/// Calls `f`, catching a panic so that it doesn't unwind into the engine. A panic is
+/// reported as an error of `callback`, the name of the method, property or class
+/// called by the engine, and `None` is returned.
+#[doc(hidden)]
+pub fn catch_panic<R, F: FnOnce() -> R>(callback: &str, f: F) -> Option<R> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            // The message was already reported by the hook.
+            let msg = if PANIC_HOOK_INSTALLED.load(Ordering::Acquire) {
+                "the call was aborted by a panic".to_string()
+            } else {
+                format!("the call was aborted by a panic: {}", panic_message(&*payload))
+            };
+            print_with_backtrace(MessageKind::Error, &msg, None, callback, file!(), line!());
+            None
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&'static str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "<non-string payload>"
+    }
+}
+
+/// Returns the frames of the current backtrace that are not part of the standard
+/// library or of this module, one per line, or `None` if backtraces are disabled.
+#[doc(hidden)]
+pub fn compact_backtrace() -> Option<String> {
+    let backtrace = Backtrace::capture();
+    if backtrace.status() != BacktraceStatus::Captured {
+        return None;
+    }
+
+    Some(compact_frames(&backtrace.to_string()))
+}
+
+// The `Display` implementation of `Backtrace` prints each frame as
+// "  N: symbol" optionally followed by "      at file:line".
+fn compact_frames(backtrace: &str) -> String {
+    let mut out = String::new();
+    let mut frames = 0;
+    let mut keep = false;
+
+    for line in backtrace.lines() {
+        let line = line.trim();
+
+        if let Some(location) = line.strip_prefix("at ") {
+            if keep {
+                let _ = write!(out, " ({})", location);
+            }
+            continue;
+        }
+
+        let symbol = match line.find(": ") {
+            Some(idx) if line[..idx].chars().all(|c| c.is_ascii_digit()) => &line[idx + 2..],
+            _ => continue,
+        };
+
+        keep = !is_internal_frame(symbol) && frames < MAX_FRAMES;
+        if keep {
+            frames += 1;
+            let _ = write!(out, "\n    at {}", symbol);
+        }
+    }
+
+    out
+}
+
+fn is_internal_frame(symbol: &str) -> bool {
+    const INTERNAL: &[&str] = &[
+        "std::",
+        "core::",
+        "alloc::",
+        "<alloc::",
+        "<core::",
+        "<std::",
+        "gdnative::diagnostics::",
+        "rust_begin_unwind",
+        "rust_panic",
+        "__rust",
+        "__libc",
+        "_start",
+        "clone",
+        "start_thread",
+        "test::",
+    ];
+
+    INTERNAL.iter().any(|prefix| symbol.starts_with(prefix))
+}
+
+#[test]
+fn backtrace_filtering() {
+    let backtrace = "   0: std::backtrace::Backtrace::capture
+             at /rustc/library/std/src/backtrace.rs:296:9
+   1: gdnative::diagnostics::compact_backtrace
+             at ./src/diagnostics.rs:71:21
+   2: my_game::player::Player::_ready
+             at ./src/player.rs:12:9
+   3: my_game::godot_nativescript_init
+   4: core::ops::function::FnOnce::call_once
+             at /rustc/library/core/src/ops/function.rs:250:5";
+
+    assert_eq!(
+        compact_frames(backtrace),
+        "\n    at my_game::player::Player::_ready (./src/player.rs:12:9)\n    at my_game::godot_nativescript_init"
+    );
+}
+
+#[test]
+fn caught_panics() {
+    assert_eq!(catch_panic("Player::jump", || 3), Some(3));
+    assert_eq!(catch_panic("Player::jump", || -> i64 { panic!("no floor") }), None);
+    assert_eq!(panic_message(&"static"), "static");
+    assert_eq!(panic_message(&format!("{} chunks", 3)), "3 chunks");
+    assert_eq!(panic_message(&3), "<non-string payload>");
+}
@@ -3,10 +3,24 @@ use get_api;
 use Variant;
 use VariantArray;
 use GodotString;
-use GodotType;
+use FromVariant;
+use ToVariant;
+use std::cmp::Ordering;
 use std::fmt;
+use std::iter::FromIterator;
 
 /// A reference-counted `Dictionary` of `Variant` key-value pairs.
+///
+/// ## Iteration order
+///
+/// The engine keeps the pairs in insertion order: `keys`, `values`, the iterators
+/// and `to_json` all list them in the order the keys were first inserted. Setting
+/// the value of an existing key keeps its position, erasing a key and inserting it
+/// again moves it to the end.
+///
+/// Two dictionaries with the same pairs inserted in a different order are equal,
+/// but they are iterated, serialized and hashed (`hash`) differently. Use
+/// `sorted_keys` or `iter_sorted` when the result must only depend on the content.
 pub struct Dictionary(pub(crate) sys::godot_dictionary);
 
 impl Dictionary {
@@ -58,7 +72,7 @@ impl Dictionary {
     /// Returns a copy of the value corresponding to the key.
     pub fn get(&self, key: &Variant) -> Variant {
         unsafe {
-            Variant((get_api().godot_dictionary_get)(&self.0, &key.0))
+            Variant::from_sys((get_api().godot_dictionary_get)(&self.0, &key.0))
         }
     }
 
@@ -110,6 +124,64 @@ impl Dictionary {
         }
     }
 
+    /// Returns an iterator over copies of the key-value pairs of the `Dictionary`.
+    ///
+    /// The iterator walks the keys the `Dictionary` had when it was created.
+    pub fn iter(&self) -> DictionaryIter<'_> {
+        DictionaryIter {
+            dictionary: self,
+            keys: self.keys(),
+            next: 0,
+        }
+    }
+
+    /// Returns an iterator over copies of the keys of the `Dictionary`.
+    pub fn iter_keys(&self) -> DictionaryKeys<'_> {
+        DictionaryKeys(self.iter())
+    }
+
+    /// Returns an iterator over copies of the values of the `Dictionary`.
+    pub fn iter_values(&self) -> DictionaryValues<'_> {
+        DictionaryValues(self.iter())
+    }
+
+    /// Returns the keys ordered by type (in the order of `VariantType`), then by
+    /// value, independently of the insertion order.
+    ///
+    /// Values of the same type are compared with the `<` operator of the engine.
+    /// Objects are compared by address, which differs between runs.
+    pub fn sorted_keys(&self) -> Vec<Variant> {
+        let mut keys: Vec<Variant> = self.iter_keys().collect();
+        keys.sort_by(variant_order);
+        keys
+    }
+
+    /// Returns an iterator over copies of the key-value pairs of the `Dictionary`, in
+    /// the order of `sorted_keys`.
+    pub fn iter_sorted(&self) -> DictionaryIter<'_> {
+        let mut keys = VariantArray::new();
+        for key in self.sorted_keys() {
+            keys.push(&key);
+        }
+
+        DictionaryIter {
+            dictionary: self,
+            keys,
+            next: 0,
+        }
+    }
+
+    /// Returns the entry of a key, to read or modify its value without looking the
+    /// key up several times:
+    ///
+    /// ```ignore
+    /// let count = counts.entry(Variant::from_str("jumps")).or_insert(Variant::from_i64(0));
+    /// *count = Variant::from_i64(count.to_i64() + 1);
+    /// ```
+    pub fn entry(&mut self, key: Variant) -> DictionaryEntry<'_> {
+        DictionaryEntry { dictionary: self, key }
+    }
+
     /// Return a hashed i32 value representing the dictionary's contents.
     pub fn hash(&self) -> i32 {
         unsafe {
@@ -123,6 +195,24 @@ impl Dictionary {
     }
 }
 
+fn variant_order(a: &Variant, b: &Variant) -> Ordering {
+    let by_type = (a.get_type() as u32).cmp(&(b.get_type() as u32));
+    if by_type != Ordering::Equal {
+        return by_type;
+    }
+
+    unsafe {
+        let less = get_api().godot_variant_operator_less;
+        if less(&a.0, &b.0) {
+            Ordering::Less
+        } else if less(&b.0, &a.0) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
 impl_basic_traits!(
     for Dictionary as godot_dictionary {
         Drop => godot_dictionary_destroy;
@@ -131,11 +221,137 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for Dictionary {
+impl_sys_access!(for Dictionary as godot_dictionary);
+
+impl ToVariant for Dictionary {
     fn to_variant(&self) -> Variant { Variant::from_dictionary(self) }
+}
+
+impl FromVariant for Dictionary {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_dictionary() }
 }
 
+/// An iterator over the key-value pairs of a `Dictionary`, see `Dictionary::iter`.
+pub struct DictionaryIter<'a> {
+    dictionary: &'a Dictionary,
+    keys: VariantArray,
+    next: i32,
+}
+
+impl<'a> Iterator for DictionaryIter<'a> {
+    type Item = (Variant, Variant);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next < self.keys.len() {
+            let key = self.keys.get_ref(self.next).clone();
+            self.next += 1;
+            // Skips the keys erased through another reference to the dictionary.
+            if self.dictionary.contains(&key) {
+                let value = self.dictionary.get(&key);
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some((self.keys.len() - self.next) as usize))
+    }
+}
+
+impl<'a> IntoIterator for &'a Dictionary {
+    type Item = (Variant, Variant);
+    type IntoIter = DictionaryIter<'a>;
+
+    fn into_iter(self) -> DictionaryIter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the keys of a `Dictionary`, see `Dictionary::iter_keys`.
+pub struct DictionaryKeys<'a>(DictionaryIter<'a>);
+
+impl<'a> Iterator for DictionaryKeys<'a> {
+    type Item = Variant;
+
+    fn next(&mut self) -> Option<Variant> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+/// An iterator over the values of a `Dictionary`, see `Dictionary::iter_values`.
+pub struct DictionaryValues<'a>(DictionaryIter<'a>);
+
+impl<'a> Iterator for DictionaryValues<'a> {
+    type Item = Variant;
+
+    fn next(&mut self) -> Option<Variant> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+/// The entry of a key in a `Dictionary`, see `Dictionary::entry`.
+pub struct DictionaryEntry<'a> {
+    dictionary: &'a mut Dictionary,
+    key: Variant,
+}
+
+impl<'a> DictionaryEntry<'a> {
+    pub fn key(&self) -> &Variant {
+        &self.key
+    }
+
+    /// Returns `true` if the key is in the `Dictionary`.
+    pub fn exists(&self) -> bool {
+        self.dictionary.contains(&self.key)
+    }
+
+    /// Inserts `default` if the key is missing and returns a reference to the value.
+    pub fn or_insert(self, default: Variant) -> &'a mut Variant {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the value returned by `default` if the key is missing and returns a
+    /// reference to the value.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut Variant
+    where F: FnOnce() -> Variant {
+        if !self.dictionary.contains(&self.key) {
+            self.dictionary.set(&self.key, &default());
+        }
+        self.dictionary.get_mut_ref(&self.key)
+    }
+
+    /// Inserts nil if the key is missing and returns a reference to the value.
+    pub fn or_nil(self) -> &'a mut Variant {
+        self.or_insert_with(Variant::new)
+    }
+
+    /// Calls `f` with the value if the key is in the `Dictionary`.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where F: FnOnce(&mut Variant) {
+        if self.dictionary.contains(&self.key) {
+            f(self.dictionary.get_mut_ref(&self.key));
+        }
+        self
+    }
+}
+
+impl<K: ToVariant, V: ToVariant> FromIterator<(K, V)> for Dictionary {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut dictionary = Dictionary::new();
+        dictionary.extend(iter);
+        dictionary
+    }
+}
+
+impl<K: ToVariant, V: ToVariant> Extend<(K, V)> for Dictionary {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.set(&key.to_variant(), &value.to_variant());
+        }
+    }
+}
+
 impl fmt::Debug for Dictionary {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         self.to_json().to_string().fmt(f)
@@ -186,6 +402,48 @@ godot_test!(test_dictionary {
     }
 });
 
+godot_test!(test_dictionary_iter {
+    let mut dict: Dictionary = vec![("a", 1i64), ("b", 2)]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    assert_eq!(dict.len(), 2);
+
+    let mut sum = 0;
+    for (key, value) in &dict {
+        assert!(key == Variant::from_str("a") || key == Variant::from_str("b"));
+        sum += value.to_i64();
+    }
+    assert_eq!(sum, 3);
+    assert_eq!(dict.iter_keys().count(), 2);
+    assert_eq!(dict.iter_values().map(|value| value.to_i64()).sum::<i64>(), 3);
+
+    *dict.entry(Variant::from_str("c")).or_insert(Variant::from_i64(3)) = Variant::from_i64(4);
+    dict.entry(Variant::from_str("a"))
+        .and_modify(|value| *value = Variant::from_i64(10))
+        .or_insert(Variant::from_i64(0));
+    assert_eq!(dict.get(&Variant::from_str("c")).to_i64(), 4);
+    assert_eq!(dict.get(&Variant::from_str("a")).to_i64(), 10);
+    assert!(dict.entry(Variant::from_str("d")).or_nil().is_nil());
+
+    dict.extend(vec![(Variant::from_str("e"), Variant::from_i64(5))]);
+    assert_eq!(dict.len(), 5);
+
+    // Insertion order, an existing key keeps its position.
+    let keys: Vec<String> = dict.iter_keys().map(|key| key.to_string()).collect();
+    assert_eq!(keys, vec!["a", "b", "c", "d", "e"]);
+    dict.erase(&Variant::from_str("b"));
+    dict.set(&Variant::from_str("b"), &Variant::from_i64(2));
+    let keys: Vec<String> = dict.iter_keys().map(|key| key.to_string()).collect();
+    assert_eq!(keys, vec!["a", "c", "d", "e", "b"]);
+
+    dict.set(&Variant::from_i64(7), &Variant::new());
+    dict.set(&Variant::from_i64(-1), &Variant::new());
+    let sorted: Vec<String> = dict.sorted_keys().iter().map(|key| key.to_string()).collect();
+    assert_eq!(sorted, vec!["-1", "7", "a", "b", "c", "d", "e"]);
+    assert_eq!(dict.iter_sorted().next().map(|(key, _)| key.to_i64()), Some(-1));
+});
+
 // TODO: clear dictionaries without affecting clones
 //godot_test!(test_dictionary_clone_clear {
 //    let foo = Variant::from_str("foo");
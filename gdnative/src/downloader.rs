@@ -0,0 +1,386 @@
+//! Files downloaded by the tasks of the `tasks` module, cached in `user://cache`.
+//!
+//! Downloading a file with `HTTPRequest` takes a node in the tree, a connection to
+//! `request_completed`, and the parsing of its arguments. `Download` does all of it
+//! from a task, and returns the body or the resource it contains:
+//!
+//! ```ignore
+//! spawn(async move {
+//!     let level = Download::new("https://example.com/levels/forest.tscn")
+//!         .on_progress(move |downloaded, total| bar.set_value(downloaded as f64 / total as f64))
+//!         .resource::<PackedScene>()
+//!         .await;
+//!
+//!     match level {
+//!         Ok(level) => owner.add_child(level.instance(0).map(|node| node.as_object()), false),
+//!         Err(err) => godot_error!("the level can't be downloaded: {}", err),
+//!     }
+//! });
+//! ```
+//!
+//! The body is written to `user://cache`, with the `ETag` header sent by the server.
+//! The next download of the same URL sends it back in `If-None-Match`, and reads the
+//! file of the cache if the server answers that it didn't change.
+//!
+//! The `HTTPRequest` is added to the root of the scene tree at the end of the frame,
+//! and freed when the download ends or the future is dropped.
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tasks::{scene_tree, SignalFuture};
+use ByteArray;
+use GodotError;
+use GodotObject;
+use GodotString;
+use HTTPClientMethod;
+use HTTPRequest;
+use StringArray;
+use Variant;
+use _Directory;
+use _File;
+use _FileModeFlags;
+use _ResourceLoader;
+
+/// The directory of the downloaded files.
+pub const DOWNLOAD_CACHE_DIR: &str = "user://cache";
+
+/// A download, see the `downloader` module.
+pub struct Download {
+    url: String,
+    headers: Vec<String>,
+    cache: bool,
+    ssl_validate_domain: bool,
+    progress: Option<Box<dyn FnMut(i64, i64)>>,
+}
+
+impl Download {
+    /// A download of `url`, cached.
+    pub fn new(url: &str) -> Self {
+        Download {
+            url: url.to_string(),
+            headers: Vec::new(),
+            cache: true,
+            ssl_validate_domain: true,
+            progress: None,
+        }
+    }
+
+    /// Sends the header `header`, written `Name: value`.
+    pub fn header(mut self, header: &str) -> Self {
+        self.headers.push(header.to_string());
+        self
+    }
+
+    /// Whether the body is written to the cache and the cached file reused, true by
+    /// default. `resource` always writes the file, which the engine loads.
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.cache = enabled;
+        self
+    }
+
+    /// Whether the certificate of an https server must match its domain, true by
+    /// default.
+    pub fn ssl_validate_domain(mut self, enabled: bool) -> Self {
+        self.ssl_validate_domain = enabled;
+        self
+    }
+
+    /// Calls `callback` with the bytes received and the size of the body once per
+    /// frame while they change. The size is -1 until the server sends it, and for
+    /// the bodies sent in chunks.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where F: FnMut(i64, i64) + 'static {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// The path of the cached body, named after the URL and keeping its extension
+    /// for the resource loader.
+    pub fn cache_path(&self) -> String {
+        let hash = GodotString::from_str(&self.url).md5_text().to_string();
+        match url_extension(&self.url) {
+            Some(extension) => format!("{}/{}.{}", DOWNLOAD_CACHE_DIR, hash, extension),
+            None => format!("{}/{}", DOWNLOAD_CACHE_DIR, hash),
+        }
+    }
+
+    /// The body of the response. Must be awaited by a task of the main thread.
+    pub fn bytes(self) -> DownloadFuture<ByteArray> {
+        DownloadFuture::new(self, |body, _path| Ok(body))
+    }
+
+    /// The resource in the body, loaded from the cache by the `ResourceLoader`. The
+    /// URL must end with an extension the loader recognizes, like `.tres` or
+    /// `.scn`: the images and sounds which are imported by the editor are loaded
+    /// from the bytes instead, with `Image::load_png_from_buffer` for example.
+    pub fn resource<T: GodotObject>(mut self) -> DownloadFuture<T> {
+        self.cache = true;
+        DownloadFuture::new(self, |_body, path| {
+            _ResourceLoader::godot_singleton()
+                .load(GodotString::from_str(path), GodotString::new(), true)
+                .and_then(|resource| resource.cast::<T>())
+                .ok_or_else(|| DownloadError::Load { path: path.to_string() })
+        })
+    }
+}
+
+/// Why a download failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DownloadError {
+    /// The request couldn't be sent, or the scene tree isn't running.
+    Start(GodotError),
+    /// The request failed before a response, with a `HTTPRequestResult`.
+    Request(i64),
+    /// The server answered with a status other than a success.
+    Status(i64),
+    /// The cache couldn't be read or written.
+    Cache { path: String, error: GodotError },
+    /// The cached body isn't a resource of the expected class.
+    Load { path: String },
+    /// The `HTTPRequest` was freed before the response.
+    Cancelled,
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DownloadError::Start(error) => write!(f, "the request can't be sent: {:?}", error),
+            DownloadError::Request(result) => write!(f, "the request failed with the result {}", result),
+            DownloadError::Status(status) => write!(f, "the server answered {}", status),
+            DownloadError::Cache { ref path, error } => write!(f, "{:?} can't be accessed: {:?}", path, error),
+            DownloadError::Load { ref path } => write!(f, "{:?} isn't a resource of the expected class", path),
+            DownloadError::Cancelled => write!(f, "the request was freed"),
+        }
+    }
+}
+
+impl Error for DownloadError {}
+
+/// The future returned by `Download::bytes` and `Download::resource`.
+pub struct DownloadFuture<T> {
+    download: Download,
+    finish: fn(ByteArray, &str) -> Result<T, DownloadError>,
+    state: State,
+}
+
+enum State {
+    Start,
+    // The request is added to the tree at the end of the frame.
+    Entering(HTTPRequest),
+    Requesting { http: HTTPRequest, completed: SignalFuture, progress: (i64, i64) },
+    Done,
+}
+
+impl<T> DownloadFuture<T> {
+    fn new(download: Download, finish: fn(ByteArray, &str) -> Result<T, DownloadError>) -> Self {
+        DownloadFuture { download, finish, state: State::Start }
+    }
+
+    fn start(&mut self) -> Result<HTTPRequest, DownloadError> {
+        let mut root = scene_tree()
+            .and_then(|tree| tree.get_root())
+            .ok_or(DownloadError::Start(GodotError::Unconfigured))?;
+
+        let mut http = HTTPRequest::new();
+        http.set_use_threads(true);
+        root.call_deferred(GodotString::from_str("add_child"), &[Variant::from_object(unsafe {
+            HTTPRequest::from_sys(http.to_sys())
+        })]);
+        Ok(http)
+    }
+
+    fn send(&mut self, http: &mut HTTPRequest) -> Result<SignalFuture, DownloadError> {
+        let mut headers = StringArray::new();
+        for header in &self.download.headers {
+            headers.push(&GodotString::from_str(header));
+        }
+        if self.download.cache {
+            let path = self.download.cache_path();
+            if let Some(etag) = read_etag(&path) {
+                headers.push(&GodotString::from_str(format!("If-None-Match: {}", etag)));
+            }
+        }
+
+        let completed = SignalFuture::new(http, "request_completed").map_err(DownloadError::Start)?;
+        http.request(
+            GodotString::from_str(&self.download.url),
+            headers,
+            self.download.ssl_validate_domain,
+            HTTPClientMethod::MethodGet as i64,
+            GodotString::new(),
+        ).map_err(DownloadError::Start)?;
+        Ok(completed)
+    }
+
+    fn complete(&mut self, args: Vec<Variant>) -> Result<T, DownloadError> {
+        let result = args.get(0).map_or(-1, |arg| arg.to_i64());
+        if result != 0 {
+            return Err(DownloadError::Request(result));
+        }
+
+        let status = args.get(1).map_or(0, |arg| arg.to_i64());
+        let path = self.download.cache_path();
+        let body = if status == 304 && self.download.cache {
+            read_file(&path)?
+        } else if status >= 200 && status < 300 {
+            let body = args.get(3).map(|arg| arg.to_byte_array()).unwrap_or_default();
+            if self.download.cache {
+                let headers = args.get(2).map(|arg| arg.to_string_array()).unwrap_or_default();
+                let headers: Vec<String> = (0..headers.len()).map(|i| headers.get(i).to_string()).collect();
+                write_cache(&path, &body, header_value(&headers, "ETag"))?;
+            }
+            body
+        } else {
+            return Err(DownloadError::Status(status));
+        };
+
+        (self.finish)(body, &path)
+    }
+}
+
+impl<T> Future for DownloadFuture<T> {
+    type Output = Result<T, DownloadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            match ::std::mem::replace(&mut this.state, State::Done) {
+                State::Start => match this.start() {
+                    Ok(http) => this.state = State::Entering(http),
+                    Err(err) => return Poll::Ready(Err(err)),
+                },
+                State::Entering(mut http) => {
+                    if !http.is_inside_tree() {
+                        this.state = State::Entering(http);
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    match this.send(&mut http) {
+                        Ok(completed) => this.state = State::Requesting { http, completed, progress: (-1, -1) },
+                        Err(err) => {
+                            http.queue_free();
+                            return Poll::Ready(Err(err));
+                        }
+                    }
+                }
+                State::Requesting { mut http, mut completed, progress } => {
+                    match Pin::new(&mut completed).poll(cx) {
+                        Poll::Ready(Some(args)) => {
+                            http.queue_free();
+                            return Poll::Ready(this.complete(args));
+                        }
+                        Poll::Ready(None) => return Poll::Ready(Err(DownloadError::Cancelled)),
+                        Poll::Pending => {}
+                    }
+
+                    let current = (http.get_downloaded_bytes(), http.get_body_size());
+                    if current != progress {
+                        if let Some(ref mut callback) = this.download.progress {
+                            callback(current.0, current.1);
+                        }
+                    }
+                    this.state = State::Requesting { http, completed, progress: current };
+
+                    // The progress is checked again by the next frame.
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                State::Done => panic!("DownloadFuture polled after completion"),
+            }
+        }
+    }
+}
+
+impl<T> Drop for DownloadFuture<T> {
+    fn drop(&mut self) {
+        match self.state {
+            State::Entering(ref mut http) | State::Requesting { ref mut http, .. } => http.queue_free(),
+            State::Start | State::Done => {}
+        }
+    }
+}
+
+fn read_file(path: &str) -> Result<ByteArray, DownloadError> {
+    let mut file = _File::new();
+    file.open(GodotString::from_str(path), _FileModeFlags::Read as i64)
+        .map_err(|error| DownloadError::Cache { path: path.to_string(), error })?;
+    let bytes = file.get_buffer(file.get_len());
+    file.close();
+    Ok(bytes)
+}
+
+fn read_etag(path: &str) -> Option<String> {
+    let mut file = _File::new();
+    if !file.file_exists(GodotString::from_str(path)) {
+        return None;
+    }
+    file.open(GodotString::from_str(format!("{}.etag", path)), _FileModeFlags::Read as i64).ok()?;
+    let etag = file.get_as_text().to_string();
+    file.close();
+    Some(etag.trim().to_string()).filter(|etag| !etag.is_empty())
+}
+
+fn write_cache(path: &str, body: &ByteArray, etag: Option<&str>) -> Result<(), DownloadError> {
+    let cache_error = |error| DownloadError::Cache { path: path.to_string(), error };
+    _Directory::new().make_dir_recursive(GodotString::from_str(DOWNLOAD_CACHE_DIR)).map_err(cache_error)?;
+
+    let mut file = _File::new();
+    file.open(GodotString::from_str(path), _FileModeFlags::Write as i64).map_err(cache_error)?;
+    file.store_buffer(body.new_ref());
+    file.close();
+
+    // A stale tag would make the server answer 304 for another body.
+    let etag_path = GodotString::from_str(format!("{}.etag", path));
+    match etag {
+        Some(etag) => {
+            file.open(etag_path, _FileModeFlags::Write as i64).map_err(cache_error)?;
+            file.store_string(GodotString::from_str(etag));
+            file.close();
+        }
+        None => {
+            let _ = _Directory::new().remove(etag_path);
+        }
+    }
+    Ok(())
+}
+
+// The value of the header `name` in the lines `Name: value` of a response.
+fn header_value<'a>(headers: &'a [String], name: &str) -> Option<&'a str> {
+    headers.iter().find_map(|header| {
+        let mut parts = header.splitn(2, ':');
+        let key = parts.next()?.trim();
+        let value = parts.next()?.trim();
+        if key.eq_ignore_ascii_case(name) { Some(value) } else { None }
+    })
+}
+
+// The extension of the last segment of the path of `url`, without the query.
+fn url_extension(url: &str) -> Option<&str> {
+    let path = url.split(|c| c == '?' || c == '#').next().unwrap_or(url);
+    let path = path.splitn(2, "://").last().unwrap_or(path);
+    let file = path.rsplit('/').next().filter(|file| !file.is_empty() && path.contains('/'))?;
+    let extension = file.rsplit('.').next().filter(|extension| extension.len() < file.len())?;
+    if extension.is_empty() || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(extension)
+}
+
+#[test]
+fn download_response_parsing() {
+    let headers = vec![
+        "Content-Type: text/plain".to_string(),
+        "etag:  \"33a64df5\" ".to_string(),
+    ];
+    assert_eq!(header_value(&headers, "ETag"), Some("\"33a64df5\""));
+    assert_eq!(header_value(&headers, "Content-Length"), None);
+
+    assert_eq!(url_extension("https://example.com/levels/forest.tscn?v=2#top"), Some("tscn"));
+    assert_eq!(url_extension("https://example.com/data.tar.gz"), Some("gz"));
+    assert_eq!(url_extension("https://example.com/levels/"), None);
+    assert_eq!(url_extension("https://example.com"), None);
+    assert_eq!(url_extension("https://example.com/README"), None);
+}
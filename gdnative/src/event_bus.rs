@@ -0,0 +1,256 @@
+//! Events published by rust systems and emitted as signals once per frame.
+//!
+//! Rust code publishes typed events from anywhere, including other threads, with
+//! `EventBus::publish`. The events are queued and emitted as signals of the
+//! `EventBus` node at the beginning of the next frame, so GDScript only connects to
+//! the signals of a single node:
+//!
+//! ```ignore
+//! struct HealthChanged {
+//!     entity: i64,
+//!     health: f64,
+//! }
+//!
+//! impl Event for HealthChanged {
+//!     fn signal() -> &'static str { "health_changed" }
+//!     fn argument_names() -> &'static [&'static str] { &["entity", "health"] }
+//!
+//!     fn arguments(&self) -> Vec<Variant> {
+//!         vec![Variant::from_i64(self.entity), Variant::from_f64(self.health)]
+//!     }
+//!
+//!     // Only the last change of every entity is emitted.
+//!     fn coalesce_key(&self) -> Option<u64> { Some(self.entity as u64) }
+//! }
+//!
+//! fn init(handle: init::InitHandle) {
+//!     let bus = handle.add_event_bus("Events");
+//!     bus.add_event::<HealthChanged>();
+//! }
+//!
+//! // In a system:
+//! EventBus::publish(HealthChanged { entity: 3, health: 12.0 });
+//! ```
+//!
+//! The bus is meant to be an autoload: the `.gdns` script of the class is added as a
+//! singleton in the project settings and the UI connects with
+//! `Events.connect("health_changed", self, "_on_health_changed")`. Autoloads are
+//! processed before the scene, so the events of a frame reach the UI before the
+//! nodes of the scene are processed again. The queue is shared by all the instances
+//! of the class, only one of them should be in the tree.
+
+use std::sync::Mutex;
+use init::{ClassBuilder, ClassDescriptor, InitHandle, PropertyHint, PropertyUsage, Signal, SignalArgument};
+use GodotString;
+use LocalCellData;
+use NativeClass;
+use NativeInstanceHeader;
+use Node;
+use NodePauseMode;
+use Variant;
+
+/// An event published on the `EventBus` and emitted as a signal.
+pub trait Event: Send + 'static {
+    /// The name of the signal.
+    fn signal() -> &'static str where Self: Sized;
+
+    /// The names of the arguments of the signal, shown by the editor.
+    fn argument_names() -> &'static [&'static str] where Self: Sized {
+        &[]
+    }
+
+    /// The arguments of the signal. Called on the main thread when the event is
+    /// emitted.
+    fn arguments(&self) -> Vec<Variant>;
+
+    /// Queued events of the same signal with the same key are coalesced: the last
+    /// one published replaces the others, at the position of the first. `None`, the
+    /// default, emits every event.
+    fn coalesce_key(&self) -> Option<u64> {
+        None
+    }
+}
+
+struct QueuedEvent {
+    signal: &'static str,
+    key: Option<u64>,
+    event: Box<dyn Event>,
+}
+
+struct EventQueue {
+    events: Vec<QueuedEvent>,
+}
+
+impl EventQueue {
+    const fn new() -> Self {
+        EventQueue { events: Vec::new() }
+    }
+
+    fn push(&mut self, event: QueuedEvent) {
+        if event.key.is_some() {
+            let duplicate = self.events.iter_mut()
+                .find(|queued| queued.signal == event.signal && queued.key == event.key);
+            if let Some(queued) = duplicate {
+                *queued = event;
+                return;
+            }
+        }
+        self.events.push(event);
+    }
+}
+
+static QUEUE: Mutex<EventQueue> = Mutex::new(EventQueue::new());
+
+impl<'a> InitHandle<'a> {
+    /// Registers the `EventBus` class under the given name. The signals of the events
+    /// are declared with `ClassBuilder::add_event`.
+    pub fn add_event_bus(&self, class_name: &str) -> ClassBuilder<'a, EventBus> {
+        let class = self.add_class::<EventBus>(ClassDescriptor {
+            name: class_name,
+            base_class: "Node",
+            constructor: Some(godot_wrap_constructor!(EventBus, EventBus::new)),
+            destructor: Some(godot_wrap_destructor!(EventBus)),
+        });
+
+        class.add_method("_ready", godot_wrap_method!(
+            EventBus,
+            fn _ready(&mut self, #[owner] owner: Node) -> ()
+        ));
+        class.add_method("_process", godot_wrap_method!(
+            EventBus,
+            fn _process(&mut self, #[owner] owner: Node, _delta: f64) -> ()
+        ));
+        class.add_method("flush", godot_wrap_method!(
+            EventBus,
+            fn flush(&mut self, #[owner] owner: Node) -> ()
+        ));
+
+        class
+    }
+}
+
+impl<'a> ClassBuilder<'a, EventBus> {
+    /// Declares the signal emitted for the events of type `E`.
+    pub fn add_event<E: Event>(&self) {
+        let args: Vec<SignalArgument> = E::argument_names().iter()
+            .map(|name| SignalArgument {
+                name,
+                default: Variant::new(),
+                hint: PropertyHint::None,
+                usage: PropertyUsage::DEFAULT,
+            })
+            .collect();
+
+        self.add_signal(Signal {
+            name: E::signal(),
+            args: &args,
+            documentation: None,
+        });
+    }
+}
+
+/// A node emitting the published events as signals, see the `event_bus` module.
+pub struct EventBus {
+    header: NativeInstanceHeader,
+}
+
+impl NativeClass for EventBus {
+    type UserData = LocalCellData<EventBus>;
+
+    fn class_name() -> &'static str {
+        "EventBus"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+impl EventBus {
+    fn new(header: NativeInstanceHeader) -> Self {
+        EventBus { header }
+    }
+
+    /// Queues the event, it is emitted at the beginning of the next frame.
+    pub fn publish<E: Event>(event: E) {
+        let queued = QueuedEvent {
+            signal: E::signal(),
+            key: event.coalesce_key(),
+            event: Box::new(event),
+        };
+
+        if let Ok(mut queue) = QUEUE.lock() {
+            queue.push(queued);
+        }
+    }
+
+    /// The number of events waiting to be emitted.
+    pub fn pending_events() -> usize {
+        QUEUE.lock().map(|queue| queue.events.len()).unwrap_or(0)
+    }
+
+    /// Drops the events waiting to be emitted.
+    pub fn clear() {
+        if let Ok(mut queue) = QUEUE.lock() {
+            queue.events.clear();
+        }
+    }
+
+    fn _ready(&mut self, mut owner: Node) {
+        // The UI usually still listens while the game is paused.
+        owner.set_pause_mode(NodePauseMode::PauseModeProcess as i64);
+    }
+
+    fn _process(&mut self, owner: Node, _delta: f64) {
+        self.flush(owner);
+    }
+
+    // Events published by the signal handlers are emitted with the next flush.
+    fn flush(&mut self, mut owner: Node) {
+        let events = match QUEUE.lock() {
+            Ok(mut queue) => ::std::mem::replace(&mut queue.events, Vec::new()),
+            Err(_) => return,
+        };
+
+        for queued in events {
+            owner.emit_signal(GodotString::from_str(queued.signal), &queued.event.arguments());
+        }
+    }
+}
+
+#[test]
+fn coalescing() {
+    struct Moved(u64);
+
+    impl Event for Moved {
+        fn signal() -> &'static str { "moved" }
+        fn arguments(&self) -> Vec<Variant> { Vec::new() }
+        fn coalesce_key(&self) -> Option<u64> { Some(self.0) }
+    }
+
+    struct Clicked;
+
+    impl Event for Clicked {
+        fn signal() -> &'static str { "clicked" }
+        fn arguments(&self) -> Vec<Variant> { Vec::new() }
+    }
+
+    fn queued<E: Event>(event: E) -> QueuedEvent {
+        QueuedEvent { signal: E::signal(), key: event.coalesce_key(), event: Box::new(event) }
+    }
+
+    let mut queue = EventQueue::new();
+    queue.push(queued(Moved(1)));
+    queue.push(queued(Clicked));
+    queue.push(queued(Moved(2)));
+    queue.push(queued(Clicked));
+    queue.push(queued(Moved(1)));
+
+    let events: Vec<_> = queue.events.iter().map(|queued| (queued.signal, queued.key)).collect();
+    assert_eq!(events, vec![
+        ("moved", Some(1)),
+        ("clicked", None),
+        ("moved", Some(2)),
+        ("clicked", None),
+    ]);
+}
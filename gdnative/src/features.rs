@@ -0,0 +1,96 @@
+//! The feature tags of the running engine, and whether it is the editor.
+//!
+//! Godot describes the platform and the kind of build with feature tags, the same
+//! tags export presets and `OS.has_feature` use: `"debug"` or `"release"`,
+//! `"standalone"` for exported games, `"Windows"`, `"X11"`, `"Android"`... Checking
+//! them at runtime, rather than with `cfg` attributes, lets a single build of the
+//! library behave like the GDScript code of the project:
+//!
+//! ```ignore
+//! editor_only! {
+//!     owner.update_configuration_warning();
+//! }
+//!
+//! debug_only! {
+//!     godot_print!("spawned {} enemies", count);
+//! }
+//!
+//! if has_feature("mobile") {
+//!     self.particles = self.particles / 4;
+//! }
+//! ```
+//!
+//! The answers don't change while the engine runs, they are cached after the first
+//! call.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+use GodotString;
+use _Engine;
+use _OS;
+
+const UNKNOWN: u8 = 0;
+const NO: u8 = 1;
+const YES: u8 = 2;
+
+static EDITOR_HINT: AtomicU8 = AtomicU8::new(UNKNOWN);
+static DEBUG_BUILD: AtomicU8 = AtomicU8::new(UNKNOWN);
+static FEATURES: Mutex<Option<HashMap<String, bool>>> = Mutex::new(None);
+
+fn cached<F: FnOnce() -> bool>(cache: &AtomicU8, query: F) -> bool {
+    match cache.load(Ordering::Relaxed) {
+        UNKNOWN => {
+            let value = query();
+            cache.store(if value { YES } else { NO }, Ordering::Relaxed);
+            value
+        }
+        value => value == YES,
+    }
+}
+
+/// `true` when the code runs in the editor, in a tool script or an editor plugin.
+pub fn is_editor_hint() -> bool {
+    cached(&EDITOR_HINT, || _Engine::godot_singleton().is_editor_hint())
+}
+
+/// `true` for the editor and the debug export templates, the `"debug"` tag.
+pub fn is_debug_build() -> bool {
+    cached(&DEBUG_BUILD, || _OS::godot_singleton().is_debug_build())
+}
+
+/// `true` in exported games, the `"standalone"` tag.
+pub fn is_standalone() -> bool {
+    has_feature("standalone")
+}
+
+/// Whether the engine has the feature tag, like `OS.has_feature`. Custom tags of
+/// the export presets are included.
+pub fn has_feature(tag: &str) -> bool {
+    let query = || _OS::godot_singleton().has_feature(GodotString::from_str(tag));
+    let mut features = match FEATURES.lock() {
+        Ok(features) => features,
+        Err(_) => return query(),
+    };
+
+    let features = features.get_or_insert_with(HashMap::new);
+    if let Some(&value) = features.get(tag) {
+        return value;
+    }
+    let value = query();
+    features.insert(tag.to_string(), value);
+    value
+}
+
+godot_test!(test_features {
+    assert_eq!(has_feature("debug"), is_debug_build());
+    assert_eq!(has_feature("debug"), has_feature("debug"));
+    assert!(!has_feature("gdnative_missing_tag"));
+    assert_eq!(is_editor_hint(), is_editor_hint());
+
+    let mut ran = false;
+    debug_only! {
+        ran = true;
+    }
+    assert_eq!(ran, is_debug_build());
+});
@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 
 /// A reference-counted vector of `f32` that uses Godot's pool allocator.
@@ -100,7 +101,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for Float32Array {
+impl_sys_access!(for Float32Array as godot_pool_real_array);
+
+impl ToVariant for Float32Array {
     fn to_variant(&self) -> Variant { Variant::from_float32_array(self) }
+}
+
+impl FromVariant for Float32Array {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_float32_array() }
 }
@@ -0,0 +1,343 @@
+//! Rust coroutines GDScript can `yield` to, like the function states of GDScript.
+//!
+//! A GDScript function calling `yield` returns a `GDScriptFunctionState`, which the
+//! caller waits for with `yield(state, "completed")` or continues with
+//! `state.resume(value)`. An exported method returning a `FunctionState` behaves
+//! the same way, with an async block as body:
+//!
+//! ```ignore
+//! #[export]
+//! fn open_chest(&self, mut owner: AnimationPlayer) -> FunctionState {
+//!     FunctionState::spawn(move |resumer| async move {
+//!         owner.play("open".into(), -1.0, 1.0, false);
+//!         if let Ok(finished) = SignalFuture::new(&owner, "animation_finished") {
+//!             finished.await;
+//!         }
+//!
+//!         // Waits for `state.resume(choice)` in GDScript.
+//!         let choice = resumer.resumed().await;
+//!         choice.to_i64() * 10
+//!     })
+//! }
+//! ```
+//!
+//! ```gdscript
+//! var state = chest.open_chest()
+//! # ...
+//! var result = yield(state, "completed")
+//! ```
+//!
+//! The body runs as a task of the `tasks` module: it starts with the next frame,
+//! continues in the frame after the signals it waits for, and at once when GDScript
+//! calls `resume`. Its result is passed to the `completed` signal and returned by
+//! the `resume` call finishing it. The state keeps itself alive until the body
+//! finishes.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use init::{register_after_init, ClassDescriptor, InitHandle, PropertyHint, PropertyUsage, Signal, SignalArgument};
+use info;
+use tasks::{spawn_task, task_waker};
+use FromVariant;
+use GodotObject;
+use GodotString;
+use Instance;
+use LocalCellData;
+use NativeClass;
+use NativeInstanceHeader;
+use NativeScript;
+use Object;
+use Reference;
+use ReturnValue;
+use ToVariant;
+use Variant;
+
+type Body = Pin<Box<dyn Future<Output = Variant>>>;
+
+struct Coroutine {
+    body: Option<Body>,
+    resume_value: Option<Variant>,
+    // A reference to the state object, until the body finishes.
+    owner: Option<Variant>,
+    task: u64,
+}
+
+/// A running rust coroutine seen by GDScript as a function state, see the
+/// `function_state` module.
+pub struct FunctionState {
+    // Holds a reference, `Reference` wrappers don't.
+    object: Variant,
+}
+
+impl FunctionState {
+    /// Runs the async block returned by `body` as a coroutine. `body` receives the
+    /// `Resumer` waiting for the `resume` calls of GDScript.
+    ///
+    /// Returns `None` if the library is not initialized.
+    pub fn try_spawn<F, B, R>(body: F) -> Option<Self>
+    where
+        F: FnOnce(Resumer) -> B,
+        B: Future<Output = R> + 'static,
+        R: ReturnValue + 'static,
+    {
+        let script = state_script()?;
+        let reference = Reference::new();
+        reference.as_object().set_script(script.cast());
+        let object = Variant::from_object(reference.as_object());
+        let instance = Instance::<StateInstance>::try_from_base(&reference)?;
+
+        let coroutine = Rc::new(RefCell::new(Coroutine {
+            body: None,
+            resume_value: None,
+            owner: Some(object.clone()),
+            task: 0,
+        }));
+        let body = body(Resumer { coroutine: coroutine.clone() });
+        coroutine.borrow_mut().body = Some(Box::pin(async_output(body)));
+        coroutine.borrow_mut().task = spawn_task(Box::pin(Drive { coroutine: coroutine.clone() }));
+
+        instance.map_mut(|instance| instance.coroutine = Some(coroutine)).ok()?;
+        Some(FunctionState { object })
+    }
+
+    /// Like `try_spawn`, but panics if the library is not initialized.
+    pub fn spawn<F, B, R>(body: F) -> Self
+    where
+        F: FnOnce(Resumer) -> B,
+        B: Future<Output = R> + 'static,
+        R: ReturnValue + 'static,
+    {
+        Self::try_spawn(body).expect("the library is not initialized")
+    }
+
+    /// `false` once the coroutine finished.
+    pub fn is_valid(&self) -> bool {
+        self.object.try_to_object::<Reference>()
+            .and_then(|reference| Instance::<StateInstance>::try_from_base(&reference))
+            .and_then(|instance| instance.map(|instance| instance.is_valid()).ok())
+            .unwrap_or(false)
+    }
+}
+
+impl ToVariant for FunctionState {
+    fn to_variant(&self) -> Variant {
+        self.object.clone()
+    }
+}
+
+impl FromVariant for FunctionState {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        let reference = variant.try_to_object::<Reference>()?;
+        Instance::<StateInstance>::try_from_base(&reference)?;
+        Some(FunctionState { object: variant.clone() })
+    }
+}
+
+// Converts the output of the body to a variant.
+fn async_output<B, R>(body: B) -> impl Future<Output = Variant>
+where
+    B: Future<Output = R>,
+    R: ReturnValue,
+{
+    struct Output<B>(Pin<Box<B>>);
+
+    impl<B, R> Future for Output<B>
+    where
+        B: Future<Output = R>,
+        R: ReturnValue,
+    {
+        type Output = Variant;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Variant> {
+            self.0.as_mut().poll(cx).map(|ret| ret.to_return_variant())
+        }
+    }
+
+    Output(Box::pin(body))
+}
+
+/// Waits for the `resume` calls of GDScript in the body of a `FunctionState`.
+pub struct Resumer {
+    coroutine: Rc<RefCell<Coroutine>>,
+}
+
+impl Resumer {
+    /// Resolves with the argument of the next `resume` call, nil if it has none.
+    pub fn resumed(&self) -> Resumed {
+        Resumed { coroutine: self.coroutine.clone() }
+    }
+}
+
+/// The future returned by `Resumer::resumed`.
+pub struct Resumed {
+    coroutine: Rc<RefCell<Coroutine>>,
+}
+
+impl Future for Resumed {
+    type Output = Variant;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Variant> {
+        // `resume` polls the body itself, no waker is needed.
+        match self.coroutine.borrow_mut().resume_value.take() {
+            Some(value) => Poll::Ready(value),
+            None => Poll::Pending,
+        }
+    }
+}
+
+// Polls the body, and emits `completed` when it finishes.
+fn poll_body(coroutine: &Rc<RefCell<Coroutine>>, cx: &mut Context) -> Poll<Variant> {
+    // The body is taken out while it runs, it can resume its own state.
+    let mut body = match coroutine.borrow_mut().body.take() {
+        Some(body) => body,
+        None => return Poll::Ready(Variant::new()),
+    };
+
+    match body.as_mut().poll(cx) {
+        Poll::Ready(ret) => {
+            let owner = coroutine.borrow_mut().owner.take();
+            if let Some(mut owner) = owner.and_then(|owner| owner.try_to_object::<Object>()) {
+                owner.emit_signal(GodotString::from_str("completed"), &[ret.clone()]);
+            }
+            Poll::Ready(ret)
+        }
+        Poll::Pending => {
+            coroutine.borrow_mut().body = Some(body);
+            Poll::Pending
+        }
+    }
+}
+
+// The task running the body between the `resume` calls.
+struct Drive {
+    coroutine: Rc<RefCell<Coroutine>>,
+}
+
+impl Future for Drive {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        poll_body(&self.coroutine, cx).map(|_| ())
+    }
+}
+
+struct StateInstance {
+    header: NativeInstanceHeader,
+    coroutine: Option<Rc<RefCell<Coroutine>>>,
+}
+
+impl StateInstance {
+    fn new(header: NativeInstanceHeader) -> Self {
+        StateInstance { header, coroutine: None }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.coroutine.as_ref().map(|coroutine| coroutine.borrow().body.is_some()).unwrap_or(false)
+    }
+
+    fn resume(&self, arg: Variant) -> Variant {
+        let coroutine = match self.coroutine {
+            Some(ref coroutine) if coroutine.borrow().body.is_some() => coroutine.clone(),
+            _ => {
+                godot_error!("RustFunctionState::resume: the function finished or is running");
+                return Variant::new();
+            }
+        };
+
+        let waker = {
+            let mut state = coroutine.borrow_mut();
+            state.resume_value = Some(arg);
+            task_waker(state.task)
+        };
+
+        // Like GDScript, the result of the function once it finished, else the state.
+        match poll_body(&coroutine, &mut Context::from_waker(&waker)) {
+            Poll::Ready(ret) => {
+                // The task driving the body ends when it sees it finished.
+                waker.wake();
+                ret
+            }
+            Poll::Pending => Variant::from_object(unsafe { Object::from_sys(self.header.this) }),
+        }
+    }
+}
+
+impl NativeClass for StateInstance {
+    type UserData = LocalCellData<StateInstance>;
+
+    fn class_name() -> &'static str {
+        "RustFunctionState"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+// The reference count of a script is atomic.
+struct StateScript(NativeScript);
+unsafe impl Send for StateScript {}
+
+// One script for all the threads, like the relay script of `signal_relay`.
+static STATE_SCRIPT: Mutex<Option<StateScript>> = Mutex::new(None);
+
+// Registers the state class the first time a coroutine is spawned, and returns a
+// script of that class.
+fn state_script() -> Option<NativeScript> {
+    let mut script = STATE_SCRIPT.lock().ok()?;
+    if let Some(ref script) = *script {
+        return Some(script.0.new_ref());
+    }
+
+    let library = info::library_resource()?;
+    register_after_init(register).ok()?;
+
+    let mut new_script = NativeScript::new();
+    new_script.set_class_name(GodotString::from_str(StateInstance::class_name()));
+    new_script.set_library(Some(library));
+
+    *script = Some(StateScript(new_script.new_ref()));
+    Some(new_script)
+}
+
+fn register(handle: &InitHandle) {
+    let class = handle.add_class::<StateInstance>(ClassDescriptor {
+        name: StateInstance::class_name(),
+        base_class: "Reference",
+        constructor: Some(godot_wrap_constructor!(StateInstance, StateInstance::new)),
+        destructor: Some(godot_wrap_destructor!(StateInstance)),
+    });
+
+    class.add_method("resume", godot_wrap_method!(
+        StateInstance,
+        fn resume(&self, #[opt] arg: Variant = Variant::new()) -> Variant
+    ));
+    class.add_method("is_valid", godot_wrap_method!(
+        StateInstance,
+        fn is_valid(&self) -> bool
+    ));
+    class.add_signal(Signal {
+        name: "completed",
+        args: &[SignalArgument {
+            name: "result",
+            default: Variant::new(),
+            hint: PropertyHint::None,
+            usage: PropertyUsage::DEFAULT,
+        }],
+        documentation: None,
+    });
+}
+
+godot_test!(test_function_state {
+    let state = FunctionState::spawn(|resumer| resumer.resumed());
+    assert!(state.is_valid());
+
+    let mut object = state.to_variant().try_to_object::<Object>().unwrap();
+    let ret = object.call(GodotString::from_str("resume"), &[Variant::from_i64(42)]);
+    assert_eq!(ret, Variant::from_i64(42));
+    assert!(!state.is_valid());
+});
@@ -0,0 +1,283 @@
+//! Builders of arbitrary variants from raw bytes, to fuzz the conversions of
+//! variants coming from save files and network peers, with the `fuzzing` feature.
+//!
+//! `fuzz_variant` decodes a variant from the bytes given by the fuzzer, then runs
+//! every `FromVariant` conversion on it and traverses its dictionaries and arrays.
+//! `fuzz_string` checks that strings survive the UTF-8 round trip through the
+//! engine. Both panic when a conversion misbehaves:
+//!
+//! ```ignore
+//! for input in corpus {
+//!     gdnative::fuzz_variant(&input);
+//!     gdnative::fuzz_string(&input);
+//! }
+//! ```
+//!
+//! The variants are built by the engine, so the functions must run in a process
+//! where the library was initialized, like the test project, or after
+//! `install_mock_engine`. The `fuzz` package of the repository runs them with
+//! `cargo fuzz run variant` and `cargo fuzz run string`, against the mock.
+
+use std::collections::HashMap;
+use std::str;
+use ByteArray;
+use Color;
+use ColorArray;
+use Dictionary;
+use Float32Array;
+use FromVariant;
+use GodotString;
+use Int32Array;
+use NodePath;
+use StringArray;
+use ToVariant;
+use Variant;
+use VariantArray;
+use Vector2;
+use Vector2Array;
+use Vector3;
+use Vector3Array;
+
+/// How deep `arbitrary_variant` nests arrays and dictionaries.
+pub const MAX_FUZZ_DEPTH: usize = 4;
+
+// The longest string or collection built by `arbitrary_variant`.
+const MAX_FUZZ_LEN: usize = 16;
+
+/// Reads the values building an arbitrary variant from the bytes of a fuzzer.
+///
+/// Reading past the end gives zeros, so every input decodes to a value.
+pub struct ByteSource<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteSource<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        ByteSource { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn u8(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((&byte, rest)) => {
+                self.data = rest;
+                byte
+            }
+            None => 0,
+        }
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        for byte in bytes.iter_mut() {
+            *byte = self.u8();
+        }
+        u32::from_le_bytes(bytes)
+    }
+
+    pub fn i64(&mut self) -> i64 {
+        (self.u32() as i64) << 32 | self.u32() as i64
+    }
+
+    /// Any float, including NaN and the infinities.
+    pub fn f32(&mut self) -> f32 {
+        f32::from_bits(self.u32())
+    }
+
+    /// A length from 0 to `max`.
+    pub fn len(&mut self, max: usize) -> usize {
+        self.u8() as usize % (max + 1)
+    }
+
+    /// Up to `max` bytes, which may not be valid UTF-8.
+    pub fn bytes(&mut self, max: usize) -> &'a [u8] {
+        let len = self.len(max).min(self.data.len());
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        bytes
+    }
+
+    /// Up to `max` bytes, with the invalid UTF-8 sequences replaced.
+    pub fn string(&mut self, max: usize) -> String {
+        String::from_utf8_lossy(self.bytes(max)).into_owned()
+    }
+}
+
+/// A variant of any type but objects and `Rid`s, with arrays and dictionaries
+/// nested up to `depth` levels.
+pub fn arbitrary_variant(source: &mut ByteSource, depth: usize) -> Variant {
+    let kind = if depth == 0 { source.u8() % 16 } else { source.u8() % 18 };
+    match kind {
+        0 => Variant::new(),
+        1 => Variant::from_bool(source.u8() & 1 == 1),
+        2 => Variant::from_i64(source.i64()),
+        3 => (source.f32() as f64).to_variant(),
+        4 => Variant::from_str(source.string(MAX_FUZZ_LEN)),
+        5 => Variant::from_vector2(&Vector2::new(source.f32(), source.f32())),
+        6 => Variant::from_vector3(&Vector3::new(source.f32(), source.f32(), source.f32())),
+        7 => Variant::from_color(&Color::rgba(source.f32(), source.f32(), source.f32(), source.f32())),
+        8 => Variant::from_node_path(&NodePath::from_str(&source.string(MAX_FUZZ_LEN))),
+        9 => Variant::from_byte_array(&source.bytes(MAX_FUZZ_LEN).iter().cloned().collect::<ByteArray>()),
+        10 => {
+            let len = source.len(MAX_FUZZ_LEN);
+            Variant::from_int32_array(&(0..len).map(|_| source.u32() as i32).collect::<Int32Array>())
+        }
+        11 => {
+            let len = source.len(MAX_FUZZ_LEN);
+            Variant::from_float32_array(&(0..len).map(|_| source.f32()).collect::<Float32Array>())
+        }
+        12 => {
+            let len = source.len(MAX_FUZZ_LEN);
+            let strings: StringArray = (0..len)
+                .map(|_| GodotString::from_str(source.string(MAX_FUZZ_LEN)))
+                .collect();
+            Variant::from_string_array(&strings)
+        }
+        13 => {
+            let len = source.len(MAX_FUZZ_LEN);
+            let vectors: Vector2Array = (0..len).map(|_| Vector2::new(source.f32(), source.f32())).collect();
+            Variant::from_vector2_array(&vectors)
+        }
+        14 => {
+            let len = source.len(MAX_FUZZ_LEN);
+            let vectors: Vector3Array = (0..len)
+                .map(|_| Vector3::new(source.f32(), source.f32(), source.f32()))
+                .collect();
+            Variant::from_vector3_array(&vectors)
+        }
+        15 => {
+            let len = source.len(MAX_FUZZ_LEN);
+            let colors: ColorArray = (0..len)
+                .map(|_| Color::rgba(source.f32(), source.f32(), source.f32(), source.f32()))
+                .collect();
+            Variant::from_color_array(&colors)
+        }
+        16 => {
+            let mut array = VariantArray::new();
+            for _ in 0..source.len(MAX_FUZZ_LEN) {
+                array.push(&arbitrary_variant(source, depth - 1));
+            }
+            Variant::from_array(&array)
+        }
+        _ => {
+            let mut dictionary = Dictionary::new();
+            for _ in 0..source.len(MAX_FUZZ_LEN) {
+                let key = arbitrary_variant(source, depth - 1);
+                dictionary.set(&key, &arbitrary_variant(source, depth - 1));
+            }
+            Variant::from_dictionary(&dictionary)
+        }
+    }
+}
+
+/// Runs the `FromVariant` conversions of the common types on `variant`, and on the
+/// elements of its arrays and dictionaries. Panics if a conversion or the
+/// traversal misbehaves.
+pub fn exercise_variant(variant: &Variant) {
+    let _ = i64::from_variant(variant);
+    let _ = u8::from_variant(variant);
+    let _ = i32::from_variant(variant);
+    let _ = f32::from_variant(variant);
+    let _ = f64::from_variant(variant);
+    let _ = bool::from_variant(variant);
+    let _ = Option::<i64>::from_variant(variant);
+    let _ = Vector2::from_variant(variant);
+    let _ = Color::from_variant(variant);
+    let _ = Vec::<f32>::from_variant(variant);
+    let _ = Vec::<String>::from_variant(variant);
+    let _ = HashMap::<String, Variant>::from_variant(variant);
+    let _ = <(i64, String)>::from_variant(variant);
+    let _ = Result::<Variant, String>::from_variant(variant);
+
+    if let Some(string) = String::from_variant(variant) {
+        check_utf8(&GodotString::from_str(&string));
+    }
+    let _ = format!("{:?}", variant);
+    check_utf8(&GodotString::from_str(variant.to_string()));
+
+    if let Some(array) = variant.try_to_array() {
+        let len = array.len();
+        let mut count = 0;
+        for element in array.iter() {
+            exercise_variant(&element);
+            count += 1;
+        }
+        assert_eq!(count, len, "array iteration skipped elements");
+    }
+
+    if let Some(dictionary) = variant.try_to_dictionary() {
+        let len = dictionary.len();
+        let mut count = 0;
+        for (key, value) in dictionary.iter() {
+            assert!(dictionary.contains(&key), "dictionary iteration returned a missing key");
+            exercise_variant(&key);
+            exercise_variant(&value);
+            count += 1;
+        }
+        assert_eq!(count, len, "dictionary iteration skipped entries");
+        assert_eq!(dictionary.keys().len(), len);
+        assert_eq!(dictionary.values().len(), len);
+    }
+}
+
+/// Builds a variant from `data` and runs `exercise_variant` on it.
+pub fn fuzz_variant(data: &[u8]) {
+    let variant = arbitrary_variant(&mut ByteSource::new(data), MAX_FUZZ_DEPTH);
+    exercise_variant(&variant);
+}
+
+/// Checks that `data`, with the invalid UTF-8 sequences replaced, survives the
+/// conversion to a `GodotString` and back. The strings of the engine end at the
+/// first nul character, so it is removed first.
+pub fn fuzz_string(data: &[u8]) {
+    let text = String::from_utf8_lossy(data).replace('\0', "");
+    let string = GodotString::from_str(&text);
+    check_utf8(&string);
+    assert_eq!(string.to_string(), text, "string changed by the UTF-8 round trip");
+    assert_eq!(string.len(), text.chars().count(), "the engine counted other characters");
+
+    let variant = Variant::from_godot_string(&string);
+    assert_eq!(String::from_variant(&variant), Some(text));
+}
+
+// `Utf8String::as_str` trusts the engine to return valid UTF-8.
+fn check_utf8(string: &GodotString) {
+    let utf8 = string.to_utf8();
+    assert!(str::from_utf8(utf8.as_bytes()).is_ok(), "the engine returned invalid UTF-8");
+}
+
+godot_test!(test_fuzz_corpus {
+    // A fixed corpus of pseudo-random inputs, xorshift from a constant seed.
+    let mut state = 0x2545_f491u32;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+
+    for len in 0..256 {
+        let input: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+        fuzz_variant(&input);
+        fuzz_string(&input);
+    }
+    fuzz_string("héllo wörld, 日本語, 🎮".as_bytes());
+    fuzz_string(&[0xf0, 0x9f, 0x8e, 0xff, b'a', 0]);
+});
+
+#[test]
+fn byte_source() {
+    let mut source = ByteSource::new(&[3, b'a', 0xff, b'b', 1, 0, 0, 0]);
+    assert_eq!(source.string(16), "a\u{fffd}b");
+    assert_eq!(source.u32(), 1);
+    assert!(source.is_empty());
+    assert_eq!(source.u8(), 0);
+    assert_eq!(source.bytes(16), &[] as &[u8]);
+
+    let mut source = ByteSource::new(&[20, 0, 0, 0xc0, 0x7f]);
+    assert_eq!(source.len(16), 3);
+    assert!(source.f32().is_nan());
+}
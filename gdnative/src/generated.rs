@@ -7,8 +7,20 @@ use super::*;
 
 use std::sync::{Once, ONCE_INIT};
 use std::ops::*;
+#[cfg_attr(feature = "dynamic_dispatch", allow(unused_imports))]
 use libc;
 
 
 include!(concat!(env!("OUT_DIR"), "/types.rs"));
 
+godot_test!(test_object_debug {
+    let node = Node::new();
+    let id = node.get_instance_id();
+    assert_eq!(format!("{:?}", node), format!("Node {{ id: {} }}", id));
+
+    // The actual class, not the class of the wrapper.
+    let object = unsafe { Object::from_sys(node.to_sys()) };
+    assert_eq!(format!("{:?}", object), format!("Node {{ id: {} }}", id));
+
+    unsafe { node.free(); }
+});
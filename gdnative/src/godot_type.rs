@@ -1,19 +1,168 @@
 use super::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
 
-pub trait GodotType: Sized {
+/// Types that can be converted to a `Variant`.
+///
+/// Structs and enums can implement it with `#[derive(ToVariant)]`, see the
+/// `gdnative_derive` crate.
+pub trait ToVariant {
     fn to_variant(&self) -> Variant;
+
+    // The error of a `Err`, for the methods logging their errors, see `MethodErrors`.
+    #[doc(hidden)]
+    fn to_error_variant(&self) -> Option<Variant> {
+        None
+    }
+}
+
+/// Types that can be converted from a `Variant`, the conversion returns `None` if
+/// the variant doesn't hold a value of the expected type.
+///
+/// `try_from_variant` describes why the conversion failed instead. The default
+/// implementation only reports the expected type, the containers implement it to
+/// locate the invalid element.
+///
+/// Structs and enums can implement it with `#[derive(FromVariant)]`, see the
+/// `gdnative_derive` crate.
+pub trait FromVariant: Sized {
     fn from_variant(variant: &Variant) -> Option<Self>;
 
+    fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+        Self::from_variant(variant).ok_or_else(|| FromVariantError::invalid_type::<Self>(variant))
+    }
+
     fn from_sys_variant(variant: &sys::godot_variant) -> Option<Self> {
         Self::from_variant(Variant::cast_ref(variant))
     }
+
+    #[doc(hidden)]
+    fn try_from_sys_variant(variant: &sys::godot_variant) -> Result<Self, FromVariantError> {
+        Self::try_from_variant(Variant::cast_ref(variant))
+    }
+
+    // Whether the trailing arguments of this type can be omitted by the callers of
+    // typed methods, which then receive nil. Only `Option`.
+    #[doc(hidden)]
+    fn is_optional_argument() -> bool {
+        false
+    }
+}
+
+/// The reason a variant couldn't be converted by `FromVariant::try_from_variant`.
+///
+/// Displayed like `at [2]["hp"]: expected i64, found GodotString`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FromVariantError {
+    /// The location of the invalid value in the nested arrays, dictionaries and
+    /// fields, from the outermost. Empty if the converted variant itself is invalid.
+    pub path: Vec<VariantPath>,
+    pub kind: FromVariantErrorKind,
+}
+
+/// A step of `FromVariantError::path`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariantPath {
+    /// An element of an array.
+    Index(i32),
+    /// A value of a dictionary, with the key converted to a string.
+    Key(String),
+    /// A field of a struct implementing `FromVariant` with the derive macro.
+    Field(&'static str),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FromVariantErrorKind {
+    /// The variant holds a value of another type.
+    InvalidType { expected: &'static str, actual: VariantType },
+    /// An array converted to a tuple has the wrong number of elements.
+    InvalidLength { expected: usize, actual: usize },
+    /// The value or the name doesn't match any variant of an enum.
+    UnknownVariant { value: String },
+}
+
+impl FromVariantError {
+    pub fn new(kind: FromVariantErrorKind) -> Self {
+        FromVariantError { path: Vec::new(), kind }
+    }
+
+    /// The variant doesn't hold a `T`.
+    pub fn invalid_type<T>(variant: &Variant) -> Self {
+        FromVariantError::new(FromVariantErrorKind::InvalidType {
+            expected: ::std::any::type_name::<T>(),
+            actual: variant.get_type(),
+        })
+    }
+
+    /// Locates the error in the element `idx` of an array.
+    pub fn at_index(self, idx: i32) -> Self {
+        self.at(VariantPath::Index(idx))
+    }
+
+    /// Locates the error in the value of `key` in a dictionary.
+    pub fn at_key(self, key: &Variant) -> Self {
+        self.at(VariantPath::Key(key.to_string()))
+    }
+
+    /// Locates the error in a field.
+    pub fn at_field(self, field: &'static str) -> Self {
+        self.at(VariantPath::Field(field))
+    }
+
+    fn at(mut self, step: VariantPath) -> Self {
+        self.path.insert(0, step);
+        self
+    }
+}
+
+impl fmt::Display for FromVariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "at ")?;
+            for (idx, step) in self.path.iter().enumerate() {
+                match *step {
+                    VariantPath::Index(index) => write!(f, "[{}]", index)?,
+                    VariantPath::Key(ref key) => write!(f, "[{:?}]", key)?,
+                    VariantPath::Field(field) if idx == 0 => write!(f, "{}", field)?,
+                    VariantPath::Field(field) => write!(f, ".{}", field)?,
+                }
+            }
+            write!(f, ": ")?;
+        }
+
+        match self.kind {
+            FromVariantErrorKind::InvalidType { expected, actual } => {
+                write!(f, "expected {}, found {:?}", expected, actual)
+            }
+            FromVariantErrorKind::InvalidLength { expected, actual } => {
+                write!(f, "expected {} elements, found {}", expected, actual)
+            }
+            FromVariantErrorKind::UnknownVariant { ref value } => {
+                write!(f, "no enum variant matches {}", value)
+            }
+        }
+    }
 }
 
-impl GodotType for () {
+impl Error for FromVariantError {}
+
+/// Types that can be converted to and from a `Variant`, used by the arguments of the
+/// methods and by the properties. Implemented for all the types implementing both
+/// `ToVariant` and `FromVariant`.
+pub trait GodotType: ToVariant + FromVariant {}
+
+impl<T: ToVariant + FromVariant> GodotType for T {}
+
+impl ToVariant for () {
     fn to_variant(&self) -> Variant {
         Variant::new()
     }
+}
 
+impl FromVariant for () {
     fn from_variant(variant: &Variant) -> Option<Self> {
         if variant.get_type() == VariantType::Nil {
             Some(())
@@ -25,15 +174,17 @@ impl GodotType for () {
 
 macro_rules! godot_int_impl {
     ($ty:ty) => (
-        impl GodotType for $ty {
+        impl ToVariant for $ty {
             fn to_variant(&self) -> Variant {
                 unsafe {
                     let mut ret = sys::godot_variant::default();
                     (get_api().godot_variant_new_int)(&mut ret, i64::from(*self));
-                    Variant(ret)
+                    Variant::from_sys(ret)
                 }
             }
+        }
 
+        impl FromVariant for $ty {
             fn from_variant(variant: &Variant) -> Option<Self> {
                 unsafe {
                     let api = get_api();
@@ -55,15 +206,17 @@ godot_int_impl!(i64);
 
 macro_rules! godot_uint_impl {
     ($ty:ty) => (
-        impl GodotType for $ty {
+        impl ToVariant for $ty {
             fn to_variant(&self) -> Variant {
                 unsafe {
                     let mut ret = sys::godot_variant::default();
                     (get_api().godot_variant_new_uint)(&mut ret, u64::from(*self));
-                    Variant(ret)
+                    Variant::from_sys(ret)
                 }
             }
+        }
 
+        impl FromVariant for $ty {
             fn from_variant(variant: &Variant) -> Option<Self> {
                 unsafe {
                     let api = get_api();
@@ -84,15 +237,17 @@ godot_uint_impl!(u32);
 godot_uint_impl!(u64);
 
 
-impl GodotType for f32 {
+impl ToVariant for f32 {
     fn to_variant(&self) -> Variant {
         unsafe {
             let mut ret = sys::godot_variant::default();
             (get_api().godot_variant_new_real)(&mut ret, f64::from(*self));
-            Variant(ret)
+            Variant::from_sys(ret)
         }
     }
+}
 
+impl FromVariant for f32 {
     fn from_variant(variant: &Variant) -> Option<Self> {
         unsafe {
             let api = get_api();
@@ -105,15 +260,17 @@ impl GodotType for f32 {
     }
 }
 
-impl GodotType for f64 {
+impl ToVariant for f64 {
     fn to_variant(&self) -> Variant {
         unsafe {
             let mut ret = sys::godot_variant::default();
             (get_api().godot_variant_new_real)(&mut ret, *self);
-            Variant(ret)
+            Variant::from_sys(ret)
         }
     }
+}
 
+impl FromVariant for f64 {
     fn from_variant(variant: &Variant) -> Option<Self> {
         unsafe {
             let api = get_api();
@@ -126,11 +283,27 @@ impl GodotType for f64 {
     }
 }
 
-impl GodotType for String {
+impl ToVariant for String {
     fn to_variant(&self) -> Variant {
         Variant::from_str(&self)
     }
+}
+
+impl ToVariant for str {
+    fn to_variant(&self) -> Variant {
+        Variant::from_str(self)
+    }
+}
 
+/// Converts the referenced value, so that `call!` and the other helpers taking
+/// values to convert accept string literals and borrowed values.
+impl<'l, T: ToVariant + ?Sized> ToVariant for &'l T {
+    fn to_variant(&self) -> Variant {
+        (**self).to_variant()
+    }
+}
+
+impl FromVariant for String {
     fn from_variant(variant: &Variant) -> Option<Self> {
         unsafe {
             let api = get_api();
@@ -148,3 +321,532 @@ impl GodotType for String {
         }
     }
 }
+
+impl ToVariant for bool {
+    fn to_variant(&self) -> Variant {
+        Variant::from_bool(*self)
+    }
+}
+
+impl FromVariant for bool {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        variant.try_to_bool()
+    }
+}
+
+impl ToVariant for Variant {
+    fn to_variant(&self) -> Variant {
+        self.clone()
+    }
+}
+
+impl FromVariant for Variant {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        Some(variant.clone())
+    }
+}
+
+macro_rules! godot_core_type_impl {
+    ($($ty:ty : $from:ident, $try_to:ident;)*) => (
+        $(
+            impl ToVariant for $ty {
+                fn to_variant(&self) -> Variant {
+                    Variant::$from(self)
+                }
+            }
+
+            impl FromVariant for $ty {
+                fn from_variant(variant: &Variant) -> Option<Self> {
+                    variant.$try_to()
+                }
+            }
+        )*
+    )
+}
+
+godot_core_type_impl!(
+    Vector2 : from_vector2, try_to_vector2;
+    Vector3 : from_vector3, try_to_vector3;
+    Quat : from_quat, try_to_quat;
+    Plane : from_plane, try_to_plane;
+    Rect2 : from_rect2, try_to_rect2;
+    Transform : from_transform, try_to_transform;
+    Transform2D : from_transform2d, try_to_transform2d;
+    Basis : from_basis, try_to_basis;
+    Color : from_color, try_to_color;
+    Aabb : from_aabb, try_to_aabb;
+    Rid : from_rid, try_to_rid;
+);
+
+// The standard collections are converted to and from engine containers, element
+// by element. A conversion from a variant fails if any element has the wrong type.
+
+/// Converted to an array.
+impl<T: ToVariant> ToVariant for Vec<T> {
+    fn to_variant(&self) -> Variant {
+        let mut array = VariantArray::new();
+        for value in self {
+            array.push(&value.to_variant());
+        }
+        Variant::from_array(&array)
+    }
+}
+
+/// Converted to an array.
+impl<T: FromVariant> FromVariant for Vec<T> {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        Self::try_from_variant(variant).ok()
+    }
+
+    fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+        let array = variant.try_to_array().ok_or_else(|| FromVariantError::invalid_type::<Self>(variant))?;
+        (0..array.len())
+            .map(|idx| T::try_from_variant(array.get_ref(idx)).map_err(|err| err.at_index(idx)))
+            .collect()
+    }
+}
+
+/// Converted to a dictionary.
+impl<K, V> ToVariant for HashMap<K, V>
+where
+    K: ToVariant + Eq + Hash,
+    V: ToVariant,
+{
+    fn to_variant(&self) -> Variant {
+        let mut dictionary = Dictionary::new();
+        for (key, value) in self {
+            dictionary.set(&key.to_variant(), &value.to_variant());
+        }
+        Variant::from_dictionary(&dictionary)
+    }
+}
+
+/// Converted to a dictionary.
+impl<K, V> FromVariant for HashMap<K, V>
+where
+    K: FromVariant + Eq + Hash,
+    V: FromVariant,
+{
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        Self::try_from_variant(variant).ok()
+    }
+
+    fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+        let dictionary = variant.try_to_dictionary().ok_or_else(|| FromVariantError::invalid_type::<Self>(variant))?;
+        let keys = dictionary.keys();
+        (0..keys.len())
+            .map(|idx| {
+                let key = keys.get_ref(idx);
+                let value = V::try_from_variant(dictionary.get_ref(key)).map_err(|err| err.at_key(key))?;
+                // Keys that can't be converted are located by their index.
+                Ok((K::try_from_variant(key).map_err(|err| err.at_index(idx))?, value))
+            })
+            .collect()
+    }
+}
+
+/// `None` is converted to nil.
+impl<T: ToVariant> ToVariant for Option<T> {
+    fn to_variant(&self) -> Variant {
+        match *self {
+            Some(ref value) => value.to_variant(),
+            None => Variant::new(),
+        }
+    }
+}
+
+/// `None` is converted to nil.
+impl<T: FromVariant> FromVariant for Option<T> {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        Self::try_from_variant(variant).ok()
+    }
+
+    fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+        if variant.is_nil() {
+            Ok(None)
+        } else {
+            T::try_from_variant(variant).map(Some)
+        }
+    }
+
+    fn is_optional_argument() -> bool {
+        true
+    }
+}
+
+/// `Ok` is converted to its value and `Err` to a dictionary with the error under the
+/// key `"error"`, which GDScript can check with `result is Dictionary and
+/// result.has("error")`. A dictionary with the single key `"error"` is always
+/// converted back to `Err`.
+impl<T: ToVariant, E: ToVariant> ToVariant for Result<T, E> {
+    fn to_variant(&self) -> Variant {
+        match *self {
+            Ok(ref value) => value.to_variant(),
+            Err(ref err) => {
+                let mut dictionary = Dictionary::new();
+                dictionary.set(&Variant::from_str("error"), &err.to_variant());
+                Variant::from_dictionary(&dictionary)
+            }
+        }
+    }
+
+    fn to_error_variant(&self) -> Option<Variant> {
+        self.as_ref().err().map(|err| err.to_variant())
+    }
+}
+
+/// A dictionary with the single key `"error"` is converted to `Err`, see the
+/// `ToVariant` implementation.
+impl<T: FromVariant, E: FromVariant> FromVariant for Result<T, E> {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        Self::try_from_variant(variant).ok()
+    }
+
+    fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+        if let Some(dictionary) = variant.try_to_dictionary() {
+            let key = Variant::from_str("error");
+            if dictionary.len() == 1 && dictionary.contains(&key) {
+                return E::try_from_variant(dictionary.get_ref(&key))
+                    .map(Err)
+                    .map_err(|err| err.at_key(&key));
+            }
+        }
+        T::try_from_variant(variant).map(Ok)
+    }
+}
+
+// Tuples are converted to arrays with one element per field.
+macro_rules! godot_tuple_impl {
+    ($count:expr; $($idx:tt : $ty:ident),*) => (
+        impl<$($ty: ToVariant),*> ToVariant for ($($ty,)*) {
+            fn to_variant(&self) -> Variant {
+                let mut array = VariantArray::new();
+                $(array.push(&self.$idx.to_variant());)*
+                Variant::from_array(&array)
+            }
+        }
+
+        impl<$($ty: FromVariant),*> FromVariant for ($($ty,)*) {
+            fn from_variant(variant: &Variant) -> Option<Self> {
+                Self::try_from_variant(variant).ok()
+            }
+
+            fn try_from_variant(variant: &Variant) -> Result<Self, FromVariantError> {
+                let array = variant.try_to_array().ok_or_else(|| FromVariantError::invalid_type::<Self>(variant))?;
+                if array.len() != $count {
+                    return Err(FromVariantError::new(FromVariantErrorKind::InvalidLength {
+                        expected: $count,
+                        actual: array.len() as usize,
+                    }));
+                }
+                Ok(($(
+                    $ty::try_from_variant(array.get_ref($idx)).map_err(|err| err.at_index($idx))?,
+                )*))
+            }
+        }
+    )
+}
+
+godot_tuple_impl!(1; 0: A);
+godot_tuple_impl!(2; 0: A, 1: B);
+godot_tuple_impl!(3; 0: A, 1: B, 2: C);
+godot_tuple_impl!(4; 0: A, 1: B, 2: C, 3: D);
+godot_tuple_impl!(5; 0: A, 1: B, 2: C, 3: D, 4: E);
+godot_tuple_impl!(6; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+godot_tuple_impl!(7; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+godot_tuple_impl!(8; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+
+// Implementation details of `#[derive(ToVariant)]` and `#[derive(FromVariant)]`.
+
+/// Converts the value of a field of a derived struct, a missing field is converted
+/// from nil.
+#[doc(hidden)]
+pub fn dictionary_field<T: FromVariant>(dictionary: &Dictionary, field: &'static str) -> Result<T, FromVariantError> {
+    let key = Variant::from_str(field);
+    let value = if dictionary.contains(&key) {
+        T::try_from_variant(dictionary.get_ref(&key))
+    } else {
+        T::try_from_variant(&Variant::new())
+    };
+    value.map_err(|err| err.at_field(field))
+}
+
+/// Converts an element of an array holding the fields of a derived tuple struct.
+#[doc(hidden)]
+pub fn array_field<T: FromVariant>(array: &VariantArray, idx: i32) -> Result<T, FromVariantError> {
+    T::try_from_variant(array.get_ref(idx)).map_err(|err| err.at_index(idx))
+}
+
+/// Converts a variant of a derived enum with fields.
+#[doc(hidden)]
+pub fn tagged_variant(tag: &str, payload: Variant) -> Variant {
+    let mut dictionary = Dictionary::new();
+    dictionary.set(&Variant::from_str(tag), &payload);
+    Variant::from_dictionary(&dictionary)
+}
+
+/// Returns the tag and the payload of a variant created with `tagged_variant`.
+#[doc(hidden)]
+pub fn variant_tag<T>(variant: &Variant) -> Result<(String, Variant), FromVariantError> {
+    let tag = variant.try_to_dictionary()
+        .filter(|dictionary| dictionary.len() == 1)
+        .and_then(|dictionary| {
+            let key = dictionary.keys().get_ref(0).clone();
+            Some((key.try_to_string()?, dictionary.get(&key)))
+        });
+    tag.ok_or_else(|| FromVariantError::invalid_type::<T>(variant))
+}
+
+/// Types that can receive the remaining arguments of a method wrapped with
+/// `godot_wrap_method!` (`#[rest]` parameters).
+pub trait Varargs<'l>: Sized {
+    fn from_varargs(args: &'l mut Vec<Variant>) -> Self;
+}
+
+impl<'l> Varargs<'l> for Vec<Variant> {
+    fn from_varargs(args: &'l mut Vec<Variant>) -> Self {
+        ::std::mem::replace(args, Vec::new())
+    }
+}
+
+impl<'l> Varargs<'l> for &'l [Variant] {
+    fn from_varargs(args: &'l mut Vec<Variant>) -> Self {
+        &args[..]
+    }
+}
+
+/// The arguments of a method registered with `ClassBuilder::add_typed_method`,
+/// implemented for tuples of up to eight `GodotType`s.
+pub trait MethodArgs: Sized {
+    /// The number of arguments.
+    fn count() -> usize;
+
+    /// Converts the arguments, or returns the index of the first argument that
+    /// can't be converted and the reason. The number of arguments must be `count()`.
+    fn from_args(args: &[&Variant]) -> Result<Self, (usize, FromVariantError)>;
+
+    /// Returns the name of the type of an argument.
+    fn type_name(idx: usize) -> &'static str;
+
+    /// Whether an argument is an `Option`, which the callers can omit when it is
+    /// only followed by other omittable arguments.
+    fn is_optional(idx: usize) -> bool;
+}
+
+impl MethodArgs for () {
+    fn count() -> usize { 0 }
+
+    fn from_args(_args: &[&Variant]) -> Result<Self, (usize, FromVariantError)> {
+        Ok(())
+    }
+
+    fn type_name(_idx: usize) -> &'static str {
+        ""
+    }
+
+    fn is_optional(_idx: usize) -> bool {
+        false
+    }
+}
+
+macro_rules! method_args_impl {
+    ($count:expr; $($idx:tt : $ty:ident),*) => (
+        impl<$($ty: GodotType),*> MethodArgs for ($($ty,)*) {
+            fn count() -> usize { $count }
+
+            fn from_args(args: &[&Variant]) -> Result<Self, (usize, FromVariantError)> {
+                Ok(($(
+                    $ty::try_from_variant(args[$idx]).map_err(|err| ($idx as usize, err))?,
+                )*))
+            }
+
+            fn type_name(idx: usize) -> &'static str {
+                match idx {
+                    $($idx => ::std::any::type_name::<$ty>(),)*
+                    _ => "",
+                }
+            }
+
+            fn is_optional(idx: usize) -> bool {
+                match idx {
+                    $($idx => $ty::is_optional_argument(),)*
+                    _ => false,
+                }
+            }
+        }
+    )
+}
+
+method_args_impl!(1; 0: A);
+method_args_impl!(2; 0: A, 1: B);
+method_args_impl!(3; 0: A, 1: B, 2: C);
+method_args_impl!(4; 0: A, 1: B, 2: C, 3: D);
+method_args_impl!(5; 0: A, 1: B, 2: C, 3: D, 4: E);
+method_args_impl!(6; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+method_args_impl!(7; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+method_args_impl!(8; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+
+/// Values that can be returned to the engine by exported methods.
+///
+/// Implemented for every `GodotType`, as well as for borrowed strings and bytes
+/// (`&str`, `Cow<str>`, `&[u8]`, `Cow<[u8]>`) which are only copied into a
+/// `GodotString` or a `ByteArray` when crossing the boundary, so the method
+/// itself doesn't need to allocate engine types.
+///
+/// A method returning a `Result` passes its error to the engine according to the
+/// `MethodErrors` of its class.
+pub trait ReturnValue {
+    fn to_return_variant(&self) -> Variant;
+
+    #[doc(hidden)]
+    fn to_error_variant(&self) -> Option<Variant> {
+        None
+    }
+}
+
+impl<T: GodotType> ReturnValue for T {
+    fn to_return_variant(&self) -> Variant {
+        self.to_variant()
+    }
+
+    fn to_error_variant(&self) -> Option<Variant> {
+        ToVariant::to_error_variant(self)
+    }
+}
+
+/// Converts the value returned by an exported method of `C`, or reports its error if
+/// `C` logs the errors of its methods.
+#[doc(hidden)]
+pub fn method_return<C: NativeClass, R: ReturnValue + ?Sized>(method: &str, ret: &R) -> Variant {
+    if C::method_errors() == MethodErrors::Log {
+        if let Some(err) = ret.to_error_variant() {
+            godot_error!("{} failed: {}", method, err.to_string());
+            return Variant::new();
+        }
+    }
+    ret.to_return_variant()
+}
+
+impl<'l> ReturnValue for &'l str {
+    fn to_return_variant(&self) -> Variant {
+        Variant::from_str(*self)
+    }
+}
+
+impl<'l> ReturnValue for Cow<'l, str> {
+    fn to_return_variant(&self) -> Variant {
+        Variant::from_str(&**self)
+    }
+}
+
+impl<'l> ReturnValue for &'l [u8] {
+    fn to_return_variant(&self) -> Variant {
+        Variant::from_byte_array(&ByteArray::from_slice(*self))
+    }
+}
+
+impl<'l> ReturnValue for Cow<'l, [u8]> {
+    fn to_return_variant(&self) -> Variant {
+        Variant::from_byte_array(&ByteArray::from_slice(&**self))
+    }
+}
+
+godot_test!(test_return_value {
+    let s = "hello".to_return_variant();
+    assert_eq!(s.try_to_godot_string(), Some(GodotString::from_str("hello")));
+
+    let s = Cow::Borrowed("world").to_return_variant();
+    assert_eq!(s.try_to_godot_string(), Some(GodotString::from_str("world")));
+
+    let bytes = (&[1u8, 2, 3][..]).to_return_variant().try_to_byte_array().unwrap();
+    assert_eq!(bytes.len(), 3);
+    assert_eq!(bytes.get(2), 3);
+});
+
+godot_test!(test_method_args {
+    let a = Variant::from_i64(1);
+    let b = Variant::from_str("two");
+
+    assert_eq!(<(i64, GodotString)>::count(), 2);
+    assert_eq!(
+        <(i64, GodotString)>::from_args(&[&a, &b]),
+        Ok((1, GodotString::from_str("two")))
+    );
+    assert_eq!(
+        <(i64, i64)>::from_args(&[&a, &b]),
+        Err((1, FromVariantError::new(FromVariantErrorKind::InvalidType {
+            expected: "i64",
+            actual: VariantType::GodotString,
+        })))
+    );
+    assert_eq!(<(i64, i64)>::type_name(1), "i64");
+});
+
+godot_test!(test_from_variant_error {
+    let mut hp = HashMap::new();
+    hp.insert("hp".to_string(), Variant::from_str("ten"));
+    let variant = vec![hp.clone(), hp].to_variant();
+
+    let err = Vec::<HashMap<String, i64>>::try_from_variant(&variant).unwrap_err();
+    assert_eq!(err.path, vec![VariantPath::Index(0), VariantPath::Key("hp".to_string())]);
+    assert_eq!(err.to_string(), "at [0][\"hp\"]: expected i64, found GodotString");
+
+    let err = <(i64, i64)>::try_from_variant(&(1i64,).to_variant()).unwrap_err();
+    assert_eq!(err.kind, FromVariantErrorKind::InvalidLength { expected: 2, actual: 1 });
+});
+
+godot_test!(test_collection_conversions {
+    let values = vec![1i64, 2, 3];
+    let variant = values.to_variant();
+    assert_eq!(variant.get_type(), VariantType::VariantArray);
+    assert_eq!(Vec::<i64>::from_variant(&variant), Some(values));
+    assert_eq!(Vec::<String>::from_variant(&variant), None);
+
+    let mut map = HashMap::new();
+    map.insert("hp".to_string(), 10i64);
+    map.insert("mp".to_string(), 4);
+    let variant = map.to_variant();
+    assert_eq!(variant.get_type(), VariantType::Dictionary);
+    assert_eq!(HashMap::<String, i64>::from_variant(&variant), Some(map));
+
+    assert!(None::<i64>.to_variant().is_nil());
+    assert_eq!(Option::<i64>::from_variant(&Variant::new()), Some(None));
+    assert_eq!(Option::<i64>::from_variant(&Variant::from_i64(5)), Some(Some(5)));
+    assert_eq!(Option::<i64>::from_variant(&Variant::from_str("5")), None);
+
+    let pair = (1i64, "two".to_string());
+    assert_eq!(<(i64, String)>::from_variant(&pair.to_variant()), Some(pair));
+    assert_eq!(<(i64, String, bool)>::from_variant(&(1i64, "two".to_string()).to_variant()), None);
+
+    let ok: Result<i64, String> = Ok(3);
+    assert_eq!(ok.to_variant(), Variant::from_i64(3));
+    assert_eq!(Result::<i64, String>::from_variant(&ok.to_variant()), Some(ok));
+    let err: Result<i64, String> = Err("not found".to_string());
+    assert_eq!(err.to_variant().get_type(), VariantType::Dictionary);
+    assert_eq!(Result::<i64, String>::from_variant(&err.to_variant()), Some(err));
+});
+
+#[test]
+fn from_variant_error_display() {
+    let err = FromVariantError::new(FromVariantErrorKind::InvalidType {
+        expected: "f64",
+        actual: VariantType::Nil,
+    });
+    assert_eq!(err.to_string(), "expected f64, found Nil");
+
+    let err = err.at_field("position").at_index(3).at_field("waypoints");
+    assert_eq!(err.to_string(), "at waypoints[3].position: expected f64, found Nil");
+
+    let err = FromVariantError::new(FromVariantErrorKind::UnknownVariant { value: "7".to_string() });
+    assert_eq!(err.to_string(), "no enum variant matches 7");
+}
+
+#[test]
+fn optional_method_args() {
+    type Args = (i64, Option<String>, Option<Vec<i64>>);
+    assert_eq!(Args::count(), 3);
+    assert!(!Args::is_optional(0));
+    assert!(Args::is_optional(1));
+    assert!(Args::is_optional(2));
+    assert!(!Args::is_optional(3));
+}
@@ -0,0 +1,195 @@
+//! Typed helpers for `GridMap` and `MeshLibrary`.
+//!
+//! The engine API addresses cells with three integer arguments and uses `-1` for
+//! empty cells. The helpers here use `GridCell` and `Option` instead, and
+//! `GridMap::fill` and `GridMap::set_cells` write many cells at once, which is
+//! what world generators usually need:
+//!
+//! ```ignore
+//! let floor = library.find_item("floor").unwrap();
+//! grid_map.fill(GridCell::new(0, 0, 0), GridCell::new(15, 0, 15), Some(GridItem::new(floor)));
+//! ```
+
+use GodotString;
+use GridMap;
+use MeshLibrary;
+use Vector3;
+
+/// The coordinates of a cell of a `GridMap`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GridCell {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl GridCell {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        GridCell { x, y, z }
+    }
+
+    /// Converts the map coordinates returned by the engine, for example by
+    /// `world_to_map` or `get_used_cells`.
+    pub fn from_vector3(v: Vector3) -> Self {
+        GridCell::new(v.x.round() as i32, v.y.round() as i32, v.z.round() as i32)
+    }
+
+    pub fn to_vector3(&self) -> Vector3 {
+        Vector3::new(self.x as f32, self.y as f32, self.z as f32)
+    }
+
+    /// Iterates over the cells of the box between `from` and `to`, both included.
+    ///
+    /// The cells are visited in x, then z, then y order, so that a generator can
+    /// fill a world layer by layer. The bounds can be given in any order.
+    pub fn region(from: GridCell, to: GridCell) -> impl Iterator<Item = GridCell> {
+        let (x0, x1) = (from.x.min(to.x), from.x.max(to.x));
+        let (y0, y1) = (from.y.min(to.y), from.y.max(to.y));
+        let (z0, z1) = (from.z.min(to.z), from.z.max(to.z));
+
+        (y0..y1 + 1).flat_map(move |y| {
+            (z0..z1 + 1).flat_map(move |z| {
+                (x0..x1 + 1).map(move |x| GridCell::new(x, y, z))
+            })
+        })
+    }
+}
+
+/// The content of a non-empty `GridMap` cell: a `MeshLibrary` item id and the
+/// orientation of the item.
+///
+/// The orientation is the orthogonal rotation index used by the engine (0 to 23),
+/// where 0 is no rotation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GridItem {
+    pub item: i32,
+    pub orientation: i32,
+}
+
+impl GridItem {
+    /// An item with no rotation.
+    pub fn new(item: i32) -> Self {
+        GridItem { item, orientation: 0 }
+    }
+
+    pub fn with_orientation(item: i32, orientation: i32) -> Self {
+        GridItem { item, orientation }
+    }
+}
+
+impl GridMap {
+    /// The item id the engine uses for empty cells.
+    pub const INVALID_CELL_ITEM: i32 = -1;
+
+    /// Returns the content of a cell, or `None` if the cell is empty.
+    pub fn cell(&self, cell: GridCell) -> Option<GridItem> {
+        let item = self.get_cell_item(cell.x as i64, cell.y as i64, cell.z as i64) as i32;
+        if item == Self::INVALID_CELL_ITEM {
+            return None;
+        }
+
+        let orientation = self.get_cell_item_orientation(cell.x as i64, cell.y as i64, cell.z as i64);
+        Some(GridItem::with_orientation(item, orientation as i32))
+    }
+
+    /// Sets the content of a cell. `None` clears the cell.
+    pub fn set_cell(&mut self, cell: GridCell, item: Option<GridItem>) {
+        let (item, orientation) = match item {
+            Some(item) => (item.item, item.orientation),
+            None => (Self::INVALID_CELL_ITEM, 0),
+        };
+        self.set_cell_item(cell.x as i64, cell.y as i64, cell.z as i64, item as i64, orientation as i64);
+    }
+
+    pub fn clear_cell(&mut self, cell: GridCell) {
+        self.set_cell(cell, None);
+    }
+
+    /// Sets the content of many cells.
+    pub fn set_cells<I>(&mut self, cells: I)
+    where
+        I: IntoIterator<Item = (GridCell, Option<GridItem>)>,
+    {
+        for (cell, item) in cells {
+            self.set_cell(cell, item);
+        }
+    }
+
+    /// Sets all the cells of the box between `from` and `to` (both included) to the
+    /// same content.
+    pub fn fill(&mut self, from: GridCell, to: GridCell, item: Option<GridItem>) {
+        self.set_cells(GridCell::region(from, to).map(|cell| (cell, item)));
+    }
+
+    /// Sets the cells of the box between `from` and `to` (both included) to the
+    /// content returned by `generate`, in the order of `GridCell::region`.
+    pub fn fill_with<F>(&mut self, from: GridCell, to: GridCell, mut generate: F)
+    where
+        F: FnMut(GridCell) -> Option<GridItem>,
+    {
+        self.set_cells(GridCell::region(from, to).map(|cell| (cell, generate(cell))));
+    }
+
+    /// Returns the coordinates of the non-empty cells.
+    pub fn used_cells(&self) -> Vec<GridCell> {
+        let cells = self.get_used_cells();
+        (0..cells.len())
+            .filter_map(|idx| cells.get_ref(idx).try_to_vector3())
+            .map(GridCell::from_vector3)
+            .collect()
+    }
+
+    /// Returns the cell containing a position in the local space of the map.
+    pub fn world_to_cell(&self, position: Vector3) -> GridCell {
+        GridCell::from_vector3(self.world_to_map(position))
+    }
+
+    /// Returns the position of the center of a cell in the local space of the map.
+    pub fn cell_to_world(&self, cell: GridCell) -> Vector3 {
+        self.map_to_world(cell.x as i64, cell.y as i64, cell.z as i64)
+    }
+}
+
+impl MeshLibrary {
+    /// Returns the ids of the items of the library.
+    pub fn item_ids(&self) -> Vec<i32> {
+        let items = self.get_item_list();
+        (0..items.len()).map(|idx| items.get(idx)).collect()
+    }
+
+    /// Returns the id and the name of each item of the library.
+    pub fn items(&self) -> Vec<(i32, String)> {
+        self.item_ids()
+            .into_iter()
+            .map(|id| (id, self.get_item_name(id as i64).to_string()))
+            .collect()
+    }
+
+    /// Returns the id of the item with the given name.
+    pub fn find_item(&self, name: &str) -> Option<i32> {
+        let id = self.find_item_by_name(GodotString::from_str(name)) as i32;
+        if id < 0 {
+            None
+        } else {
+            Some(id)
+        }
+    }
+}
+
+#[test]
+fn grid_region() {
+    let cells: Vec<_> = GridCell::region(GridCell::new(1, 0, 0), GridCell::new(0, 1, 1)).collect();
+    assert_eq!(cells, vec![
+        GridCell::new(0, 0, 0),
+        GridCell::new(1, 0, 0),
+        GridCell::new(0, 0, 1),
+        GridCell::new(1, 0, 1),
+        GridCell::new(0, 1, 0),
+        GridCell::new(1, 1, 0),
+        GridCell::new(0, 1, 1),
+        GridCell::new(1, 1, 1),
+    ]);
+
+    assert_eq!(GridCell::region(GridCell::new(2, 2, 2), GridCell::new(2, 2, 2)).count(), 1);
+    assert_eq!(GridCell::from_vector3(Vector3::new(-1.0, 2.0, 3.0)), GridCell::new(-1, 2, 3));
+}
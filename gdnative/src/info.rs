@@ -0,0 +1,468 @@
+//! Diagnostics about the library loaded by the engine.
+//!
+//! Every class and member registered through `InitHandle` and `ClassBuilder` is
+//! recorded. Registering the `GDNativeInfo` class with `godot_gdnative_info!`
+//! makes this information available from GDScript or the debugger:
+//!
+//! ```ignore
+//! var info = GDNativeInfo.new()
+//! info.list()            # Prints the registered classes and the library version.
+//! var dict = info.get_info()
+//! ```
+//!
+//! This is mostly useful to find out which build of a library the editor actually
+//! loaded.
+
+use std::ffi::CString;
+use std::fmt::Write;
+use std::fs;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use init::{ClassDescriptor, InitHandle, RegisteredClass};
+use libc;
+use sys;
+use Dictionary;
+use GDNativeLibrary;
+use GodotObject;
+use GodotString;
+use NativeClass;
+use LocalCellData;
+use NativeInstanceHeader;
+use StringArray;
+use Utf8String;
+use Variant;
+use VariantArray;
+
+/// Name and version of the crate providing the library, see `godot_gdnative_info!`.
+#[derive(Copy, Clone, Debug)]
+pub struct LibraryInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum MemberKind {
+    Method,
+    Property,
+    Signal,
+}
+
+struct ClassInfo {
+    name: String,
+    base_class: String,
+    tool: bool,
+    methods: Vec<String>,
+    properties: Vec<String>,
+    signals: Vec<String>,
+}
+
+static CLASSES: Mutex<Vec<ClassInfo>> = Mutex::new(Vec::new());
+static LIBRARY: Mutex<Option<LibraryInfo>> = Mutex::new(None);
+static LIBRARY_PATH: Mutex<Option<String>> = Mutex::new(None);
+static LIBRARY_OBJECT: AtomicPtr<sys::godot_object> = AtomicPtr::new(ptr::null_mut());
+
+pub(crate) fn register_class(name: &str, base_class: &str, tool: bool) {
+    if let Ok(mut classes) = CLASSES.lock() {
+        classes.retain(|class| class.name != name);
+        classes.push(ClassInfo {
+            name: name.to_string(),
+            base_class: base_class.to_string(),
+            tool,
+            methods: Vec::new(),
+            properties: Vec::new(),
+            signals: Vec::new(),
+        });
+    }
+}
+
+pub(crate) fn is_class_registered(name: &str) -> bool {
+    CLASSES.lock().map(|classes| classes.iter().any(|class| class.name == name)).unwrap_or(false)
+}
+
+pub(crate) fn class_names() -> Vec<String> {
+    CLASSES.lock()
+        .map(|classes| classes.iter().map(|class| format!("{} : {}", class.name, class.base_class)).collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn registered_classes() -> Vec<RegisteredClass> {
+    CLASSES.lock()
+        .map(|classes| classes.iter().map(|class| RegisteredClass {
+            name: class.name.clone(),
+            base_class: class.base_class.clone(),
+            tool: class.tool,
+            methods: class.methods.clone(),
+            properties: class.properties.clone(),
+            signals: class.signals.clone(),
+        }).collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn register_member(class_name: &str, kind: MemberKind, name: &str) {
+    if let Ok(mut classes) = CLASSES.lock() {
+        if let Some(class) = classes.iter_mut().find(|class| class.name == class_name) {
+            let members = match kind {
+                MemberKind::Method => &mut class.methods,
+                MemberKind::Property => &mut class.properties,
+                MemberKind::Signal => &mut class.signals,
+            };
+            members.push(name.to_string());
+        }
+    }
+}
+
+#[doc(hidden)]
+pub unsafe fn set_library_path(options: *const sys::godot_gdnative_init_options) {
+    LIBRARY_OBJECT.store((*options).gd_native_library, Ordering::Release);
+
+    let path = (*options).active_library_path;
+    if path.is_null() {
+        return;
+    }
+
+    let path = Utf8String((::get_api().godot_string_utf8)(path)).to_string();
+    if let Ok(mut library_path) = LIBRARY_PATH.lock() {
+        *library_path = Some(path);
+    }
+}
+
+/// The options the engine passes to `godot_gdnative_init`, given to the callback of
+/// `godot_gdnative_init!`:
+///
+/// ```ignore
+/// fn init(info: &InitializeInfo) {
+///     if info.in_editor() {
+///         // Skip loading the game data.
+///     }
+///     if let Some(library) = info.library() {
+///         godot_print!("loaded by {}", library.get_path().to_string());
+///     }
+/// }
+///
+/// godot_gdnative_init!(init);
+/// ```
+pub struct InitializeInfo {
+    options: *mut sys::godot_gdnative_init_options,
+}
+
+/// The version of an API struct of the engine.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl ApiVersion {
+    fn from_sys(version: sys::godot_gdnative_api_version) -> Self {
+        ApiVersion { major: version.major as u32, minor: version.minor as u32 }
+    }
+
+    fn to_sys(&self) -> sys::godot_gdnative_api_version {
+        sys::godot_gdnative_api_version { major: self.major as _, minor: self.minor as _ }
+    }
+}
+
+impl InitializeInfo {
+    #[doc(hidden)]
+    pub unsafe fn new(options: *mut sys::godot_gdnative_init_options) -> Self {
+        InitializeInfo { options }
+    }
+
+    fn options(&self) -> &sys::godot_gdnative_init_options {
+        unsafe { &*self.options }
+    }
+
+    /// `true` when the library is loaded by the editor.
+    pub fn in_editor(&self) -> bool {
+        self.options().in_editor
+    }
+
+    /// The path of the binary loaded by the engine, for the platform it runs on.
+    pub fn active_library_path(&self) -> String {
+        let path = self.options().active_library_path;
+        if path.is_null() {
+            return String::new();
+        }
+        unsafe { Utf8String((::get_api().godot_string_utf8)(path)).to_string() }
+    }
+
+    /// The `.gdnlib` resource of the library, `get_path` gives its location.
+    pub fn library(&self) -> Option<GDNativeLibrary> {
+        let library = self.options().gd_native_library;
+        if library.is_null() {
+            None
+        } else {
+            Some(unsafe { GDNativeLibrary::from_sys(library) })
+        }
+    }
+
+    /// The version of the core API struct.
+    pub fn core_api_version(&self) -> ApiVersion {
+        unsafe { ApiVersion::from_sys((*self.options().api_struct).version) }
+    }
+
+    /// The versions of the extension API structs of the given type, from the first
+    /// to the latest.
+    pub fn extension_versions(&self, api_type: sys::GDNATIVE_API_TYPES) -> Vec<ApiVersion> {
+        let mut versions = Vec::new();
+        unsafe {
+            let core = &*self.options().api_struct;
+            for i in 0..core.num_extensions {
+                let mut api = *core.extensions.offset(i as isize);
+                if (*api).type_ != api_type as libc::c_uint {
+                    continue;
+                }
+                while !api.is_null() {
+                    versions.push(ApiVersion::from_sys((*api).version));
+                    api = (*api).next;
+                }
+            }
+        }
+        versions
+    }
+
+    /// The hash of the core API the engine was built with.
+    pub fn core_api_hash(&self) -> u64 {
+        self.options().core_api_hash
+    }
+
+    /// The hash of the editor API, which includes the core API.
+    pub fn editor_api_hash(&self) -> u64 {
+        self.options().editor_api_hash
+    }
+
+    pub fn no_api_hash(&self) -> u64 {
+        self.options().no_api_hash
+    }
+
+    /// Reports that the engine provides version `have` of an API when the library
+    /// needs version `want`. The engine prints the error and the name of the library.
+    pub fn report_version_mismatch(&self, what: &str, want: ApiVersion, have: ApiVersion) {
+        let report = match self.options().report_version_mismatch {
+            Some(report) => report,
+            None => return,
+        };
+        let what = CString::new(what.replace('\0', "")).unwrap_or_default();
+        unsafe { report(self.options().gd_native_library, what.as_ptr(), want.to_sys(), have.to_sys()) };
+    }
+
+    /// Reports an error preventing the library from loading. The engine prints the
+    /// error and the name of the library.
+    pub fn report_loading_error(&self, what: &str) {
+        let report = match self.options().report_loading_error {
+            Some(report) => report,
+            None => return,
+        };
+        let what = CString::new(what.replace('\0', "")).unwrap_or_default();
+        unsafe { report(self.options().gd_native_library, what.as_ptr()) };
+    }
+
+    /// The options, for the fields without an accessor.
+    pub fn as_raw(&self) -> *mut sys::godot_gdnative_init_options {
+        self.options
+    }
+}
+
+impl<'a> InitHandle<'a> {
+    /// Registers the `GDNativeInfo` diagnostic class under the given name.
+    ///
+    /// Prefer the `godot_gdnative_info!` macro which fills in the name and the
+    /// version of the calling crate.
+    pub fn add_info_class(&self, class_name: &str, library: LibraryInfo) {
+        if let Ok(mut info) = LIBRARY.lock() {
+            *info = Some(library);
+        }
+
+        let class = self.add_class::<GDNativeInfo>(ClassDescriptor {
+            name: class_name,
+            base_class: "Reference",
+            constructor: Some(godot_wrap_constructor!(GDNativeInfo, GDNativeInfo::new)),
+            destructor: Some(godot_wrap_destructor!(GDNativeInfo)),
+        });
+
+        class.add_method("list", godot_wrap_method!(
+            GDNativeInfo,
+            fn list(&mut self) -> GodotString
+        ));
+        class.add_method("get_info", godot_wrap_method!(
+            GDNativeInfo,
+            fn get_info(&mut self) -> Dictionary
+        ));
+    }
+}
+
+#[doc(hidden)]
+pub struct GDNativeInfo {
+    header: NativeInstanceHeader,
+}
+
+impl NativeClass for GDNativeInfo {
+    type UserData = LocalCellData<GDNativeInfo>;
+
+    fn class_name() -> &'static str {
+        "GDNativeInfo"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+impl GDNativeInfo {
+    fn new(header: NativeInstanceHeader) -> Self {
+        GDNativeInfo { header }
+    }
+
+    fn list(&mut self) -> GodotString {
+        let report = report();
+        godot_print!("{}", report);
+        GodotString::from_str(report)
+    }
+
+    fn get_info(&mut self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let library = library();
+        let path = library_path();
+
+        set(&mut dict, "name", Variant::from_str(library.map_or("", |lib| lib.name)));
+        set(&mut dict, "version", Variant::from_str(library.map_or("", |lib| lib.version)));
+        set(&mut dict, "gdnative_version", Variant::from_str(env!("CARGO_PKG_VERSION")));
+        set(&mut dict, "library_path", Variant::from_str(path.as_ref().map_or("", |p| &p[..])));
+        set(&mut dict, "library_modified", Variant::from_u64(
+            path.as_ref().and_then(|p| modified_time(p)).unwrap_or(0)
+        ));
+
+        let mut classes = VariantArray::new();
+        if let Ok(registered) = CLASSES.lock() {
+            for class in registered.iter() {
+                let mut entry = Dictionary::new();
+                set(&mut entry, "name", Variant::from_str(&class.name));
+                set(&mut entry, "base_class", Variant::from_str(&class.base_class));
+                set(&mut entry, "methods", Variant::from_string_array(&string_array(&class.methods)));
+                set(&mut entry, "properties", Variant::from_string_array(&string_array(&class.properties)));
+                set(&mut entry, "signals", Variant::from_string_array(&string_array(&class.signals)));
+                classes.push(&Variant::from_dictionary(&entry));
+            }
+        }
+        set(&mut dict, "classes", Variant::from_array(&classes));
+
+        dict
+    }
+}
+
+fn set(dict: &mut Dictionary, key: &str, value: Variant) {
+    dict.set(&Variant::from_str(key), &value);
+}
+
+fn string_array(strings: &[String]) -> StringArray {
+    let mut array = StringArray::new();
+    for s in strings {
+        array.push(&GodotString::from_str(s));
+    }
+    array
+}
+
+fn library() -> Option<LibraryInfo> {
+    LIBRARY.lock().ok().and_then(|info| *info)
+}
+
+pub(crate) fn library_path() -> Option<String> {
+    LIBRARY_PATH.lock().ok().and_then(|path| path.clone())
+}
+
+/// The `GDNativeLibrary` resource the library was loaded from.
+pub(crate) fn library_resource() -> Option<GDNativeLibrary> {
+    let library = LIBRARY_OBJECT.load(Ordering::Acquire);
+    if library.is_null() {
+        None
+    } else {
+        Some(unsafe { GDNativeLibrary::from_sys(library) })
+    }
+}
+
+// Seconds since the unix epoch.
+fn modified_time(path: &str) -> Option<u64> {
+    fs::metadata(path).ok()
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+fn report() -> String {
+    let mut out = String::new();
+
+    match library() {
+        Some(library) => { let _ = writeln!(out, "{} {}", library.name, library.version); }
+        None => { let _ = writeln!(out, "<unknown library>"); }
+    }
+    let _ = writeln!(out, "  gdnative {}", env!("CARGO_PKG_VERSION"));
+
+    if let Some(path) = library_path() {
+        let _ = writeln!(out, "  loaded from {}", path);
+        if let Some(modified) = modified_time(&path) {
+            let _ = writeln!(out, "  built {} ({})", format_timestamp(modified), format_age(modified));
+        }
+    }
+
+    if let Ok(classes) = CLASSES.lock() {
+        let _ = writeln!(out, "  {} registered classes:", classes.len());
+        for class in classes.iter() {
+            let _ = writeln!(out, "    {} : {}", class.name, class.base_class);
+            for (label, members) in &[
+                ("methods", &class.methods),
+                ("properties", &class.properties),
+                ("signals", &class.signals),
+            ] {
+                if !members.is_empty() {
+                    let _ = writeln!(out, "      {}: {}", label, members.join(", "));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let age = now.saturating_sub(timestamp);
+    if age < 60 {
+        format!("{}s ago", age)
+    } else if age < 3600 {
+        format!("{}min ago", age / 60)
+    } else if age < 86400 {
+        format!("{}h ago", age / 3600)
+    } else {
+        format!("{} days ago", age / 86400)
+    }
+}
+
+// Formats a unix timestamp as an UTC date.
+pub(crate) fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, secs / 3600, secs / 60 % 60, secs % 60
+    )
+}
+
+#[test]
+fn timestamp_format() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+    assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+    assert_eq!(format_timestamp(1_530_000_000), "2018-06-26 08:00:00 UTC");
+}
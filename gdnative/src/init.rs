@@ -8,83 +8,506 @@
 //! - [`godot_nativescript_init`](macro.godot_nativescript_init.html),
 //! - [`godot_gdnative_terminate`](macro.godot_gdnative_terminate.html),
 //!
-//! All three must be present.
+//! All three must be present in the libraries registering classes. The libraries
+//! without classes only need `godot_gdnative_init`, see the `native_function`
+//! module.
 //!
 //! ## Registering a class using the `godot_class` macro
 //!
 //! See the [spinning_cube example](https://github.com/GodotNativeTools/godot-rust/tree/master/examples/spinning_cube)
 //! in the repositiory.
 //!
+//! ## Registering a class using `#[derive(NativeClass)]`
+//!
+//! See the [derived_class example](https://github.com/GodotNativeTools/godot-rust/tree/master/examples/derived_class)
+//! in the repositiory.
+//!
 //! ## Registering a class manually
 //!
 //! See the [manually_registered example](https://github.com/GodotNativeTools/godot-rust/tree/master/examples/manually_registered)
 //! in the repositiory.
 //!
+//! Plain rust methods can be turned into the `ScriptMethodFn` expected by
+//! `ClassBuilder::add_method` without unsafe code using
+//! [`godot_wrap_method`](../macro.godot_wrap_method.html), which also gives access
+//! to the owner of the script:
+//!
+//! ```ignore
+//! class.add_method("describe", godot_wrap_method!(
+//!     MyClass,
+//!     fn describe(&mut self, #[owner] owner: Node, prefix: GodotString) -> GodotString
+//! ));
+//! ```
+//!
 
 use super::*;
 use get_api;
 use Variant;
 use GodotType;
+use ReturnValue;
 use NativeClass;
+use timing::{self, TimingKind};
+use info::{self, MemberKind};
+use type_tag;
+use reload;
+use instance_borrow::{with_instance, with_instance_mut};
+use user_data::UserData;
+use nativescript_1_1::{nativescript_1_1, DocumentationSetter, MethodArg, NativeScriptApi11};
 use sys::godot_property_usage_flags::*;
 use sys::godot_property_hint::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 use std::mem;
 use std::ops::Range;
 use std::ffi::CString;
 use std::marker::PhantomData;
 use std::ptr;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
 use libc;
 
 /// A handle that can register new classes to the engine during initialization.
 ///
 /// See [`godot_nativescript_init`](macro.godot_nativescript_init.html).
-#[derive(Copy, Clone)]
-pub struct InitHandle {
+///
+/// The handle and the `ClassBuilder`s it returns borrow the scope of the init
+/// callback: a handle used after the callback returned points to freed memory, so
+/// they can't be stored or moved out of it. Functions registering classes take a
+/// `&InitHandle`. The helpers of the crate registering their classes on first use,
+/// like `connect_fn`, get a handle of their own once `godot_nativescript_init` ran.
+///
+/// The callback must accept a handle of any lifetime, a handle declared as
+/// `InitHandle<'static>` to stash it is rejected:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate gdnative;
+///
+/// use std::cell::RefCell;
+/// use gdnative::init::InitHandle;
+///
+/// thread_local! {
+///     static HANDLE: RefCell<Option<InitHandle<'static>>> = RefCell::new(None);
+/// }
+///
+/// fn init(handle: InitHandle<'static>) {
+///     HANDLE.with(|stashed| *stashed.borrow_mut() = Some(handle));
+/// }
+///
+/// godot_nativescript_init!(init);
+/// # fn main() {}
+/// ```
+pub struct InitHandle<'a> {
     #[doc(hidden)]
     handle: *mut libc::c_void,
+    _scope: PhantomData<&'a ()>,
 }
 
-impl InitHandle {
+impl<'a> InitHandle<'a> {
     #[doc(hidden)]
-    pub unsafe fn new(handle: *mut libc::c_void) -> Self { InitHandle { handle } }
+    pub unsafe fn new(handle: *mut libc::c_void) -> Self { InitHandle { handle, _scope: PhantomData } }
 
     /// Registers a new class to the engine.
     ///
     /// The return `ClassBuilder` can be used to add methods, signals and properties
-    /// to the class.
-    pub fn add_class<C>(&self, desc: ClassDescriptor) -> ClassBuilder<C>
+    /// to the class. If the class can't be registered the error is printed and the
+    /// members added to the returned builder are ignored, see `try_add_class`.
+    pub fn add_class<C>(&self, desc: ClassDescriptor) -> ClassBuilder<'a, C>
     where C: NativeClass {
-        unsafe {
-            let class_name = CString::new(desc.name).unwrap();
-            let base_name = CString::new(desc.base_class).unwrap();
+        self.register_class_or_report(desc, false)
+    }
 
-            let create = sys::godot_instance_create_func {
-                create_func: desc.constructor,
-                method_data: ptr::null_mut(),
-                free_func: None,
-            };
+    /// Registers a new class to the engine, or returns why it can't be registered.
+    pub fn try_add_class<C>(&self, desc: ClassDescriptor) -> Result<ClassBuilder<'a, C>, RegisterError>
+    where C: NativeClass {
+        self.register_class(desc, false)
+    }
 
-            let destroy = sys::godot_instance_destroy_func {
-                destroy_func: desc.destructor,
-                method_data: ptr::null_mut(),
-                free_func: None,
-            };
+    /// Registers a new tool class to the engine.
+    ///
+    /// Like GDScript's `tool` scripts, the instances of tool classes run in the
+    /// editor, which is needed for editor plugins or for nodes updating their
+    /// appearance while the scene is edited. Use `_Engine::is_editor_hint` to find
+    /// out whether the code is running in the editor.
+    pub fn add_tool_class<C>(&self, desc: ClassDescriptor) -> ClassBuilder<'a, C>
+    where C: NativeClass {
+        self.register_class_or_report(desc, true)
+    }
 
-            (get_api().godot_nativescript_register_class)(
-                self.handle as *mut _,
-                class_name.as_ptr() as *const _,
-                base_name.as_ptr() as *const _,
-                create,
-                destroy
-            );
+    /// Registers a new tool class, see `add_tool_class` and `try_add_class`.
+    pub fn try_add_tool_class<C>(&self, desc: ClassDescriptor) -> Result<ClassBuilder<'a, C>, RegisterError>
+    where C: NativeClass {
+        self.register_class(desc, true)
+    }
 
+    fn register_class_or_report<C>(&self, desc: ClassDescriptor, tool: bool) -> ClassBuilder<'a, C>
+    where C: NativeClass {
+        let name = desc.name;
+        self.register_class(desc, tool).unwrap_or_else(|err| {
+            godot_error!("could not register the class {:?}: {}", name, err);
             ClassBuilder {
                 init_handle: self.handle,
-                class_name,
+                class_name: CString::new(name.replace('\0', "")).unwrap_or_default(),
+                registered: false,
+                members: RefCell::new(HashMap::new()),
+                registering_base: Cell::new(false),
                 _marker: PhantomData,
+                _scope: PhantomData,
             }
+        })
+    }
+
+    fn register_class<C>(&self, desc: ClassDescriptor, tool: bool) -> Result<ClassBuilder<'a, C>, RegisterError>
+    where C: NativeClass {
+        let class_name = self.register_descriptor(desc, tool, type_tag::register::<C>)?;
+        Ok(ClassBuilder {
+            init_handle: self.handle,
+            class_name,
+            registered: true,
+            members: RefCell::new(HashMap::new()),
+            registering_base: Cell::new(false),
+            _marker: PhantomData,
+            _scope: PhantomData,
+        })
+    }
+
+    // Registers the class, `register_tag` records the type tag of its instances.
+    fn register_descriptor(&self, desc: ClassDescriptor, tool: bool, register_tag: fn(&str)) -> Result<CString, RegisterError> {
+        let class_name = c_name(desc.name)?;
+        let base_name = c_name(desc.base_class)?;
+        if info::is_class_registered(desc.name) {
+            return Err(RegisterError::DuplicateClass { name: desc.name.to_string() });
+        }
+        if !is_engine_class(&base_name) && !info::is_class_registered(desc.base_class) {
+            return Err(RegisterError::UnknownBaseClass {
+                class: desc.name.to_string(),
+                base_class: desc.base_class.to_string(),
+            });
+        }
+
+        unsafe {
+
+            let create = reload::create_func(desc.name, desc.constructor);
+            let destroy = reload::destroy_func(desc.destructor);
+
+            info::register_class(desc.name, desc.base_class, tool);
+            register_tag(desc.name);
+
+            let register = if tool {
+                get_api().godot_nativescript_register_tool_class
+            } else {
+                get_api().godot_nativescript_register_class
+            };
+
+            timing::time(TimingKind::Class, || desc.name.to_string(), || {
+                (register)(
+                    self.handle as *mut _,
+                    class_name.as_ptr() as *const _,
+                    base_name.as_ptr() as *const _,
+                    create,
+                    destroy
+                );
+            });
+        }
+        Ok(class_name)
+    }
+
+    /// Registers an abstract class, which scripts and the classes of the library
+    /// extend but which can't be instanced itself, see `BaseClass`.
+    ///
+    /// The classes extending it must be registered after it.
+    pub fn add_base_class<B>(&self)
+    where B: BaseClass {
+        fn no_tag(_class_name: &str) {}
+
+        let desc = ClassDescriptor {
+            name: B::class_name(),
+            base_class: B::base_class(),
+            constructor: None,
+            destructor: None,
+        };
+        if let Err(err) = self.register_descriptor(desc, false, no_tag) {
+            godot_error!("could not register the class {:?}: {}", B::class_name(), err);
         }
     }
+
+    /// Registers a class declared with `#[derive(NativeClass)]` and `#[methods]`, or
+    /// implementing `NativeClassInit` and `NativeClassMethods` manually.
+    pub fn add_native_class<C>(&self)
+    where C: NativeClassInit + NativeClassMethods {
+        self.register_native_class::<C>(false)
+    }
+
+    /// Registers a class declared with `#[derive(NativeClass)]` and `#[methods]` as a
+    /// tool class, see `add_tool_class`.
+    pub fn add_native_tool_class<C>(&self)
+    where C: NativeClassInit + NativeClassMethods {
+        self.register_native_class::<C>(true)
+    }
+
+    fn register_native_class<C>(&self, tool: bool)
+    where C: NativeClassInit + NativeClassMethods {
+        unsafe extern "C" fn constructor<C: NativeClassInit>(
+            this: *mut sys::godot_object,
+            _method_data: *mut libc::c_void
+        ) -> *mut libc::c_void {
+            let val = C::init(NativeInstanceHeader { this });
+            alloc_boxed(C::UserData::new(val)) as *mut _
+        }
+
+        unsafe extern "C" fn destructor<C: NativeClass>(
+            _this: *mut sys::godot_object,
+            _method_data: *mut libc::c_void,
+            user_data: *mut libc::c_void
+        ) {
+            free_boxed(user_data as *mut C::UserData)
+        }
+
+        let builder = self.register_class_or_report::<C>(ClassDescriptor {
+            name: C::class_name(),
+            base_class: C::base_class(),
+            constructor: Some(constructor::<C>),
+            destructor: Some(destructor::<C>),
+        }, tool);
+
+        C::register_properties(&builder);
+        C::register_methods(&builder);
+    }
+
+    /// Registers a callback invoked once the nativescript init callback returns, with
+    /// every class registered by the library.
+    ///
+    /// This is the place to resolve references between classes, build tables keyed
+    /// by class name or validate the configuration. The callbacks run in the order
+    /// they were registered, a callback can register more callbacks which run after
+    /// it.
+    ///
+    /// ```ignore
+    /// fn init(handle: InitHandle) {
+    ///     handle.add_native_class::<Player>();
+    ///     handle.add_native_class::<Enemy>();
+    ///     handle.on_all_classes_registered(|registry| {
+    ///         for class in registry.subclasses_of("KinematicBody2D") {
+    ///             godot_print!("{} can move", class.name);
+    ///         }
+    ///     });
+    /// }
+    /// ```
+    pub fn on_all_classes_registered<F>(&self, callback: F)
+    where F: FnOnce(&Registry) + Send + 'static {
+        if let Ok(mut callbacks) = REGISTERED_CALLBACKS.lock() {
+            callbacks.push(Box::new(callback));
+        }
+    }
+}
+
+/// The reason a class or a member can't be registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegisterError {
+    /// The name of a class or a member is empty.
+    EmptyName,
+    /// The name contains a NUL character, which the engine can't represent.
+    InteriorNul { name: String },
+    /// The library already registered a class with this name.
+    DuplicateClass { name: String },
+    /// The base class is neither a class of the engine nor a class registered by the
+    /// library.
+    UnknownBaseClass { class: String, base_class: String },
+    /// A class is registered on first use, and the engine didn't call
+    /// `godot_nativescript_init`.
+    NotInitialized,
+    /// A class is registered on first use, from another thread than the main one.
+    NotMainThread,
+    /// The class of the member failed to register.
+    ClassNotRegistered { class: String },
+    /// The class already has a member of this kind with this name. The members of
+    /// the base classes, see `add_base_members`, can be registered again.
+    DuplicateMember { class: String, name: String },
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegisterError::EmptyName => write!(f, "the name is empty"),
+            RegisterError::InteriorNul { ref name } => write!(f, "the name {:?} contains a NUL character", name),
+            RegisterError::DuplicateClass { ref name } => write!(f, "the class {} is already registered", name),
+            RegisterError::UnknownBaseClass { ref class, ref base_class } => {
+                write!(f, "the base class {} of {} doesn't exist", base_class, class)
+            }
+            RegisterError::NotInitialized => write!(f, "godot_nativescript_init wasn't called"),
+            RegisterError::NotMainThread => write!(f, "the class can only be registered from the main thread"),
+            RegisterError::ClassNotRegistered { ref class } => write!(f, "the class {} isn't registered", class),
+            RegisterError::DuplicateMember { ref class, ref name } => {
+                write!(f, "the class {} already has a member named {}", class, name)
+            }
+        }
+    }
+}
+
+impl Error for RegisterError {}
+
+fn c_name(name: &str) -> Result<CString, RegisterError> {
+    if name.is_empty() {
+        return Err(RegisterError::EmptyName);
+    }
+    CString::new(name).map_err(|_| RegisterError::InteriorNul { name: name.to_string() })
+}
+
+// Like `_ready` or `body_entered`: lowercase words separated by underscores, with
+// leading underscores for the virtual methods.
+#[cfg_attr(not(feature = "check_names"), allow(dead_code))]
+fn is_snake_case(name: &str) -> bool {
+    let name = name.trim_start_matches('_');
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && !name.ends_with('_')
+        && !name.contains("__")
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+// Every class of the engine inherits `Object::get_class`, the lookup of a method
+// fails for the unknown classes only.
+fn is_engine_class(name: &CString) -> bool {
+    unsafe {
+        let method = (get_api().godot_method_bind_get_method)(name.as_ptr(), b"get_class\0".as_ptr() as *const _);
+        !method.is_null()
+    }
+}
+
+/// Calls the init callback with the handle given to `godot_nativescript_init`,
+/// called by `godot_nativescript_init!`. The callback can't choose the lifetime of
+/// the handle, so it can't keep it after returning.
+#[doc(hidden)]
+pub unsafe fn run_nativescript_init<F>(handle: *mut libc::c_void, callback: F)
+where F: for<'a> FnOnce(InitHandle<'a>) {
+    if !handle.is_null() {
+        let library_path = (*(handle as *const GodotString)).to_string();
+        if let Ok(mut init) = NATIVESCRIPT_INIT.lock() {
+            *init = Some(NativeScriptInit { library_path, main_thread: thread::current().id() });
+        }
+        on_terminate(|_| {
+            if let Ok(mut init) = NATIVESCRIPT_INIT.lock() {
+                *init = None;
+            }
+        });
+    }
+    callback(InitHandle::new(handle));
+}
+
+// What `register_after_init` needs from `godot_nativescript_init`, which the engine
+// calls on the main thread.
+struct NativeScriptInit {
+    library_path: String,
+    main_thread: ThreadId,
+}
+
+static NATIVESCRIPT_INIT: Mutex<Option<NativeScriptInit>> = Mutex::new(None);
+
+/// Registers classes after `godot_nativescript_init` returned, for the helpers of
+/// the crate creating their classes on first use.
+///
+/// The handle given to `godot_nativescript_init` points to the path of the
+/// library, which NativeScript 1.0 uses to find the classes of the library when a
+/// class or a member is registered. Registering later with a handle pointing to the
+/// same path is valid from the main thread, until the library is terminated, as
+/// long as no script of the class was instanced before. The classes are dropped
+/// with the others when the library is unloaded.
+///
+/// Returns `NotMainThread` when called from another thread than the one which ran
+/// `godot_nativescript_init`.
+pub(crate) fn register_after_init<F, R>(register: F) -> Result<R, RegisterError>
+where F: for<'a> FnOnce(&InitHandle<'a>) -> R {
+    let path = {
+        let init = NATIVESCRIPT_INIT.lock().map_err(|_| RegisterError::NotInitialized)?;
+        let init = init.as_ref().ok_or(RegisterError::NotInitialized)?;
+        if thread::current().id() != init.main_thread {
+            return Err(RegisterError::NotMainThread);
+        }
+        GodotString::from_str(&init.library_path)
+    };
+    let handle = unsafe { InitHandle::new(&path.0 as *const _ as *mut libc::c_void) };
+    Ok(register(&handle))
+}
+
+static REGISTERED_CALLBACKS: Mutex<Vec<Box<dyn FnOnce(&Registry) + Send>>> = Mutex::new(Vec::new());
+
+/// Runs the callbacks registered with `InitHandle::on_all_classes_registered`, called
+/// by `godot_nativescript_init!`.
+#[doc(hidden)]
+pub fn all_classes_registered() {
+    let mut registry = None;
+    loop {
+        let callbacks = match REGISTERED_CALLBACKS.lock() {
+            Ok(mut callbacks) => mem::replace(&mut *callbacks, Vec::new()),
+            Err(_) => return,
+        };
+        if callbacks.is_empty() {
+            return;
+        }
+
+        let registry = registry.get_or_insert_with(|| Registry { classes: info::registered_classes() });
+        for callback in callbacks {
+            callback(registry);
+        }
+    }
+}
+
+/// The classes registered by the library, see `InitHandle::on_all_classes_registered`.
+#[derive(Clone, Debug)]
+pub struct Registry {
+    classes: Vec<RegisteredClass>,
+}
+
+/// A class registered by the library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegisteredClass {
+    pub name: String,
+    pub base_class: String,
+    /// `true` for the classes registered with `add_tool_class`.
+    pub tool: bool,
+    pub methods: Vec<String>,
+    pub properties: Vec<String>,
+    pub signals: Vec<String>,
+}
+
+impl Registry {
+    /// Returns the classes in the order they were registered.
+    pub fn classes(&self) -> &[RegisteredClass] {
+        &self.classes
+    }
+
+    /// Returns the class registered under the given name.
+    pub fn get(&self, name: &str) -> Option<&RegisteredClass> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    /// Returns `true` if a class is registered under the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns the classes extending `base_class` directly or through other classes
+    /// of the library.
+    pub fn subclasses_of<'a>(&'a self, base_class: &'a str) -> impl Iterator<Item = &'a RegisteredClass> + 'a {
+        self.classes.iter().filter(move |class| self.extends(class, base_class))
+    }
+
+    fn extends(&self, class: &RegisteredClass, base_class: &str) -> bool {
+        let mut current = class;
+        // The engine rejects inheritance cycles, the bound only guards the loop.
+        for _ in 0..self.classes.len() {
+            if current.base_class == base_class {
+                return true;
+            }
+            match self.get(&current.base_class) {
+                Some(base) => current = base,
+                None => return false,
+            }
+        }
+        false
+    }
 }
 
 pub type ScriptMethodFn = unsafe extern "C" fn(
@@ -95,164 +518,1186 @@ pub type ScriptMethodFn = unsafe extern "C" fn(
     *mut *mut sys::godot_variant
 ) -> sys::godot_variant;
 
-pub type ScriptConstructorFn = unsafe extern "C" fn(
-    *mut sys::godot_object,
-    *mut libc::c_void
-) -> *mut libc::c_void;
+pub type ScriptConstructorFn = unsafe extern "C" fn(
+    *mut sys::godot_object,
+    *mut libc::c_void
+) -> *mut libc::c_void;
+
+pub type ScriptDestructorFn = unsafe extern "C" fn(
+    *mut sys::godot_object,
+    *mut libc::c_void,
+    *mut libc::c_void
+) -> ();
+
+/// How a method can be called through the high-level multiplayer API (`rpc` and
+/// `rpc_id`).
+///
+/// `MasterSync` and `PuppetSync` are only supported by engines providing the
+/// corresponding rpc modes in their GDNative headers. With the headers these
+/// bindings are generated from, a method using one of them is registered without
+/// rpc and an error is printed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RpcMode {
+    Disabled,
+    Remote,
+    Sync,
+    Master,
+    Slave,
+    MasterSync,
+    PuppetSync,
+}
+
+impl RpcMode {
+    /// Returns the rpc mode expected by the engine, or `None` if the engine doesn't
+    /// support this mode.
+    pub fn to_sys(&self) -> Option<sys::godot_method_rpc_mode> {
+        use sys::godot_method_rpc_mode::*;
+        match *self {
+            RpcMode::Disabled => Some(GODOT_METHOD_RPC_MODE_DISABLED),
+            RpcMode::Remote => Some(GODOT_METHOD_RPC_MODE_REMOTE),
+            RpcMode::Sync => Some(GODOT_METHOD_RPC_MODE_SYNC),
+            RpcMode::Master => Some(GODOT_METHOD_RPC_MODE_MASTER),
+            RpcMode::Slave => Some(GODOT_METHOD_RPC_MODE_SLAVE),
+            RpcMode::MasterSync | RpcMode::PuppetSync => None,
+        }
+    }
+}
+
+/// How the calls of an rpc method are sent over the network.
+///
+/// Godot 3 sends every rpc call reliably, other modes are reported with a warning
+/// when the method is registered and the call is still sent reliably.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RpcTransferMode {
+    Reliable,
+    Unreliable,
+    UnreliableOrdered,
+}
+
+/// The registration options of a method. Build them with `Default` or from an
+/// `RpcMode`, so that code keeps compiling when options are added:
+///
+/// ```ignore
+/// builder.add_rpc_method(
+///     "sync_position",
+///     sync_position,
+///     ScriptMethodAttributes::from(RpcMode::Remote)
+///         .with_transfer_mode(RpcTransferMode::UnreliableOrdered)
+///         .with_channel(1),
+/// );
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScriptMethodAttributes {
+    pub rpc_mode: RpcMode,
+    pub rpc_transfer_mode: RpcTransferMode,
+    /// The channel the rpc calls are sent on, 0 being the default channel. Godot 3
+    /// only has the default channel.
+    pub rpc_channel: u32,
+}
+
+impl ScriptMethodAttributes {
+    pub fn with_transfer_mode(mut self, transfer_mode: RpcTransferMode) -> Self {
+        self.rpc_transfer_mode = transfer_mode;
+        self
+    }
+
+    pub fn with_channel(mut self, channel: u32) -> Self {
+        self.rpc_channel = channel;
+        self
+    }
+}
+
+impl Default for ScriptMethodAttributes {
+    fn default() -> Self {
+        ScriptMethodAttributes::from(RpcMode::Disabled)
+    }
+}
+
+impl From<RpcMode> for ScriptMethodAttributes {
+    fn from(rpc_mode: RpcMode) -> Self {
+        ScriptMethodAttributes {
+            rpc_mode,
+            rpc_transfer_mode: RpcTransferMode::Reliable,
+            rpc_channel: 0,
+        }
+    }
+}
+
+pub struct ScriptMethod<'l> {
+    pub name: &'l str,
+    pub method_ptr: Option<ScriptMethodFn>,
+    pub attributes: ScriptMethodAttributes,
+    /// Shown in the class reference of the editor, see
+    /// `ClassBuilder::set_class_documentation`.
+    pub documentation: Option<&'l str>,
+
+    pub method_data: *mut libc::c_void,
+    pub free_func: Option<unsafe extern "C" fn(*mut libc::c_void) -> ()>,
+}
+
+/// The name of an argument of a method, and its default value for the arguments the
+/// callers can omit, see `ClassBuilder::set_method_arguments`.
+#[derive(Clone, Debug)]
+pub struct MethodArgument<'l> {
+    pub name: &'l str,
+    pub default: Option<Variant>,
+}
+
+impl<'l> MethodArgument<'l> {
+    /// An argument the callers must pass.
+    pub fn new(name: &'l str) -> Self {
+        MethodArgument { name, default: None }
+    }
+
+    pub fn with_default<T: ToVariant>(mut self, default: T) -> Self {
+        self.default = Some(default.to_variant());
+        self
+    }
+}
+
+// The arguments with a default value after the last one without.
+fn trailing_defaults<'a, 'l>(arguments: &'a [MethodArgument<'l>]) -> &'a [MethodArgument<'l>] {
+    let num_required = arguments.iter().rposition(|argument| argument.default.is_none()).map_or(0, |idx| idx + 1);
+    &arguments[num_required..]
+}
+
+pub struct ClassDescriptor<'l> {
+    pub name: &'l str,
+    pub base_class: &'l str,
+    /// Creates the user data of a new instance. The classes without constructor
+    /// are abstract: instancing them is reported with `godot_error!`, and leaves
+    /// an object without a script instance.
+    pub constructor: Option<ScriptConstructorFn>,
+    pub destructor: Option<ScriptDestructorFn>,
+}
+
+/// A class that can be registered with `InitHandle::add_native_class`.
+///
+/// Usually implemented with `#[derive(NativeClass)]`.
+pub trait NativeClassInit: NativeClass + Sized {
+    /// The name of the engine class the class inherits from.
+    fn base_class() -> &'static str;
+
+    /// Creates the rust part of a new instance.
+    fn init(header: NativeInstanceHeader) -> Self;
+
+    fn register_properties(_builder: &ClassBuilder<'_, Self>) {}
+}
+
+/// The methods of a class registered with `InitHandle::add_native_class`.
+///
+/// Usually implemented with the `#[methods]` attribute.
+pub trait NativeClassMethods: NativeClass + Sized {
+    fn register_methods(builder: &ClassBuilder<'_, Self>);
+}
+
+/// An abstract class of the library, registered with `InitHandle::add_base_class`.
+///
+/// The engine calls the methods a script inherits with the instance of the script,
+/// so an abstract class can't register methods reading its own instances. Its
+/// state is stored in a field of each class extending it instead, and it
+/// registers its members on each of them:
+///
+/// ```ignore
+/// struct Enemy {
+///     health: i64,
+/// }
+///
+/// impl BaseClass for Enemy {
+///     fn class_name() -> &'static str { "Enemy" }
+///     fn base_class() -> &'static str { "KinematicBody2D" }
+///
+///     fn register_members<C: Extends<Self>>(builder: &ClassBuilder<'_, C>) {
+///         builder.add_typed_method("take_damage", |this: &mut C, _owner: &mut KinematicBody2D, (amount,): (i64,)| {
+///             this.base_mut().health -= amount;
+///         });
+///     }
+/// }
+///
+/// handle.add_base_class::<Enemy>();
+/// handle.add_native_class::<Slime>();
+/// ```
+///
+/// `#[derive(NativeClass)]` extends the class of the field marked `#[base]`.
+pub trait BaseClass: Sized + 'static {
+    /// The name the class is registered with.
+    fn class_name() -> &'static str;
+
+    /// The name of the class it inherits from.
+    fn base_class() -> &'static str;
+
+    /// Registers the methods, properties and signals of the class on `builder`, the
+    /// builder of a class extending it.
+    fn register_members<C: Extends<Self>>(builder: &ClassBuilder<'_, C>);
+}
+
+/// A class extending the abstract class `B`, which it stores.
+pub trait Extends<B: BaseClass>: NativeClass {
+    fn base(&self) -> &B;
+
+    fn base_mut(&mut self) -> &mut B;
+}
+
+/// Registers the members of a class, returned by `InitHandle::add_class`. Like the
+/// `InitHandle`, it can't outlive the init callback.
+pub struct ClassBuilder<'a, C: NativeClass> {
+    #[doc(hidden)]
+    pub init_handle: *mut libc::c_void,
+    class_name: CString,
+    // `false` for the builders of the classes that failed to register.
+    registered: bool,
+    // The registered members, `true` for the members of the base classes which the
+    // class can register again.
+    members: RefCell<HashMap<(MemberKind, String), bool>>,
+    registering_base: Cell<bool>,
+    _marker: PhantomData<C>,
+    _scope: PhantomData<&'a ()>,
+}
+
+impl<'a, C: NativeClass> ClassBuilder<'a, C> {
+
+    pub fn add_method_advanced(&self, method: ScriptMethod) -> &Self {
+        let name = method.name;
+        let result = self.try_add_method_advanced(method);
+        self.report_error(name, result).unwrap_or(self)
+    }
+
+    /// Registers a method, or returns why it can't be registered. The data of the
+    /// method is freed on errors.
+    pub fn try_add_method_advanced(&self, method: ScriptMethod) -> Result<&Self, RegisterError> {
+        let method_name = match self.new_member_c_name(MemberKind::Method, method.name) {
+            Ok(name) => name,
+            Err(err) => {
+                // The engine would have freed the data with the class.
+                if let Some(free_func) = method.free_func {
+                    unsafe { free_func(method.method_data) };
+                }
+                return Err(err);
+            }
+        };
+        self.check_name("method", method.name);
+        let rpc_type = match method.attributes.rpc_mode.to_sys() {
+            Some(rpc_type) => rpc_type,
+            None => {
+                godot_error!(
+                    "{}: rpc mode {:?} is not supported by the engine, the method is registered without rpc",
+                    self.member_name(method.name), method.attributes.rpc_mode
+                );
+                sys::godot_method_rpc_mode::GODOT_METHOD_RPC_MODE_DISABLED
+            }
+        };
+        self.check_rpc_options(method.name, &method.attributes);
+        let attr = sys::godot_method_attributes { rpc_type };
+
+        let method_desc = sys::godot_instance_method {
+            method: method.method_ptr,
+            method_data: method.method_data,
+            free_func: method.free_func
+        };
+
+        info::register_member(&self.class_name.to_string_lossy(), MemberKind::Method, method.name);
+
+        timing::time(TimingKind::Method, || self.member_name(method.name), || unsafe {
+            (get_api().godot_nativescript_register_method)(
+                self.init_handle,
+                self.class_name.as_ptr() as *const _,
+                method_name.as_ptr() as *const _,
+                attr,
+                method_desc
+            );
+        });
+
+        if let Some(documentation) = method.documentation {
+            self.set_method_documentation(method.name, documentation);
+        }
+
+        Ok(self)
+    }
+
+    // The engine only takes the rpc mode, the other options are reported instead of
+    // being dropped silently.
+    fn check_rpc_options(&self, name: &str, attributes: &ScriptMethodAttributes) {
+        if attributes.rpc_transfer_mode == RpcTransferMode::Reliable && attributes.rpc_channel == 0 {
+            return;
+        }
+
+        if attributes.rpc_mode == RpcMode::Disabled {
+            godot_warn!(
+                "{}: the method is not an rpc, its transfer mode and channel are ignored",
+                self.member_name(name)
+            );
+        } else {
+            godot_warn!(
+                "{}: the engine doesn't support rpc transfer mode {:?} and channel {}, \
+                 the calls are sent reliably on the default channel",
+                self.member_name(name), attributes.rpc_transfer_mode, attributes.rpc_channel
+            );
+        }
+    }
+
+    pub fn add_method(&self, name: &str, method: ScriptMethodFn) -> &Self {
+        let result = self.try_add_method(name, method);
+        self.report_error(name, result).unwrap_or(self)
+    }
+
+    /// Registers a method, or returns why it can't be registered, see `add_method`.
+    pub fn try_add_method(&self, name: &str, method: ScriptMethodFn) -> Result<&Self, RegisterError> {
+        self.try_add_method_advanced(
+            ScriptMethod {
+                name: name,
+                method_ptr: Some(method),
+                attributes: ScriptMethodAttributes::default(),
+                documentation: None,
+                method_data: ptr::null_mut(),
+                free_func: None
+            },
+        )
+    }
+
+    /// Registers a method that can be called through the multiplayer API. Takes an
+    /// `RpcMode` or `ScriptMethodAttributes` with more rpc options.
+    pub fn add_rpc_method<A>(&self, name: &str, method: ScriptMethodFn, attributes: A) -> &Self
+    where A: Into<ScriptMethodAttributes> {
+        self.add_method_advanced(
+            ScriptMethod {
+                name: name,
+                method_ptr: Some(method),
+                attributes: attributes.into(),
+                documentation: None,
+                method_data: ptr::null_mut(),
+                free_func: None
+            },
+        )
+    }
+
+    /// Registers a method implemented by a closure.
+    ///
+    /// The closure receives the instance, the object it is attached to and the
+    /// arguments as a tuple of `GodotType`s. The number and the types of the arguments
+    /// are checked before calling the closure: on a mismatch an error is printed and
+    /// the method returns `null`.
+    ///
+    /// ```ignore
+    /// class.add_typed_method("move_by", |this: &mut Player, owner: &mut Spatial, (x, z): (f64, f64)| {
+    ///     owner.translate(Vector3::new(x as f32, 0.0, z as f32));
+    ///     this.moves += 1;
+    ///     this.moves
+    /// });
+    /// ```
+    ///
+    /// The last arguments of type `Option` can be omitted by the callers, they are
+    /// then `None` like when the caller passes `null`. An `Option` followed by a
+    /// required argument is reported with an error when registering, and must be
+    /// passed.
+    pub fn add_typed_method<B, A, R, F>(&self, name: &str, method: F) -> &Self
+    where
+        B: GodotObject,
+        A: MethodArgs,
+        R: ReturnValue,
+        F: Fn(&mut C, &mut B, A) -> R,
+    {
+        self.add_typed_method_with_arguments(name, &[], method)
+    }
+
+    /// Registers a method implemented by a closure like `add_typed_method`, with the
+    /// names of the arguments and the default values of the last ones, see
+    /// `set_method_arguments`:
+    ///
+    /// ```ignore
+    /// class.add_typed_method_with_arguments(
+    ///     "move_by",
+    ///     &[MethodArgument::new("x"), MethodArgument::new("z").with_default(0.0)],
+    ///     |this: &mut Player, owner: &mut Spatial, (x, z): (f64, f64)| {
+    ///         owner.translate(Vector3::new(x as f32, 0.0, z as f32));
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// The callers can omit the arguments with a default value, which is converted
+    /// like a value passed by the caller. `arguments` names all the arguments or is
+    /// empty.
+    pub fn add_typed_method_with_arguments<B, A, R, F>(&self, name: &str, arguments: &[MethodArgument], method: F) -> &Self
+    where
+        B: GodotObject,
+        A: MethodArgs,
+        R: ReturnValue,
+        F: Fn(&mut C, &mut B, A) -> R,
+    {
+        use std::slice;
+
+        struct TypedMethod<F> {
+            name: String,
+            func: F,
+            // The default values of the last arguments.
+            defaults: Vec<Variant>,
+            // The arguments before the ones with a default value or of type `Option`.
+            num_required: usize,
+        }
+
+        extern "C" fn invoke<C, B, A, R, F>(
+            this: *mut sys::godot_object,
+            method: *mut libc::c_void,
+            class: *mut libc::c_void,
+            num_args: libc::c_int,
+            args: *mut *mut sys::godot_variant
+        ) -> sys::godot_variant
+            where C: NativeClass,
+                  B: GodotObject,
+                  A: MethodArgs,
+                  R: ReturnValue,
+                  F: Fn(&mut C, &mut B, A) -> R,
+        {
+            unsafe {
+                let method = &*(method as *mut TypedMethod<F>);
+
+                let args: Vec<&Variant> = if args.is_null() {
+                    Vec::new()
+                } else {
+                    slice::from_raw_parts(args, num_args as usize)
+                        .iter()
+                        .map(|&arg| Variant::cast_ref(arg))
+                        .collect()
+                };
+
+                let num_required = method.num_required;
+                let mut args = args;
+                if args.len() < num_required || args.len() > A::count() {
+                    if num_required < A::count() {
+                        godot_error!(
+                            "{}: incorrect number of parameters: expected {} to {} but got {}",
+                            method.name, num_required, A::count(), args.len()
+                        );
+                    } else {
+                        godot_error!(
+                            "{}: incorrect number of parameters: expected {} but got {}",
+                            method.name, A::count(), args.len()
+                        );
+                    }
+                    return Variant::new().forget();
+                }
+                // The omitted arguments take their default value, or nil for the
+                // `Option`s without one.
+                let nil = Variant::new();
+                let first_default = A::count() - method.defaults.len();
+                for idx in args.len()..A::count() {
+                    if idx >= first_default {
+                        args.push(&method.defaults[idx - first_default]);
+                    } else {
+                        args.push(&nil);
+                    }
+                }
+
+                let typed_args = match A::from_args(&args) {
+                    Ok(typed_args) => typed_args,
+                    Err((idx, err)) => {
+                        godot_error!("{}: incorrect argument {}: {}", method.name, idx, err);
+                        return Variant::new().forget();
+                    }
+                };
+
+                let mut owner = B::from_sys(this);
+
+                // The return value is converted inside of the closure since it may
+                // borrow from the instance.
+                let ret = with_instance_mut::<C, _, _>(class, &method.name, |rust_ty| {
+                    catch_panic(&method.name, || {
+                        method_return::<C, R>(&method.name, &(method.func)(rust_ty, &mut owner, typed_args))
+                    })
+                });
+
+                match ret {
+                    Some(Some(ret)) => ret.forget(),
+                    _ => Variant::new().forget(),
+                }
+            }
+        }
+
+        extern "C" fn free_func<F>(data: *mut libc::c_void) {
+            unsafe {
+                free_boxed(data as *mut TypedMethod<F>);
+            }
+        }
+
+        if !arguments.is_empty() && arguments.len() != A::count() {
+            godot_error!(
+                "{}: {} arguments are described but the method takes {}, they are ignored",
+                self.member_name(name), arguments.len(), A::count()
+            );
+        }
+        let arguments = if arguments.len() == A::count() { arguments } else { &[] };
+
+        let defaults: Vec<Variant> = trailing_defaults(arguments).iter()
+            .filter_map(|argument| argument.default.clone())
+            .collect();
+        let first_default = A::count() - defaults.len();
+        let mut num_required = first_default;
+        while num_required > 0 && A::is_optional(num_required - 1) {
+            num_required -= 1;
+        }
+        for idx in (0..num_required).filter(|&idx| A::is_optional(idx)) {
+            godot_error!(
+                "{}: argument {} is an `Option` followed by required arguments, the callers can't omit it",
+                self.member_name(name), idx
+            );
+        }
+        let method = TypedMethod {
+            name: self.member_name(name),
+            func: method,
+            defaults,
+            num_required,
+        };
+
+        self.add_method_advanced(ScriptMethod {
+            name,
+            method_ptr: Some(invoke::<C, B, A, R, F>),
+            attributes: ScriptMethodAttributes::default(),
+            documentation: None,
+            method_data: alloc_boxed(method) as *mut _,
+            free_func: Some(free_func::<F>),
+        });
+        if !arguments.is_empty() {
+            self.set_method_arguments(name, arguments);
+        }
+        self
+    }
+
+    pub fn add_property<T, S, G, K>(&self, property: Property<T, S, G>) -> &Self
+    where
+        T: GodotType,
+        S: PropertySetter<C, T>,
+        G: PropertyGetter<C, T, K>,
+    {
+        let name = property.name;
+        let result = self.try_add_property(property);
+        self.report_error(name, result).unwrap_or(self)
+    }
+
+    /// Registers a property, or returns why it can't be registered.
+    pub fn try_add_property<T, S, G, K>(&self, property: Property<T, S, G>) -> Result<&Self, RegisterError>
+    where
+        T: GodotType,
+        S: PropertySetter<C, T>,
+        G: PropertyGetter<C, T, K>,
+    {
+        let name = property.name;
+        let path = self.new_member_c_name(MemberKind::Property, name)?;
+        self.check_name("property", name);
+        let documentation = property.documentation;
+        info::register_member(&self.class_name.to_string_lossy(), MemberKind::Property, name);
+
+        timing::time(TimingKind::Property, || self.member_name(name), || unsafe {
+            let hint_string = property.hint.to_hint_string();
+
+            let default: Variant = property.default.to_variant();
+            let ty = default.get_type();
+
+            let mut attr = sys::godot_property_attributes {
+                rset_type: sys::godot_method_rpc_mode::GODOT_METHOD_RPC_MODE_DISABLED, // TODO:
+                type_: mem::transmute(ty),
+                hint: property.hint.to_sys(),
+                hint_string: hint_string.to_sys(),
+                usage: property.usage.to_sys(),
+                default_value: default.to_sys(),
+            };
+
+            let set = property.setter.as_godot_function(self.member_name(name));
+            let get = property.getter.as_godot_function(self.member_name(name));
+
+            (get_api().godot_nativescript_register_property)(
+                self.init_handle,
+                self.class_name.as_ptr(),
+                path.as_ptr() as *const _,
+                &mut attr, set, get
+            );
+        });
+
+        if let Some(documentation) = documentation {
+            self.set_member_documentation(name, documentation, |api| api.set_property_documentation);
+        }
+
+        Ok(self)
+    }
+
+    /// Starts the registration of a property, for when only a few of the fields of
+    /// `Property` differ from their defaults. The property is registered by `done`:
+    ///
+    /// ```ignore
+    /// builder
+    ///     .property::<f64>("speed")
+    ///     .default(5.0)
+    ///     .hint(range(0.0..10.0))
+    ///     .with_getter(|this: &mut Player| this.speed)
+    ///     .with_setter(|this: &mut Player, speed| this.speed = speed)
+    ///     .done();
+    /// ```
+    ///
+    /// Without accessors the property is neither readable nor writable. The default
+    /// value is `T::default()` and the usage `PropertyUsage::DEFAULT`.
+    pub fn property<'l, T>(&self, name: &'l str) -> PropertyBuilder<'_, 'l, C, T, (), ()>
+    where T: GodotType + Default {
+        PropertyBuilder {
+            class: self,
+            property: Property {
+                name,
+                setter: (),
+                getter: (),
+                default: T::default(),
+                hint: PropertyHint::None,
+                usage: PropertyUsage::DEFAULT,
+                documentation: None,
+            },
+        }
+    }
+
+    /// Registers a property backed by a field of the instance, without writing the
+    /// getter and the setter:
+    ///
+    /// ```ignore
+    /// builder.add_field_property("health", |this: &Player| &this.health, |this: &mut Player| &mut this.health);
+    /// ```
+    ///
+    /// The default value is `T::default()`, `property(name).with_field(..)` also sets
+    /// the hint, the default or the usage.
+    pub fn add_field_property<T, F, FM>(&self, name: &str, field: F, field_mut: FM) -> &Self
+    where
+        T: GodotType + Default,
+        F: Fn(&C) -> &T,
+        FM: Fn(&mut C) -> &mut T,
+    {
+        self.property(name).with_field(field, field_mut).done();
+        self
+    }
+
+    /// Starts a foldable group in the inspector, named `name`, holding the properties
+    /// registered after it whose names start with `prefix`. The prefix is removed
+    /// from their names in the inspector:
+    ///
+    /// ```ignore
+    /// builder.add_property_group("Movement", "movement_");
+    /// builder.add_field_property("movement_speed", |this: &Player| &this.speed, |this: &mut Player| &mut this.speed);
+    /// builder.add_field_property("movement_jump_height", |this: &Player| &this.jump, |this: &mut Player| &mut this.jump);
+    /// ```
+    ///
+    /// The group ends at the first property without the prefix, or at the next group
+    /// or category. With an empty prefix it holds all the properties up to the next
+    /// group or category.
+    pub fn add_property_group(&self, name: &str, prefix: &str) -> &Self {
+        self.add_inspector_marker(name, prefix, PropertyUsage::GROUP);
+        self
+    }
+
+    /// Inserts a category header in the inspector, like the headers of the base
+    /// classes, above the properties registered after it. Used by the
+    /// `inspector_category` attribute of `#[derive(NativeClass)]`.
+    pub fn add_property_category(&self, name: &str) -> &Self {
+        self.add_inspector_marker(name, "", PropertyUsage::CATEGORY);
+        self
+    }
+
+    /// Registers the members of the abstract class `B` the class extends, see
+    /// `BaseClass`. Its methods are registered again by the class, which overrides
+    /// them by registering the same names after.
+    pub fn add_base_members<B>(&self) -> &Self
+    where B: BaseClass, C: Extends<B> {
+        let registering_base = self.registering_base.replace(true);
+        B::register_members(self);
+        self.registering_base.set(registering_base);
+        self
+    }
+
+    // Groups and categories are properties without a type, value or accessors. They
+    // aren't listed in the `LibraryInfo` of the class.
+    fn add_inspector_marker(&self, name: &str, hint_string: &str, usage: PropertyUsage) {
+        let path = match self.member_c_name(name) {
+            Some(path) => path,
+            None => return,
+        };
+
+        unsafe {
+            let hint_string = GodotString::from_str(hint_string);
+            let default = Variant::new();
+            let mut attr = sys::godot_property_attributes {
+                rset_type: sys::godot_method_rpc_mode::GODOT_METHOD_RPC_MODE_DISABLED,
+                type_: mem::transmute(VariantType::Nil),
+                hint: PropertyHint::None.to_sys(),
+                hint_string: hint_string.to_sys(),
+                usage: usage.to_sys(),
+                default_value: default.to_sys(),
+            };
+
+            let set = <() as PropertySetter<C, ()>>::as_godot_function((), self.member_name(name));
+            let get = <() as PropertyGetter<C, (), SharedAccess>>::as_godot_function((), self.member_name(name));
+
+            (get_api().godot_nativescript_register_property)(
+                self.init_handle,
+                self.class_name.as_ptr(),
+                path.as_ptr() as *const _,
+                &mut attr, set, get
+            );
+        }
+    }
+
+    pub fn add_signal(&self, signal: Signal) -> &Self {
+        let name = signal.name;
+        let result = self.try_add_signal(signal);
+        self.report_error(name, result).unwrap_or(self)
+    }
+
+    /// Registers a signal, or returns why it can't be registered.
+    pub fn try_add_signal(&self, signal: Signal) -> Result<&Self, RegisterError> {
+        self.new_member_c_name(MemberKind::Signal, signal.name)?;
+        self.check_name("signal", signal.name);
+        info::register_member(&self.class_name.to_string_lossy(), MemberKind::Signal, signal.name);
+
+        timing::time(TimingKind::Signal, || self.member_name(signal.name), || unsafe {
+            let name = GodotString::from_str(signal.name);
+
+            // The strings must outlive the registration call.
+            let arg_names: Vec<GodotString> = signal.args.iter()
+                .map(|arg| GodotString::from_str(arg.name))
+                .collect();
+            let hint_strings: Vec<GodotString> = signal.args.iter()
+                .map(|arg| arg.hint.to_hint_string())
+                .collect();
+
+            let mut args: Vec<sys::godot_signal_argument> = signal.args.iter()
+                .zip(arg_names.iter().zip(hint_strings.iter()))
+                .map(|(arg, (name, hint_string))| sys::godot_signal_argument {
+                    name: name.to_sys(),
+                    type_: arg.default.get_type() as i32 as sys::godot_int,
+                    hint: arg.hint.to_sys(),
+                    hint_string: hint_string.to_sys(),
+                    usage: arg.usage.to_sys(),
+                    default_value: arg.default.to_sys(),
+                })
+                .collect();
+
+            // Default arguments apply to the trailing arguments, stop at the last
+            // argument without a default value.
+            let mut default_args: Vec<sys::godot_variant> = signal.args.iter()
+                .rev()
+                .take_while(|arg| !arg.default.is_nil())
+                .map(|arg| arg.default.to_sys())
+                .collect();
+            default_args.reverse();
+
+            (get_api().godot_nativescript_register_signal)(
+                self.init_handle,
+                self.class_name.as_ptr(),
+                &sys::godot_signal {
+                    name: name.to_sys(),
+                    num_args: args.len() as libc::c_int,
+                    args: args.as_mut_ptr(),
+                    num_default_args: default_args.len() as libc::c_int,
+                    default_args: default_args.as_mut_ptr(),
+                }
+            );
+        });
+
+        if let Some(documentation) = signal.documentation {
+            self.set_member_documentation(signal.name, documentation, |api| api.set_signal_documentation);
+        }
+
+        Ok(self)
+    }
+
+    /// Exposes a constant to scripts, for example `MAX_SPEED`.
+    ///
+    /// NativeScript has no class-level constants in the engine versions supported
+    /// by these bindings, so the constant is registered as a read-only property of
+    /// the instances (`player.MAX_SPEED` rather than `Player.MAX_SPEED`). Assigning
+    /// it prints an error. The property is only visible to scripts, with the usage
+    /// `PropertyUsage::SCRIPT_VARIABLE`: it isn't stored in scenes, so changing the
+    /// value in rust applies to the scenes saved before, and it isn't shown in the
+    /// inspector, which can't display a property without letting the user edit it.
+    pub fn add_constant(&self, name: &str, value: Variant) -> &Self {
+        let member_name = self.member_name(name);
+
+        self.add_property(Property {
+            name,
+            setter: move |_: &mut C, _: Variant| {
+                godot_error!("{} is a constant and can't be assigned", member_name);
+            },
+            getter: {
+                let value = value.clone();
+                move |_: &C| value.clone()
+            },
+            default: value,
+            hint: PropertyHint::None,
+            usage: PropertyUsage::SCRIPT_VARIABLE,
+            documentation: None,
+        })
+    }
+
+    /// Exposes an enum to scripts, like a GDScript named enum.
+    ///
+    /// Each value is registered as a constant (see `add_constant`), and `name` as a
+    /// constant dictionary mapping the names to the values:
+    ///
+    /// ```ignore
+    /// class.add_enum("State", &[("IDLE", 0), ("RUNNING", 1), ("JUMPING", 2)]);
+    ///
+    /// // In GDScript: `player.IDLE` or `player.State.RUNNING`.
+    /// ```
+    ///
+    /// To select a value of the enum in the inspector, use `PropertyHint::Enum` on
+    /// the property holding it.
+    pub fn add_enum(&self, name: &str, values: &[(&str, i64)]) -> &Self {
+        let mut dictionary = Dictionary::new();
+        for &(key, value) in values {
+            dictionary.set(&Variant::from_str(key), &Variant::from_i64(value));
+            self.add_constant(key, Variant::from_i64(value));
+        }
+
+        self.add_constant(name, Variant::from_dictionary(&dictionary))
+    }
 
-pub type ScriptDestructorFn = unsafe extern "C" fn(
-    *mut sys::godot_object,
-    *mut libc::c_void,
-    *mut libc::c_void
-) -> ();
+    /// Implements the `_to_string` virtual method, used by the engine when the object
+    /// is converted to a string (in `print`, `str` or the debugger).
+    ///
+    /// The engine calls the closure after the registration returned, so it can't
+    /// borrow from the registering function:
+    ///
+    /// ```compile_fail
+    /// extern crate gdnative;
+    ///
+    /// use gdnative::NativeClass;
+    /// use gdnative::init::ClassBuilder;
+    ///
+    /// fn register<C: NativeClass>(builder: &ClassBuilder<C>) {
+    ///     let name = String::from("MyClass");
+    ///     builder.add_to_string(|_| name.clone());
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn add_to_string<F>(&self, to_string: F) -> &Self
+    where F: Fn(&C) -> String + 'static
+    {
+        self.add_virtual_method("_to_string", move |class, _args| {
+            to_string(class).to_variant()
+        })
+    }
 
-pub enum RpcMode {
-    Disabled,
-    Remote,
-    Sync,
-    Mater,
-    Slave
-}
+    /// Implements the `_get_configuration_warning` virtual method of `Node`.
+    ///
+    /// A non-empty string is displayed as a warning next to the node in the scene
+    /// dock. The editor only calls this method for tool scripts. The checks of the
+    /// common cases are in `ConfigurationWarnings`, see `add_configuration_warnings`.
+    pub fn add_configuration_warning<F>(&self, warning: F) -> &Self
+    where F: Fn(&C) -> String + 'static
+    {
+        self.add_virtual_method("_get_configuration_warning", move |class, _args| {
+            warning(class).to_variant()
+        })
+    }
 
-pub struct ScriptMethodAttributes {
-    pub rpc_mode: RpcMode
-}
+    /// Implements the `get_import_options` virtual method of `EditorImportPlugin`.
+    ///
+    /// The closure receives the index of the selected preset and returns an array of
+    /// dictionaries describing the options (`name`, `default_value`, and optionally
+    /// `property_hint`, `hint_string` and `usage`).
+    pub fn add_import_options<F>(&self, options: F) -> &Self
+    where F: Fn(&C, i64) -> VariantArray + 'static
+    {
+        self.add_virtual_method("get_import_options", move |class, args| {
+            match args.get(0).and_then(|preset| i64::from_variant(preset)) {
+                Some(preset) => options(class, preset).to_variant(),
+                None => {
+                    godot_error!("get_import_options expects the preset index as argument");
+                    VariantArray::new().to_variant()
+                }
+            }
+        })
+    }
 
-pub struct ScriptMethod<'l> {
-    pub name: &'l str,
-    pub method_ptr: Option<ScriptMethodFn>,
-    pub attributes: ScriptMethodAttributes,
+    fn add_virtual_method<F>(&self, name: &str, func: F) -> &Self
+    where F: Fn(&C, &[&Variant]) -> Variant + 'static
+    {
+        use std::slice;
 
-    pub method_data: *mut libc::c_void,
-    pub free_func: Option<unsafe extern "C" fn(*mut libc::c_void) -> ()>,
-}
+        struct VirtualMethod<F> {
+            name: String,
+            func: F,
+        }
 
-pub struct ClassDescriptor<'l> {
-    pub name: &'l str,
-    pub base_class: &'l str,
-    pub constructor: Option<ScriptConstructorFn>,
-    pub destructor: Option<ScriptDestructorFn>,
-}
+        extern "C" fn invoke<C, F>(
+            _this: *mut sys::godot_object,
+            method: *mut libc::c_void,
+            class: *mut libc::c_void,
+            num_args: libc::c_int,
+            args: *mut *mut sys::godot_variant
+        ) -> sys::godot_variant
+            where C: NativeClass,
+                  F: Fn(&C, &[&Variant]) -> Variant + 'static,
+        {
+            unsafe {
+                let method = &*(method as *mut VirtualMethod<F>);
 
-pub struct ClassBuilder<C: NativeClass> {
-    #[doc(hidden)]
-    pub init_handle: *mut libc::c_void,
-    class_name: CString,
-    _marker: PhantomData<C>,
-}
+                let args: Vec<&Variant> = if args.is_null() {
+                    Vec::new()
+                } else {
+                    slice::from_raw_parts(args, num_args as usize)
+                        .iter()
+                        .map(|&arg| Variant::cast_ref(arg))
+                        .collect()
+                };
+
+                let ret = with_instance::<C, _, _>(class, &method.name, |rust_ty| {
+                    catch_panic(&method.name, || (method.func)(rust_ty, &args))
+                });
+                ret.and_then(|ret| ret).unwrap_or_else(Variant::new).forget()
+            }
+        }
 
-impl<C: NativeClass> ClassBuilder<C> {
+        extern "C" fn free_func<F>(data: *mut libc::c_void) {
+            unsafe {
+                free_boxed(data as *mut VirtualMethod<F>);
+            }
+        }
 
-    pub fn add_method_advanced(&self, method: ScriptMethod) {
-        let method_name = CString::new(method.name).unwrap();
-        let attr = sys::godot_method_attributes {
-            rpc_type: sys::godot_method_rpc_mode::GODOT_METHOD_RPC_MODE_DISABLED
+        let method = VirtualMethod {
+            name: self.member_name(name),
+            func,
         };
 
-        let method_desc = sys::godot_instance_method {
-            method: method.method_ptr,
-            method_data: method.method_data,
-            free_func: method.free_func
+        self.add_method_advanced(ScriptMethod {
+            name,
+            method_ptr: Some(invoke::<C, F>),
+            attributes: ScriptMethodAttributes::default(),
+            documentation: None,
+            method_data: alloc_boxed(method) as *mut _,
+            free_func: Some(free_func::<F>),
+        })
+    }
+
+    /// Attaches a description to the class, shown in the class reference of the
+    /// editor.
+    ///
+    /// Methods, properties and signals are documented with the `documentation`
+    /// field of their descriptor, or with `set_method_documentation` for the
+    /// methods registered with `add_method`. Documentation requires NativeScript 1.1
+    /// (Godot 3.1 or later) and is ignored by older engines.
+    pub fn set_class_documentation(&self, documentation: &str) {
+        let set = match nativescript_1_1().and_then(|api| api.set_class_documentation) {
+            Some(set) if self.registered => set,
+            _ => return,
         };
 
+        // The engine copies the string.
+        let documentation = GodotString::from_str(documentation);
         unsafe {
-            (get_api().godot_nativescript_register_method)(
-                self.init_handle,
-                self.class_name.as_ptr() as *const _,
-                method_name.as_ptr() as *const _,
-                attr,
-                method_desc
-            );
+            set(self.init_handle, self.class_name.as_ptr(), documentation.to_sys());
         }
     }
 
-    pub fn add_method(&self, name: &str, method: ScriptMethodFn) {
-        self.add_method_advanced(
-            ScriptMethod {
-                name: name,
-                method_ptr: Some(method),
-                attributes: ScriptMethodAttributes {
-                    rpc_mode: RpcMode::Disabled
+    /// Attaches a description to a method already registered, see
+    /// `set_class_documentation`.
+    pub fn set_method_documentation(&self, method: &str, documentation: &str) {
+        self.set_member_documentation(method, documentation, |api| api.set_method_documentation);
+    }
+
+    /// Describes the arguments of a method already registered, for the
+    /// autocompletion of the script editor and `get_method_list`. Requires
+    /// NativeScript 1.1, see `set_class_documentation`.
+    ///
+    /// The engine has no field for the default values: the arguments with a default
+    /// have the type of the default and its text as hint string, the others accept
+    /// any type. The method itself applies the defaults, see
+    /// `add_typed_method_with_arguments` and the `#[opt]` parameters of
+    /// `godot_wrap_method!`, described by `godot_method_arguments!`. Only the
+    /// defaults of the last arguments are described.
+    pub fn set_method_arguments(&self, method: &str, arguments: &[MethodArgument]) {
+        let set = match nativescript_1_1().and_then(|api| api.set_method_argument_information) {
+            Some(set) => set,
+            None => return,
+        };
+        let method = match self.member_c_name(method) {
+            Some(method) => method,
+            None => return,
+        };
+
+        let num_required = arguments.len() - trailing_defaults(arguments).len();
+        // The strings are copied by the engine, and destroyed with the arguments.
+        let args: Vec<MethodArg> = arguments.iter().enumerate().map(|(idx, argument)| {
+            let default = argument.default.as_ref().filter(|_| idx >= num_required);
+            MethodArg {
+                name: GodotString::from_str(argument.name).forget(),
+                type_: match default {
+                    Some(default) => unsafe { (get_api().godot_variant_get_type)(&default.0) },
+                    None => sys::godot_variant_type::GODOT_VARIANT_TYPE_NIL,
                 },
-                method_data: ptr::null_mut(),
-                free_func: None
-            },
-        );
+                hint: GODOT_PROPERTY_HINT_NONE,
+                hint_string: GodotString::from_str(default.map(|default| default.to_string()).unwrap_or_default()).forget(),
+            }
+        }).collect();
+
+        unsafe {
+            set(self.init_handle, self.class_name.as_ptr(), method.as_ptr(), args.len() as _, args.as_ptr());
+        }
+        for arg in args {
+            drop(GodotString(arg.name));
+            drop(GodotString(arg.hint_string));
+        }
     }
 
-    pub fn add_property<T, S, G>(&self, property: Property<T, S, G>)
+    fn set_member_documentation<F>(&self, member: &str, documentation: &str, setter: F)
     where
-        T: GodotType,
-        S: PropertySetter<C, T>,
-        G: PropertyGetter<C, T>,
+        F: FnOnce(&NativeScriptApi11) -> Option<DocumentationSetter>,
     {
+        let set = match nativescript_1_1().and_then(setter) {
+            Some(set) => set,
+            None => return,
+        };
+
+        let member = match self.member_c_name(member) {
+            Some(member) => member,
+            None => return,
+        };
+        let documentation = GodotString::from_str(documentation);
         unsafe {
-            let hint_text = match property.hint {
-                PropertyHint::Range { ref range, step, slider } => {
+            set(self.init_handle, self.class_name.as_ptr(), member.as_ptr(), documentation.to_sys());
+        }
+    }
 
-                    if slider {
-                        Some(format!("{},{},{},slider", range.start, range.end, step))
-                    } else {
-                        Some(format!("{},{},{}", range.start, range.end, step))
-                    }
-                }
-                PropertyHint::Enum { values } | PropertyHint::Flags { values } => { Some(values.join(",")) }
-                PropertyHint::NodePathToEditedNode | PropertyHint::None => { None }
-            };
-            let hint_string = if let Some(text) = hint_text {
-                GodotString::from_str(text)
-            } else {
-                GodotString::default()
-            };
+    pub(crate) fn name(&self) -> String {
+        self.class_name.to_string_lossy().into_owned()
+    }
 
-            let default: Variant = property.default.to_variant();
-            let ty = default.get_type();
+    fn member_name(&self, member: &str) -> String {
+        format!("{}::{}", self.class_name.to_string_lossy(), member)
+    }
 
-            let mut attr = sys::godot_property_attributes {
-                rset_type: sys::godot_method_rpc_mode::GODOT_METHOD_RPC_MODE_DISABLED, // TODO:
-                type_: mem::transmute(ty),
-                hint: property.hint.to_sys(),
-                hint_string: hint_string.to_sys(),
-                usage: property.usage.to_sys(),
-                default_value: default.to_sys(),
-            };
+    // With the `check_names` feature, warns about the members not named in
+    // snake_case like the members of the engine. The paths of the properties are
+    // checked segment by segment.
+    #[cfg_attr(not(feature = "check_names"), allow(unused_variables))]
+    fn check_name(&self, kind: &str, name: &str) {
+        #[cfg(feature = "check_names")]
+        {
+            if !name.split('/').all(is_snake_case) {
+                godot_warn!("the {} {} isn't named in snake_case", kind, self.member_name(name));
+            }
+        }
+    }
 
-            let path = ::std::ffi::CString::new(property.name).unwrap();
+    // The name of a member as expected by the engine, or why it can't be registered.
+    fn try_member_c_name(&self, member: &str) -> Result<CString, RegisterError> {
+        if !self.registered {
+            return Err(RegisterError::ClassNotRegistered { class: self.name() });
+        }
+        c_name(member)
+    }
 
-            let set = property.setter.as_godot_function();
-            let get = property.getter.as_godot_function();
+    // Like `try_member_c_name`, `None` after printing the error.
+    fn member_c_name(&self, member: &str) -> Option<CString> {
+        self.report_error(member, self.try_member_c_name(member)).ok()
+    }
 
-            (get_api().godot_nativescript_register_property)(
-                self.init_handle,
-                self.class_name.as_ptr(),
-                path.as_ptr() as *const _,
-                &mut attr, set, get
-            );
+    // Like `try_member_c_name` for a new member of the class, which is recorded.
+    fn new_member_c_name(&self, kind: MemberKind, member: &str) -> Result<CString, RegisterError> {
+        let name = self.try_member_c_name(member)?;
+        let mut members = self.members.borrow_mut();
+        let key = (kind, member.to_string());
+        if members.get(&key) == Some(&false) {
+            return Err(RegisterError::DuplicateMember { class: self.name(), name: member.to_string() });
         }
+        members.insert(key, self.registering_base.get());
+        Ok(name)
     }
 
-    pub fn add_signal(&self, signal: Signal) {
-        use std::ptr;
-        unsafe {
-            let name = GodotString::from_str(signal.name);
-            (get_api().godot_nativescript_register_signal)(
-                self.init_handle,
-                self.class_name.as_ptr(),
-                &sys::godot_signal {
-                    name: name.to_sys(),
-                    num_args: 0,
-                    args: ptr::null_mut(),
-                    num_default_args: 0,
-                    default_args: ptr::null_mut(),
-                }
-            );
+    // Prints the error of a member, except for the classes that failed to register,
+    // which already printed theirs.
+    fn report_error<T>(&self, member: &str, result: Result<T, RegisterError>) -> Result<T, RegisterError> {
+        if let Err(ref err) = result {
+            match *err {
+                RegisterError::ClassNotRegistered { .. } => {}
+                _ => godot_error!("could not register {}: {}", self.member_name(member), err),
+            }
         }
+        result
     }
 }
 
+/// Turns the path of the module declaring a class into the name of its inspector
+/// category: the crate name is dropped and each module becomes a title-cased level,
+/// so `my_game::ai::steering_behaviors` gives `Ai/Steering Behaviors`. Classes
+/// declared at the root of the crate use the name of the crate.
+#[doc(hidden)]
+pub fn module_category(module_path: &str) -> String {
+    let segments: Vec<&str> = module_path.split("::").collect();
+    let segments = if segments.len() > 1 { &segments[1..] } else { &segments[..] };
+
+    segments.iter()
+        .map(|segment| {
+            segment.split('_')
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+#[test]
+fn registry_subclasses() {
+    let class = |name: &str, base_class: &str| RegisteredClass {
+        name: name.to_string(),
+        base_class: base_class.to_string(),
+        tool: false,
+        methods: Vec::new(),
+        properties: Vec::new(),
+        signals: Vec::new(),
+    };
+    let registry = Registry {
+        classes: vec![class("Actor", "KinematicBody2D"), class("Player", "Actor"), class("Hud", "Control")],
+    };
+
+    assert!(registry.contains("Player"));
+    assert_eq!(registry.get("Hud").map(|class| &class.base_class[..]), Some("Control"));
+    let names: Vec<&str> = registry.subclasses_of("KinematicBody2D").map(|class| &class.name[..]).collect();
+    assert_eq!(names, vec!["Actor", "Player"]);
+    assert_eq!(registry.subclasses_of("Actor").count(), 1);
+}
+
+#[test]
+fn module_category_names() {
+    assert_eq!(module_category("my_game::ai::steering_behaviors"), "Ai/Steering Behaviors");
+    assert_eq!(module_category("my_game"), "My Game");
+}
+
+#[test]
+fn invalid_names() {
+    assert_eq!(c_name("Player").unwrap().to_str(), Ok("Player"));
+    assert_eq!(c_name(""), Err(RegisterError::EmptyName));
+    let err = c_name("Play\0er").unwrap_err();
+    assert_eq!(err, RegisterError::InteriorNul { name: "Play\0er".to_string() });
+    assert_eq!(err.to_string(), "the name \"Play\\0er\" contains a NUL character");
+}
+
+#[test]
+fn snake_case_names() {
+    assert!(is_snake_case("_ready"));
+    assert!(is_snake_case("body_entered"));
+    assert!(is_snake_case("move_2d"));
+    assert!(!is_snake_case("doThing"));
+    assert!(!is_snake_case("DoThing"));
+    assert!(!is_snake_case("do__thing"));
+    assert!(!is_snake_case("do_thing_"));
+    assert!(!is_snake_case("2d_move"));
+    assert!(!is_snake_case("_"));
+}
+
 // TODO: missing property hints.
 pub enum PropertyHint<'l> {
     None,
+    /// A `step` of 0 without slider lets the editor choose the step, see `range`.
     Range {
         range: Range<f64>,
         step: f64,
@@ -277,7 +1722,11 @@ pub enum PropertyHint<'l> {
     // Dir,
     // GlobalFile,
     // GlobalDir,
-    // ResourceType,
+    /// A resource of the given class, or of one of the classes of a comma separated
+    /// list like `"Texture,AudioStream"`.
+    ResourceType {
+        class: &'l str,
+    },
     // MultilineText,
     // ColorNoAlpha,
     // ImageCompressLossy,
@@ -296,17 +1745,73 @@ pub enum PropertyHint<'l> {
 }
 
 impl<'l> PropertyHint<'l> {
+    pub fn to_hint_string(&self) -> GodotString {
+        match *self {
+            PropertyHint::Range { ref range, step, slider } => {
+                if step == 0.0 && !slider {
+                    GodotString::from_str(format!("{},{}", range.start, range.end))
+                } else if slider {
+                    GodotString::from_str(format!("{},{},{},slider", range.start, range.end, step))
+                } else {
+                    GodotString::from_str(format!("{},{},{}", range.start, range.end, step))
+                }
+            }
+            PropertyHint::Enum { values } | PropertyHint::Flags { values } => {
+                GodotString::from_str(values.join(","))
+            }
+            PropertyHint::ResourceType { class } => GodotString::from_str(class),
+            PropertyHint::NodePathToEditedNode | PropertyHint::None => GodotString::default(),
+        }
+    }
+
     pub fn to_sys(&self) -> sys::godot_property_hint {
         match *self {
             PropertyHint::None => GODOT_PROPERTY_HINT_NONE,
             PropertyHint::Range { .. } => GODOT_PROPERTY_HINT_RANGE,
             PropertyHint::Enum { .. } => GODOT_PROPERTY_HINT_ENUM,
             PropertyHint::Flags { .. } => GODOT_PROPERTY_HINT_FLAGS,
+            PropertyHint::ResourceType { .. } => GODOT_PROPERTY_HINT_RESOURCE_TYPE,
             PropertyHint::NodePathToEditedNode => GODOT_PROPERTY_HINT_NODE_PATH_TO_EDITED_NODE,
         }
     }
 }
 
+/// Types with their own hint in the inspector. The properties of this type declared
+/// with `#[derive(NativeClass)]` use it when they don't have a `hint` option.
+///
+/// Enums without fields implement it with `#[derive(Export)]`, see the
+/// `gdnative_derive` crate, to be shown as a dropdown of their variants.
+pub trait Export: GodotType {
+    fn export_hint() -> PropertyHint<'static>;
+}
+
+// Selects the hint of the `Export` types in `godot_derive_native_class`. The inherent
+// `hint` method is only found for `Export` types, the others fall back to the method
+// of `DefaultHint`.
+#[doc(hidden)]
+pub struct ExportHint<T>(PhantomData<T>);
+
+impl<T> ExportHint<T> {
+    pub fn new() -> Self {
+        ExportHint(PhantomData)
+    }
+}
+
+impl<T: Export> ExportHint<T> {
+    pub fn hint(&self) -> PropertyHint<'static> {
+        T::export_hint()
+    }
+}
+
+#[doc(hidden)]
+pub trait DefaultHint {
+    fn hint(&self) -> PropertyHint<'static> {
+        PropertyHint::None
+    }
+}
+
+impl<T> DefaultHint for ExportHint<T> {}
+
 bitflags! {
     pub struct PropertyUsage: u32 {
         const STORAGE = GODOT_PROPERTY_USAGE_STORAGE as u32;
@@ -347,28 +1852,146 @@ pub struct Property<'l, T, S, G>
     pub default: T,
     pub hint: PropertyHint<'l>,
     pub usage: PropertyUsage,
+    /// Shown in the class reference of the editor, see
+    /// `ClassBuilder::set_class_documentation`.
+    pub documentation: Option<&'l str>,
+}
+
+/// A property being registered, see `ClassBuilder::property`.
+pub struct PropertyBuilder<'a, 'l, C: NativeClass + 'a, T, S, G> {
+    class: &'a ClassBuilder<'a, C>,
+    property: Property<'l, T, S, G>,
+}
+
+impl<'a, 'l, C, T, S, G> PropertyBuilder<'a, 'l, C, T, S, G>
+where
+    C: NativeClass,
+    T: GodotType,
+{
+    pub fn default(mut self, default: T) -> Self {
+        self.property.default = default;
+        self
+    }
+
+    pub fn hint(mut self, hint: PropertyHint<'l>) -> Self {
+        self.property.hint = hint;
+        self
+    }
+
+    pub fn usage(mut self, usage: PropertyUsage) -> Self {
+        self.property.usage = usage;
+        self
+    }
+
+    pub fn documentation(mut self, documentation: &'l str) -> Self {
+        self.property.documentation = Some(documentation);
+        self
+    }
+
+    /// Sets the function called when the property is assigned, usually a closure
+    /// like `|this: &mut MyClass, value| this.value = value`.
+    pub fn with_setter<NS>(self, setter: NS) -> PropertyBuilder<'a, 'l, C, T, NS, G>
+    where NS: PropertySetter<C, T> {
+        let property = self.property;
+        PropertyBuilder {
+            class: self.class,
+            property: Property {
+                name: property.name,
+                setter,
+                getter: property.getter,
+                default: property.default,
+                hint: property.hint,
+                usage: property.usage,
+                documentation: property.documentation,
+            },
+        }
+    }
+
+    /// Sets the function called when the property is read, usually a closure like
+    /// `|this: &MyClass| this.value`.
+    pub fn with_getter<NG, K>(self, getter: NG) -> PropertyBuilder<'a, 'l, C, T, S, NG>
+    where NG: PropertyGetter<C, T, K> {
+        let property = self.property;
+        PropertyBuilder {
+            class: self.class,
+            property: Property {
+                name: property.name,
+                setter: property.setter,
+                getter,
+                default: property.default,
+                hint: property.hint,
+                usage: property.usage,
+                documentation: property.documentation,
+            },
+        }
+    }
+
+    /// Reads and assigns the property through a field of the instance, given by two
+    /// closures like `|this: &MyClass| &this.value` and
+    /// `|this: &mut MyClass| &mut this.value`. Reading the property clones nothing,
+    /// the field is converted to a variant in place.
+    pub fn with_field<F, FM>(self, field: F, field_mut: FM)
+        -> PropertyBuilder<'a, 'l, C, T, impl Fn(&mut C, T), impl Fn(&C) -> Variant>
+    where
+        F: Fn(&C) -> &T,
+        FM: Fn(&mut C) -> &mut T,
+    {
+        self.with_setter(move |this: &mut C, value: T| *field_mut(this) = value)
+            .with_getter(move |this: &C| field(this).to_variant())
+    }
+
+    /// Registers the property, returns the class builder for further registrations.
+    pub fn done<K>(self) -> &'a ClassBuilder<'a, C>
+    where
+        S: PropertySetter<C, T>,
+        G: PropertyGetter<C, T, K>,
+    {
+        self.class.add_property(self.property)
+    }
 }
 
-// TODO: Signal arguments.
+/// A `PropertyHint::Range` without a slider. The step is chosen by the editor from
+/// the type of the property: 1 for integers, the precision of the inspector for
+/// floats.
+pub fn range(range: Range<f64>) -> PropertyHint<'static> {
+    PropertyHint::Range { range, step: 0.0, slider: false }
+}
 
-//pub struct SignalArgument<'l> {
-//    pub name: &'str,
-//    pub default: Variant,
-//    pub hint: PropertyHint,
-//    pub usage: PropertyUsage,
-//}
+/// An argument of a signal.
+///
+/// The type of the argument is deduced from the type of `default`. Arguments at
+/// the end of the list with a non-nil default value are registered as default
+/// arguments.
+pub struct SignalArgument<'l> {
+    pub name: &'l str,
+    pub default: Variant,
+    pub hint: PropertyHint<'l>,
+    pub usage: PropertyUsage,
+}
 
 pub struct Signal<'l> {
     pub name: &'l str,
-    //pub args: &'l [SignalArgument],
+    pub args: &'l [SignalArgument<'l>],
+    /// Shown in the class reference of the editor, see
+    /// `ClassBuilder::set_class_documentation`.
+    pub documentation: Option<&'l str>,
 }
 
+/// `name` is the name of the property prefixed with the class name, used in error
+/// messages.
 pub unsafe trait PropertySetter<C: NativeClass, T: GodotType> {
-    unsafe fn as_godot_function(self) -> sys::godot_property_set_func;
+    unsafe fn as_godot_function(self, name: String) -> sys::godot_property_set_func;
 }
 
-pub unsafe trait PropertyGetter<C: NativeClass, T: GodotType> {
-    unsafe fn as_godot_function(self) -> sys::godot_property_get_func;
+/// `name` is the name of the property prefixed with the class name, used in error
+/// messages.
+///
+/// Implemented by `Fn(&C) -> R` closures, which only borrow the instance and can be
+/// called while it is already borrowed, by `Fn(&mut C) -> R` closures and by `()`
+/// for write-only properties. `K` is one of `SharedAccess` and `MutAccess`, it is
+/// inferred and only keeps the implementations apart.
+pub unsafe trait PropertyGetter<C: NativeClass, T: GodotType, K> {
+    unsafe fn as_godot_function(self, name: String) -> sys::godot_property_get_func;
 }
 
 extern "C" fn empty_setter(
@@ -389,7 +2012,7 @@ extern "C" fn empty_getter(
 extern "C" fn empty_free_func(_data: *mut libc::c_void) {}
 
 unsafe impl <C: NativeClass, T: GodotType> PropertySetter<C, T> for () {
-    unsafe fn as_godot_function(self) -> sys::godot_property_set_func {
+    unsafe fn as_godot_function(self, _name: String) -> sys::godot_property_set_func {
         let mut set = sys::godot_property_set_func::default();
         set.set_func = Some(empty_setter);
         set.free_func = Some(empty_free_func);
@@ -397,8 +2020,14 @@ unsafe impl <C: NativeClass, T: GodotType> PropertySetter<C, T> for () {
     }
 }
 
-unsafe impl <C: NativeClass, T: GodotType> PropertyGetter<C, T> for () {
-    unsafe fn as_godot_function(self) -> sys::godot_property_get_func {
+/// The getters borrowing the instance immutably, see `PropertyGetter`.
+pub enum SharedAccess {}
+
+/// The getters borrowing the instance mutably, see `PropertyGetter`.
+pub enum MutAccess {}
+
+unsafe impl <C: NativeClass, T: GodotType> PropertyGetter<C, T, SharedAccess> for () {
+    unsafe fn as_godot_function(self, _name: String) -> sys::godot_property_get_func {
         let mut get = sys::godot_property_get_func::default();
         get.get_func = Some(empty_getter);
         get.free_func = Some(empty_free_func);
@@ -406,16 +2035,20 @@ unsafe impl <C: NativeClass, T: GodotType> PropertyGetter<C, T> for () {
     }
 }
 
+// The data of the setter and getter functions registered for a property.
+struct PropertyAccessor<F> {
+    name: String,
+    func: F,
+}
+
 unsafe impl <F, C, T> PropertySetter<C, T> for F
     where C: NativeClass,
           T: GodotType,
           F: Fn(&mut C, T),
 {
-    unsafe fn as_godot_function(self) -> sys::godot_property_set_func {
-        use std::cell::RefCell;
+    unsafe fn as_godot_function(self, name: String) -> sys::godot_property_set_func {
         let mut set = sys::godot_property_set_func::default();
-        let data = Box::new(self);
-        set.method_data = Box::into_raw(data) as *mut _;
+        set.method_data = alloc_boxed(PropertyAccessor { name, func: self }) as *mut _;
 
         extern "C" fn invoke<C, F, T>(_this: *mut sys::godot_object, method: *mut libc::c_void, class: *mut libc::c_void, val: *mut sys::godot_variant)
             where C: NativeClass,
@@ -424,14 +2057,15 @@ unsafe impl <F, C, T> PropertySetter<C, T> for F
 
         {
             unsafe {
-                let rust_ty = &*(class as *mut RefCell<C>);
-                let mut rust_ty = rust_ty.borrow_mut();
-                let func = &mut *(method as *mut F);
+                let setter = &*(method as *mut PropertyAccessor<F>);
 
-                if let Some(val) = T::from_variant(Variant::cast_ref(val)) {
-                    func(&mut *rust_ty, val);
-                } else {
-                    godot_error!("Incorrect type passed to property");
+                match T::try_from_variant(Variant::cast_ref(val)) {
+                    Ok(val) => {
+                        with_instance_mut::<C, _, _>(class, &setter.name, |rust_ty| {
+                            catch_panic(&setter.name, || (setter.func)(rust_ty, val))
+                        });
+                    }
+                    Err(err) => godot_error!("{}: incorrect value: {}", setter.name, err),
                 }
             }
         }
@@ -439,7 +2073,7 @@ unsafe impl <F, C, T> PropertySetter<C, T> for F
 
         extern "C" fn free_func<F>(data: *mut libc::c_void) {
             unsafe {
-                drop(Box::from_raw(data as *mut F));
+                free_boxed(data as *mut PropertyAccessor<F>);
             }
         }
         set.free_func = Some(free_func::<F>);
@@ -448,36 +2082,76 @@ unsafe impl <F, C, T> PropertySetter<C, T> for F
     }
 }
 
-unsafe impl <F, C, T> PropertyGetter<C, T> for F
+/// Getters can return any `ReturnValue` (for example a `&'static str` or a
+/// `Cow<'static, str>` for a string property), it is converted to a variant when
+/// the value is handed to the engine.
+unsafe impl <F, C, T, R> PropertyGetter<C, T, SharedAccess> for F
     where C: NativeClass,
           T: GodotType,
-          F: Fn(&mut C) -> T,
+          F: Fn(&C) -> R,
+          R: ReturnValue,
 {
-    unsafe fn as_godot_function(self) -> sys::godot_property_get_func {
-        use std::cell::RefCell;
+    unsafe fn as_godot_function(self, name: String) -> sys::godot_property_get_func {
         let mut get = sys::godot_property_get_func::default();
-        let data = Box::new(self);
-        get.method_data = Box::into_raw(data) as *mut _;
+        get.method_data = alloc_boxed(PropertyAccessor { name, func: self }) as *mut _;
 
-        extern "C" fn invoke<C, F, T>(_this: *mut sys::godot_object, method: *mut libc::c_void, class: *mut libc::c_void) -> sys::godot_variant
+        extern "C" fn invoke<C, F, R>(_this: *mut sys::godot_object, method: *mut libc::c_void, class: *mut libc::c_void) -> sys::godot_variant
             where C: NativeClass,
-                T: GodotType,
-                F: Fn(&mut C) -> T,
+                F: Fn(&C) -> R,
+                R: ReturnValue,
+
+        {
+            unsafe {
+                let getter = &*(method as *mut PropertyAccessor<F>);
+                let ret = with_instance::<C, _, _>(class, &getter.name, |rust_ty| {
+                    catch_panic(&getter.name, || (getter.func)(rust_ty).to_return_variant())
+                });
+                ret.and_then(|ret| ret).unwrap_or_else(Variant::new).forget()
+            }
+        }
+        get.get_func = Some(invoke::<C, F, R>);
+
+        extern "C" fn free_func<F>(data: *mut libc::c_void) {
+            unsafe {
+                free_boxed(data as *mut PropertyAccessor<F>);
+            }
+        }
+        get.free_func = Some(free_func::<F>);
+
+        get
+    }
+}
+
+/// Getters needing exclusive access to the instance.
+unsafe impl <F, C, T, R> PropertyGetter<C, T, MutAccess> for F
+    where C: NativeClass,
+          T: GodotType,
+          F: Fn(&mut C) -> R,
+          R: ReturnValue,
+{
+    unsafe fn as_godot_function(self, name: String) -> sys::godot_property_get_func {
+        let mut get = sys::godot_property_get_func::default();
+        get.method_data = alloc_boxed(PropertyAccessor { name, func: self }) as *mut _;
+
+        extern "C" fn invoke<C, F, R>(_this: *mut sys::godot_object, method: *mut libc::c_void, class: *mut libc::c_void) -> sys::godot_variant
+            where C: NativeClass,
+                F: Fn(&mut C) -> R,
+                R: ReturnValue,
 
         {
             unsafe {
-                let rust_ty = &*(class as *mut RefCell<C>);
-                let mut rust_ty = rust_ty.borrow_mut();
-                let func = &mut *(method as *mut F);
-                let ret = func(&mut *rust_ty);
-                ret.to_variant().forget()
+                let getter = &*(method as *mut PropertyAccessor<F>);
+                let ret = with_instance_mut::<C, _, _>(class, &getter.name, |rust_ty| {
+                    catch_panic(&getter.name, || (getter.func)(rust_ty).to_return_variant())
+                });
+                ret.and_then(|ret| ret).unwrap_or_else(Variant::new).forget()
             }
         }
-        get.get_func = Some(invoke::<C, F, T>);
+        get.get_func = Some(invoke::<C, F, R>);
 
         extern "C" fn free_func<F>(data: *mut libc::c_void) {
             unsafe {
-                drop(Box::from_raw(data as *mut F));
+                free_boxed(data as *mut PropertyAccessor<F>);
             }
         }
         get.free_func = Some(free_func::<F>);
@@ -485,3 +2159,58 @@ unsafe impl <F, C, T> PropertyGetter<C, T> for F
         get
     }
 }
+
+#[test]
+fn duplicate_members() {
+    struct Player {
+        header: NativeInstanceHeader,
+    }
+
+    impl NativeClass for Player {
+        type UserData = LocalCellData<Player>;
+        fn class_name() -> &'static str { "Player" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+    }
+
+    let builder = |registered| ClassBuilder::<Player> {
+        init_handle: ptr::null_mut(),
+        class_name: CString::new("Player").unwrap(),
+        registered,
+        members: RefCell::new(HashMap::new()),
+        registering_base: Cell::new(false),
+        _marker: PhantomData,
+        _scope: PhantomData,
+    };
+
+    let class = builder(true);
+    // The class overrides the members of its base classes.
+    class.registering_base.set(true);
+    assert!(class.new_member_c_name(MemberKind::Method, "_ready").is_ok());
+    class.registering_base.set(false);
+    assert!(class.new_member_c_name(MemberKind::Method, "_ready").is_ok());
+    assert_eq!(
+        class.new_member_c_name(MemberKind::Method, "_ready"),
+        Err(RegisterError::DuplicateMember { class: "Player".to_string(), name: "_ready".to_string() })
+    );
+    assert!(class.new_member_c_name(MemberKind::Property, "_ready").is_ok());
+    assert_eq!(class.new_member_c_name(MemberKind::Signal, ""), Err(RegisterError::EmptyName));
+
+    let failed = builder(false);
+    assert_eq!(
+        failed.new_member_c_name(MemberKind::Method, "_ready"),
+        Err(RegisterError::ClassNotRegistered { class: "Player".to_string() })
+    );
+}
+
+#[test]
+fn register_after_init_threads() {
+    assert_eq!(register_after_init(|_| ()), Err(RegisterError::NotInitialized));
+
+    *NATIVESCRIPT_INIT.lock().unwrap() = Some(NativeScriptInit {
+        library_path: "res://library.gdnlib".to_string(),
+        main_thread: thread::current().id(),
+    });
+    let registered = thread::spawn(|| register_after_init(|_| ())).join().unwrap();
+    *NATIVESCRIPT_INIT.lock().unwrap() = None;
+    assert_eq!(registered, Err(RegisterError::NotMainThread));
+}
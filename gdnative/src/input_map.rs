@@ -0,0 +1,341 @@
+//! Typed input bindings, and saving the bindings of the `InputMap`.
+//!
+//! A key rebinding screen changes the events of the actions and saves them, so that
+//! they are restored at the next start. `InputBinding` describes the events an
+//! action can be bound to and converts them from and to the engine events, and the
+//! `InputMap` helpers read, replace, save and load the bindings of the actions:
+//!
+//! ```ignore
+//! let mut input_map = InputMap::godot_singleton();
+//!
+//! // The player pressed a new key for "jump".
+//! if let Some(binding) = InputBinding::from_event(&event) {
+//!     input_map.set_action_bindings("jump", &[binding]);
+//! }
+//!
+//! let mut config = ConfigFile::new();
+//! input_map.save_bindings(&mut config, "input");
+//! config.save("user://settings.cfg".into())?;
+//!
+//! // At the next start.
+//! if config.load("user://settings.cfg".into()).is_ok() {
+//!     input_map.load_bindings(&config, "input");
+//! }
+//! ```
+//!
+//! A binding is saved as a dictionary, `{"type": "key", "scancode": 32, "shift":
+//! false, ...}`. Unknown bindings, saved by a newer version of the game for
+//! example, are skipped when loading.
+
+use ConfigFile;
+use Dictionary;
+use GodotString;
+use InputEvent;
+use InputEventJoypadButton;
+use InputEventJoypadMotion;
+use InputEventKey;
+use InputEventMouseButton;
+use InputEventWithModifiers;
+use InputMap;
+use ToVariant;
+use Variant;
+use VariantArray;
+
+/// The modifier keys held with a key or a mouse button.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeyModifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub control: bool,
+    pub meta: bool,
+}
+
+impl KeyModifiers {
+    fn read(event: &InputEventWithModifiers) -> Self {
+        KeyModifiers {
+            shift: event.get_shift(),
+            alt: event.get_alt(),
+            control: event.get_control(),
+            meta: event.get_metakey(),
+        }
+    }
+
+    fn write(&self, event: &mut InputEventWithModifiers) {
+        event.set_shift(self.shift);
+        event.set_alt(self.alt);
+        event.set_control(self.control);
+        event.set_metakey(self.meta);
+    }
+
+    fn to_dictionary(&self, dict: &mut Dictionary) {
+        dict.set(&"shift".into(), &Variant::from_bool(self.shift));
+        dict.set(&"alt".into(), &Variant::from_bool(self.alt));
+        dict.set(&"control".into(), &Variant::from_bool(self.control));
+        dict.set(&"meta".into(), &Variant::from_bool(self.meta));
+    }
+
+    fn from_dictionary(dict: &Dictionary) -> Self {
+        let flag = |name: &str| dict.get_ref(&name.into()).try_to_bool().unwrap_or(false);
+        KeyModifiers {
+            shift: flag("shift"),
+            alt: flag("alt"),
+            control: flag("control"),
+            meta: flag("meta"),
+        }
+    }
+}
+
+/// An event an action can be bound to, see the `input_map` module.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputBinding {
+    /// A key, by scancode (the `KEY_*` constants).
+    Key { scancode: i64, modifiers: KeyModifiers },
+    /// A mouse button (the `BUTTON_*` constants).
+    MouseButton { button_index: i64, modifiers: KeyModifiers },
+    /// A joypad button (the `JOY_BUTTON_*` constants).
+    JoypadButton { button_index: i64 },
+    /// A joypad axis moved in a direction, `axis_value` is -1 or 1.
+    JoypadMotion { axis: i64, axis_value: f64 },
+}
+
+impl InputBinding {
+    /// The binding matching the event, or `None` for the events an action can't be
+    /// bound to, like the mouse motions.
+    pub fn from_event(event: &InputEvent) -> Option<Self> {
+        if let Some(key) = event.cast::<InputEventKey>() {
+            return Some(InputBinding::Key {
+                scancode: key.get_scancode(),
+                modifiers: KeyModifiers::read(&key.as_input_event_with_modifiers()),
+            });
+        }
+        if let Some(button) = event.cast::<InputEventMouseButton>() {
+            let modifiers = button.cast::<InputEventWithModifiers>().map(|event| KeyModifiers::read(&event));
+            return Some(InputBinding::MouseButton {
+                button_index: button.get_button_index(),
+                modifiers: modifiers.unwrap_or_default(),
+            });
+        }
+        if let Some(button) = event.cast::<InputEventJoypadButton>() {
+            return Some(InputBinding::JoypadButton { button_index: button.get_button_index() });
+        }
+        if let Some(motion) = event.cast::<InputEventJoypadMotion>() {
+            let axis_value = if motion.get_axis_value() < 0.0 { -1.0 } else { 1.0 };
+            return Some(InputBinding::JoypadMotion { axis: motion.get_axis(), axis_value });
+        }
+        None
+    }
+
+    /// Creates the event of the binding, for `InputMap::action_add_event`.
+    pub fn to_event(&self) -> InputEvent {
+        let event = match *self {
+            InputBinding::Key { scancode, modifiers } => {
+                let mut key = InputEventKey::new();
+                key.set_scancode(scancode);
+                modifiers.write(&mut key.as_input_event_with_modifiers());
+                key.cast::<InputEvent>()
+            }
+            InputBinding::MouseButton { button_index, modifiers } => {
+                let mut button = InputEventMouseButton::new();
+                button.set_button_index(button_index);
+                if let Some(mut event) = button.cast::<InputEventWithModifiers>() {
+                    modifiers.write(&mut event);
+                }
+                button.cast::<InputEvent>()
+            }
+            InputBinding::JoypadButton { button_index } => {
+                let mut button = InputEventJoypadButton::new();
+                button.set_button_index(button_index);
+                button.cast::<InputEvent>()
+            }
+            InputBinding::JoypadMotion { axis, axis_value } => {
+                let mut motion = InputEventJoypadMotion::new();
+                motion.set_axis(axis);
+                motion.set_axis_value(axis_value);
+                motion.cast::<InputEvent>()
+            }
+        };
+        event.expect("input events are input events")
+    }
+
+    /// The saved form of the binding.
+    pub fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let mut set = |key: &str, value: Variant| dict.set(&key.into(), &value);
+        match *self {
+            InputBinding::Key { scancode, .. } => {
+                set("type", "key".into());
+                set("scancode", Variant::from_i64(scancode));
+            }
+            InputBinding::MouseButton { button_index, .. } => {
+                set("type", "mouse_button".into());
+                set("button_index", Variant::from_i64(button_index));
+            }
+            InputBinding::JoypadButton { button_index } => {
+                set("type", "joypad_button".into());
+                set("button_index", Variant::from_i64(button_index));
+            }
+            InputBinding::JoypadMotion { axis, axis_value } => {
+                set("type", "joypad_motion".into());
+                set("axis", Variant::from_i64(axis));
+                set("axis_value", axis_value.to_variant());
+            }
+        }
+
+        match *self {
+            InputBinding::Key { modifiers, .. } | InputBinding::MouseButton { modifiers, .. } => {
+                modifiers.to_dictionary(&mut dict);
+            }
+            _ => {}
+        }
+        dict
+    }
+
+    /// Reads a binding saved with `to_dictionary`, `None` if the dictionary isn't one.
+    pub fn from_dictionary(dict: &Dictionary) -> Option<Self> {
+        let int = |key: &str| dict.get_ref(&key.into()).try_to_i64();
+        let kind = dict.get_ref(&"type".into()).try_to_string()?;
+
+        let binding = match &*kind {
+            "key" => InputBinding::Key {
+                scancode: int("scancode")?,
+                modifiers: KeyModifiers::from_dictionary(dict),
+            },
+            "mouse_button" => InputBinding::MouseButton {
+                button_index: int("button_index")?,
+                modifiers: KeyModifiers::from_dictionary(dict),
+            },
+            "joypad_button" => InputBinding::JoypadButton { button_index: int("button_index")? },
+            "joypad_motion" => InputBinding::JoypadMotion {
+                axis: int("axis")?,
+                axis_value: dict.get_ref(&"axis_value".into()).try_to_f64()?,
+            },
+            _ => return None,
+        };
+        Some(binding)
+    }
+}
+
+impl InputMap {
+    /// The bindings of the action, without the events which can't be bound.
+    pub fn action_bindings(&mut self, action: &str) -> Vec<InputBinding> {
+        self.get_action_list(GodotString::from_str(action))
+            .iter()
+            .filter_map(|event| event.try_to_object::<InputEvent>())
+            .filter_map(|event| InputBinding::from_event(&event))
+            .collect()
+    }
+
+    /// Replaces the events of the action with the bindings. The action is added if
+    /// it doesn't exist.
+    pub fn set_action_bindings(&mut self, action: &str, bindings: &[InputBinding]) {
+        let name = GodotString::from_str(action);
+        if !self.has_action(name.new_ref()) {
+            self.add_action(name.new_ref());
+        }
+
+        let events = self.get_action_list(name.new_ref());
+        for event in events.iter().filter_map(|event| event.try_to_object::<InputEvent>()) {
+            self.action_erase_event(name.new_ref(), Some(event));
+        }
+        for binding in bindings {
+            self.action_add_event(name.new_ref(), Some(binding.to_event()));
+        }
+    }
+
+    /// The bindings of all the actions, an array of bindings per action.
+    pub fn bindings_to_dictionary(&mut self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        for action in self.action_names() {
+            dict.set(&Variant::from_str(&action), &Variant::from_array(&self.bindings_array(&action)));
+        }
+        dict
+    }
+
+    /// Replaces the bindings of the actions of the dictionary, see
+    /// `bindings_to_dictionary`. The other actions are left unchanged.
+    pub fn load_bindings_dictionary(&mut self, dict: &Dictionary) {
+        for (action, bindings) in dict.iter() {
+            if let (Some(action), Some(bindings)) = (action.try_to_string(), bindings.try_to_array()) {
+                self.set_action_bindings(&action, &parse_bindings(&bindings));
+            }
+        }
+    }
+
+    /// Saves the bindings of all the actions in `section`, one key per action.
+    pub fn save_bindings(&mut self, config: &mut ConfigFile, section: &str) {
+        let section = GodotString::from_str(section);
+        for action in self.action_names() {
+            let bindings = Variant::from_array(&self.bindings_array(&action));
+            config.set_value(section.new_ref(), GodotString::from_str(&action), bindings);
+        }
+    }
+
+    /// Restores the bindings saved by `save_bindings`. The actions missing from the
+    /// section are left unchanged.
+    pub fn load_bindings(&mut self, config: &ConfigFile, section: &str) {
+        let section = GodotString::from_str(section);
+        if !config.has_section(section.new_ref()) {
+            return;
+        }
+
+        for action in config.get_section_keys(section.new_ref()).iter() {
+            let bindings = config.get_value(section.new_ref(), action.new_ref(), Variant::new());
+            if let Some(bindings) = bindings.try_to_array() {
+                self.set_action_bindings(&action.to_string(), &parse_bindings(&bindings));
+            }
+        }
+    }
+
+    fn action_names(&mut self) -> Vec<String> {
+        self.get_actions().iter().filter_map(|action| action.try_to_string()).collect()
+    }
+
+    fn bindings_array(&mut self, action: &str) -> VariantArray {
+        self.action_bindings(action).iter().map(|binding| binding.to_dictionary()).collect()
+    }
+}
+
+fn parse_bindings(bindings: &VariantArray) -> Vec<InputBinding> {
+    bindings.iter()
+        .filter_map(|binding| binding.try_to_dictionary())
+        .filter_map(|binding| InputBinding::from_dictionary(&binding))
+        .collect()
+}
+
+godot_test!(test_input_bindings {
+    let bindings = [
+        InputBinding::Key {
+            scancode: 32,
+            modifiers: KeyModifiers { shift: true, ..KeyModifiers::default() },
+        },
+        InputBinding::MouseButton { button_index: 2, modifiers: KeyModifiers::default() },
+        InputBinding::JoypadButton { button_index: 0 },
+        InputBinding::JoypadMotion { axis: 1, axis_value: -1.0 },
+    ];
+
+    for binding in &bindings {
+        assert_eq!(InputBinding::from_dictionary(&binding.to_dictionary()), Some(*binding));
+        assert_eq!(InputBinding::from_event(&binding.to_event()), Some(*binding));
+    }
+
+    let mut unknown = Dictionary::new();
+    unknown.set(&"type".into(), &"touch".into());
+    assert_eq!(InputBinding::from_dictionary(&unknown), None);
+
+    let mut input_map = InputMap::godot_singleton();
+    input_map.set_action_bindings("gdnative_test_jump", &bindings[..2]);
+    assert_eq!(input_map.action_bindings("gdnative_test_jump"), &bindings[..2]);
+
+    let mut config = ConfigFile::new();
+    input_map.save_bindings(&mut config, "input");
+    input_map.set_action_bindings("gdnative_test_jump", &[]);
+    input_map.load_bindings(&config, "input");
+    assert_eq!(input_map.action_bindings("gdnative_test_jump"), &bindings[..2]);
+
+    let saved = input_map.bindings_to_dictionary();
+    input_map.set_action_bindings("gdnative_test_jump", &bindings[2..]);
+    input_map.load_bindings_dictionary(&saved);
+    assert_eq!(input_map.action_bindings("gdnative_test_jump"), &bindings[..2]);
+
+    input_map.erase_action(GodotString::from_str("gdnative_test_jump"));
+});
@@ -0,0 +1,203 @@
+//! Borrows of the rust instances by the callbacks invoked by the engine.
+//!
+//! The engine can call back into a script while one of its methods is running, for
+//! example when the method emits a signal connected to another method of the same
+//! instance. Both calls borrow the instance, and the second borrow fails if one of
+//! them needs a mutable reference. The callbacks record which member holds the
+//! borrow so that the conflict is reported with both names:
+//!
+//! ```text
+//! Player::take_damage: the instance is already borrowed by Player::_process, which
+//! is still running ...
+//! ```
+//!
+//! Methods taking `&self` only borrow the instance immutably, so with the default
+//! `LocalCellData` wrapper any number of them can run at the same time (see the
+//! `user_data` module for the other wrappers). Methods that are called back while another method of
+//! the instance runs can take `&self` and keep the state they modify in `Cell` or
+//! `RefCell` fields:
+//!
+//! ```ignore
+//! #[export]
+//! fn _on_hit(&self, damage: i64) {
+//!     self.health.set(self.health.get() - damage);
+//! }
+//! ```
+
+use std::cell::RefCell;
+use libc;
+use user_data::{AccessError, UserData};
+use NativeClass;
+
+struct ActiveBorrow {
+    user_data: *const libc::c_void,
+    // The name outlives the guard that removes the entry.
+    member: *const str,
+}
+
+thread_local! {
+    static ACTIVE_BORROWS: RefCell<Vec<ActiveBorrow>> = RefCell::new(Vec::new());
+}
+
+/// Calls `op` with a shared reference to the instance stored in the user data of a
+/// script for the member `member`, or reports why the instance can't be accessed
+/// with `godot_error!`.
+///
+/// The user data must point to a `C::UserData`.
+#[doc(hidden)]
+pub unsafe fn with_instance<C, F, U>(user_data: *mut libc::c_void, member: &str, op: F) -> Option<U>
+where
+    C: NativeClass,
+    F: FnOnce(&C) -> U,
+{
+    if user_data.is_null() {
+        godot_error!("{}: the instance wasn't constructed, its constructor panicked", member);
+        return None;
+    }
+
+    let data = &*(user_data as *const C::UserData);
+    let result = data.map(|instance| {
+        let _active = ActiveGuard::push(user_data, member);
+        op(instance)
+    });
+    handle_result(result, user_data, member)
+}
+
+/// Calls `op` with a mutable reference to the instance stored in the user data of a
+/// script for the member `member`, or reports why the instance can't be accessed
+/// with `godot_error!`.
+///
+/// The user data must point to a `C::UserData`.
+#[doc(hidden)]
+pub unsafe fn with_instance_mut<C, F, U>(user_data: *mut libc::c_void, member: &str, op: F) -> Option<U>
+where
+    C: NativeClass,
+    F: FnOnce(&mut C) -> U,
+{
+    if user_data.is_null() {
+        godot_error!("{}: the instance wasn't constructed, its constructor panicked", member);
+        return None;
+    }
+
+    let data = &*(user_data as *const C::UserData);
+    let result = data.map_mut(|instance| {
+        let _active = ActiveGuard::push(user_data, member);
+        op(instance)
+    });
+    handle_result(result, user_data, member)
+}
+
+fn handle_result<U>(result: Result<U, AccessError>, user_data: *const libc::c_void, member: &str) -> Option<U> {
+    match result {
+        Ok(value) => Some(value),
+        Err(AccessError::Borrowed) => {
+            report_conflict(user_data, member);
+            None
+        }
+        Err(AccessError::WrongThread) => {
+            godot_error!(
+                "{}: the instance was created on another thread. \
+                 Classes used from several threads must store their instances in `MutexData` or `RwLockData`.",
+                member
+            );
+            None
+        }
+        Err(AccessError::Poisoned) => {
+            godot_error!("{}: a method of the instance panicked while holding its lock", member);
+            None
+        }
+    }
+}
+
+struct ActiveGuard<'a> {
+    user_data: *const libc::c_void,
+    member: &'a str,
+}
+
+impl<'a> ActiveGuard<'a> {
+    fn push(user_data: *const libc::c_void, member: &'a str) -> Self {
+        ACTIVE_BORROWS.with(|active| {
+            active.borrow_mut().push(ActiveBorrow { user_data, member: member as *const str });
+        });
+        ActiveGuard { user_data, member }
+    }
+}
+
+impl<'a> Drop for ActiveGuard<'a> {
+    fn drop(&mut self) {
+        let member = self.member.as_ptr();
+        ACTIVE_BORROWS.with(|active| {
+            let mut active = active.borrow_mut();
+            // Shared borrows don't necessarily end in the reverse order.
+            if let Some(idx) = active.iter().rposition(|borrow| {
+                borrow.user_data == self.user_data && borrow.member as *const u8 == member
+            }) {
+                active.remove(idx);
+            }
+        });
+    }
+}
+
+fn report_conflict(user_data: *const libc::c_void, member: &str) {
+    let holder = ACTIVE_BORROWS.with(|active| {
+        active.borrow().iter()
+            .rev()
+            .find(|borrow| borrow.user_data == user_data)
+            .map(|borrow| unsafe { (*borrow.member).to_string() })
+    });
+
+    match holder {
+        Some(holder) => godot_error!(
+            "{}: the instance is already borrowed by {}, which is still running. \
+             The engine called back into the script, for example through a signal emitted by {}. \
+             Methods taking `&self` can run at the same time as other `&self` methods.",
+            member, holder, holder
+        ),
+        None => godot_error!("{}: the instance is already borrowed", member),
+    }
+}
+
+#[test]
+fn instance_borrow_tracking() {
+    use user_data::LocalCellData;
+    use NativeInstanceHeader;
+
+    struct Foo {
+        header: NativeInstanceHeader,
+        value: i32,
+    }
+
+    impl NativeClass for Foo {
+        type UserData = LocalCellData<Foo>;
+        fn class_name() -> &'static str { "Foo" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+    }
+
+    let data = Box::new(LocalCellData::new(Foo {
+        header: NativeInstanceHeader { this: ::std::ptr::null_mut() },
+        value: 0,
+    }));
+    let user_data = &*data as *const LocalCellData<Foo> as *mut libc::c_void;
+
+    unsafe {
+        let sum = with_instance::<Foo, _, _>(user_data, "Foo::first", |first| {
+            with_instance::<Foo, _, _>(user_data, "Foo::second", |second| {
+                let holders = ACTIVE_BORROWS.with(|active| {
+                    active.borrow().iter().map(|borrow| (*borrow.member).to_string()).collect::<Vec<_>>()
+                });
+                assert_eq!(holders, vec!["Foo::first".to_string(), "Foo::second".to_string()]);
+                first.value + second.value
+            })
+        });
+        assert_eq!(sum, Some(Some(0)));
+
+        let nested = with_instance_mut::<Foo, _, _>(user_data, "Foo::third", |third| {
+            third.value += 1;
+            with_instance::<Foo, _, _>(user_data, "Foo::fourth", |_| ()).is_some()
+        });
+        assert_eq!(nested, Some(false));
+    }
+
+    assert_eq!(data.map(|foo| foo.value), Ok(1));
+    assert!(ACTIVE_BORROWS.with(|active| active.borrow().is_empty()));
+}
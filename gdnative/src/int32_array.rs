@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 
 /// A reference-counted vector of `i32` that uses Godot's pool allocator.
@@ -100,7 +101,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for Int32Array {
+impl_sys_access!(for Int32Array as godot_pool_int_array);
+
+impl ToVariant for Int32Array {
     fn to_variant(&self) -> Variant { Variant::from_int32_array(self) }
+}
+
+impl FromVariant for Int32Array {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_int32_array() }
 }
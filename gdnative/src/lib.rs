@@ -23,6 +23,37 @@
 //! Some types are manually managed. This means that ownership can be passed to the
 //! engine or the object must be carfeully deallocated using the object's `free`  method.
 //!
+//! ## Binary size
+//!
+//! By default each generated method marshals its arguments and return value with its
+//! own specialized `ptrcall` code, which is fast but makes up a large part of the
+//! size of the library. With the `dynamic_dispatch` feature every generated method
+//! instead converts its arguments to `Variant`s and goes through a single shared call
+//! path. This produces considerably smaller binaries at the cost of a variant
+//! conversion (and an allocation) per argument on each engine call, so it is best
+//! suited for projects that are not bottlenecked by calls into the engine.
+//!
+//! ## Compile time
+//!
+//! Generating the wrappers of every engine class takes a large part of the compile
+//! time of this crate. To only generate the classes a project uses, set the
+//! `GDNATIVE_CLASS_WHITELIST` environment variable to the absolute path of a file
+//! listing them, one per line:
+//!
+//! ```text
+//! # Classes used by the game.
+//! Spatial
+//! KinematicBody
+//! Input
+//! ```
+//!
+//! The base classes of the listed classes and the few classes used by this crate
+//! itself are always generated. Generated methods taking or returning objects of
+//! other classes are left out, and using one of these classes triggers a deprecation
+//! warning pointing to the whitelist. The typed helpers (for example
+//! `Curve::add_point_with_modes`) are only available when all the classes they
+//! extend are generated.
+//!
 
 
 #[doc(hidden)]
@@ -33,11 +64,29 @@ pub extern crate gdnative_sys as sys;
 extern crate bitflags;
 
 pub extern crate gdnative_geom as geom;
+extern crate gdnative_derive;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
 mod macros;
+mod allocator;
+mod timing;
+mod info;
+mod diagnostics;
 #[macro_use]
 mod class;
 mod object;
+mod arg_buffer;
+mod deprecation;
+mod call;
 mod internal;
 mod godot_type;
 mod color;
@@ -47,6 +96,9 @@ mod dictionary;
 mod rid;
 mod generated;
 mod node_path;
+mod node_path_property;
+mod node_access;
+mod resource_property;
 mod string;
 mod byte_array;
 mod int32_array;
@@ -55,9 +107,90 @@ mod string_array;
 mod vector2_array;
 mod vector3_array;
 mod color_array;
+mod array_iter;
+mod pool_access;
+mod pool_stream;
+mod collision_layers;
+mod type_tag;
+mod nativescript_1_1;
+mod adhoc_script;
+mod signal_relay;
+mod instance_borrow;
+mod user_data;
+mod variant_diff;
+mod event_bus;
+mod configuration_warning;
+mod features;
+mod terminate;
+mod native_function;
+mod accessibility;
+mod number_format;
+#[cfg(gdnative_helper = "curve")]
+mod curve;
+#[cfg(gdnative_helper = "grid_map")]
+mod grid_map;
+#[cfg(gdnative_helper = "particles")]
+mod particles;
+#[cfg(gdnative_helper = "lighting")]
+mod lighting;
+#[cfg(gdnative_helper = "texture_atlas")]
+mod texture_atlas;
+#[cfg(gdnative_helper = "script_templates")]
+mod script_templates;
+#[cfg(gdnative_helper = "memory_report")]
+mod memory_report;
+#[cfg(gdnative_helper = "body_state")]
+mod body_state;
+#[cfg(gdnative_helper = "tasks")]
+mod tasks;
+#[cfg(gdnative_helper = "tasks")]
+mod function_state;
+#[cfg(gdnative_helper = "tasks")]
+mod property_observer;
+#[cfg(gdnative_helper = "tasks")]
+mod deferred_init;
+#[cfg(gdnative_helper = "downloader")]
+mod downloader;
+#[cfg(gdnative_helper = "singleton")]
+mod singleton;
+#[cfg(gdnative_helper = "input_map")]
+mod input_map;
+#[cfg(feature = "checked_calls")]
+mod checked;
+#[cfg(feature = "crash_dump")]
+mod crash_dump;
+#[cfg(feature = "serde")]
+mod variant_serde;
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
+#[cfg(feature = "fuzzing")]
+mod mock_engine;
+#[cfg(feature = "log")]
+mod logger;
+#[cfg(feature = "rayon")]
+mod parallel;
 pub mod init;
+pub mod profiler;
+pub mod reload;
+pub mod prelude;
+pub mod migration;
 
 pub use internal::*;
+pub use allocator::GodotAllocator;
+#[doc(hidden)]
+pub use allocator::{alloc_boxed, free_boxed};
+pub use timing::startup_timing_report;
+pub use info::{ApiVersion, InitializeInfo, LibraryInfo};
+#[doc(hidden)]
+pub use diagnostics::{catch_panic, install_panic_hook, print_message, record_message, MessageKind};
+#[doc(hidden)]
+pub use info::{set_library_path, GDNativeInfo};
+#[doc(hidden)]
+pub use timing::print_startup_timing_report;
+#[doc(hidden)]
+pub use instance_borrow::{with_instance, with_instance_mut};
+#[doc(hidden)]
+pub use nativescript_1_1::{bind_nativescript_1_1, unbind_nativescript_1_1};
 pub use class::*;
 pub use godot_type::*;
 pub use variant::*;
@@ -67,6 +200,11 @@ pub use geom::*;
 pub use color::*;
 pub use rid::*;
 pub use node_path::*;
+pub use node_path_property::*;
+pub use node_access::*;
+pub use call::*;
+pub use native_function::*;
+pub use resource_property::*;
 pub use generated::*;
 pub use string::*;
 pub use byte_array::*;
@@ -76,7 +214,67 @@ pub use string_array::*;
 pub use vector2_array::*;
 pub use vector3_array::*;
 pub use color_array::*;
+pub use array_iter::*;
+pub use pool_access::*;
+pub use pool_stream::*;
+pub use collision_layers::*;
+pub use type_tag::{type_tag_interface, TypeTag, TypeTagInterface, TYPE_TAG_INTERFACE_VERSION};
+pub use adhoc_script::*;
+pub use signal_relay::*;
+pub use user_data::*;
+pub use variant_diff::*;
+pub use event_bus::*;
+pub use configuration_warning::*;
+pub use features::*;
+pub use terminate::*;
+pub use accessibility::*;
+pub use number_format::*;
+#[cfg(gdnative_helper = "curve")]
+pub use curve::*;
+#[cfg(gdnative_helper = "grid_map")]
+pub use grid_map::*;
+#[cfg(gdnative_helper = "particles")]
+pub use particles::*;
+#[cfg(gdnative_helper = "lighting")]
+pub use lighting::*;
+#[cfg(gdnative_helper = "texture_atlas")]
+pub use texture_atlas::*;
+#[cfg(gdnative_helper = "script_templates")]
+pub use script_templates::*;
+#[cfg(gdnative_helper = "memory_report")]
+pub use memory_report::*;
+#[cfg(gdnative_helper = "body_state")]
+pub use body_state::*;
+#[cfg(gdnative_helper = "tasks")]
+pub use tasks::*;
+#[cfg(gdnative_helper = "tasks")]
+pub use function_state::*;
+#[cfg(gdnative_helper = "tasks")]
+pub use property_observer::*;
+#[cfg(gdnative_helper = "tasks")]
+pub use deferred_init::*;
+#[cfg(gdnative_helper = "downloader")]
+pub use downloader::*;
+#[cfg(gdnative_helper = "singleton")]
+pub use singleton::*;
+#[cfg(gdnative_helper = "input_map")]
+pub use input_map::*;
+#[cfg(feature = "checked_calls")]
+pub use checked::*;
+#[cfg(feature = "crash_dump")]
+pub use crash_dump::{install_crash_dump, write_crash_report, CrashDumpOptions};
+#[cfg(feature = "serde")]
+pub use variant_serde::*;
+#[cfg(feature = "fuzzing")]
+pub use fuzzing::*;
+#[cfg(feature = "fuzzing")]
+pub use mock_engine::install_mock_engine;
+#[cfg(feature = "log")]
+pub use logger::GodotLogger;
+#[cfg(feature = "rayon")]
+pub use parallel::*;
 pub use object::GodotObject;
+pub use gdnative_derive::{Export, FromVariant, NativeClass, ToVariant, methods};
 
 use std::mem;
 
@@ -85,7 +283,54 @@ pub static mut GODOT_API: Option<GodotApi> = None;
 #[inline]
 #[doc(hidden)]
 pub fn get_api() -> &'static GodotApi {
-    unsafe { GODOT_API.as_ref().expect("API not bound") }
+    match try_get_api() {
+        Some(api) => api,
+        None => api_not_bound(),
+    }
+}
+
+/// Returns `true` if the library was initialized by the engine.
+///
+/// When the library is loaded by something other than Godot (unit tests, tools
+/// inspecting the binary, ...) `godot_gdnative_init` is never called and the
+/// engine's API is not available. In this "stub mode" the printing macros fall
+/// back to the standard output and error streams, and the types wrapping engine
+/// objects panic with a description of the problem when used.
+#[inline]
+pub fn is_api_bound() -> bool {
+    try_get_api().is_some()
+}
+
+#[cold]
+#[inline(never)]
+fn api_not_bound() -> ! {
+    #[cfg(feature = "crash_dump")]
+    crash_dump::fatal_error_detected();
+
+    panic!(
+        "The Godot API is not bound: this library was not initialized by the engine \
+        (godot_gdnative_init was not called, or godot_gdnative_terminate was already called). \
+        Engine types such as GodotString, Variant or the generated classes can only be used \
+        while the library is loaded by Godot."
+    )
+}
+
+/// The raw API of the engine, for the functions the wrappers don't cover.
+///
+/// The wrappers give access to their raw values with `as_sys`, `as_mut_sys`
+/// and `from_sys`, and the classes with `GodotObject::to_sys` and
+/// `GodotObject::from_sys`. Calling the functions is unsafe: they follow the C
+/// conventions of the engine, and the caller is responsible for the ownership of
+/// the values they initialize or return, see `from_sys` and `forget`.
+///
+/// Panics if the library is not initialized, see `is_api_bound`.
+pub fn sys_api() -> &'static GodotApi {
+    get_api()
+}
+
+#[inline]
+pub(crate) fn try_get_api() -> Option<&'static GodotApi> {
+    unsafe { GODOT_API.as_ref() }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -150,3 +395,17 @@ pub fn result_from_sys(err: sys::godot_error) -> GodotResult {
 
     Err(unsafe { mem::transmute(err) })
 }
+
+#[test]
+fn stub_mode_printing() {
+    assert!(!is_api_bound());
+    godot_print!("printed to stdout outside of the engine");
+    godot_warn!("printed to stderr outside of the engine");
+    assert_eq!(godot_dbg!(1 + 1), 2);
+}
+
+#[test]
+#[should_panic(expected = "not initialized by the engine")]
+fn stub_mode_engine_types() {
+    GodotString::from_str("needs the engine");
+}
@@ -0,0 +1,166 @@
+//! Typed helpers for lights and environments, for example to animate the lighting of
+//! a day/night cycle.
+//!
+//! ```ignore
+//! let t = time_of_day.sun_elevation();
+//! sun.set_sun_angles(t * 180.0, 30.0);
+//! sun.set_energy(t.sin().max(0.0));
+//!
+//! env.set_ambient_light(Color::rgb(0.2, 0.2, 0.3), 0.5);
+//! env.set_fog(&Fog { color: Color::rgb(0.5, 0.6, 0.7), depth_begin: 20.0, ..Fog::default() });
+//! ```
+//!
+//! The engine API these bindings are generated from has no sky rotation. With a
+//! `ProceduralSky`, the sun can be moved with `ProceduralSky::set_sun_position`.
+
+use Color;
+use DirectionalLight;
+use DirectionalLightShadowMode;
+use Environment;
+use EnvironmentBGMode;
+use EnvironmentToneMapper;
+use Light;
+use LightParam;
+use ProceduralSky;
+use Vector3;
+
+impl Light {
+    pub fn set_parameter(&mut self, param: LightParam, value: f64) {
+        self.set_param(param as i64, value);
+    }
+
+    pub fn parameter(&self, param: LightParam) -> f64 {
+        self.get_param(param as i64)
+    }
+
+    pub fn set_energy(&mut self, energy: f64) {
+        self.set_parameter(LightParam::ParamEnergy, energy);
+    }
+
+    pub fn energy(&self) -> f64 {
+        self.parameter(LightParam::ParamEnergy)
+    }
+
+    pub fn set_indirect_energy(&mut self, energy: f64) {
+        self.set_parameter(LightParam::ParamIndirectEnergy, energy);
+    }
+
+    pub fn indirect_energy(&self) -> f64 {
+        self.parameter(LightParam::ParamIndirectEnergy)
+    }
+}
+
+impl DirectionalLight {
+    pub fn set_directional_shadow_mode(&mut self, mode: DirectionalLightShadowMode) {
+        self.set_shadow_mode(mode as i64);
+    }
+
+    /// Points the light like a sun at the given elevation above the horizon and
+    /// azimuth around the vertical axis, in degrees.
+    pub fn set_sun_angles(&mut self, elevation: f32, azimuth: f32) {
+        self.set_rotation_degrees(Vector3::new(-elevation, azimuth, 0.0));
+    }
+}
+
+/// The fog settings of an `Environment`, see `Environment::set_fog`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fog {
+    pub enabled: bool,
+    pub color: Color,
+    pub sun_color: Color,
+    pub sun_amount: f64,
+    pub depth_enabled: bool,
+    pub depth_begin: f64,
+    pub depth_curve: f64,
+    pub height_enabled: bool,
+    pub height_min: f64,
+    pub height_max: f64,
+    pub height_curve: f64,
+    pub transmit_enabled: bool,
+    pub transmit_curve: f64,
+}
+
+impl Default for Fog {
+    /// The default settings of the engine, with the fog enabled.
+    fn default() -> Self {
+        Fog {
+            enabled: true,
+            color: Color::rgb(0.5, 0.6, 0.7),
+            sun_color: Color::rgb(1.0, 0.9, 0.7),
+            sun_amount: 0.0,
+            depth_enabled: true,
+            depth_begin: 10.0,
+            depth_curve: 1.0,
+            height_enabled: false,
+            height_min: 0.0,
+            height_max: 100.0,
+            height_curve: 1.0,
+            transmit_enabled: false,
+            transmit_curve: 1.0,
+        }
+    }
+}
+
+impl Environment {
+    pub fn set_background_mode(&mut self, background: EnvironmentBGMode) {
+        self.set_background(background as i64);
+    }
+
+    pub fn set_tone_mapper(&mut self, tone_mapper: EnvironmentToneMapper) {
+        self.set_tonemapper(tone_mapper as i64);
+    }
+
+    pub fn set_ambient_light(&mut self, color: Color, energy: f64) {
+        self.set_ambient_light_color(color);
+        self.set_ambient_light_energy(energy);
+    }
+
+    /// Applies all the fog settings at once.
+    pub fn set_fog(&mut self, fog: &Fog) {
+        self.set_fog_enabled(fog.enabled);
+        self.set_fog_color(fog.color);
+        self.set_fog_sun_color(fog.sun_color);
+        self.set_fog_sun_amount(fog.sun_amount);
+        self.set_fog_depth_enabled(fog.depth_enabled);
+        self.set_fog_depth_begin(fog.depth_begin);
+        self.set_fog_depth_curve(fog.depth_curve);
+        self.set_fog_height_enabled(fog.height_enabled);
+        self.set_fog_height_min(fog.height_min);
+        self.set_fog_height_max(fog.height_max);
+        self.set_fog_height_curve(fog.height_curve);
+        self.set_fog_transmit_enabled(fog.transmit_enabled);
+        self.set_fog_transmit_curve(fog.transmit_curve);
+    }
+
+    /// Reads all the fog settings.
+    pub fn fog(&self) -> Fog {
+        Fog {
+            enabled: self.is_fog_enabled(),
+            color: self.get_fog_color(),
+            sun_color: self.get_fog_sun_color(),
+            sun_amount: self.get_fog_sun_amount(),
+            depth_enabled: self.is_fog_depth_enabled(),
+            depth_begin: self.get_fog_depth_begin(),
+            depth_curve: self.get_fog_depth_curve(),
+            height_enabled: self.is_fog_height_enabled(),
+            height_min: self.get_fog_height_min(),
+            height_max: self.get_fog_height_max(),
+            height_curve: self.get_fog_height_curve(),
+            transmit_enabled: self.is_fog_transmit_enabled(),
+            transmit_curve: self.get_fog_transmit_curve(),
+        }
+    }
+
+    /// Returns the sky if it is a `ProceduralSky`.
+    pub fn procedural_sky(&self) -> Option<ProceduralSky> {
+        self.get_sky().and_then(|sky| sky.cast())
+    }
+}
+
+impl ProceduralSky {
+    /// Moves the sun, in degrees.
+    pub fn set_sun_position(&mut self, latitude: f64, longitude: f64) {
+        self.set_sun_latitude(latitude);
+        self.set_sun_longitude(longitude);
+    }
+}
@@ -0,0 +1,104 @@
+//! A backend of the `log` crate printing to the output of the engine, with the
+//! `log` feature.
+//!
+//! Libraries using the macros of `log` show their messages in the output panel of
+//! the editor once the logger is installed:
+//!
+//! ```ignore
+//! fn init(_info: &gdnative::InitializeInfo) {
+//!     gdnative::GodotLogger::init(log::LevelFilter::Debug).unwrap();
+//! }
+//!
+//! godot_gdnative_init!(init);
+//! ```
+//!
+//! Errors and warnings are reported like `godot_error!` and `godot_warn!`, with the
+//! target as function name and the file and line of the record. The other levels
+//! are printed like `godot_print!`:
+//!
+//! ```text
+//! INFO my_game::level: loaded 12 chunks (src/level.rs:48)
+//! ```
+
+use log::{self, Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use diagnostics::{print_message, MessageKind};
+
+static LOGGER: GodotLogger = GodotLogger;
+
+/// Prints the records of the `log` crate to the output of the engine, see the
+/// `logger` module.
+///
+/// The records above the maximum level of `log` are ignored.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GodotLogger;
+
+impl GodotLogger {
+    /// Installs the logger as the logger of `log`, with `level` as maximum level.
+    ///
+    /// Fails if a logger was already installed, by another library of the process for
+    /// example.
+    pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_logger(&LOGGER)?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for GodotLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let kind = match record.level() {
+            Level::Error => MessageKind::Error,
+            Level::Warn => MessageKind::Warning,
+            _ => {
+                godot_print!("{}", format_record(record));
+                return;
+            }
+        };
+
+        print_message(
+            kind,
+            &record.args().to_string(),
+            record.target(),
+            record.file().unwrap_or("<unknown>"),
+            record.line().unwrap_or(0),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+fn format_record(record: &Record) -> String {
+    let mut line = format!("{} {}: {}", record.level(), record.target(), record.args());
+    if let (Some(file), Some(number)) = (record.file(), record.line()) {
+        line.push_str(&format!(" ({}:{})", file, number));
+    }
+    line
+}
+
+#[test]
+fn record_format() {
+    // The arguments are temporaries, the records are formatted in the same statement.
+    let line = format_record(&Record::builder()
+        .level(Level::Info)
+        .target("my_game::level")
+        .args(format_args!("loaded {} chunks", 12))
+        .file(Some("src/level.rs"))
+        .line(Some(48))
+        .build());
+    assert_eq!(line, "INFO my_game::level: loaded 12 chunks (src/level.rs:48)");
+
+    let line = format_record(&Record::builder()
+        .level(Level::Debug)
+        .target("net")
+        .args(format_args!("ping"))
+        .build());
+    assert_eq!(line, "DEBUG net: ping");
+}
@@ -13,10 +13,12 @@
 ///
 /// Overriding the default entry point names can be useful if several gdnative
 /// libraries are linked statically  to avoid name clashes.
+///
+/// The callback receives the options of the engine as an `&InitializeInfo`.
 #[macro_export]
 macro_rules! godot_gdnative_init {
     () => {
-        fn godot_gdnative_init_empty(_options: *mut $crate::sys::godot_gdnative_init_options) {}
+        fn godot_gdnative_init_empty(_info: &$crate::InitializeInfo) {}
         godot_gdnative_init!(godot_gdnative_init_empty);
     };
     ($callback:ident) => {
@@ -35,13 +37,51 @@ macro_rules! godot_gdnative_init {
             // without checking for initialization.
             $crate::ReferenceMethodTable::get(api);
 
-            $callback(options);
+            unsafe {
+                $crate::set_library_path(options);
+                $crate::bind_nativescript_1_1(options);
+            }
+            $crate::reload::take_reload_state();
+            $crate::install_panic_hook();
+
+            $callback(&unsafe { $crate::InitializeInfo::new(options) });
+        }
+    };
+}
+
+/// Runs the statements only when the code runs in the editor, see
+/// `is_editor_hint`.
+///
+/// ```ignore
+/// editor_only! {
+///     owner.update_configuration_warning();
+/// }
+/// ```
+#[macro_export]
+macro_rules! editor_only {
+    ($($body:tt)*) => {
+        if $crate::is_editor_hint() {
+            $($body)*
+        }
+    };
+}
+
+/// Runs the statements only in the editor and the debug export templates, see
+/// `is_debug_build`.
+#[macro_export]
+macro_rules! debug_only {
+    ($($body:tt)*) => {
+        if $crate::is_debug_build() {
+            $($body)*
         }
     };
 }
 
 /// Declare the API endpoint invoked during shutdown.
 ///
+/// The callback runs first, then the callbacks registered with `reload::on_unload`
+/// and with `on_terminate`.
+///
 /// By default this declares an extern function named `godot_gdnative_terminate`.
 /// This can be overridden, for example:
 ///
@@ -67,8 +107,12 @@ macro_rules! godot_gdnative_terminate {
         #[doc(hidden)]
         pub extern "C" fn $fn_name(options: *mut $crate::sys::godot_gdnative_terminate_options) {
             $callback(options);
+            $crate::reload::unload(unsafe { (*options).in_editor });
+            $crate::run_terminate_callbacks(unsafe { (*options).in_editor });
 
             unsafe {
+                $crate::unbind_nativescript_1_1();
+                $crate::clear_string_cache();
                 $crate::GODOT_API = None;
             }
         }
@@ -102,70 +146,126 @@ macro_rules! godot_nativescript_init {
         #[doc(hidden)]
         pub extern "C" fn $fn_name(handle: *mut $crate::libc::c_void) {
             unsafe {
-                $callback($crate::init::InitHandle::new(handle));
+                $crate::init::run_nativescript_init(handle, $callback);
             }
+            $crate::init::all_classes_registered();
+            $crate::print_startup_timing_report();
         }
     };
 }
 
+/// Register the `GDNativeInfo` diagnostic class, listing the classes registered by
+/// the library, its name and version and the path and build time of the loaded
+/// binary.
+///
+/// Must be invoked from the nativescript init callback:
+///
+/// ```ignore
+/// fn init(handle: gdnative::init::InitHandle) {
+///     godot_gdnative_info!(handle);
+///     // Or, to avoid name clashes when several rust libraries are loaded:
+///     godot_gdnative_info!(handle as MyLibraryInfo);
+/// }
+/// ```
+///
+/// From GDScript, `GDNativeInfo.new().list()` prints a report and `get_info()`
+/// returns the same information as a dictionary.
+#[macro_export]
+macro_rules! godot_gdnative_info {
+    ($handle:ident) => {
+        godot_gdnative_info!($handle as GDNativeInfo);
+    };
+    ($handle:ident as $class_name:ident) => {
+        $handle.add_info_class(
+            stringify!($class_name),
+            $crate::LibraryInfo {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            }
+        );
+    };
+}
+
 /// Print a message using the engine's logging system (visible in the editor).
 #[macro_export]
 macro_rules! godot_print {
     ($($args:tt)*) => ({
         let msg = format!($($args)*);
+        $crate::record_message(&msg);
 
-        #[allow(unused_unsafe)]
-        unsafe {
-            let msg = $crate::GodotString::from_str(msg);
-            ($crate::get_api().godot_print)(&msg.to_sys() as *const _);
+        if !$crate::is_api_bound() {
+            println!("{}", msg);
+        } else {
+            #[allow(unused_unsafe)]
+            unsafe {
+                let msg = $crate::GodotString::from_str(msg);
+                ($crate::get_api().godot_print)(&msg.to_sys() as *const _);
+            }
         }
     });
 }
 
 /// Print a warning using the engine's logging system (visible in the editor).
+///
+/// The message is reported with the module path, file and line of the invocation.
+/// When backtraces are enabled (with the `RUST_BACKTRACE` environment variable), a
+/// compact backtrace of the rust frames is appended to the message.
 #[macro_export]
 macro_rules! godot_warn {
     ($($args:tt)*) => ({
-        let msg = format!($($args)*);
-        let line = line!();
-        let file = file!();
-        #[allow(unused_unsafe)]
-        unsafe {
-            let msg = ::std::ffi::CString::new(msg).unwrap();
-            let file = ::std::ffi::CString::new(file).unwrap();
-            let func = b"<native>\0";
-            ($crate::get_api().godot_print_warning)(
-                msg.as_ptr() as *const _,
-                func.as_ptr() as *const _,
-                file.as_ptr() as *const _,
-                line as _,
-            );
-        }
+        $crate::print_message(
+            $crate::MessageKind::Warning,
+            &format!($($args)*),
+            module_path!(),
+            file!(),
+            line!(),
+        );
     })
 }
 
 /// Print an error using the engine's logging system (visible in the editor).
+///
+/// The message is reported with the module path, file and line of the invocation.
+/// When backtraces are enabled (with the `RUST_BACKTRACE` environment variable), a
+/// compact backtrace of the rust frames is appended to the message.
 #[macro_export]
 macro_rules! godot_error {
     ($($args:tt)*) => ({
-        let msg = format!($($args)*);
-        let line = line!();
-        let file = file!();
-        #[allow(unused_unsafe)]
-        unsafe {
-            let msg = ::std::ffi::CString::new(msg).unwrap();
-            let file = ::std::ffi::CString::new(file).unwrap();
-            let func = b"<native>\0";
-            ($crate::get_api().godot_print_error)(
-                msg.as_ptr() as *const _,
-                func.as_ptr() as *const _,
-                file.as_ptr() as *const _,
-                line as _,
-            );
-        }
+        $crate::print_message(
+            $crate::MessageKind::Error,
+            &format!($($args)*),
+            module_path!(),
+            file!(),
+            line!(),
+        );
     })
 }
 
+/// Prints and returns the value of an expression, like `std::dbg!` but using the
+/// engine's logging system.
+///
+/// ```ignore
+/// let velocity = godot_dbg!(direction * speed);
+/// // Prints "[src/player.rs:12] direction * speed = (1.0, 0.0)"
+/// ```
+#[macro_export]
+macro_rules! godot_dbg {
+    () => {
+        godot_print!("[{}:{}]", file!(), line!())
+    };
+    ($val:expr) => {
+        match $val {
+            tmp => {
+                godot_print!("[{}:{}] {} = {:#?}", file!(), line!(), stringify!($val), &tmp);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)*) => {
+        ($(godot_dbg!($val)),+,)
+    };
+}
+
 macro_rules! impl_basic_trait {
     (
         Drop for $Type:ident as $GdType:ident : $gd_method:ident
@@ -247,6 +347,61 @@ macro_rules! impl_basic_traits {
     )
 }
 
+// The escape hatches to the raw API for the functions the wrappers don't cover,
+// see `sys_api`. `Variant` and `GodotString` implement them by hand.
+macro_rules! impl_sys_access {
+    (for $Type:ident as $GdType:ident) => (
+        impl_sys_access!(for $Type as $GdType without forget);
+
+        impl $Type {
+            /// Returns the raw value and consumes the wrapper without destroying
+            /// it.
+            ///
+            /// The caller becomes responsible for destroying the value, or for
+            /// passing it to a function of the API taking ownership of it,
+            /// otherwise it is leaked.
+            pub fn forget(self) -> sys::$GdType {
+                let v = self.0;
+                ::std::mem::forget(self);
+                v
+            }
+        }
+    );
+    (for $Type:ident as $GdType:ident without forget) => (
+        impl $Type {
+            /// The raw value, to pass to the functions of `sys_api()`.
+            ///
+            /// The value remains owned by the wrapper, the functions must not
+            /// destroy it.
+            pub fn as_sys(&self) -> &sys::$GdType {
+                &self.0
+            }
+
+            /// The raw value, to pass to the functions of `sys_api()` modifying
+            /// it in place.
+            ///
+            /// The value remains owned by the wrapper, the functions must not
+            /// destroy it or overwrite it without destroying it first.
+            pub fn as_mut_sys(&mut self) -> &mut sys::$GdType {
+                &mut self.0
+            }
+
+            /// Takes ownership of a raw value, the wrapper destroys it when
+            /// dropped.
+            ///
+            /// # Safety
+            ///
+            /// `sys` must have been initialized by a constructor of the API, or
+            /// returned by a function of the API passing ownership to the caller.
+            /// Nothing else may destroy it afterwards, including the wrapper it
+            /// was taken from with `as_sys`.
+            pub unsafe fn from_sys(sys: sys::$GdType) -> Self {
+                $Type(sys)
+            }
+        }
+    );
+}
+
 macro_rules! impl_common_method {
     (
         $(#[$attr:meta])*
@@ -312,13 +467,11 @@ macro_rules! godot_wrap_constructor {
                 this: *mut $crate::sys::godot_object,
                 _method_data: *mut $crate::libc::c_void
             ) -> *mut $crate::libc::c_void {
-                use std::cell::RefCell;
-                use std::boxed::Box;
+                use $crate::UserData;
 
                 let val = $c($crate::NativeInstanceHeader{ this: this });
 
-                let wrapper = Box::new(RefCell::new(val));
-                Box::into_raw(wrapper) as *mut _
+                $crate::alloc_boxed(<$_name as $crate::NativeClass>::UserData::new(val)) as *mut _
             }
 
             constructor
@@ -338,11 +491,7 @@ macro_rules! godot_wrap_destructor {
                 _method_data: *mut $crate::libc::c_void,
                 user_data: *mut $crate::libc::c_void
             ) -> () {
-                use std::cell::RefCell;
-                use std::boxed::Box;
-
-                let wrapper: Box<RefCell<$name>> = unsafe { Box::from_raw(user_data as *mut _) };
-                drop(wrapper)
+                unsafe { $crate::free_boxed(user_data as *mut <$name as $crate::NativeClass>::UserData) }
             }
 
             destructor
@@ -363,17 +512,66 @@ macro_rules! godot_wrap_method_parameter_count {
 
 /// Convenience macro to wrap an object's method into a function pointer
 /// that can be passed to the engine when registering a class.
+///
+/// The wrapper extracts the instance from the user data, converts the arguments
+/// from variants and the return value to a variant, and catches panics. Wrong
+/// argument counts or types are reported with `godot_error!` and return nil.
+///
+/// The first parameter can be marked with `#[owner]` to receive the engine object
+/// the script is attached to:
+///
+/// ```ignore
+/// impl MyClass {
+///     fn describe(&mut self, owner: Node, prefix: GodotString) -> GodotString {
+///         // ...
+///     }
+/// }
+///
+/// godot_wrap_method!(
+///     MyClass,
+///     fn describe(&mut self, #[owner] owner: Node, prefix: GodotString) -> GodotString
+/// )
+/// ```
+///
+/// The required parameters can be followed by optional parameters marked with
+/// `#[opt]` that take their default value when the caller omits them, and by a
+/// `#[rest]` parameter of type `Vec<Variant>` or `&[Variant]` that receives the
+/// remaining arguments:
+///
+/// ```ignore
+/// godot_wrap_method!(
+///     MyClass,
+///     fn log(&mut self, level: i64, #[opt] tag: GodotString = GodotString::new(), #[rest] values: Vec<Variant>) -> ()
+/// )
+/// ```
+///
+/// The wrapper only converts the arguments. The callers of the script editor and
+/// `get_method_list` see the names of the parameters and the defaults once they are
+/// registered with `godot_method_arguments!`.
+///
+/// Methods taking `&mut self` borrow the instance mutably. Methods taking `&self`
+/// only borrow it immutably and, with the default `LocalCellData` wrapper, can run
+/// while other `&self` methods of the instance are running, for example when a
+/// signal emitted by one of them is connected to the other. Conflicting borrows are
+/// reported with the names of both methods.
+///
+/// With the `method_profiling` feature, each call is reported to the profiler of the
+/// editor, see the `profiler` module.
 #[macro_export]
 macro_rules! godot_wrap_method {
     (
+        @wrap $borrow:ident,
         $type_name:ty,
         fn $method_name:ident(
-            &mut $self:ident
-            $(,$pname:ident : $pty:ty)*
+            $self:ident
+            $(, #[owner] $owner_name:ident : $owner_ty:ty)*
+            $(, $pname:ident : $pty:ty)*
+            $(, #[opt] $oname:ident : $oty:ty = $odefault:expr)*
+            $(, #[rest] $rname:ident : $rty:ty)*
         ) -> $retty:ty
     ) => {
         {
-            #[allow(unused_unsafe, unused_variables, unused_assignments, unused_mut)]
+            #[allow(unused_unsafe, unused_variables, unused_assignments, unused_mut, unused_comparisons)]
             unsafe extern "C" fn method(
                 this: *mut $crate::sys::godot_object,
                 method_data: *mut $crate::libc::c_void,
@@ -382,43 +580,332 @@ macro_rules! godot_wrap_method {
                 args: *mut *mut $crate::sys::godot_variant
             ) -> $crate::sys::godot_variant {
 
-                use std::cell::RefCell;
-                use std::panic::{self, AssertUnwindSafe};
-
-                let num_params = godot_wrap_method_parameter_count!($($pname,)*);
-                if num_args != num_params {
-                    godot_error!("Incorrect number of parameters: expected {} but got {}", num_params, num_args);
+                let num_required = godot_wrap_method_parameter_count!($($pname,)*);
+                let num_optional = godot_wrap_method_parameter_count!($($oname,)*);
+                let has_rest = godot_wrap_method_parameter_count!($($rname,)*) > 0;
+                if num_args < num_required || (!has_rest && num_args > num_required + num_optional) {
+                    if has_rest {
+                        godot_error!("Incorrect number of parameters: expected at least {} but got {}", num_required, num_args);
+                    } else if num_optional > 0 {
+                        godot_error!("Incorrect number of parameters: expected {} to {} but got {}", num_required, num_required + num_optional, num_args);
+                    } else {
+                        godot_error!("Incorrect number of parameters: expected {} but got {}", num_required, num_args);
+                    }
                     return $crate::Variant::new().to_sys();
                 }
 
                 let mut offset = 0;
                 $(
-                    let $pname = if let Some(val) = <$pty as $crate::GodotType>::from_sys_variant(&mut *(*args).offset(offset)) {
-                        val
+                    let $pname = match <$pty as $crate::FromVariant>::try_from_sys_variant(&*(*args.offset(offset))) {
+                        Ok(val) => val,
+                        Err(err) => {
+                            godot_error!("Incorrect argument type for argument {}: {}", offset, err);
+                            return $crate::Variant::new().to_sys();
+                        }
+                    };
+
+                    offset += 1;
+                )*
+
+                $(
+                    let $oname: $oty = if offset < num_args as isize {
+                        match <$oty as $crate::FromVariant>::try_from_sys_variant(&*(*args.offset(offset))) {
+                            Ok(val) => val,
+                            Err(err) => {
+                                godot_error!("Incorrect argument type for argument {}: {}", offset, err);
+                                return $crate::Variant::new().to_sys();
+                            }
+                        }
                     } else {
-                        godot_error!("Incorrect argument type for argument {}", offset);
-                        return $crate::Variant::new().to_sys();
+                        $odefault
                     };
 
                     offset += 1;
                 )*
 
-                let __rust_val = &*(user_data as *mut RefCell<$type_name>);
-                let mut __rust_val = __rust_val.borrow_mut();
+                $(
+                    let mut __rest_args: Vec<$crate::Variant> = (offset..num_args as isize)
+                        .filter_map(|idx| <$crate::Variant as $crate::FromVariant>::from_sys_variant(&*(*args.offset(idx))))
+                        .collect();
+                    let $rname: $rty = $crate::Varargs::from_varargs(&mut __rest_args);
+                )*
 
-                let rust_ret = match panic::catch_unwind(AssertUnwindSafe(|| {
-                    __rust_val.$method_name($($pname,)*)
-                })) {
-                    Ok(val) => val,
-                    Err(err) => {
-                        return $crate::Variant::new().to_sys();
-                    }
-                };
+                $(
+                    let $owner_name = <$owner_ty as $crate::GodotObject>::from_sys(this);
+                )*
 
-                <$retty as $crate::GodotType>::to_variant(&rust_ret).forget()
+                // The return value is converted inside of the closure since it may
+                // borrow from the instance.
+                let __member = concat!(stringify!($type_name), "::", stringify!($method_name));
+                let ret = $crate::$borrow::<$type_name, _, _>(
+                    user_data,
+                    __member,
+                    |__rust_val| $crate::catch_panic(__member, || {
+                        let _timer = $crate::profiler::method_timer(concat!(
+                            file!(), "::", line!(), "::", stringify!($type_name), ".", stringify!($method_name)
+                        ));
+                        let rust_ret: $retty = __rust_val.$method_name($($owner_name,)* $($pname,)* $($oname,)* $($rname,)*);
+                        $crate::method_return::<$type_name, $retty>(__member, &rust_ret)
+                    })
+                );
+
+                match ret {
+                    Some(Some(val)) => val.forget(),
+                    _ => $crate::Variant::new().to_sys(),
+                }
             }
 
             method
         }
     };
-}
\ No newline at end of file
+    (
+        $type_name:ty,
+        fn $method_name:ident(&mut $self:ident $($args:tt)*) -> $retty:ty
+    ) => {
+        godot_wrap_method!(@wrap with_instance_mut, $type_name, fn $method_name($self $($args)*) -> $retty)
+    };
+    (
+        $type_name:ty,
+        fn $method_name:ident(&$self:ident $($args:tt)*) -> $retty:ty
+    ) => {
+        godot_wrap_method!(@wrap with_instance, $type_name, fn $method_name($self $($args)*) -> $retty)
+    };
+}
+
+/// The arguments of a method wrapped with `godot_wrap_method!`, with the defaults of
+/// its `#[opt]` parameters, for `ClassBuilder::set_method_arguments`. Takes the same
+/// input as `godot_wrap_method!`:
+///
+/// ```ignore
+/// builder.add_method("log", godot_wrap_method!(
+///     MyClass,
+///     fn log(&mut self, level: i64, #[opt] tag: GodotString = GodotString::new(), #[rest] values: Vec<Variant>) -> ()
+/// ));
+/// builder.set_method_arguments("log", &godot_method_arguments!(
+///     MyClass,
+///     fn log(&mut self, level: i64, #[opt] tag: GodotString = GodotString::new(), #[rest] values: Vec<Variant>) -> ()
+/// ));
+/// ```
+///
+/// The `#[owner]` and `#[rest]` parameters are not arguments of the method in the
+/// engine, which has no variadic arguments.
+#[macro_export]
+macro_rules! godot_method_arguments {
+    (
+        @arguments $self:ident
+        $(, #[owner] $owner_name:ident : $owner_ty:ty)*
+        $(, $pname:ident : $pty:ty)*
+        $(, #[opt] $oname:ident : $oty:ty = $odefault:expr)*
+        $(, #[rest] $rname:ident : $rty:ty)*
+    ) => {
+        {
+            let arguments: Vec<$crate::init::MethodArgument> = vec![
+                $($crate::init::MethodArgument::new(stringify!($pname)),)*
+                $(
+                    $crate::init::MethodArgument::new(stringify!($oname))
+                        .with_default({ let default: $oty = $odefault; default }),
+                )*
+            ];
+            arguments
+        }
+    };
+    (
+        $type_name:ty,
+        fn $method_name:ident(&mut $self:ident $($args:tt)*) -> $retty:ty
+    ) => {
+        godot_method_arguments!(@arguments $self $($args)*)
+    };
+    (
+        $type_name:ty,
+        fn $method_name:ident(&$self:ident $($args:tt)*) -> $retty:ty
+    ) => {
+        godot_method_arguments!(@arguments $self $($args)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! godot_derive_value {
+    (; $fallback:expr) => { $fallback };
+    ($value:expr; $fallback:expr) => { $value };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! godot_derive_type {
+    (; $fallback:ty) => { $fallback };
+    ($value:ty; $fallback:ty) => { $value };
+}
+
+/// Asserts that two variants are equal, comparing dictionaries and arrays
+/// recursively. On failure, panics with the differences listed by `variant_diff`.
+///
+/// ```ignore
+/// assert_variant_eq!(expected, node.call("save".into(), &[]));
+/// assert_variant_eq!(expected, actual, "after loading {}", path);
+/// ```
+#[macro_export]
+macro_rules! assert_variant_eq {
+    ($expected:expr, $actual:expr) => {
+        if let Some(report) = $crate::variant_diff(&$expected, &$actual) {
+            panic!("{}", report);
+        }
+    };
+    ($expected:expr, $actual:expr, $($arg:tt)+) => {
+        if let Some(report) = $crate::variant_diff(&$expected, &$actual) {
+            panic!("{}: {}", format_args!($($arg)+), report);
+        }
+    };
+}
+
+/// Reports the time spent in a block to the profiler of the editor, see the
+/// `profiler` module.
+///
+/// The block is timed under the signature `file::line::tag`, the tag must be a
+/// string literal.
+///
+/// ```ignore
+/// let path = profiled!("find_path", {
+///     navigation.get_simple_path(from, to, true)
+/// });
+/// ```
+#[macro_export]
+macro_rules! profiled {
+    ($tag:expr, $body:block) => {
+        {
+            let _timer = $crate::profiler::Timer::start(concat!(file!(), "::", line!(), "::", $tag));
+            $body
+        }
+    };
+}
+
+/// Calls a method of an object by name, converting the arguments with `ToVariant`
+/// and the result with `FromVariant`. Returns a `Result<R, CallError>`, see the
+/// `call` module.
+///
+/// ```ignore
+/// let health: i64 = call!(enemy, "take_damage", 10, true)?;
+/// call!(node, "add_child", Variant::from_object(child))?;
+/// ```
+#[macro_export]
+macro_rules! call {
+    ($object:expr, $method:expr $(, $arg:expr)* $(,)*) => {
+        $crate::call(&$object, $method, &[$($crate::ToVariant::to_variant(&$arg)),*])
+    };
+}
+
+/// Implementation detail of `#[derive(NativeClass)]`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! godot_derive_native_class {
+    (
+        $name:ident : $base:ty, header: $header:ident $(, extends: $extends_field:ident : $extends:ty)*
+            $(, user_data: $user_data:ty)*
+            $(, method_errors: $method_errors:ident)*
+            $(, category: $category:expr)* $(, module_category: $module_path:expr)* $(, doc: $doc:expr)*;
+        $(
+            $field:ident : $fty:ty {
+                path: $path:expr
+                $(, default: $default:expr)*
+                $(, hint: $hint:expr)*
+                $(, usage: $usage:expr)*
+                $(, doc: $pdoc:expr)*
+            }
+        )*
+    ) => {
+        impl $crate::NativeClass for $name {
+            type UserData = godot_derive_type!($($user_data)*; $crate::LocalCellData<$name>);
+            fn class_name() -> &'static str { stringify!($name) }
+            fn get_header(&self) -> &$crate::NativeInstanceHeader { &self.$header }
+            $(fn method_errors() -> $crate::MethodErrors { $crate::MethodErrors::$method_errors })*
+        }
+
+        $(
+            impl $crate::init::Extends<$extends> for $name {
+                fn base(&self) -> &$extends { &self.$extends_field }
+                fn base_mut(&mut self) -> &mut $extends { &mut self.$extends_field }
+            }
+        )*
+
+        impl $crate::init::NativeClassInit for $name {
+            fn base_class() -> &'static str {
+                godot_derive_value!(
+                    $(<$extends as $crate::init::BaseClass>::class_name())*;
+                    <$base as $crate::GodotObject>::class_name()
+                )
+            }
+
+            fn init(header: $crate::NativeInstanceHeader) -> Self {
+                <$name>::_init(header)
+            }
+
+            #[allow(unused_variables)]
+            fn register_properties(builder: &$crate::init::ClassBuilder<'_, Self>) {
+                $(builder.set_class_documentation($doc);)*
+                $(builder.add_base_members::<$extends>();)*
+                $(builder.add_property_category($category);)*
+                $(builder.add_property_category(&$crate::init::module_category($module_path));)*
+                $(
+                    let default: $fty = godot_derive_value!($($default)*; <$fty as Default>::default());
+                    builder.add_property($crate::init::Property {
+                        name: $path,
+                        default,
+                        hint: godot_derive_value!($($hint)*; {
+                            #[allow(unused_imports)]
+                            use $crate::init::DefaultHint;
+                            $crate::init::ExportHint::<$fty>::new().hint()
+                        }),
+                        usage: godot_derive_value!($($usage)*; $crate::init::PropertyUsage::DEFAULT),
+                        getter: |this: &$name| $crate::ToVariant::to_variant(&this.$field),
+                        setter: |this: &mut $name, value: $fty| this.$field = value,
+                        documentation: godot_derive_value!($(Some($pdoc))*; None),
+                    });
+                )*
+            }
+        }
+    };
+}
+
+/// Implementation detail of the `#[methods]` attribute.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! godot_derive_methods {
+    (
+        $type_name:ty;
+        $(
+            $(#[doc = $doc:expr])*
+            fn $method:ident $args:tt -> $retty:ty;
+            name($name:expr);
+        )*
+    ) => {
+        impl $crate::init::NativeClassMethods for $type_name {
+            #[allow(unused_variables)]
+            fn register_methods(builder: &$crate::init::ClassBuilder<'_, Self>) {
+                $(
+                    builder.add_method(
+                        $name,
+                        godot_wrap_method!($type_name, fn $method $args -> $retty),
+                    );
+                    $(builder.set_method_documentation($name, $doc);)*
+                    builder.set_method_arguments(
+                        $name,
+                        &godot_method_arguments!($type_name, fn $method $args -> $retty),
+                    );
+                )*
+            }
+        }
+    };
+}
+
+/// Implementation detail of `#[derive(ToVariant)]` and `#[derive(FromVariant)]`,
+/// gives the generated impl a path to this crate.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! godot_derive_variant_impl {
+    ($item:item) => {
+        const _: () = {
+            use $crate as __gdnative;
+            $item
+        };
+    };
+}
@@ -0,0 +1,118 @@
+//! Memory usage of the engine and of the rust wrappers.
+//!
+//! Leaks in long sessions are easier to find by comparing reports taken a few
+//! minutes apart, for example printed from a debug key:
+//!
+//! ```ignore
+//! godot_print!("{}", MemoryReport::current());
+//! // engine: static 48.2 MiB (peak 51.0 MiB), dynamic 3.1 MiB
+//! // objects: 5312 (402 resources, 1250 nodes)
+//! // rust: 87 variants, 412 references, 1304 boxed values (96.5 KiB)
+//! ```
+//!
+//! The memory of the engine is only tracked by debug builds of the engine, release
+//! builds report 0. The rust counts are tracked by this crate: a number of variants
+//! or references growing steadily points to wrappers stored and never dropped.
+
+use std::fmt;
+use allocator;
+use is_api_bound;
+use object;
+use variant;
+use Performance;
+use PerformanceMonitor;
+use _OS;
+
+/// A snapshot of the memory used by the engine and by the rust wrappers, see the
+/// `memory_report` module.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Bytes allocated by the engine, `OS.get_static_memory_usage()`.
+    pub static_memory: u64,
+    /// `OS.get_static_memory_peak_usage()`.
+    pub static_memory_peak: u64,
+    /// Bytes used by the pools of the engine, `OS.get_dynamic_memory_usage()`.
+    pub dynamic_memory: u64,
+    /// All the objects of the engine, `Performance.OBJECT_COUNT`.
+    pub objects: u64,
+    /// `Performance.OBJECT_RESOURCE_COUNT`.
+    pub resources: u64,
+    /// `Performance.OBJECT_NODE_COUNT`.
+    pub nodes: u64,
+    /// The variants owned by rust code.
+    pub variants: usize,
+    /// The references to reference counted objects held by rust wrappers.
+    pub references: usize,
+    /// The values allocated by the crate and owned by the engine: instances of the
+    /// rust classes, methods, property accessors and connected closures.
+    pub boxed_values: usize,
+    /// The size of the boxed values, without the heap memory they own.
+    pub boxed_bytes: usize,
+}
+
+impl MemoryReport {
+    /// Takes a snapshot. Before the API is bound only the rust counts are filled.
+    pub fn current() -> Self {
+        let (boxed_values, boxed_bytes) = allocator::live_boxes();
+        let mut report = MemoryReport {
+            variants: variant::live_variants(),
+            references: object::live_references(),
+            boxed_values,
+            boxed_bytes,
+            ..MemoryReport::default()
+        };
+
+        if is_api_bound() {
+            let os = _OS::godot_singleton();
+            report.static_memory = os.get_static_memory_usage() as u64;
+            report.static_memory_peak = os.get_static_memory_peak_usage() as u64;
+            report.dynamic_memory = os.get_dynamic_memory_usage() as u64;
+
+            let performance = Performance::godot_singleton();
+            let monitor = |monitor: PerformanceMonitor| performance.get_monitor(monitor as i64) as u64;
+            report.objects = monitor(PerformanceMonitor::ObjectCount);
+            report.resources = monitor(PerformanceMonitor::ObjectResourceCount);
+            report.nodes = monitor(PerformanceMonitor::ObjectNodeCount);
+        }
+
+        report
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f, "engine: static {} (peak {}), dynamic {}",
+            format_bytes(self.static_memory), format_bytes(self.static_memory_peak), format_bytes(self.dynamic_memory)
+        )?;
+        writeln!(f, "objects: {} ({} resources, {} nodes)", self.objects, self.resources, self.nodes)?;
+        write!(
+            f, "rust: {} variants, {} references, {} boxed values ({})",
+            self.variants, self.references, self.boxed_values, format_bytes(self.boxed_bytes as u64)
+        )
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[test]
+fn rust_counts() {
+    let boxed = ::alloc_boxed([0u8; 64]);
+    let report = MemoryReport::current();
+    assert!(report.boxed_values >= 1);
+    assert!(report.boxed_bytes >= 64);
+    assert_eq!(report.objects, 0);
+    unsafe { ::free_boxed(boxed); }
+
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(48 * 1024 * 1024), "48.0 MiB");
+}
@@ -0,0 +1,75 @@
+//! Upgrading code written for an earlier version of the crate.
+//!
+//! The renamed and reorganized APIs keep a deprecated shim for at least one release
+//! after the change, so a game can build with the new version first and fix the
+//! deprecation warnings one at a time. The shims are removed in the release after.
+//! This module lists the changes, with the mechanical rewrite of each.
+//!
+//! # From 0.3.0
+//!
+//! ## `GodotType` split into `ToVariant` and `FromVariant`
+//!
+//! `GodotType` is implemented for every type implementing both traits, and can't be
+//! implemented directly anymore. The implementations are split in two:
+//!
+//! ```ignore
+//! // Before.
+//! impl GodotType for Score {
+//!     fn to_variant(&self) -> Variant { self.0.to_variant() }
+//!     fn from_variant(variant: &Variant) -> Option<Self> { i64::from_variant(variant).map(Score) }
+//! }
+//!
+//! // After.
+//! impl ToVariant for Score {
+//!     fn to_variant(&self) -> Variant { self.0.to_variant() }
+//! }
+//!
+//! impl FromVariant for Score {
+//!     fn from_variant(variant: &Variant) -> Option<Self> { i64::from_variant(variant).map(Score) }
+//! }
+//! ```
+//!
+//! Structs and enums converted field by field can use `#[derive(ToVariant,
+//! FromVariant)]` instead. The bounds `T: GodotType` still work.
+//!
+//! ## Instances stored in `UserData`
+//!
+//! `NativeClass` has an associated type `UserData`, the wrapper storing the
+//! instances, see the `user_data` module. The manual implementations add the
+//! wrapper of the previous versions:
+//!
+//! ```ignore
+//! impl NativeClass for Player {
+//!     type UserData = LocalCellData<Player>;
+//!     // ...
+//! }
+//! ```
+//!
+//! `NativeRef` dereferences to the wrapper instead of a `RefCell`. The deprecated
+//! `LocalCellData::borrow` and `borrow_mut` keep the `RefCell` calls building, and
+//! are replaced by `map` and `map_mut`, which report the conflicting accesses
+//! instead of panicking:
+//!
+//! ```ignore
+//! // Before.
+//! native_ref.borrow_mut().jump();
+//!
+//! // After.
+//! native_ref.map_mut(|player| player.jump()).expect("the player is already borrowed");
+//! ```
+//!
+//! ## Registration
+//!
+//! `InitHandle` and `ClassBuilder` have a lifetime, elided in the signatures of the
+//! registration functions: `fn init(handle: InitHandle)` and `builder:
+//! &ClassBuilder<Player>` are unchanged. The methods of `ClassBuilder` return the
+//! builder to chain the registrations, the callers ignoring the result are unchanged.
+//!
+//! ## Imports
+//!
+//! The lists of imports of the types used by most classes can be replaced by the
+//! `prelude` module, which follows the next reorganizations:
+//!
+//! ```ignore
+//! use gdnative::prelude::*;
+//! ```
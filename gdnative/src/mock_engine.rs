@@ -0,0 +1,996 @@
+//! A mock of the engine's core API, for running the fuzzers of the `fuzzing`
+//! feature outside of Godot, like the targets of the `fuzz` package.
+//!
+//! `install_mock_engine` binds the library to an API whose variants, strings,
+//! arrays, dictionaries and node paths are plain Rust values, with the sharing
+//! semantics of the engine: arrays and dictionaries are references, the other
+//! types are copied. Only the functions used by the fuzzed conversions are
+//! mocked, calling any other function of the engine aborts the process.
+
+use libc;
+use std::cell::RefCell;
+use std::char;
+use std::mem;
+use std::process;
+use std::ptr;
+use std::rc::Rc;
+use std::slice;
+use sys::*;
+use GodotApi;
+use GODOT_API;
+use is_api_bound;
+
+type Text = Vec<wchar_t>;
+type Shared<T> = Rc<RefCell<T>>;
+
+/// The value behind every mocked opaque type of the engine, whose first bytes hold
+/// a pointer to its boxed `Value`.
+#[derive(Clone)]
+enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Real(f64),
+    // Strings end with a nul character, for `godot_string_wide_str`.
+    String(Text),
+    Vector2([f32; 2]),
+    Vector3([f32; 3]),
+    Color([f32; 4]),
+    NodePath(Text),
+    Dictionary(Shared<Vec<(Element, Element)>>),
+    Array(Shared<Vec<Element>>),
+    ByteArray(Vec<u8>),
+    IntArray(Vec<i32>),
+    RealArray(Vec<f32>),
+    StringArray(Vec<Text>),
+    Vector2Array(Vec<[f32; 2]>),
+    Vector3Array(Vec<[f32; 3]>),
+    ColorArray(Vec<[f32; 4]>),
+    // The UTF-8 bytes of a `godot_char_string`, ending with a nul byte.
+    Utf8(Vec<u8>),
+}
+
+// The opaque types may not be aligned for a pointer.
+unsafe fn init<T>(dest: *mut T, value: Value) {
+    ptr::write_unaligned(dest as *mut *mut Value, Box::into_raw(Box::new(value)));
+}
+
+unsafe fn make<T>(value: Value) -> T {
+    let mut dest = mem::zeroed();
+    init(&mut dest, value);
+    dest
+}
+
+unsafe fn get<'a, T>(this: *const T) -> &'a Value {
+    &*ptr::read_unaligned(this as *const *mut Value)
+}
+
+unsafe fn get_mut<'a, T>(this: *mut T) -> &'a mut Value {
+    &mut *ptr::read_unaligned(this as *const *mut Value)
+}
+
+unsafe fn release<T>(this: *mut T) {
+    drop(Box::from_raw(ptr::read_unaligned(this as *const *mut Value)));
+}
+
+/// A variant owned by a mocked array or dictionary, which hands out pointers to it.
+#[repr(transparent)]
+struct Element(godot_variant);
+
+impl Element {
+    fn new(value: Value) -> Self {
+        unsafe { Element(make(value)) }
+    }
+
+    fn value(&self) -> &Value {
+        unsafe { get(&self.0) }
+    }
+
+    fn into_sys(self) -> godot_variant {
+        let variant = self.0;
+        mem::forget(self);
+        variant
+    }
+}
+
+impl Clone for Element {
+    fn clone(&self) -> Self {
+        Element::new(self.value().clone())
+    }
+}
+
+impl Drop for Element {
+    fn drop(&mut self) {
+        unsafe { release(&mut self.0) }
+    }
+}
+
+fn text(string: &str) -> Text {
+    string.chars().map(|c| c as u32 as wchar_t).chain(Some(0)).collect()
+}
+
+fn chars(text: &[wchar_t]) -> &[wchar_t] {
+    &text[..text.len() - 1]
+}
+
+fn to_string(text: &[wchar_t]) -> String {
+    chars(text)
+        .iter()
+        .map(|&c| char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+unsafe fn read<T: Copy>(src: *const T) -> T {
+    ptr::read_unaligned(src)
+}
+
+unsafe fn write<T, V>(dest: *mut T, value: V) {
+    ptr::write_unaligned(dest as *mut V, value);
+}
+
+fn real(value: &Value) -> f64 {
+    match *value {
+        Value::Bool(b) => b as i64 as f64,
+        Value::Int(i) => i as f64,
+        Value::Real(r) => r,
+        Value::String(ref s) => to_string(s).trim().parse().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+fn int(value: &Value) -> i64 {
+    match *value {
+        Value::Bool(b) => b as i64,
+        Value::Int(i) => i,
+        Value::Real(r) => r as i64,
+        Value::String(ref s) => to_string(s).trim().parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+fn boolean(value: &Value) -> bool {
+    match *value {
+        Value::Nil => false,
+        Value::Bool(b) => b,
+        Value::Int(i) => i != 0,
+        Value::Real(r) => r != 0.0,
+        Value::String(ref s) => !chars(s).is_empty(),
+        Value::Array(ref a) => !a.borrow().is_empty(),
+        Value::Dictionary(ref d) => !d.borrow().is_empty(),
+        _ => true,
+    }
+}
+
+fn format(value: &Value) -> String {
+    fn list<T, F: Fn(&T) -> String>(items: &[T], f: F) -> String {
+        let items: Vec<_> = items.iter().map(f).collect();
+        format!("[{}]", items.join(", "))
+    }
+    let vector2 = |v: &[f32; 2]| format!("({}, {})", v[0], v[1]);
+    let vector3 = |v: &[f32; 3]| format!("({}, {}, {})", v[0], v[1], v[2]);
+    let color = |c: &[f32; 4]| format!("{},{},{},{}", c[0], c[1], c[2], c[3]);
+
+    match *value {
+        Value::Nil => "Null".to_string(),
+        Value::Bool(b) => if b { "True" } else { "False" }.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::Real(r) => r.to_string(),
+        Value::String(ref s) | Value::NodePath(ref s) => to_string(s),
+        Value::Vector2(ref v) => vector2(v),
+        Value::Vector3(ref v) => vector3(v),
+        Value::Color(ref c) => color(c),
+        Value::Dictionary(ref d) => {
+            let entries: Vec<_> = d
+                .borrow()
+                .iter()
+                .map(|&(ref k, ref v)| format!("{}:{}", format(k.value()), format(v.value())))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Value::Array(ref a) => list(&a.borrow(), |e| format(e.value())),
+        Value::ByteArray(ref a) => list(a, |b| b.to_string()),
+        Value::IntArray(ref a) => list(a, |i| i.to_string()),
+        Value::RealArray(ref a) => list(a, |r| r.to_string()),
+        Value::StringArray(ref a) => list(a, |s| to_string(s)),
+        Value::Vector2Array(ref a) => list(a, vector2),
+        Value::Vector3Array(ref a) => list(a, vector3),
+        Value::ColorArray(ref a) => list(a, color),
+        Value::Utf8(_) => unreachable!(),
+    }
+}
+
+/// The `==` operator of the engine when `exact` is false, which compares the
+/// numbers of different types. When `exact` is true, it's the comparison of the
+/// dictionary keys, where the types must match and NaN equals NaN.
+fn equal(a: &Value, b: &Value, exact: bool) -> bool {
+    fn float(a: f64, b: f64, exact: bool) -> bool {
+        a == b || (exact && a.is_nan() && b.is_nan())
+    }
+    fn floats(a: &[f32], b: &[f32], exact: bool) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| float(a as f64, b as f64, exact))
+    }
+
+    match (a, b) {
+        (&Value::Nil, &Value::Nil) => true,
+        (&Value::Bool(a), &Value::Bool(b)) => a == b,
+        (&Value::Int(a), &Value::Int(b)) => a == b,
+        (&Value::Real(a), &Value::Real(b)) => float(a, b, exact),
+        (&Value::Int(_), &Value::Real(_)) | (&Value::Real(_), &Value::Int(_)) => {
+            !exact && real(a) == real(b)
+        }
+        (&Value::String(ref a), &Value::String(ref b)) => a == b,
+        (&Value::NodePath(ref a), &Value::NodePath(ref b)) => a == b,
+        (&Value::Vector2(ref a), &Value::Vector2(ref b)) => floats(a, b, exact),
+        (&Value::Vector3(ref a), &Value::Vector3(ref b)) => floats(a, b, exact),
+        (&Value::Color(ref a), &Value::Color(ref b)) => floats(a, b, exact),
+        (&Value::Array(ref a), &Value::Array(ref b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| equal(a.value(), b.value(), exact))
+        }
+        (&Value::Dictionary(ref a), &Value::Dictionary(ref b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len()
+                && a.iter().all(|&(ref key, ref value)| {
+                    b.iter()
+                        .find(|&&(ref k, _)| equal(k.value(), key.value(), true))
+                        .map_or(false, |&(_, ref v)| equal(v.value(), value.value(), exact))
+                })
+        }
+        (&Value::ByteArray(ref a), &Value::ByteArray(ref b)) => a == b,
+        (&Value::IntArray(ref a), &Value::IntArray(ref b)) => a == b,
+        (&Value::RealArray(ref a), &Value::RealArray(ref b)) => floats(a, b, exact),
+        (&Value::StringArray(ref a), &Value::StringArray(ref b)) => a == b,
+        (&Value::Vector2Array(ref a), &Value::Vector2Array(ref b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| floats(a, b, exact))
+        }
+        (&Value::Vector3Array(ref a), &Value::Vector3Array(ref b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| floats(a, b, exact))
+        }
+        (&Value::ColorArray(ref a), &Value::ColorArray(ref b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| floats(a, b, exact))
+        }
+        _ => false,
+    }
+}
+
+// The elements of an array, converted like the constructors of the pool arrays.
+fn elements(value: &Value) -> Vec<Value> {
+    match *value {
+        Value::Array(ref a) => a.borrow().iter().map(|e| e.value().clone()).collect(),
+        Value::ByteArray(ref a) => a.iter().map(|&b| Value::Int(b as i64)).collect(),
+        Value::IntArray(ref a) => a.iter().map(|&i| Value::Int(i as i64)).collect(),
+        Value::RealArray(ref a) => a.iter().map(|&r| Value::Real(r as f64)).collect(),
+        Value::StringArray(ref a) => a.iter().map(|s| Value::String(s.clone())).collect(),
+        Value::Vector2Array(ref a) => a.iter().map(|&v| Value::Vector2(v)).collect(),
+        Value::Vector3Array(ref a) => a.iter().map(|&v| Value::Vector3(v)).collect(),
+        Value::ColorArray(ref a) => a.iter().map(|&c| Value::Color(c)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn array(values: Vec<Value>) -> Value {
+    Value::Array(Rc::new(RefCell::new(values.into_iter().map(Element::new).collect())))
+}
+
+fn string_of(value: &Value) -> Text {
+    match *value {
+        Value::String(ref s) => s.clone(),
+        ref other => text(&format(other)),
+    }
+}
+
+fn vector2_of(value: &Value) -> [f32; 2] {
+    match *value {
+        Value::Vector2(v) => v,
+        _ => [0.0; 2],
+    }
+}
+
+fn vector3_of(value: &Value) -> [f32; 3] {
+    match *value {
+        Value::Vector3(v) => v,
+        _ => [0.0; 3],
+    }
+}
+
+fn color_of(value: &Value) -> [f32; 4] {
+    match *value {
+        Value::Color(c) => c,
+        _ => [0.0; 4],
+    }
+}
+
+unsafe extern "C" fn unmocked() {
+    eprintln!("Called a function of the engine that the mock engine doesn't implement.");
+    process::abort();
+}
+
+// Fills the function pointers left empty in `api`, from the one at `first`, with
+// `unmocked`.
+unsafe fn fill_unmocked<S, F>(api: *mut S, first: *const F) {
+    type Slot = Option<unsafe extern "C" fn()>;
+    let start = first as usize - api as usize;
+    let count = (mem::size_of::<S>() - start) / mem::size_of::<Slot>();
+    let slots = slice::from_raw_parts_mut((api as *mut u8).add(start) as *mut Slot, count);
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = Some(unmocked);
+    }
+}
+
+/// Binds the library to a mock of the engine, for running the fuzzers outside of
+/// Godot. Returns `false`, and does nothing, if the library is already bound to
+/// an engine. The classes of the engine can't be used with the mock.
+///
+/// # Safety
+///
+/// The mock is not thread-safe: the engine types must only be used by the thread
+/// which installed it.
+pub unsafe fn install_mock_engine() -> bool {
+    if is_api_bound() {
+        return false;
+    }
+
+    let native: &'static mut godot_gdnative_ext_nativescript_api_struct = Box::leak(Box::new(mem::zeroed()));
+    native.type_ = GDNATIVE_API_TYPES::GDNATIVE_EXT_NATIVESCRIPT as u32;
+    fill_unmocked(native, &native.godot_nativescript_register_class as *const _);
+    let extensions: &'static mut [*const godot_gdnative_api_struct; 1] =
+        Box::leak(Box::new([native as *const _ as *const godot_gdnative_api_struct]));
+
+    let core: &'static mut godot_gdnative_core_api_struct = Box::leak(Box::new(mem::zeroed()));
+    core.type_ = GDNATIVE_API_TYPES::GDNATIVE_CORE as u32;
+    core.num_extensions = 1;
+    core.extensions = extensions.as_mut_ptr();
+    mock_core(core);
+    fill_unmocked(core, &core.godot_color_new_rgba as *const _);
+
+    GODOT_API = Some(GodotApi::from_raw(core));
+    true
+}
+
+fn mock_core(api: &mut godot_gdnative_core_api_struct) {
+    api.godot_variant_new_nil = Some(variant_new_nil);
+    api.godot_variant_new_copy = Some(variant_new_copy);
+    api.godot_variant_new_bool = Some(variant_new_bool);
+    api.godot_variant_new_int = Some(variant_new_int);
+    api.godot_variant_new_uint = Some(variant_new_uint);
+    api.godot_variant_new_real = Some(variant_new_real);
+    api.godot_variant_new_string = Some(variant_new_string);
+    api.godot_variant_new_vector2 = Some(variant_new_vector2);
+    api.godot_variant_new_vector3 = Some(variant_new_vector3);
+    api.godot_variant_new_color = Some(variant_new_color);
+    api.godot_variant_new_node_path = Some(variant_new_node_path);
+    api.godot_variant_new_dictionary = Some(variant_new_dictionary);
+    api.godot_variant_new_array = Some(variant_new_array);
+    api.godot_variant_new_pool_byte_array = Some(variant_new_pool_byte_array);
+    api.godot_variant_new_pool_int_array = Some(variant_new_pool_int_array);
+    api.godot_variant_new_pool_real_array = Some(variant_new_pool_real_array);
+    api.godot_variant_new_pool_string_array = Some(variant_new_pool_string_array);
+    api.godot_variant_new_pool_vector2_array = Some(variant_new_pool_vector2_array);
+    api.godot_variant_new_pool_vector3_array = Some(variant_new_pool_vector3_array);
+    api.godot_variant_new_pool_color_array = Some(variant_new_pool_color_array);
+    api.godot_variant_get_type = Some(variant_get_type);
+    api.godot_variant_as_bool = Some(variant_as_bool);
+    api.godot_variant_as_int = Some(variant_as_int);
+    api.godot_variant_as_uint = Some(variant_as_uint);
+    api.godot_variant_as_real = Some(variant_as_real);
+    api.godot_variant_as_string = Some(variant_as_string);
+    api.godot_variant_as_vector2 = Some(variant_as_vector2);
+    api.godot_variant_as_vector3 = Some(variant_as_vector3);
+    api.godot_variant_as_color = Some(variant_as_color);
+    api.godot_variant_as_node_path = Some(variant_as_node_path);
+    api.godot_variant_as_dictionary = Some(variant_as_dictionary);
+    api.godot_variant_as_array = Some(variant_as_array);
+    api.godot_variant_as_pool_byte_array = Some(variant_as_pool_byte_array);
+    api.godot_variant_as_pool_int_array = Some(variant_as_pool_int_array);
+    api.godot_variant_as_pool_real_array = Some(variant_as_pool_real_array);
+    api.godot_variant_as_pool_string_array = Some(variant_as_pool_string_array);
+    api.godot_variant_as_pool_vector2_array = Some(variant_as_pool_vector2_array);
+    api.godot_variant_as_pool_vector3_array = Some(variant_as_pool_vector3_array);
+    api.godot_variant_as_pool_color_array = Some(variant_as_pool_color_array);
+    api.godot_variant_operator_equal = Some(variant_operator_equal);
+    api.godot_variant_hash_compare = Some(variant_hash_compare);
+    api.godot_variant_booleanize = Some(variant_booleanize);
+    api.godot_variant_destroy = Some(destroy::<godot_variant>);
+
+    api.godot_string_new = Some(string_new);
+    api.godot_string_new_copy = Some(new_copy::<godot_string>);
+    api.godot_string_new_with_wide_string = Some(string_new_with_wide_string);
+    api.godot_string_chars_to_utf8 = Some(string_chars_to_utf8);
+    api.godot_string_chars_to_utf8_with_len = Some(string_chars_to_utf8_with_len);
+    api.godot_string_length = Some(string_length);
+    api.godot_string_wide_str = Some(string_wide_str);
+    api.godot_string_utf8 = Some(string_utf8);
+    api.godot_string_operator_equal = Some(string_operator_equal);
+    api.godot_string_operator_less = Some(string_operator_less);
+    api.godot_string_destroy = Some(destroy::<godot_string>);
+    api.godot_char_string_length = Some(char_string_length);
+    api.godot_char_string_get_data = Some(char_string_get_data);
+    api.godot_char_string_destroy = Some(destroy::<godot_char_string>);
+
+    api.godot_node_path_new = Some(node_path_new);
+    api.godot_node_path_new_copy = Some(new_copy::<godot_node_path>);
+    api.godot_node_path_as_string = Some(node_path_as_string);
+    api.godot_node_path_destroy = Some(destroy::<godot_node_path>);
+
+    api.godot_array_new = Some(array_new);
+    api.godot_array_new_copy = Some(new_copy::<godot_array>);
+    api.godot_array_size = Some(array_size);
+    api.godot_array_get = Some(array_get);
+    api.godot_array_set = Some(array_set);
+    api.godot_array_operator_index = Some(array_operator_index);
+    api.godot_array_operator_index_const = Some(array_operator_index_const);
+    api.godot_array_append = Some(array_push_back);
+    api.godot_array_push_back = Some(array_push_back);
+    api.godot_array_destroy = Some(destroy::<godot_array>);
+
+    api.godot_dictionary_new = Some(dictionary_new);
+    api.godot_dictionary_new_copy = Some(new_copy::<godot_dictionary>);
+    api.godot_dictionary_size = Some(dictionary_size);
+    api.godot_dictionary_empty = Some(dictionary_empty);
+    api.godot_dictionary_has = Some(dictionary_has);
+    api.godot_dictionary_get = Some(dictionary_get);
+    api.godot_dictionary_set = Some(dictionary_set);
+    api.godot_dictionary_operator_index = Some(dictionary_operator_index);
+    api.godot_dictionary_operator_index_const = Some(dictionary_operator_index_const);
+    api.godot_dictionary_next = Some(dictionary_next);
+    api.godot_dictionary_keys = Some(dictionary_keys);
+    api.godot_dictionary_values = Some(dictionary_values);
+    api.godot_dictionary_erase = Some(dictionary_erase);
+    api.godot_dictionary_destroy = Some(destroy::<godot_dictionary>);
+
+    macro_rules! mock_pool_array {
+        ($module:ident: $new:ident, $new_copy:ident, $new_with_array:ident, $append:ident,
+            $get:ident, $set:ident, $size:ident, $destroy:ident) => {
+            api.$new = Some($module::new);
+            api.$new_copy = Some(new_copy);
+            api.$new_with_array = Some($module::new_with_array);
+            api.$append = Some($module::append);
+            api.$get = Some($module::element);
+            api.$set = Some($module::set);
+            api.$size = Some($module::size);
+            api.$destroy = Some(destroy);
+        };
+    }
+
+    mock_pool_array!(pool_byte_array: godot_pool_byte_array_new, godot_pool_byte_array_new_copy,
+        godot_pool_byte_array_new_with_array, godot_pool_byte_array_append, godot_pool_byte_array_get,
+        godot_pool_byte_array_set, godot_pool_byte_array_size, godot_pool_byte_array_destroy);
+    mock_pool_array!(pool_int_array: godot_pool_int_array_new, godot_pool_int_array_new_copy,
+        godot_pool_int_array_new_with_array, godot_pool_int_array_append, godot_pool_int_array_get,
+        godot_pool_int_array_set, godot_pool_int_array_size, godot_pool_int_array_destroy);
+    mock_pool_array!(pool_real_array: godot_pool_real_array_new, godot_pool_real_array_new_copy,
+        godot_pool_real_array_new_with_array, godot_pool_real_array_append, godot_pool_real_array_get,
+        godot_pool_real_array_set, godot_pool_real_array_size, godot_pool_real_array_destroy);
+    mock_pool_array!(pool_string_array: godot_pool_string_array_new, godot_pool_string_array_new_copy,
+        godot_pool_string_array_new_with_array, godot_pool_string_array_append, godot_pool_string_array_get,
+        godot_pool_string_array_set, godot_pool_string_array_size, godot_pool_string_array_destroy);
+    mock_pool_array!(pool_vector2_array: godot_pool_vector2_array_new, godot_pool_vector2_array_new_copy,
+        godot_pool_vector2_array_new_with_array, godot_pool_vector2_array_append, godot_pool_vector2_array_get,
+        godot_pool_vector2_array_set, godot_pool_vector2_array_size, godot_pool_vector2_array_destroy);
+    mock_pool_array!(pool_vector3_array: godot_pool_vector3_array_new, godot_pool_vector3_array_new_copy,
+        godot_pool_vector3_array_new_with_array, godot_pool_vector3_array_append, godot_pool_vector3_array_get,
+        godot_pool_vector3_array_set, godot_pool_vector3_array_size, godot_pool_vector3_array_destroy);
+    mock_pool_array!(pool_color_array: godot_pool_color_array_new, godot_pool_color_array_new_copy,
+        godot_pool_color_array_new_with_array, godot_pool_color_array_append, godot_pool_color_array_get,
+        godot_pool_color_array_set, godot_pool_color_array_size, godot_pool_color_array_destroy);
+}
+
+unsafe extern "C" fn new_copy<T>(dest: *mut T, src: *const T) {
+    init(dest, get(src).clone());
+}
+
+unsafe extern "C" fn destroy<T>(this: *mut T) {
+    release(this);
+}
+
+unsafe extern "C" fn variant_new_nil(dest: *mut godot_variant) {
+    init(dest, Value::Nil);
+}
+
+unsafe extern "C" fn variant_new_copy(dest: *mut godot_variant, src: *const godot_variant) {
+    init(dest, get(src).clone());
+}
+
+unsafe extern "C" fn variant_new_bool(dest: *mut godot_variant, b: godot_bool) {
+    init(dest, Value::Bool(b));
+}
+
+unsafe extern "C" fn variant_new_int(dest: *mut godot_variant, i: int64_t) {
+    init(dest, Value::Int(i));
+}
+
+unsafe extern "C" fn variant_new_uint(dest: *mut godot_variant, i: uint64_t) {
+    init(dest, Value::Int(i as i64));
+}
+
+unsafe extern "C" fn variant_new_real(dest: *mut godot_variant, r: f64) {
+    init(dest, Value::Real(r));
+}
+
+unsafe extern "C" fn variant_new_string(dest: *mut godot_variant, s: *const godot_string) {
+    init(dest, get(s).clone());
+}
+
+unsafe extern "C" fn variant_new_vector2(dest: *mut godot_variant, v: *const godot_vector2) {
+    init(dest, Value::Vector2(read(v as *const [f32; 2])));
+}
+
+unsafe extern "C" fn variant_new_vector3(dest: *mut godot_variant, v: *const godot_vector3) {
+    init(dest, Value::Vector3(read(v as *const [f32; 3])));
+}
+
+unsafe extern "C" fn variant_new_color(dest: *mut godot_variant, c: *const godot_color) {
+    init(dest, Value::Color(read(c as *const [f32; 4])));
+}
+
+unsafe extern "C" fn variant_new_node_path(dest: *mut godot_variant, np: *const godot_node_path) {
+    init(dest, get(np).clone());
+}
+
+unsafe extern "C" fn variant_new_dictionary(dest: *mut godot_variant, d: *const godot_dictionary) {
+    init(dest, get(d).clone());
+}
+
+unsafe extern "C" fn variant_new_array(dest: *mut godot_variant, a: *const godot_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_byte_array(dest: *mut godot_variant, a: *const godot_pool_byte_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_int_array(dest: *mut godot_variant, a: *const godot_pool_int_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_real_array(dest: *mut godot_variant, a: *const godot_pool_real_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_string_array(dest: *mut godot_variant, a: *const godot_pool_string_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_vector2_array(dest: *mut godot_variant, a: *const godot_pool_vector2_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_vector3_array(dest: *mut godot_variant, a: *const godot_pool_vector3_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_new_pool_color_array(dest: *mut godot_variant, a: *const godot_pool_color_array) {
+    init(dest, get(a).clone());
+}
+
+unsafe extern "C" fn variant_get_type(this: *const godot_variant) -> godot_variant_type {
+    use sys::godot_variant_type::*;
+    match *get(this) {
+        Value::Nil => GODOT_VARIANT_TYPE_NIL,
+        Value::Bool(_) => GODOT_VARIANT_TYPE_BOOL,
+        Value::Int(_) => GODOT_VARIANT_TYPE_INT,
+        Value::Real(_) => GODOT_VARIANT_TYPE_REAL,
+        Value::String(_) => GODOT_VARIANT_TYPE_STRING,
+        Value::Vector2(_) => GODOT_VARIANT_TYPE_VECTOR2,
+        Value::Vector3(_) => GODOT_VARIANT_TYPE_VECTOR3,
+        Value::Color(_) => GODOT_VARIANT_TYPE_COLOR,
+        Value::NodePath(_) => GODOT_VARIANT_TYPE_NODE_PATH,
+        Value::Dictionary(_) => GODOT_VARIANT_TYPE_DICTIONARY,
+        Value::Array(_) => GODOT_VARIANT_TYPE_ARRAY,
+        Value::ByteArray(_) => GODOT_VARIANT_TYPE_POOL_BYTE_ARRAY,
+        Value::IntArray(_) => GODOT_VARIANT_TYPE_POOL_INT_ARRAY,
+        Value::RealArray(_) => GODOT_VARIANT_TYPE_POOL_REAL_ARRAY,
+        Value::StringArray(_) => GODOT_VARIANT_TYPE_POOL_STRING_ARRAY,
+        Value::Vector2Array(_) => GODOT_VARIANT_TYPE_POOL_VECTOR2_ARRAY,
+        Value::Vector3Array(_) => GODOT_VARIANT_TYPE_POOL_VECTOR3_ARRAY,
+        Value::ColorArray(_) => GODOT_VARIANT_TYPE_POOL_COLOR_ARRAY,
+        Value::Utf8(_) => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn variant_as_bool(this: *const godot_variant) -> godot_bool {
+    boolean(get(this))
+}
+
+unsafe extern "C" fn variant_as_int(this: *const godot_variant) -> int64_t {
+    int(get(this))
+}
+
+unsafe extern "C" fn variant_as_uint(this: *const godot_variant) -> uint64_t {
+    int(get(this)) as uint64_t
+}
+
+unsafe extern "C" fn variant_as_real(this: *const godot_variant) -> f64 {
+    real(get(this))
+}
+
+unsafe extern "C" fn variant_as_string(this: *const godot_variant) -> godot_string {
+    make(Value::String(string_of(get(this))))
+}
+
+unsafe extern "C" fn variant_as_vector2(this: *const godot_variant) -> godot_vector2 {
+    to_sys(&vector2_of(get(this)))
+}
+
+unsafe extern "C" fn variant_as_vector3(this: *const godot_variant) -> godot_vector3 {
+    to_sys(&vector3_of(get(this)))
+}
+
+unsafe extern "C" fn variant_as_color(this: *const godot_variant) -> godot_color {
+    to_sys(&color_of(get(this)))
+}
+
+unsafe extern "C" fn variant_as_node_path(this: *const godot_variant) -> godot_node_path {
+    match *get(this) {
+        Value::NodePath(ref np) => make(Value::NodePath(np.clone())),
+        ref other => make(Value::NodePath(string_of(other))),
+    }
+}
+
+unsafe extern "C" fn variant_as_dictionary(this: *const godot_variant) -> godot_dictionary {
+    match *get(this) {
+        Value::Dictionary(ref d) => make(Value::Dictionary(d.clone())),
+        _ => make(Value::Dictionary(Default::default())),
+    }
+}
+
+unsafe extern "C" fn variant_as_array(this: *const godot_variant) -> godot_array {
+    match *get(this) {
+        Value::Array(ref a) => make(Value::Array(a.clone())),
+        ref other => make(array(elements(other))),
+    }
+}
+
+macro_rules! variant_as_pool_array {
+    ($(fn $name:ident -> $ty:ident : $variant:ident, |$element:ident| $convert:expr;)*) => {$(
+        unsafe extern "C" fn $name(this: *const godot_variant) -> $ty {
+            match *get(this) {
+                ref value @ Value::$variant(_) => make(value.clone()),
+                ref other => make(Value::$variant(elements(other).iter().map(|$element| $convert).collect())),
+            }
+        }
+    )*}
+}
+
+variant_as_pool_array! {
+    fn variant_as_pool_byte_array -> godot_pool_byte_array : ByteArray, |e| int(e) as u8;
+    fn variant_as_pool_int_array -> godot_pool_int_array : IntArray, |e| int(e) as i32;
+    fn variant_as_pool_real_array -> godot_pool_real_array : RealArray, |e| real(e) as f32;
+    fn variant_as_pool_string_array -> godot_pool_string_array : StringArray, |e| string_of(e);
+    fn variant_as_pool_vector2_array -> godot_pool_vector2_array : Vector2Array, |e| vector2_of(e);
+    fn variant_as_pool_vector3_array -> godot_pool_vector3_array : Vector3Array, |e| vector3_of(e);
+    fn variant_as_pool_color_array -> godot_pool_color_array : ColorArray, |e| color_of(e);
+}
+
+unsafe extern "C" fn variant_operator_equal(this: *const godot_variant, other: *const godot_variant) -> godot_bool {
+    equal(get(this), get(other), false)
+}
+
+unsafe extern "C" fn variant_hash_compare(this: *const godot_variant, other: *const godot_variant) -> godot_bool {
+    equal(get(this), get(other), true)
+}
+
+unsafe extern "C" fn variant_booleanize(this: *const godot_variant) -> godot_bool {
+    boolean(get(this))
+}
+
+unsafe extern "C" fn string_new(dest: *mut godot_string) {
+    init(dest, Value::String(text("")));
+}
+
+// Like the engine, the strings stop at the first nul character.
+unsafe extern "C" fn string_new_with_wide_string(dest: *mut godot_string, contents: *const wchar_t, size: libc::c_int) {
+    let mut string = Text::new();
+    let mut i = 0;
+    while (size < 0 || i < size as isize) && *contents.offset(i) != 0 {
+        string.push(*contents.offset(i));
+        i += 1;
+    }
+    string.push(0);
+    init(dest, Value::String(string));
+}
+
+unsafe extern "C" fn string_chars_to_utf8(utf8: *const libc::c_char) -> godot_string {
+    string_chars_to_utf8_with_len(utf8, libc::strlen(utf8) as godot_int)
+}
+
+unsafe extern "C" fn string_chars_to_utf8_with_len(utf8: *const libc::c_char, len: godot_int) -> godot_string {
+    let bytes = slice::from_raw_parts(utf8 as *const u8, len as usize);
+    let bytes = bytes.split(|&b| b == 0).next().unwrap_or(bytes);
+    make(Value::String(text(&String::from_utf8_lossy(bytes))))
+}
+
+unsafe extern "C" fn string_length(this: *const godot_string) -> godot_int {
+    match *get(this) {
+        Value::String(ref s) => chars(s).len() as godot_int,
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn string_wide_str(this: *const godot_string) -> *const wchar_t {
+    match *get(this) {
+        Value::String(ref s) => s.as_ptr(),
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn string_utf8(this: *const godot_string) -> godot_char_string {
+    match *get(this) {
+        Value::String(ref s) => make(Value::Utf8(to_string(s).into_bytes().into_iter().chain(Some(0)).collect())),
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn string_operator_equal(this: *const godot_string, other: *const godot_string) -> godot_bool {
+    equal(get(this), get(other), true)
+}
+
+unsafe extern "C" fn string_operator_less(this: *const godot_string, other: *const godot_string) -> godot_bool {
+    match (get(this), get(other)) {
+        (&Value::String(ref a), &Value::String(ref b)) => a < b,
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn char_string_length(this: *const godot_char_string) -> godot_int {
+    match *get(this) {
+        Value::Utf8(ref bytes) => bytes.len() as godot_int - 1,
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn char_string_get_data(this: *const godot_char_string) -> *const libc::c_char {
+    match *get(this) {
+        Value::Utf8(ref bytes) => bytes.as_ptr() as *const libc::c_char,
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn node_path_new(dest: *mut godot_node_path, from: *const godot_string) {
+    init(dest, Value::NodePath(string_of(get(from))));
+}
+
+unsafe extern "C" fn node_path_as_string(this: *const godot_node_path) -> godot_string {
+    match *get(this) {
+        Value::NodePath(ref np) => make(Value::String(np.clone())),
+        _ => unreachable!(),
+    }
+}
+
+unsafe fn array_elements<'a>(this: *const godot_array) -> &'a Shared<Vec<Element>> {
+    match *get(this) {
+        Value::Array(ref a) => a,
+        _ => unreachable!(),
+    }
+}
+
+unsafe extern "C" fn array_new(dest: *mut godot_array) {
+    init(dest, array(Vec::new()));
+}
+
+unsafe extern "C" fn array_size(this: *const godot_array) -> godot_int {
+    array_elements(this).borrow().len() as godot_int
+}
+
+unsafe extern "C" fn array_get(this: *const godot_array, index: godot_int) -> godot_variant {
+    array_elements(this).borrow()[index as usize].clone().into_sys()
+}
+
+unsafe extern "C" fn array_set(this: *mut godot_array, index: godot_int, value: *const godot_variant) {
+    let value = Element::new(get(value).clone());
+    array_elements(this).borrow_mut()[index as usize] = value;
+}
+
+unsafe extern "C" fn array_operator_index(this: *mut godot_array, index: godot_int) -> *mut godot_variant {
+    &mut array_elements(this).borrow_mut()[index as usize].0
+}
+
+unsafe extern "C" fn array_operator_index_const(this: *const godot_array, index: godot_int) -> *const godot_variant {
+    &array_elements(this).borrow()[index as usize].0
+}
+
+unsafe extern "C" fn array_push_back(this: *mut godot_array, value: *const godot_variant) {
+    let value = Element::new(get(value).clone());
+    array_elements(this).borrow_mut().push(value);
+}
+
+unsafe fn dictionary_entries<'a>(this: *const godot_dictionary) -> &'a Shared<Vec<(Element, Element)>> {
+    match *get(this) {
+        Value::Dictionary(ref d) => d,
+        _ => unreachable!(),
+    }
+}
+
+unsafe fn dictionary_find(this: *const godot_dictionary, key: *const godot_variant) -> Option<usize> {
+    let key = get(key);
+    dictionary_entries(this).borrow().iter().position(|&(ref k, _)| equal(k.value(), key, true))
+}
+
+unsafe extern "C" fn dictionary_new(dest: *mut godot_dictionary) {
+    init(dest, Value::Dictionary(Default::default()));
+}
+
+unsafe extern "C" fn dictionary_size(this: *const godot_dictionary) -> godot_int {
+    dictionary_entries(this).borrow().len() as godot_int
+}
+
+unsafe extern "C" fn dictionary_empty(this: *const godot_dictionary) -> godot_bool {
+    dictionary_entries(this).borrow().is_empty()
+}
+
+unsafe extern "C" fn dictionary_has(this: *const godot_dictionary, key: *const godot_variant) -> godot_bool {
+    dictionary_find(this, key).is_some()
+}
+
+unsafe extern "C" fn dictionary_get(this: *const godot_dictionary, key: *const godot_variant) -> godot_variant {
+    match dictionary_find(this, key) {
+        Some(i) => dictionary_entries(this).borrow()[i].1.clone().into_sys(),
+        None => make(Value::Nil),
+    }
+}
+
+unsafe extern "C" fn dictionary_set(this: *mut godot_dictionary, key: *const godot_variant, value: *const godot_variant) {
+    let value = Element::new(get(value).clone());
+    match dictionary_find(this, key) {
+        Some(i) => dictionary_entries(this).borrow_mut()[i].1 = value,
+        None => {
+            let key = Element::new(get(key).clone());
+            dictionary_entries(this).borrow_mut().push((key, value));
+        }
+    }
+}
+
+unsafe extern "C" fn dictionary_operator_index(this: *mut godot_dictionary, key: *const godot_variant) -> *mut godot_variant {
+    let i = match dictionary_find(this, key) {
+        Some(i) => i,
+        None => {
+            let entry = (Element::new(get(key).clone()), Element::new(Value::Nil));
+            let mut entries = dictionary_entries(this).borrow_mut();
+            entries.push(entry);
+            entries.len() - 1
+        }
+    };
+    &mut dictionary_entries(this).borrow_mut()[i].1 .0
+}
+
+unsafe extern "C" fn dictionary_operator_index_const(this: *const godot_dictionary, key: *const godot_variant) -> *const godot_variant {
+    let i = dictionary_find(this, key).expect("the key is not in the dictionary");
+    &dictionary_entries(this).borrow()[i].1 .0
+}
+
+unsafe extern "C" fn dictionary_next(this: *const godot_dictionary, key: *const godot_variant) -> *mut godot_variant {
+    let next = if key.is_null() {
+        0
+    } else {
+        match dictionary_find(this, key) {
+            Some(i) => i + 1,
+            None => return ptr::null_mut(),
+        }
+    };
+    match dictionary_entries(this).borrow_mut().get_mut(next) {
+        Some(&mut (ref mut k, _)) => &mut k.0,
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn dictionary_keys(this: *const godot_dictionary) -> godot_array {
+    make(array(dictionary_entries(this).borrow().iter().map(|&(ref k, _)| k.value().clone()).collect()))
+}
+
+unsafe extern "C" fn dictionary_values(this: *const godot_dictionary) -> godot_array {
+    make(array(dictionary_entries(this).borrow().iter().map(|&(_, ref v)| v.value().clone()).collect()))
+}
+
+unsafe extern "C" fn dictionary_erase(this: *mut godot_dictionary, key: *const godot_variant) {
+    if let Some(i) = dictionary_find(this, key) {
+        let entry = dictionary_entries(this).borrow_mut().remove(i);
+        drop(entry);
+    }
+}
+
+unsafe fn to_sys<T, A: Copy>(value: &A) -> T {
+    let mut dest = mem::zeroed();
+    write(&mut dest, *value);
+    dest
+}
+
+macro_rules! pool_array {
+    (mod $module:ident: $variant:ident<$ty:ident, $stored:ty>, ($element:ty) -> $ret:ty {
+        from_value: |$value:ident| $from_value:expr,
+        from_sys: |$input:ident| $from_sys:expr,
+        to_sys: |$output:ident| $to_sys:expr,
+    }) => {
+        mod $module {
+            use super::*;
+
+            unsafe fn vec<'a>(this: *const $ty) -> &'a mut Vec<$stored> {
+                match *get_mut(this as *mut $ty) {
+                    Value::$variant(ref mut vec) => vec,
+                    _ => unreachable!(),
+                }
+            }
+
+            pub unsafe extern "C" fn new(dest: *mut $ty) {
+                init(dest, Value::$variant(Vec::new()));
+            }
+
+            pub unsafe extern "C" fn new_with_array(dest: *mut $ty, array: *const godot_array) {
+                let values = elements(get(array)).iter().map(|$value| $from_value).collect();
+                init(dest, Value::$variant(values));
+            }
+
+            pub unsafe extern "C" fn append(this: *mut $ty, $input: $element) {
+                vec(this).push($from_sys);
+            }
+
+            pub unsafe extern "C" fn element(this: *const $ty, index: godot_int) -> $ret {
+                let $output = &vec(this)[index as usize];
+                $to_sys
+            }
+
+            pub unsafe extern "C" fn set(this: *mut $ty, index: godot_int, $input: $element) {
+                vec(this)[index as usize] = $from_sys;
+            }
+
+            pub unsafe extern "C" fn size(this: *const $ty) -> godot_int {
+                vec(this).len() as godot_int
+            }
+        }
+    };
+}
+
+pool_array! {
+    mod pool_byte_array: ByteArray<godot_pool_byte_array, u8>, (uint8_t) -> uint8_t {
+        from_value: |value| int(value) as u8,
+        from_sys: |data| data,
+        to_sys: |stored| *stored,
+    }
+}
+
+pool_array! {
+    mod pool_int_array: IntArray<godot_pool_int_array, i32>, (godot_int) -> godot_int {
+        from_value: |value| int(value) as i32,
+        from_sys: |data| data,
+        to_sys: |stored| *stored,
+    }
+}
+
+pool_array! {
+    mod pool_real_array: RealArray<godot_pool_real_array, f32>, (godot_real) -> godot_real {
+        from_value: |value| real(value) as f32,
+        from_sys: |data| data,
+        to_sys: |stored| *stored,
+    }
+}
+
+pool_array! {
+    mod pool_string_array: StringArray<godot_pool_string_array, Text>, (*const godot_string) -> godot_string {
+        from_value: |value| string_of(value),
+        from_sys: |data| string_of(get(data)),
+        to_sys: |stored| make(Value::String(stored.clone())),
+    }
+}
+
+pool_array! {
+    mod pool_vector2_array: Vector2Array<godot_pool_vector2_array, [f32; 2]>, (*const godot_vector2) -> godot_vector2 {
+        from_value: |value| vector2_of(value),
+        from_sys: |data| read(data as *const [f32; 2]),
+        to_sys: |stored| to_sys(stored),
+    }
+}
+
+pool_array! {
+    mod pool_vector3_array: Vector3Array<godot_pool_vector3_array, [f32; 3]>, (*const godot_vector3) -> godot_vector3 {
+        from_value: |value| vector3_of(value),
+        from_sys: |data| read(data as *const [f32; 3]),
+        to_sys: |stored| to_sys(stored),
+    }
+}
+
+pool_array! {
+    mod pool_color_array: ColorArray<godot_pool_color_array, [f32; 4]>, (*const godot_color) -> godot_color {
+        from_value: |value| color_of(value),
+        from_sys: |data| read(data as *const [f32; 4]),
+        to_sys: |stored| to_sys(stored),
+    }
+}
@@ -0,0 +1,154 @@
+//! Libraries without NativeScript classes.
+//!
+//! A library which only imports resources or provides utility functions doesn't
+//! register classes, and only needs `godot_gdnative_init!`. The core types and the
+//! engine classes work as soon as it ran. `godot_gdnative_terminate!` is optional
+//! but runs the `on_terminate` callbacks.
+//!
+//! Marking the library as a singleton in its `.gdnlib` resource (`singleton = true`
+//! in the `[general]` section) makes the engine load it at startup and keep it
+//! loaded until it quits. The engine then calls the function declared with
+//! `godot_gdnative_singleton!`, where the library registers its importers or sets up
+//! its state:
+//!
+//! ```ignore
+//! fn init(_info: &InitializeInfo) {}
+//!
+//! fn startup() {
+//!     godot_print!("the importers are ready");
+//! }
+//!
+//! fn checksum(args: &VariantArray) -> Variant {
+//!     let data = args.get_ref(0).try_to_byte_array().unwrap_or_default();
+//!     Variant::from_u64(crc32(&data))
+//! }
+//!
+//! godot_gdnative_init!(init);
+//! godot_gdnative_singleton!(startup);
+//! godot_native_function!(checksum);
+//! godot_gdnative_terminate!();
+//! ```
+//!
+//! The functions declared with `godot_native_function!` are called from GDScript
+//! through a `GDNative` object of the library, with their arguments in an array:
+//!
+//! ```ignore
+//! var native = GDNative.new()
+//! native.library = preload("res://utils.gdnlib")
+//! native.initialize()
+//! var checksum = native.call_native("standard_varcall", "checksum", [data])
+//! ```
+//!
+//! The API of the engine can't add singletons to `Engine`. An autoload script
+//! holding the `GDNative` object gives the project a global name to call them with.
+//! The helpers instancing scripts of the library, like `connect_fn`, need the
+//! `godot_nativescript_init!` endpoint.
+
+use sys;
+use Variant;
+use VariantArray;
+
+/// Declare the API endpoint called once the library is loaded, for the libraries
+/// marked as singletons, see the `native_function` module.
+///
+/// By default this declares an extern function named `godot_gdnative_singleton`,
+/// which can be overridden like the other endpoints:
+///
+/// ```ignore
+/// godot_gdnative_singleton!(my_startup as custom_gdnative_singleton);
+/// ```
+#[macro_export]
+macro_rules! godot_gdnative_singleton {
+    ($callback:ident) => {
+        godot_gdnative_singleton!($callback as godot_gdnative_singleton);
+    };
+    ($callback:ident as $fn_name:ident) => {
+        #[no_mangle]
+        #[doc(hidden)]
+        pub extern "C" fn $fn_name() {
+            $crate::catch_panic(stringify!($callback), || $callback());
+        }
+    };
+}
+
+/// Export a `fn(&VariantArray) -> Variant`, callable with
+/// `GDNative.call_native("standard_varcall", name, args)`, see the
+/// `native_function` module.
+///
+/// The function is exported under its own name, or another one:
+///
+/// ```ignore
+/// godot_native_function!(checksum);
+/// // Called with `call_native("standard_varcall", "utils_checksum", [data])`.
+/// godot_native_function!(checksum as utils_checksum);
+/// ```
+///
+/// The exported names are global to the process: two libraries exporting the same
+/// name conflict.
+#[macro_export]
+macro_rules! godot_native_function {
+    ($function:ident) => {
+        godot_native_function!($function as $function);
+    };
+    ($function:ident as $fn_name:ident) => {
+        #[doc(hidden)]
+        pub mod $fn_name {
+            #[no_mangle]
+            pub extern "C" fn $fn_name(
+                _data: *mut $crate::libc::c_void,
+                args: *mut $crate::sys::godot_array,
+            ) -> $crate::sys::godot_variant {
+                unsafe { $crate::call_native_function(stringify!($fn_name), args, super::$function) }
+            }
+        }
+    };
+}
+
+/// Called by `godot_native_function!`.
+#[doc(hidden)]
+pub unsafe fn call_native_function(
+    name: &str,
+    args: *mut sys::godot_array,
+    function: fn(&VariantArray) -> Variant,
+) -> sys::godot_variant {
+    let args = if args.is_null() {
+        VariantArray::new()
+    } else {
+        // The array stays owned by the engine, the function gets another reference.
+        let mut copy = sys::godot_array::default();
+        (::get_api().godot_array_new_copy)(&mut copy, args);
+        VariantArray::from_sys(copy)
+    };
+    ::catch_panic(name, || function(&args))
+        .unwrap_or_else(Variant::new)
+        .forget()
+}
+
+godot_test!(test_native_function {
+    use ToVariant;
+
+    fn sum(args: &VariantArray) -> Variant {
+        let mut total = 0;
+        for idx in 0..args.len() {
+            total += args.get_ref(idx).try_to_i64().unwrap_or(0);
+        }
+        Variant::from_i64(total)
+    }
+
+    fn fail(_args: &VariantArray) -> Variant {
+        panic!("the native function failed");
+    }
+
+    let mut args = VariantArray::new();
+    args.push(&1.to_variant());
+    args.push(&2.to_variant());
+    let ret = unsafe { Variant::from_sys(call_native_function("sum", args.as_mut_sys(), sum)) };
+    assert_eq!(ret.try_to_i64(), Some(3));
+    assert_eq!(args.len(), 2);
+
+    let ret = unsafe { Variant::from_sys(call_native_function("sum", ::std::ptr::null_mut(), sum)) };
+    assert_eq!(ret.try_to_i64(), Some(0));
+
+    let ret = unsafe { Variant::from_sys(call_native_function("fail", args.as_mut_sys(), fail)) };
+    assert!(ret.is_nil());
+});
@@ -0,0 +1,87 @@
+//! Bindings to the NativeScript 1.1 extension, provided by Godot 3.1 and later.
+//!
+//! The headers these bindings are generated from only describe NativeScript 1.0, so
+//! the layout of the 1.1 API struct is declared here. The struct is found during
+//! initialization by following the `next` pointers of the NativeScript extension.
+//! The features relying on it do nothing when the engine doesn't provide it.
+
+use libc::{c_char, c_int, c_uint, c_void};
+use sys;
+
+/// Attaches documentation to a member (method, property or signal) of a class.
+#[doc(hidden)]
+pub type DocumentationSetter = unsafe extern "C" fn(
+    handle: *mut c_void, name: *const c_char, member: *const c_char, documentation: sys::godot_string
+);
+
+/// The name and type of an argument of a method, the `godot_method_arg` of the 1.1
+/// headers.
+#[doc(hidden)]
+#[repr(C)]
+pub struct MethodArg {
+    pub name: sys::godot_string,
+    pub type_: sys::godot_variant_type,
+    pub hint: sys::godot_property_hint,
+    pub hint_string: sys::godot_string,
+}
+
+#[doc(hidden)]
+#[repr(C)]
+pub struct NativeScriptApi11 {
+    pub type_: c_uint,
+    pub version: sys::godot_gdnative_api_version,
+    pub next: *const sys::godot_gdnative_api_struct,
+    pub set_method_argument_information: Option<unsafe extern "C" fn(
+        handle: *mut c_void, name: *const c_char, function_name: *const c_char, num_args: c_int, args: *const MethodArg
+    )>,
+    pub set_class_documentation: Option<unsafe extern "C" fn(
+        handle: *mut c_void, name: *const c_char, documentation: sys::godot_string
+    )>,
+    pub set_method_documentation: Option<DocumentationSetter>,
+    pub set_property_documentation: Option<DocumentationSetter>,
+    pub set_signal_documentation: Option<DocumentationSetter>,
+    pub set_global_type_tag: *const c_void,
+    pub get_global_type_tag: *const c_void,
+    pub set_type_tag: *const c_void,
+    pub get_type_tag: *const c_void,
+    pub register_instance_binding_data_functions: *const c_void,
+    pub unregister_instance_binding_data_functions: *const c_void,
+    pub get_instance_binding_data: *const c_void,
+    pub profiling_add_data: Option<unsafe extern "C" fn(signature: *const c_char, time: u64)>,
+}
+
+static mut NATIVESCRIPT_1_1: Option<&'static NativeScriptApi11> = None;
+
+/// Looks up the NativeScript 1.1 API, called by `godot_gdnative_init!`.
+#[doc(hidden)]
+pub unsafe fn bind_nativescript_1_1(options: *const sys::godot_gdnative_init_options) {
+    NATIVESCRIPT_1_1 = None;
+
+    let core = &*(*options).api_struct;
+    for i in 0..core.num_extensions {
+        let ext = *core.extensions.offset(i as isize);
+        if (*ext).type_ != sys::GDNATIVE_API_TYPES::GDNATIVE_EXT_NATIVESCRIPT as c_uint {
+            continue;
+        }
+
+        let mut api = ext;
+        while !api.is_null() {
+            if (*api).version.major == 1 && (*api).version.minor == 1 {
+                NATIVESCRIPT_1_1 = Some(&*(api as *const NativeScriptApi11));
+                return;
+            }
+            api = (*api).next;
+        }
+    }
+}
+
+/// Forgets the NativeScript 1.1 API, called by `godot_gdnative_terminate!`.
+#[doc(hidden)]
+pub unsafe fn unbind_nativescript_1_1() {
+    NATIVESCRIPT_1_1 = None;
+}
+
+/// Returns the NativeScript 1.1 API if the engine provides it.
+pub(crate) fn nativescript_1_1() -> Option<&'static NativeScriptApi11> {
+    unsafe { NATIVESCRIPT_1_1 }
+}
@@ -0,0 +1,91 @@
+//! Typed access to the nodes of the scene tree.
+//!
+//! Finding a node and casting it to its class, or getting the rust instance of its
+//! script, takes one call returning `None` when any step fails:
+//!
+//! ```ignore
+//! let sprite = owner.get_node_as::<Sprite>("Body/Sprite")?;
+//! let inventory = owner.get_typed_instance::<Inventory>("/root/Inventory")?;
+//! ```
+
+use GodotObject;
+use GodotString;
+use Instance;
+use NativeClass;
+use Node;
+use NodePath;
+
+impl Node {
+    /// The node at `path`, relative to this node or absolute, cast to `T`:
+    ///
+    /// ```ignore
+    /// let sprite = owner.get_node_as::<Sprite>("Body/Sprite").expect("the sprite is missing");
+    /// ```
+    ///
+    /// Returns `None` if there is no node at the path, without the error `get_node`
+    /// prints, or if it isn't a `T`. The other classes of nodes call it through
+    /// `as_node`.
+    pub fn get_node_as<T: GodotObject>(&self, path: &str) -> Option<T> {
+        self.get_node_checked(path).and_then(|node| node.cast::<T>())
+    }
+
+    /// The instance of the rust class `C` attached to the node at `path`, `None` if
+    /// there is no node at the path or if it has another script.
+    pub fn get_typed_instance<C: NativeClass>(&self, path: &str) -> Option<Instance<C>> {
+        self.get_node_checked(path).and_then(|node| Instance::try_from_base(&node))
+    }
+
+    /// The first descendant whose name matches `mask`, like `find_node` with the
+    /// defaults of GDScript, cast to `T`. The mask can contain the wildcards `*` and
+    /// `?`, and only the nodes owned by the owner of the scene are searched.
+    pub fn find_node_as<T: GodotObject>(&self, mask: &str) -> Option<T> {
+        self.find_node(GodotString::from_str(mask), true, true).and_then(|node| node.cast::<T>())
+    }
+
+    /// The instance of the rust class `C` attached to the first descendant whose
+    /// name matches `mask`, see `find_node_as`.
+    pub fn find_typed_instance<C: NativeClass>(&self, mask: &str) -> Option<Instance<C>> {
+        self.find_node(GodotString::from_str(mask), true, true)
+            .and_then(|node| Instance::try_from_base(&node))
+    }
+
+    fn get_node_checked(&self, path: &str) -> Option<Node> {
+        let path = NodePath::from_str(path);
+        // Absolute paths are an error outside the tree.
+        if path.is_absolute() && !self.is_inside_tree() {
+            return None;
+        }
+        if !self.has_node(path.new_ref()) {
+            return None;
+        }
+        self.get_node(path)
+    }
+}
+
+godot_test!(test_node_access {
+    use Node2D;
+    use Spatial;
+
+    let mut owner = Node::new();
+    let mut body = Node2D::new();
+    body.set_name(GodotString::from_str("Body"));
+    let mut sprite = Node2D::new();
+    sprite.set_name(GodotString::from_str("Sprite"));
+    body.add_child(Some(sprite.as_object()), false);
+    owner.add_child(Some(body.as_object()), false);
+    sprite.set_owner(Some(owner.as_object()));
+    body.set_owner(Some(owner.as_object()));
+
+    assert!(owner.get_node_as::<Node2D>("Body/Sprite").is_some());
+    assert!(owner.get_node_as::<Node>("Body").is_some());
+    assert!(owner.get_node_as::<Spatial>("Body/Sprite").is_none());
+    assert!(owner.get_node_as::<Node2D>("Body/Missing").is_none());
+    assert!(owner.get_node_as::<Node2D>("/root/Body").is_none());
+
+    assert!(owner.find_node_as::<Node2D>("Spr*").is_some());
+    assert!(owner.find_node_as::<Spatial>("Spr*").is_none());
+    assert!(owner.find_node_as::<Node2D>("Missing").is_none());
+
+    // Freeing the owner frees the children too.
+    unsafe { owner.free(); }
+});
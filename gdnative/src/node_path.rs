@@ -1,6 +1,7 @@
 use sys;
 use get_api;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use GodotString;
 use Variant;
 use std::fmt;
@@ -112,8 +113,13 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for NodePath {
+impl_sys_access!(for NodePath as godot_node_path);
+
+impl ToVariant for NodePath {
     fn to_variant(&self) -> Variant { Variant::from_node_path(self) }
+}
+
+impl FromVariant for NodePath {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_node_path() }
 }
 
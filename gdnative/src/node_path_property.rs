@@ -0,0 +1,204 @@
+//! Exported node paths, resolved to nodes at runtime.
+//!
+//! Exporting a `NodePath` and fetching the node in `_ready` is the usual way for a
+//! script to reference another node of the scene. `NodePathProperty` is a `NodePath`
+//! that can be used as a property, with a `resolve` method that fetches and casts
+//! the node and describes what went wrong:
+//!
+//! ```ignore
+//! godot_class! {
+//!     class Turret: Node2D {
+//!         fields {
+//!             target: NodePathProperty,
+//!         }
+//!
+//!         setup(builder) {
+//!             builder.add_property(Property {
+//!                 name: "target",
+//!                 default: NodePathProperty::default(),
+//!                 hint: NodePathProperty::hint(),
+//!                 getter: |this: &mut Turret| this.target.clone(),
+//!                 setter: |this: &mut Turret, path| this.target = path,
+//!                 usage: PropertyUsage::DEFAULT,
+//!                 documentation: None,
+//!             });
+//!         }
+//!
+//!         export fn _ready(&mut self) {
+//!             match self.target.resolve::<Spatial>(&self.get_owner()) {
+//!                 Ok(target) => { /* ... */ }
+//!                 Err(err) => godot_error!("{}", err),
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! The property is a `NodePath` for the engine, scripts and scenes see no difference.
+
+use std::error::Error;
+use std::fmt;
+use init::PropertyHint;
+use FromVariant;
+use GodotObject;
+use Node;
+use NodePath;
+use ToVariant;
+use Variant;
+
+/// A `NodePath` exported as a property, see the `node_path_property` module.
+pub struct NodePathProperty {
+    path: NodePath,
+}
+
+impl NodePathProperty {
+    /// Creates a property holding the path, e.g. `"../Player"`.
+    pub fn new(path: &str) -> Self {
+        NodePathProperty { path: NodePath::from_str(path) }
+    }
+
+    pub fn from_node_path(path: NodePath) -> Self {
+        NodePathProperty { path }
+    }
+
+    /// The hint letting the editor pick the node in the edited scene.
+    pub fn hint() -> PropertyHint<'static> {
+        PropertyHint::NodePathToEditedNode
+    }
+
+    pub fn path(&self) -> &NodePath {
+        &self.path
+    }
+
+    pub fn into_node_path(self) -> NodePath {
+        self.path
+    }
+
+    /// Returns `true` if no node was assigned to the property.
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Fetches the node at the path, relative to `owner`, and casts it to `T`.
+    pub fn resolve<T: GodotObject>(&self, owner: &Node) -> Result<T, ResolveError> {
+        if self.path.is_empty() {
+            return Err(ResolveError::Empty);
+        }
+
+        let node = if owner.has_node(self.path.new_ref()) {
+            owner.get_node(self.path.new_ref())
+        } else {
+            None
+        };
+
+        let node = match node {
+            Some(node) => node,
+            None => return Err(ResolveError::NotFound { path: self.path.to_string() }),
+        };
+
+        node.cast::<T>().ok_or_else(|| ResolveError::WrongType {
+            path: self.path.to_string(),
+            expected: T::class_name(),
+            found: node.get_class().to_string(),
+        })
+    }
+}
+
+impl Default for NodePathProperty {
+    fn default() -> Self {
+        NodePathProperty::new("")
+    }
+}
+
+impl Clone for NodePathProperty {
+    fn clone(&self) -> Self {
+        NodePathProperty { path: self.path.new_ref() }
+    }
+}
+
+impl PartialEq for NodePathProperty {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl From<NodePath> for NodePathProperty {
+    fn from(path: NodePath) -> Self {
+        NodePathProperty { path }
+    }
+}
+
+impl ToVariant for NodePathProperty {
+    fn to_variant(&self) -> Variant { self.path.to_variant() }
+}
+
+impl FromVariant for NodePathProperty {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        NodePath::from_variant(variant).map(NodePathProperty::from_node_path)
+    }
+}
+
+impl fmt::Debug for NodePathProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NodePathProperty({})", self.path.to_string())
+    }
+}
+
+/// The reason a `NodePathProperty` couldn't be resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    /// No path was assigned to the property.
+    Empty,
+    /// There is no node at the path.
+    NotFound { path: String },
+    /// The node at the path is not of the expected class.
+    WrongType { path: String, expected: &'static str, found: String },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResolveError::Empty => write!(f, "no node path was assigned"),
+            ResolveError::NotFound { ref path } => write!(f, "node not found: {:?}", path),
+            ResolveError::WrongType { ref path, expected, ref found } => write!(
+                f, "the node at {:?} is a {}, expected a {}", path, found, expected
+            ),
+        }
+    }
+}
+
+impl Error for ResolveError {}
+
+godot_test!(test_node_path_property {
+    use GodotString;
+    use Node2D;
+
+    let mut owner = Node::new();
+    let mut child = Node2D::new();
+    child.set_name(GodotString::from_str("Child"));
+    owner.add_child(Some(child.as_object()), false);
+
+    assert_eq!(NodePathProperty::default().resolve::<Node>(&owner).err(), Some(ResolveError::Empty));
+
+    let path = NodePathProperty::new("Child");
+    assert!(path.resolve::<Node2D>(&owner).is_ok());
+    match path.resolve::<::Spatial>(&owner) {
+        Err(ResolveError::WrongType { expected, found, .. }) => {
+            assert_eq!(expected, "Spatial");
+            assert_eq!(found, "Node2D");
+        }
+        _ => panic!("expected a type error"),
+    }
+
+    let missing = NodePathProperty::new("Missing");
+    assert_eq!(
+        missing.resolve::<Node>(&owner).err(),
+        Some(ResolveError::NotFound { path: "Missing".to_string() })
+    );
+
+    let variant = path.to_variant();
+    assert_eq!(NodePathProperty::from_variant(&variant), Some(path));
+
+    // Freeing the owner frees the child too.
+    unsafe { owner.free(); }
+});
@@ -0,0 +1,166 @@
+//! Numbers formatted with the separators of the locale of the game.
+//!
+//! The engine formats numbers with `GodotString::num` and friends, always with a
+//! dot and without grouping the thousands. UI code displaying numbers to the player
+//! applies the separators of the current locale of the `TranslationServer` with
+//! `NumberFormat`, so they read like the translated text around them:
+//!
+//! ```ignore
+//! let format = NumberFormat::current();
+//! label.set_text(GodotString::from_str(format.format_int(gold)));
+//! label.set_text(GodotString::from_str(format.format_percent(progress, 1)));
+//! // Any number formatted by the engine.
+//! let distance = format.localize(&GodotString::num_with_decimals(meters, 2).to_string());
+//! ```
+//!
+//! The separators come from a table of the common languages, by the language of the
+//! locale, and a few regions using other separators. Unknown locales use the
+//! English separators.
+
+use TranslationServer;
+
+// The space of the separators, which doesn't break the lines between the digits.
+const NBSP: char = '\u{a0}';
+
+/// The separators of the numbers of a locale, see the `number_format` module.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal_separator: char,
+    /// The separator of the groups of 3 digits, `None` to not group them.
+    pub group_separator: Option<char>,
+    /// Whether a space goes between the number and the percent sign.
+    pub percent_space: bool,
+}
+
+impl Default for NumberFormat {
+    /// The separators of English.
+    fn default() -> Self {
+        NumberFormat {
+            decimal_separator: '.',
+            group_separator: Some(','),
+            percent_space: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// The separators of the locale of the `TranslationServer`. Must be called again
+    /// after the locale changes.
+    pub fn current() -> Self {
+        let locale = TranslationServer::godot_singleton().get_locale();
+        NumberFormat::for_locale(&locale.to_string())
+    }
+
+    /// The separators of a locale like `fr`, `pt_BR` or `de-CH`.
+    pub fn for_locale(locale: &str) -> Self {
+        let mut parts = locale.split(|c| c == '_' || c == '-');
+        let language = parts.next().unwrap_or("").to_lowercase();
+        let region = parts.next().unwrap_or("").to_uppercase();
+
+        let (decimal_separator, group_separator) = match (language.as_str(), region.as_str()) {
+            ("de", "CH") | ("de", "LI") | ("it", "CH") => ('.', Some('\'')),
+            ("pt", "PT") => (',', Some(NBSP)),
+            ("es", "MX") | ("es", "US") => ('.', Some(',')),
+            ("de", _) | ("es", _) | ("it", _) | ("nl", _) | ("pt", _) | ("id", _) | ("tr", _)
+            | ("da", _) | ("el", _) | ("ro", _) | ("hr", _) | ("sl", _) | ("sr", _) | ("vi", _) => {
+                (',', Some('.'))
+            }
+            ("fr", _) | ("ru", _) | ("pl", _) | ("cs", _) | ("sk", _) | ("sv", _) | ("fi", _)
+            | ("nb", _) | ("nn", _) | ("no", _) | ("uk", _) | ("hu", _) | ("bg", _) | ("lt", _)
+            | ("lv", _) | ("et", _) => (',', Some(NBSP)),
+            _ => ('.', Some(',')),
+        };
+        let percent_space = match language.as_str() {
+            "fr" | "de" | "es" | "sv" | "fi" | "nb" | "nn" | "no" | "da" | "cs" | "sk" | "ru"
+            | "uk" => true,
+            _ => false,
+        };
+
+        NumberFormat {
+            decimal_separator,
+            group_separator,
+            percent_space,
+        }
+    }
+
+    /// The integer with its digits grouped.
+    pub fn format_int(&self, value: i64) -> String {
+        self.localize(&value.to_string())
+    }
+
+    /// The number rounded to `decimals` decimals, padded with zeros.
+    pub fn format_decimal(&self, value: f64, decimals: usize) -> String {
+        self.localize(&format!("{:.*}", decimals, value))
+    }
+
+    /// The ratio, 1 for 100%, as a percentage rounded to `decimals` decimals.
+    pub fn format_percent(&self, ratio: f64, decimals: usize) -> String {
+        let number = self.format_decimal(ratio * 100.0, decimals);
+        if self.percent_space {
+            format!("{}{}%", number, NBSP)
+        } else {
+            format!("{}%", number)
+        }
+    }
+
+    /// Replaces the separators of a number formatted by the engine or by rust, with
+    /// an optional sign, a dot before the decimals and an optional exponent. Other
+    /// text, like `inf`, is returned unchanged.
+    pub fn localize(&self, number: &str) -> String {
+        let (sign, unsigned) = match number.chars().next() {
+            Some(c @ '-') | Some(c @ '+') => (Some(c), &number[1..]),
+            _ => (None, number),
+        };
+        let (mantissa, exponent) = match unsigned.find(|c| c == 'e' || c == 'E') {
+            Some(idx) => unsigned.split_at(idx),
+            None => (unsigned, ""),
+        };
+        let (integer, decimals) = match mantissa.find('.') {
+            Some(idx) => (&mantissa[..idx], Some(&mantissa[idx + 1..])),
+            None => (mantissa, None),
+        };
+        if integer.is_empty() || !integer.bytes().all(|b| b.is_ascii_digit()) {
+            return number.to_string();
+        }
+
+        let mut result = String::with_capacity(number.len() + integer.len() / 3);
+        result.extend(sign);
+        for (idx, digit) in integer.chars().enumerate() {
+            if idx > 0 && (integer.len() - idx) % 3 == 0 {
+                result.extend(self.group_separator);
+            }
+            result.push(digit);
+        }
+        if let Some(decimals) = decimals {
+            result.push(self.decimal_separator);
+            result.push_str(decimals);
+        }
+        result.push_str(exponent);
+        result
+    }
+}
+
+#[test]
+fn number_format() {
+    let english = NumberFormat::for_locale("en_US");
+    assert_eq!(english, NumberFormat::default());
+    assert_eq!(english.format_int(-1234567), "-1,234,567");
+    assert_eq!(english.format_int(123), "123");
+    assert_eq!(english.format_decimal(1234.5678, 2), "1,234.57");
+    assert_eq!(english.format_percent(0.256, 1), "25.6%");
+    assert_eq!(english.localize("1.5e+20"), "1.5e+20");
+    assert_eq!(english.localize("inf"), "inf");
+    assert_eq!(english.localize(".5"), ".5");
+
+    let french = NumberFormat::for_locale("fr_FR");
+    assert_eq!(french.format_decimal(-1234.5, 1), "-1\u{a0}234,5");
+    assert_eq!(french.format_percent(0.5, 0), "50\u{a0}%");
+
+    assert_eq!(NumberFormat::for_locale("de").format_int(1000000), "1.000.000");
+    assert_eq!(NumberFormat::for_locale("de-CH").format_decimal(12345.25, 2), "12'345.25");
+    assert_eq!(NumberFormat::for_locale("pt_BR").localize("1000.5"), "1.000,5");
+    assert_eq!(NumberFormat::for_locale("ja").format_percent(1.0, 0), "100%");
+
+    let ungrouped = NumberFormat { group_separator: None, ..NumberFormat::default() };
+    assert_eq!(ungrouped.format_int(123456), "123456");
+}
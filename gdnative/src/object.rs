@@ -1,15 +1,56 @@
+use std::fmt;
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use libc;
 use sys;
+use Node;
+use Object;
 use ObjectMethodTable;
+#[cfg(feature = "dynamic_dispatch")]
+use GodotType;
+#[cfg(feature = "dynamic_dispatch")]
+use Variant;
 
-/// Internal details.
+/// The wrappers of the engine classes.
+///
+/// `as_sys`, `to_sys` and `from_sys` are the escape hatches to the raw API for the
+/// methods the wrappers don't cover, see `sys_api`.
+///
+/// Implemented by the generated classes only, implementing it for another type
+/// is undefined behavior.
 pub unsafe trait GodotObject {
     fn class_name() -> &'static str;
-    #[doc(hidden)]
+
+    /// The raw pointer to the object, to pass to the functions of `sys_api()`.
+    ///
+    /// # Safety
+    ///
+    /// The pointer doesn't hold a reference: it must not be used after the
+    /// object is freed, or after the last reference to a `Reference` is dropped.
     unsafe fn to_sys(&self) -> *mut sys::godot_object;
-    #[doc(hidden)]
+
+    /// Wraps a raw pointer to an object. For `Reference`s, the wrapper adds a
+    /// reference, released when it is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `obj` must point to a live object of this class or of a class inheriting
+    /// it.
     unsafe fn from_sys(obj: *mut sys::godot_object) -> Self;
+
+    /// The raw pointer to the object, like `to_sys`. Using it is unsafe, with the
+    /// same contract.
+    fn as_sys(&self) -> *mut sys::godot_object {
+        unsafe { self.to_sys() }
+    }
+}
+
+// The number of references held by rust wrappers, see `MemoryReport`.
+static LIVE_REFERENCES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg_attr(not(gdnative_helper = "memory_report"), allow(dead_code))]
+pub(crate) fn live_references() -> usize {
+    LIVE_REFERENCES.load(Ordering::Relaxed)
 }
 
 // This function assumes the godot_object is reference counted.
@@ -32,6 +73,7 @@ pub(crate) unsafe fn add_ref(obj: *mut sys::godot_object) {
     // and we tried to increment the ref count of a dead object (who's ref
     // count is equal to zero).
     debug_assert!(ok);
+    LIVE_REFERENCES.fetch_add(1, Ordering::Relaxed);
 }
 
 // This function assumes the godot_object is reference counted.
@@ -49,6 +91,7 @@ pub(crate) unsafe fn unref(obj: *mut sys::godot_object) -> bool {
         ret_ptr as *mut _
     );
 
+    LIVE_REFERENCES.fetch_sub(1, Ordering::Relaxed);
     last_reference
 }
 
@@ -68,6 +111,7 @@ pub(crate) unsafe fn init_ref_count(obj: *mut sys::godot_object) {
     );
 
     debug_assert!(ok);
+    LIVE_REFERENCES.fetch_add(1, Ordering::Relaxed);
 }
 
 pub fn is_class(obj: *mut sys::godot_object, class_name: &str) -> bool {
@@ -98,6 +142,26 @@ pub fn is_class(obj: *mut sys::godot_object, class_name: &str) -> bool {
     }
 }
 
+// The `Debug` output of the wrappers, like `Sprite { id: 1187, path: "/root/Main/Player" }`:
+// the actual class of the object, its instance id, and the path of the nodes inside
+// the tree.
+pub(crate) fn debug_object(obj: *mut sys::godot_object, f: &mut fmt::Formatter) -> fmt::Result {
+    if obj.is_null() {
+        return f.write_str("null");
+    }
+
+    let object = unsafe { Object::from_sys(obj) };
+    let class = object.get_class().to_string();
+    let mut debug = f.debug_struct(&class);
+    debug.field("id", &object.get_instance_id());
+    if let Some(node) = godot_cast::<Node>(obj) {
+        if node.is_inside_tree() {
+            debug.field("path", &node.get_path().to_string());
+        }
+    }
+    debug.finish()
+}
+
 pub fn godot_cast<T>(from: *mut sys::godot_object) -> Option<T>
 where
     T: GodotObject,
@@ -110,3 +174,54 @@ where
         Some(T::from_sys(from))
     }
 }
+
+// Used by the methods generated with the `dynamic_dispatch` feature. Every
+// generated method goes through this single function instead of inlining its
+// own ptrcall marshalling code, which keeps the size of the bindings down at
+// the cost of converting the arguments and the return value to variants.
+#[cfg(feature = "dynamic_dispatch")]
+#[inline(never)]
+pub(crate) unsafe fn dynamic_call(
+    method_bind: *mut sys::godot_method_bind,
+    obj: *mut sys::godot_object,
+    args: &[Variant],
+) -> Variant {
+    let api = ::get_api();
+
+    let mut argument_buffer = ::arg_buffer::ArgBuffer::from_variants(args);
+
+    let mut err = sys::godot_variant_call_error::default();
+    let ret = (api.godot_method_bind_call)(
+        method_bind,
+        obj,
+        argument_buffer.as_mut_ptr(),
+        argument_buffer.len() as _,
+        &mut err,
+    );
+
+    debug_assert!(
+        err.error == sys::godot_variant_call_error_error::GODOT_CALL_ERROR_CALL_OK,
+        "Dynamic method call failed"
+    );
+
+    Variant::from_sys(ret)
+}
+
+// Converts an object argument for `dynamic_call`.
+#[cfg(feature = "dynamic_dispatch")]
+#[inline(never)]
+pub(crate) unsafe fn object_to_variant(obj: *mut sys::godot_object) -> Variant {
+    if obj.is_null() {
+        return Variant::new();
+    }
+
+    let mut dest = sys::godot_variant::default();
+    (::get_api().godot_variant_new_object)(&mut dest, obj);
+    Variant::from_sys(dest)
+}
+
+// Converts the return value of `dynamic_call`.
+#[cfg(feature = "dynamic_dispatch")]
+pub(crate) fn variant_to_return<T: GodotType>(ret: &Variant) -> T {
+    T::from_variant(ret).expect("Unexpected return type in dynamic method call")
+}
@@ -0,0 +1,104 @@
+//! Parallel computations with rayon, with the `rayon` feature.
+//!
+//! The engine objects can't be used from the threads of rayon: the wrappers aren't
+//! `Send`, and the scene tree may only be changed from the main thread. Parallel
+//! code works on plain rust data cached from the nodes, and the results are applied
+//! to the nodes on the main thread, either when the parallel part returns:
+//!
+//! ```ignore
+//! // `self.boids` is a `Vec<(Spatial, Boid)>`, the `Boid`s cache the positions.
+//! par_process_nodes(
+//!     &mut self.boids,
+//!     |boid| boid.steer(&neighbours),
+//!     |node, velocity| node.translate(velocity * delta),
+//! );
+//! ```
+//!
+//! or later, by jobs queued from any thread with `run_on_main_thread`. The jobs run
+//! when the main thread calls `run_main_thread_jobs`, usually once per frame from
+//! the `_process` method of an autoload:
+//!
+//! ```ignore
+//! let target = node.get_instance_id();
+//! rayon::spawn(move || {
+//!     let path = navmesh.find_path(from, to);
+//!     run_on_main_thread(move || {
+//!         if let Some(mut node) = instance_from_id::<Spatial>(target) {
+//!             node.follow(path);
+//!         }
+//!     });
+//! });
+//! ```
+
+use std::mem;
+use std::sync::Mutex;
+use rayon::prelude::*;
+
+static MAIN_THREAD_JOBS: Mutex<Vec<Box<dyn FnOnce() + Send>>> = Mutex::new(Vec::new());
+
+/// Queues a job to be run on the main thread by `run_main_thread_jobs`, see the
+/// `parallel` module. Can be called from any thread.
+pub fn run_on_main_thread<F>(job: F)
+where F: FnOnce() + Send + 'static {
+    if let Ok(mut jobs) = MAIN_THREAD_JOBS.lock() {
+        jobs.push(Box::new(job));
+    }
+}
+
+/// Runs the jobs queued by `run_on_main_thread`, in the order they were queued, and
+/// returns their number. Must be called from the main thread.
+///
+/// Jobs queued while the jobs run are left for the next call.
+pub fn run_main_thread_jobs() -> usize {
+    let jobs = match MAIN_THREAD_JOBS.lock() {
+        Ok(mut jobs) => mem::replace(&mut *jobs, Vec::new()),
+        Err(_) => return 0,
+    };
+
+    let count = jobs.len();
+    for job in jobs {
+        job();
+    }
+    count
+}
+
+/// The number of jobs waiting for `run_main_thread_jobs`.
+pub fn pending_main_thread_jobs() -> usize {
+    MAIN_THREAD_JOBS.lock().map(|jobs| jobs.len()).unwrap_or(0)
+}
+
+/// Computes a result from the cached data of every node on the threads of rayon,
+/// then applies the results to the nodes, in order, on the calling thread. Must be
+/// called from the main thread when `apply` changes the scene tree.
+pub fn par_process_nodes<N, D, R, C, A>(nodes: &mut [(N, D)], compute: C, mut apply: A)
+where
+    D: Sync,
+    R: Send,
+    C: Fn(&D) -> R + Sync,
+    A: FnMut(&mut N, R),
+{
+    let data: Vec<&D> = nodes.iter().map(|&(_, ref data)| data).collect();
+    let results: Vec<R> = data.par_iter().map(|data| compute(data)).collect();
+
+    for (&mut (ref mut node, _), result) in nodes.iter_mut().zip(results) {
+        apply(node, result);
+    }
+}
+
+#[test]
+fn parallel_processing() {
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    // Like the engine wrappers, the nodes are neither `Send` nor `Sync`.
+    let mut nodes: Vec<(Rc<Cell<i64>>, i64)> = (0..100).map(|idx| (Rc::new(Cell::new(0)), idx)).collect();
+    par_process_nodes(&mut nodes, |data| data * 2, |node, result| node.set(result));
+    assert!(nodes.iter().all(|&(ref node, data)| node.get() == data * 2));
+
+    run_on_main_thread(|| run_on_main_thread(|| ()));
+    ::std::thread::spawn(|| run_on_main_thread(|| ())).join().unwrap();
+    assert_eq!(pending_main_thread_jobs(), 2);
+    assert_eq!(run_main_thread_jobs(), 2);
+    assert_eq!(run_main_thread_jobs(), 1);
+    assert_eq!(run_main_thread_jobs(), 0);
+}
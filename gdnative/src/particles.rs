@@ -0,0 +1,121 @@
+//! Typed helpers for `Particles`, `Particles2D` and `ParticlesMaterial`.
+//!
+//! `CPUParticles` is not part of the engine API these bindings are generated from,
+//! so only the GPU particle nodes are covered.
+//!
+//! ```ignore
+//! let mut material = sparks.process_material().unwrap();
+//! material.set_parameter(ParticlesMaterialParameter::ParamInitialLinearVelocity, 8.0);
+//! material.set_emission(EmissionShape::Sphere { radius: 0.2 });
+//!
+//! // Emit a single burst of sparks at the hit position.
+//! sparks.emit_once();
+//! ```
+
+use Particles;
+use Particles2D;
+use ParticlesMaterial;
+use ParticlesMaterialEmissionShape;
+use ParticlesMaterialFlags;
+use ParticlesMaterialParameter;
+use Texture;
+use Vector3;
+
+/// The shape particles are emitted from.
+///
+/// The shapes emitting from the points of a texture can be set up with
+/// `set_emission_shape` and the `set_emission_*_texture` methods.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EmissionShape {
+    Point,
+    Sphere { radius: f64 },
+    Box { extents: Vector3 },
+}
+
+impl ParticlesMaterial {
+    pub fn set_parameter(&mut self, param: ParticlesMaterialParameter, value: f64) {
+        self.set_param(param as i64, value);
+    }
+
+    pub fn parameter(&self, param: ParticlesMaterialParameter) -> f64 {
+        self.get_param(param as i64)
+    }
+
+    /// Sets the randomness ratio of a parameter, between 0 and 1.
+    pub fn set_parameter_randomness(&mut self, param: ParticlesMaterialParameter, randomness: f64) {
+        self.set_param_randomness(param as i64, randomness);
+    }
+
+    pub fn parameter_randomness(&self, param: ParticlesMaterialParameter) -> f64 {
+        self.get_param_randomness(param as i64)
+    }
+
+    /// Sets the texture used to vary a parameter over the lifetime of the particles.
+    pub fn set_parameter_texture(&mut self, param: ParticlesMaterialParameter, texture: Option<Texture>) {
+        self.set_param_texture(param as i64, texture);
+    }
+
+    pub fn parameter_texture(&self, param: ParticlesMaterialParameter) -> Option<Texture> {
+        self.get_param_texture(param as i64)
+    }
+
+    pub fn set_flag_enabled(&mut self, flag: ParticlesMaterialFlags, enabled: bool) {
+        self.set_flag(flag as i64, enabled);
+    }
+
+    pub fn is_flag_enabled(&self, flag: ParticlesMaterialFlags) -> bool {
+        self.get_flag(flag as i64)
+    }
+
+    /// Sets the emission shape along with its size.
+    pub fn set_emission(&mut self, shape: EmissionShape) {
+        match shape {
+            EmissionShape::Point => {
+                self.set_emission_shape(ParticlesMaterialEmissionShape::EmissionShapePoint as i64);
+            }
+            EmissionShape::Sphere { radius } => {
+                self.set_emission_shape(ParticlesMaterialEmissionShape::EmissionShapeSphere as i64);
+                self.set_emission_sphere_radius(radius);
+            }
+            EmissionShape::Box { extents } => {
+                self.set_emission_shape(ParticlesMaterialEmissionShape::EmissionShapeBox as i64);
+                self.set_emission_box_extents(extents);
+            }
+        }
+    }
+}
+
+macro_rules! particles_impl {
+    ($ty:ident) => (
+        impl $ty {
+            /// Starts emitting particles.
+            pub fn start(&mut self) {
+                self.set_emitting(true);
+            }
+
+            /// Stops emitting new particles. The particles already emitted finish their
+            /// lifetime.
+            pub fn stop(&mut self) {
+                self.set_emitting(false);
+            }
+
+            /// Emits a single burst of particles, for example for hit sparks.
+            ///
+            /// The node is switched to one-shot mode and the emission restarted, so the
+            /// particles of a previous burst are removed.
+            pub fn emit_once(&mut self) {
+                self.set_one_shot(true);
+                self.restart();
+                self.set_emitting(true);
+            }
+
+            /// Returns the process material if it is a `ParticlesMaterial`.
+            pub fn process_material(&self) -> Option<ParticlesMaterial> {
+                self.get_process_material().and_then(|material| material.cast())
+            }
+        }
+    )
+}
+
+particles_impl!(Particles);
+particles_impl!(Particles2D);
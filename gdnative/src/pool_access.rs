@@ -0,0 +1,199 @@
+//! Direct access to the buffers of the pool arrays.
+//!
+//! `read()` and `write()` lock the buffer of a pool array and return a guard that
+//! derefs to a slice of its elements, without copying them:
+//!
+//! ```ignore
+//! let mut pixels = image.get_data();
+//! for pixel in pixels.write().chunks_mut(4) {
+//!     pixel[3] = 255;
+//! }
+//! ```
+//!
+//! The buffer is unlocked when the guard is dropped. The array can't be resized or
+//! modified element by element while a guard is alive, and `write()` copies the
+//! buffer first if it is shared with another array (copy on write).
+
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+use sys;
+use get_api;
+use ByteArray;
+use Color;
+use ColorArray;
+use Float32Array;
+use GodotString;
+use Int32Array;
+use StringArray;
+use Vector2;
+use Vector2Array;
+use Vector3;
+use Vector3Array;
+
+type DestroyReadFn = unsafe extern "C" fn(*mut sys::godot_pool_array_read_access);
+type DestroyWriteFn = unsafe extern "C" fn(*mut sys::godot_pool_array_write_access);
+
+/// A read lock on the buffer of a pool array, derefs to a slice of its elements.
+pub struct PoolArrayRead<'a, T: 'a> {
+    access: *mut sys::godot_pool_array_read_access,
+    destroy: DestroyReadFn,
+    elements: &'a [T],
+}
+
+impl<'a, T> Deref for PoolArrayRead<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.elements
+    }
+}
+
+impl<'a, T> Drop for PoolArrayRead<'a, T> {
+    fn drop(&mut self) {
+        unsafe { (self.destroy)(self.access) }
+    }
+}
+
+/// A write lock on the buffer of a pool array, derefs to a mutable slice of its
+/// elements.
+pub struct PoolArrayWrite<'a, T: 'a> {
+    access: *mut sys::godot_pool_array_write_access,
+    destroy: DestroyWriteFn,
+    elements: &'a mut [T],
+}
+
+impl<'a, T> Deref for PoolArrayWrite<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.elements
+    }
+}
+
+impl<'a, T> DerefMut for PoolArrayWrite<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.elements
+    }
+}
+
+impl<'a, T> Drop for PoolArrayWrite<'a, T> {
+    fn drop(&mut self) {
+        unsafe { (self.destroy)(self.access) }
+    }
+}
+
+// The engine returns a null pointer for empty arrays.
+fn elements_ptr<T>(ptr: *const T, len: usize) -> *mut T {
+    if ptr.is_null() || len == 0 {
+        NonNull::dangling().as_ptr()
+    } else {
+        ptr as *mut T
+    }
+}
+
+macro_rules! pool_access_impl {
+    (
+        $Array:ident, $Element:ty,
+        $read:ident, $read_ptr:ident, $read_destroy:ident,
+        $write:ident, $write_ptr:ident, $write_destroy:ident
+    ) => (
+        impl $Array {
+            /// Locks the buffer of the array for reading, see the `pool_access` module.
+            pub fn read(&self) -> PoolArrayRead<'_, $Element> {
+                let len = self.len() as usize;
+                unsafe {
+                    let api = get_api();
+                    let access = (api.$read)(&self.0);
+                    let ptr = elements_ptr((api.$read_ptr)(access) as *const $Element, len);
+                    PoolArrayRead {
+                        access,
+                        destroy: api.$read_destroy,
+                        elements: slice::from_raw_parts(ptr, len),
+                    }
+                }
+            }
+
+            /// Locks the buffer of the array for writing, see the `pool_access` module.
+            pub fn write(&mut self) -> PoolArrayWrite<'_, $Element> {
+                let len = self.len() as usize;
+                unsafe {
+                    let api = get_api();
+                    let access = (api.$write)(&mut self.0);
+                    let ptr = elements_ptr((api.$write_ptr)(access) as *const $Element, len);
+                    PoolArrayWrite {
+                        access,
+                        destroy: api.$write_destroy,
+                        elements: slice::from_raw_parts_mut(ptr, len),
+                    }
+                }
+            }
+        }
+    )
+}
+
+pool_access_impl!(
+    ByteArray, u8,
+    godot_pool_byte_array_read, godot_pool_byte_array_read_access_ptr, godot_pool_byte_array_read_access_destroy,
+    godot_pool_byte_array_write, godot_pool_byte_array_write_access_ptr, godot_pool_byte_array_write_access_destroy
+);
+
+pool_access_impl!(
+    Int32Array, i32,
+    godot_pool_int_array_read, godot_pool_int_array_read_access_ptr, godot_pool_int_array_read_access_destroy,
+    godot_pool_int_array_write, godot_pool_int_array_write_access_ptr, godot_pool_int_array_write_access_destroy
+);
+
+pool_access_impl!(
+    Float32Array, f32,
+    godot_pool_real_array_read, godot_pool_real_array_read_access_ptr, godot_pool_real_array_read_access_destroy,
+    godot_pool_real_array_write, godot_pool_real_array_write_access_ptr, godot_pool_real_array_write_access_destroy
+);
+
+pool_access_impl!(
+    StringArray, GodotString,
+    godot_pool_string_array_read, godot_pool_string_array_read_access_ptr, godot_pool_string_array_read_access_destroy,
+    godot_pool_string_array_write, godot_pool_string_array_write_access_ptr, godot_pool_string_array_write_access_destroy
+);
+
+pool_access_impl!(
+    Vector2Array, Vector2,
+    godot_pool_vector2_array_read, godot_pool_vector2_array_read_access_ptr, godot_pool_vector2_array_read_access_destroy,
+    godot_pool_vector2_array_write, godot_pool_vector2_array_write_access_ptr, godot_pool_vector2_array_write_access_destroy
+);
+
+pool_access_impl!(
+    Vector3Array, Vector3,
+    godot_pool_vector3_array_read, godot_pool_vector3_array_read_access_ptr, godot_pool_vector3_array_read_access_destroy,
+    godot_pool_vector3_array_write, godot_pool_vector3_array_write_access_ptr, godot_pool_vector3_array_write_access_destroy
+);
+
+pool_access_impl!(
+    ColorArray, Color,
+    godot_pool_color_array_read, godot_pool_color_array_read_access_ptr, godot_pool_color_array_read_access_destroy,
+    godot_pool_color_array_write, godot_pool_color_array_write_access_ptr, godot_pool_color_array_write_access_destroy
+);
+
+godot_test!(test_pool_access {
+    let mut bytes = ByteArray::from_slice(&[1, 2, 3, 4]);
+    assert_eq!(&*bytes.read(), &[1, 2, 3, 4]);
+
+    for byte in bytes.write().iter_mut() {
+        *byte *= 2;
+    }
+    assert_eq!(bytes.get(3), 8);
+
+    // Writing to a shared buffer copies it.
+    let copy = bytes.new_ref();
+    bytes.write()[0] = 0;
+    assert_eq!(copy.read()[0], 2);
+    assert_eq!(bytes.read()[0], 0);
+
+    let empty = Float32Array::new();
+    assert!(empty.read().is_empty());
+
+    let mut strings = StringArray::new();
+    strings.push(&GodotString::from_str("a"));
+    strings.write()[0] = GodotString::from_str("b");
+    assert_eq!(strings.read()[0].to_string(), "b");
+});
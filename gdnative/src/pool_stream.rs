@@ -0,0 +1,235 @@
+//! Moving large amounts of data between Rust and pool arrays or files in chunks.
+//!
+//! Converting a pool array with `get`/`set` or through a `Vec` keeps two copies of
+//! the data alive: the engine buffer and the Rust one. For datasets of hundreds of
+//! megabytes (heightmaps, navigation data, recorded audio...) this doubles the peak
+//! memory usage. The functions of this module copy the data directly between the
+//! engine buffer and Rust slices, a chunk at a time:
+//!
+//! | operation                        | memory used besides the array         |
+//! |----------------------------------|---------------------------------------|
+//! | `extend_from_slice(&data)`       | none, `data` is copied into the array |
+//! | `read_into(offset, &mut buffer)` | none, the array is copied to `buffer` |
+//! | `for_each_chunk(len, op)`        | none, `op` borrows the engine buffer  |
+//! | `FileReader::read(&mut buffer)`  | one engine chunk of `buffer.len()`    |
+//! | `FileWriter::write(&data)`       | one engine chunk of `data.len()`      |
+//!
+//! `ByteArray`, `Int32Array`, `Float32Array`, `Vector2Array`, `Vector3Array` and
+//! `ColorArray` have the array functions. `StringArray` doesn't, its elements are
+//! engine strings.
+//!
+//! The whole buffer of an array can also be accessed without a copy with `read` and
+//! `write`, see the `pool_access` module.
+//!
+//! `FileReader` and `FileWriter` implement `std::io::Read` and `std::io::Write` for
+//! files of the engine, which gives access to `res://` and `user://`. Streaming a
+//! file of any size with a fixed buffer:
+//!
+//! ```ignore
+//! let mut reader = FileReader::open("user://terrain.bin")?;
+//! let mut chunk = vec![0; 1 << 20];
+//! loop {
+//!     let len = reader.read(&mut chunk)?;
+//!     if len == 0 {
+//!         break;
+//!     }
+//!     heightmap.extend_from_slice(&chunk[..len]);
+//! }
+//! ```
+//!
+//! A whole pool array is written without a copy with `_File::store_buffer`, which
+//! takes a new reference to the array.
+
+use std::cmp;
+use std::io;
+use ByteArray;
+use Color;
+use ColorArray;
+use Float32Array;
+use GodotError;
+use GodotString;
+use Int32Array;
+use Vector2;
+use Vector2Array;
+use Vector3;
+use Vector3Array;
+use _File;
+use _FileModeFlags;
+
+macro_rules! pool_stream_impl {
+    ($Array:ident, $Element:ty) => (
+        impl $Array {
+            /// Appends the elements of the slice, resizing the array once.
+            pub fn extend_from_slice(&mut self, elements: &[$Element]) {
+                if elements.is_empty() {
+                    return;
+                }
+
+                let start = self.len();
+                self.resize(start + elements.len() as i32);
+                self.write()[start as usize..].copy_from_slice(elements);
+            }
+
+            /// Copies the elements starting at `offset` into `buffer` and returns the
+            /// number of elements copied, which is smaller than the length of the
+            /// buffer at the end of the array.
+            pub fn read_into(&self, offset: i32, buffer: &mut [$Element]) -> usize {
+                let elements = self.read();
+                if offset < 0 || offset as usize >= elements.len() {
+                    return 0;
+                }
+
+                let offset = offset as usize;
+                let count = cmp::min(buffer.len(), elements.len() - offset);
+                buffer[..count].copy_from_slice(&elements[offset..offset + count]);
+                count
+            }
+
+            /// Calls `op` with consecutive chunks of at most `chunk_len` elements,
+            /// borrowed from the buffer of the engine.
+            pub fn for_each_chunk<F>(&self, chunk_len: usize, mut op: F)
+            where F: FnMut(&[$Element]) {
+                for chunk in self.read().chunks(cmp::max(chunk_len, 1)) {
+                    op(chunk);
+                }
+            }
+        }
+    )
+}
+
+pool_stream_impl!(ByteArray, u8);
+pool_stream_impl!(Int32Array, i32);
+pool_stream_impl!(Float32Array, f32);
+pool_stream_impl!(Vector2Array, Vector2);
+pool_stream_impl!(Vector3Array, Vector3);
+pool_stream_impl!(ColorArray, Color);
+
+// `_File` reads and writes `ByteArray`s, which are indexed by `i32`.
+const MAX_CHUNK_LEN: usize = i32::max_value() as usize;
+
+/// Reads an engine file with `std::io::Read`, see the `pool_stream` module.
+pub struct FileReader {
+    file: _File,
+}
+
+impl FileReader {
+    /// Opens the file at `path` for reading.
+    pub fn open(path: &str) -> Result<Self, GodotError> {
+        let mut file = _File::new();
+        file.open(GodotString::from_str(path), _FileModeFlags::Read as i64)?;
+        Ok(FileReader { file })
+    }
+
+    /// Reads from a file opened for reading.
+    pub fn new(file: _File) -> Self {
+        FileReader { file }
+    }
+
+    /// Returns the file.
+    pub fn into_inner(self) -> _File {
+        self.file
+    }
+}
+
+impl io::Read for FileReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() || self.file.eof_reached() {
+            return Ok(0);
+        }
+
+        let len = cmp::min(buffer.len(), MAX_CHUNK_LEN);
+        let chunk = self.file.get_buffer(len as i64);
+        check_file_error(&self.file)?;
+        Ok(chunk.read_into(0, &mut buffer[..len]))
+    }
+}
+
+/// Writes an engine file with `std::io::Write`, see the `pool_stream` module.
+pub struct FileWriter {
+    file: _File,
+}
+
+impl FileWriter {
+    /// Creates or truncates the file at `path` and opens it for writing.
+    pub fn create(path: &str) -> Result<Self, GodotError> {
+        let mut file = _File::new();
+        file.open(GodotString::from_str(path), _FileModeFlags::Write as i64)?;
+        Ok(FileWriter { file })
+    }
+
+    /// Writes to a file opened for writing.
+    pub fn new(file: _File) -> Self {
+        FileWriter { file }
+    }
+
+    /// Returns the file.
+    pub fn into_inner(self) -> _File {
+        self.file
+    }
+}
+
+impl io::Write for FileWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let len = cmp::min(data.len(), MAX_CHUNK_LEN);
+        let mut chunk = ByteArray::new();
+        chunk.extend_from_slice(&data[..len]);
+        self.file.store_buffer(chunk);
+        check_file_error(&self.file)?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn check_file_error(file: &_File) -> io::Result<()> {
+    match file.get_error() {
+        Ok(()) | Err(GodotError::FileEof) => Ok(()),
+        Err(err) => Err(io::Error::new(io::ErrorKind::Other, format!("{:?}", err))),
+    }
+}
+
+godot_test!(test_pool_stream {
+    use std::io::{Read, Write};
+
+    let mut bytes = ByteArray::new();
+    bytes.push(1);
+    bytes.extend_from_slice(&[2, 3, 4, 5]);
+    assert_eq!(bytes.len(), 5);
+    assert_eq!(bytes.get(4), 5);
+
+    let mut buffer = [0; 3];
+    assert_eq!(bytes.read_into(3, &mut buffer), 2);
+    assert_eq!(&buffer[..2], &[4, 5]);
+    assert_eq!(bytes.read_into(5, &mut buffer), 0);
+
+    let mut chunks = Vec::new();
+    bytes.for_each_chunk(2, |chunk| chunks.push(chunk.to_vec()));
+    assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+    let mut vectors = Vector2Array::new();
+    vectors.extend_from_slice(&[Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)]);
+    assert_eq!(vectors.get(1), Vector2::new(3.0, 4.0));
+
+    let data: Vec<u8> = (0..10000).map(|i| i as u8).collect();
+    {
+        let mut writer = FileWriter::create("user://gdnative_pool_stream_test.bin").unwrap();
+        for chunk in data.chunks(4096) {
+            writer.write_all(chunk).unwrap();
+        }
+    }
+
+    let mut reader = FileReader::open("user://gdnative_pool_stream_test.bin").unwrap();
+    let mut read = Vec::new();
+    let mut chunk = [0; 3000];
+    loop {
+        let len = reader.read(&mut chunk).unwrap();
+        if len == 0 {
+            break;
+        }
+        read.extend_from_slice(&chunk[..len]);
+    }
+    assert_eq!(read, data);
+    reader.into_inner().close();
+});
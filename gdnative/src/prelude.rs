@@ -0,0 +1,28 @@
+//! The types, traits and macros used by most classes, in one import:
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate gdnative;
+//!
+//! use gdnative::prelude::*;
+//! ```
+//!
+//! The prelude follows the reorganizations of the crate, so the files importing it
+//! don't need to. The other engine classes and the helpers are imported from the
+//! root of the crate.
+
+pub use init::{
+    BaseClass, ClassBuilder, Export, Extends, InitHandle, MethodArgument, NativeClassInit, NativeClassMethods,
+    Property, PropertyHint, PropertyUsage, Signal, SignalArgument,
+};
+pub use gdnative_derive::{Export, methods};
+pub use {
+    CallError, Color, Dictionary, FromVariant, GodotObject, GodotString, GodotType, Instance, NativeClass,
+    NativeInstanceHeader, NodePath, Rid, ToVariant, Variant, VariantArray, VariantType, Vector2,
+    Vector2Godot, Vector3, Vector3Godot,
+};
+pub use {Node, Node2D, Object, Reference, Resource, Spatial};
+pub use {
+    call, godot_dbg, godot_error, godot_gdnative_init, godot_gdnative_terminate, godot_nativescript_init,
+    godot_print, godot_warn, godot_wrap_constructor, godot_wrap_destructor, godot_wrap_method,
+};
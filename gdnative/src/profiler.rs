@@ -0,0 +1,105 @@
+//! Integration with the profiler of the editor.
+//!
+//! Godot 3.1 and later (NativeScript 1.1) show the time spent in rust code in the
+//! "Profiler" tab of the debugger, next to the GDScript functions. Time is reported
+//! under a signature of the form `file::line::name`, which the editor displays like
+//! the signature of a GDScript function. The name must not contain `::`.
+//!
+//! The `profiled!` macro times a block under a signature built from its location:
+//!
+//! ```ignore
+//! let path = profiled!("find_path", {
+//!     navigation.get_simple_path(from, to, true)
+//! });
+//! ```
+//!
+//! With the `method_profiling` feature, every method wrapped with `godot_wrap_method!`,
+//! which includes the methods exported with `#[export]`, is timed under the name
+//! `Class.method`.
+//!
+//! Nothing is reported when the engine doesn't provide NativeScript 1.1. The engine
+//! only keeps the data while the profiler is running.
+
+use std::ffi::CString;
+use std::time::{Duration, Instant};
+use nativescript_1_1::nativescript_1_1;
+
+/// Returns `true` if the engine accepts profiling data.
+pub fn is_supported() -> bool {
+    nativescript_1_1().and_then(|api| api.profiling_add_data).is_some()
+}
+
+/// Reports time spent under `signature`, which should have the form
+/// `file::line::name`.
+pub fn add_data(signature: &str, time: Duration) {
+    let add_data = match nativescript_1_1().and_then(|api| api.profiling_add_data) {
+        Some(add_data) => add_data,
+        None => return,
+    };
+
+    let signature = match CString::new(signature) {
+        Ok(signature) => signature,
+        Err(_) => {
+            godot_error!("Invalid profiling signature {:?}", signature);
+            return;
+        }
+    };
+
+    // The engine expects microseconds.
+    let micros = time.as_secs() * 1_000_000 + (time.subsec_nanos() / 1_000) as u64;
+    unsafe {
+        add_data(signature.as_ptr(), micros);
+    }
+}
+
+/// Runs `f` and reports the time it took under `signature`.
+pub fn profile<F, R>(signature: &str, f: F) -> R
+where F: FnOnce() -> R {
+    let _timer = Timer::start(signature);
+    f()
+}
+
+/// Reports the time elapsed between its creation and its drop, see `profiled!`.
+pub struct Timer<'a> {
+    signature: &'a str,
+    start: Option<Instant>,
+}
+
+impl<'a> Timer<'a> {
+    pub fn start(signature: &'a str) -> Self {
+        Timer {
+            signature,
+            start: if is_supported() { Some(Instant::now()) } else { None },
+        }
+    }
+}
+
+impl<'a> Drop for Timer<'a> {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            add_data(self.signature, start.elapsed());
+        }
+    }
+}
+
+/// Times a call of a method wrapped with `godot_wrap_method!` if the
+/// `method_profiling` feature is enabled.
+#[doc(hidden)]
+#[cfg(feature = "method_profiling")]
+pub fn method_timer(signature: &'static str) -> Option<Timer<'static>> {
+    Some(Timer::start(signature))
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "method_profiling"))]
+#[inline(always)]
+pub fn method_timer(_signature: &'static str) -> Option<Timer<'static>> {
+    None
+}
+
+#[test]
+fn profiled_block_value() {
+    // Without the engine nothing is reported, but the block still runs.
+    assert_eq!(profiled!("answer", { 40 + 2 }), 42);
+    assert_eq!(profile("profiler.rs::0::answer", || 42), 42);
+}
@@ -0,0 +1,146 @@
+//! Closures called when a property of an engine object changes.
+//!
+//! The engine doesn't notify anyone but the object itself of the changes of its
+//! properties. `observe_property` compares the value of a property once per frame
+//! instead, and calls a closure with the previous and the new value when it differs:
+//!
+//! ```ignore
+//! let observer = observe_property(&player, "position", move |_old, new| {
+//!     minimap.move_marker(new.to_vector2());
+//! })?;
+//!
+//! // Later, when the minimap is closed.
+//! observer.cancel();
+//! ```
+//!
+//! The values are read by a task of the `tasks` module, so the closure runs in the
+//! frame after the change, and a property changing several times between two frames
+//! is only reported once. Values are compared with the equality operator of the
+//! engine.
+//!
+//! An observer stops when it is cancelled, or when the observed object is freed.
+//! It doesn't keep the object alive.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tasks::{freed_flag, spawn};
+use GodotError;
+use GodotObject;
+use GodotString;
+use Object;
+use Variant;
+
+/// Observes a property of an object, see the `property_observer` module.
+///
+/// Dropping it doesn't stop the observation, see `cancel`.
+pub struct PropertyObserver {
+    cancelled: Rc<Cell<bool>>,
+    freed: Rc<Cell<bool>>,
+}
+
+impl PropertyObserver {
+    /// Stops the observation, the closure isn't called anymore.
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    /// `false` once the observer was cancelled or the object freed.
+    pub fn is_active(&self) -> bool {
+        !self.cancelled.get() && !self.freed.get()
+    }
+}
+
+/// Calls `callback` with the previous and the new value of `property` each frame
+/// the property of `object` changed, until the observer is cancelled or the object
+/// freed. Must be called from the main thread.
+///
+/// Fails if the library is not initialized.
+pub fn observe_property<O, F>(object: &O, property: &str, callback: F) -> Result<PropertyObserver, GodotError>
+where
+    O: GodotObject,
+    F: FnMut(&Variant, &Variant) + 'static,
+{
+    let freed = freed_flag(object)?;
+    let cancelled = Rc::new(Cell::new(false));
+
+    let object = unsafe { Object::from_sys(object.to_sys()) };
+    let property = GodotString::from_str(property);
+    let value = object.get(property.new_ref());
+    spawn(Observe {
+        object,
+        property,
+        value,
+        callback: Box::new(callback),
+        cancelled: cancelled.clone(),
+        freed: freed.clone(),
+    });
+
+    Ok(PropertyObserver { cancelled, freed })
+}
+
+// The task comparing the value once per frame.
+struct Observe {
+    object: Object,
+    property: GodotString,
+    value: Variant,
+    callback: Box<dyn FnMut(&Variant, &Variant)>,
+    cancelled: Rc<Cell<bool>>,
+    freed: Rc<Cell<bool>>,
+}
+
+impl Future for Observe {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = &mut *self;
+        if this.cancelled.get() || this.freed.get() {
+            return Poll::Ready(());
+        }
+
+        let value = this.object.get(this.property.new_ref());
+        if value != this.value {
+            let old = mem::replace(&mut this.value, value);
+            (this.callback)(&old, &this.value);
+        }
+
+        // Polled again by the next frame.
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+godot_test!(test_property_observer {
+    use std::cell::RefCell;
+    use tasks::{pending_tasks, run_tasks};
+    use Node;
+
+    let mut node = Node::new();
+    node.set_name(GodotString::from_str("First"));
+    let changes = Rc::new(RefCell::new(Vec::new()));
+
+    let recorded = changes.clone();
+    let observer = observe_property(&node, "name", move |old, new| {
+        recorded.borrow_mut().push((old.to_string(), new.to_string()));
+    }).unwrap();
+    let cancelled = Rc::new(Cell::new(0));
+    let counter = cancelled.clone();
+    observe_property(&node, "name", move |_, _| counter.set(counter.get() + 1)).unwrap().cancel();
+
+    run_tasks();
+    node.set_name(GodotString::from_str("Second"));
+    node.set_name(GodotString::from_str("Third"));
+    run_tasks();
+    assert_eq!(*changes.borrow(), vec![("First".to_string(), "Third".to_string())]);
+    assert_eq!(cancelled.get(), 0);
+    assert!(observer.is_active());
+
+    let tasks = pending_tasks();
+    unsafe { node.free(); }
+    assert!(!observer.is_active());
+    run_tasks();
+    assert_eq!(pending_tasks(), tasks - 1);
+});
@@ -0,0 +1,335 @@
+//! Keeping the state of the rust instances when the library is reloaded.
+//!
+//! The editor unloads the reloadable libraries when it loses the focus and loads
+//! them again when it regains it, to pick up new builds. Rust memory doesn't survive
+//! the unload, so the state of the instances that isn't stored in properties is lost.
+//!
+//! Classes can register hooks saving the state of their instances to a `Variant`
+//! before the library is unloaded, and restoring it when the reloaded library creates
+//! the instance of the same object again:
+//!
+//! ```ignore
+//! class.set_reload_hooks(
+//!     |this: &Counter| this.count.to_variant(),
+//!     |this: &mut Counter, state| this.count = i64::from_variant(&state).unwrap_or(0),
+//! );
+//! ```
+//!
+//! The states are only saved in the editor, they are kept in the metadata of the
+//! `Engine` singleton between the unload and the next load.
+//!
+//! Resources that must be released before the library is unloaded, like threads
+//! or handles given to the engine, can be released by a callback registered with
+//! `on_unload`.
+
+use std::collections::HashMap;
+use std::mem;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use diagnostics::catch_panic;
+use init::{ClassBuilder, ScriptConstructorFn, ScriptDestructorFn};
+use info;
+use libc;
+use sys;
+use Dictionary;
+use GodotObject;
+use GodotString;
+use NativeClass;
+use Object;
+use UserData;
+use Variant;
+use VariantArray;
+use _Engine;
+use {alloc_boxed, free_boxed};
+
+const RELOAD_STATE_META: &str = "__gdnative_reload_state";
+
+struct ClassHooks {
+    save: Box<dyn Fn(*const libc::c_void) -> Variant + Send>,
+    load: Box<dyn Fn(*const libc::c_void, Variant) + Send>,
+}
+
+struct TrackedInstance {
+    this: *mut sys::godot_object,
+    class_name: String,
+}
+
+struct PendingState {
+    class_name: String,
+    state: Variant,
+}
+
+// The objects and variants are only accessed from the thread loading and unloading
+// the library, or from the thread creating the instance.
+unsafe impl Send for TrackedInstance {}
+unsafe impl Send for PendingState {}
+
+static HAS_HOOKS: AtomicBool = AtomicBool::new(false);
+static HOOKS: Mutex<Option<HashMap<String, ClassHooks>>> = Mutex::new(None);
+static INSTANCES: Mutex<Option<HashMap<usize, TrackedInstance>>> = Mutex::new(None);
+static PENDING: Mutex<Option<HashMap<i64, PendingState>>> = Mutex::new(None);
+static UNLOAD_CALLBACKS: Mutex<Vec<Box<dyn FnOnce() + Send>>> = Mutex::new(Vec::new());
+
+impl<'a, C: NativeClass + 'static> ClassBuilder<'a, C> {
+    /// Registers functions saving the state of the instances before the library is
+    /// reloaded and restoring it afterwards, see the `reload` module.
+    pub fn set_reload_hooks<S, L>(&self, save: S, load: L)
+    where
+        S: Fn(&C) -> Variant + Send + 'static,
+        L: Fn(&mut C, Variant) + Send + 'static,
+    {
+        let save_name = self.name();
+        let load_name = self.name();
+
+        let hooks = ClassHooks {
+            save: Box::new(move |user_data| {
+                let data = unsafe { &*(user_data as *const C::UserData) };
+                match data.map(|this| save(this)) {
+                    Ok(state) => state,
+                    Err(err) => {
+                        godot_error!("{}: {}, its state is not saved", save_name, err);
+                        Variant::new()
+                    }
+                }
+            }),
+            load: Box::new(move |user_data, state| {
+                let data = unsafe { &*(user_data as *const C::UserData) };
+                if let Err(err) = data.map_mut(|this| load(this, state)) {
+                    godot_error!("{}: {}, its state is not restored", load_name, err);
+                }
+            }),
+        };
+
+        if let Ok(mut all_hooks) = HOOKS.lock() {
+            all_hooks.get_or_insert_with(HashMap::new).insert(self.name(), hooks);
+            HAS_HOOKS.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Registers a callback invoked when the library is unloaded, after the states of
+/// the instances are saved and before the bindings are torn down.
+///
+/// The callbacks run once, in the order they were registered.
+pub fn on_unload<F>(callback: F)
+where F: FnOnce() + Send + 'static {
+    if let Ok(mut callbacks) = UNLOAD_CALLBACKS.lock() {
+        callbacks.push(Box::new(callback));
+    }
+}
+
+struct CreateData {
+    class_name: String,
+    constructor: Option<ScriptConstructorFn>,
+}
+
+/// Wraps the constructor of a class to track the instances of the classes with
+/// reload hooks.
+///
+/// The engine calls the function without checking it, the classes without a
+/// constructor report the instantiation instead.
+pub(crate) fn create_func(class_name: &str, constructor: Option<ScriptConstructorFn>) -> sys::godot_instance_create_func {
+    unsafe extern "C" fn create(this: *mut sys::godot_object, method_data: *mut libc::c_void) -> *mut libc::c_void {
+        let data = &*(method_data as *const CreateData);
+        let constructor = match data.constructor {
+            Some(constructor) => constructor,
+            None => {
+                godot_error!(
+                    "{} is an abstract class and can't be instanced, instance a class extending it",
+                    data.class_name
+                );
+                return ptr::null_mut();
+            }
+        };
+
+        // The engine keeps the instance without user data, the callbacks report it.
+        let user_data = catch_panic(&data.class_name, || constructor(this, ptr::null_mut()))
+            .unwrap_or(ptr::null_mut());
+        if HAS_HOOKS.load(Ordering::Acquire) && !user_data.is_null() {
+            instance_created(this, &data.class_name, user_data);
+        }
+        user_data
+    }
+
+    extern "C" fn free_func(data: *mut libc::c_void) {
+        unsafe { free_boxed(data as *mut CreateData) }
+    }
+
+    let data = CreateData {
+        class_name: class_name.to_string(),
+        constructor,
+    };
+
+    sys::godot_instance_create_func {
+        create_func: Some(create),
+        method_data: alloc_boxed(data) as *mut _,
+        free_func: Some(free_func),
+    }
+}
+
+/// Wraps the destructor of a class to stop tracking the destroyed instances.
+pub(crate) fn destroy_func(destructor: Option<ScriptDestructorFn>) -> sys::godot_instance_destroy_func {
+    unsafe extern "C" fn destroy(this: *mut sys::godot_object, method_data: *mut libc::c_void, user_data: *mut libc::c_void) {
+        if HAS_HOOKS.load(Ordering::Acquire) {
+            if let Ok(mut instances) = INSTANCES.lock() {
+                if let Some(instances) = instances.as_mut() {
+                    instances.remove(&(user_data as usize));
+                }
+            }
+        }
+
+        // The instances of the abstract classes have no user data.
+        if user_data.is_null() || method_data.is_null() {
+            return;
+        }
+
+        let destructor: ScriptDestructorFn = mem::transmute(method_data);
+        catch_panic("the destructor of an instance", || destructor(this, ptr::null_mut(), user_data));
+    }
+
+    sys::godot_instance_destroy_func {
+        destroy_func: Some(destroy),
+        method_data: destructor.map_or(ptr::null_mut(), |destructor| destructor as *mut libc::c_void),
+        free_func: None,
+    }
+}
+
+unsafe fn instance_created(this: *mut sys::godot_object, class_name: &str, user_data: *mut libc::c_void) {
+    let has_hooks = HOOKS.lock().ok()
+        .map_or(false, |hooks| hooks.as_ref().map_or(false, |hooks| hooks.contains_key(class_name)));
+    if !has_hooks {
+        return;
+    }
+
+    if let Ok(mut instances) = INSTANCES.lock() {
+        instances.get_or_insert_with(HashMap::new).insert(user_data as usize, TrackedInstance {
+            this,
+            class_name: class_name.to_string(),
+        });
+    }
+
+    let instance_id = Object::from_sys(this).get_instance_id();
+    let pending = PENDING.lock().ok()
+        .and_then(|mut pending| pending.as_mut().and_then(|pending| pending.remove(&instance_id)));
+
+    if let Some(pending) = pending {
+        if pending.class_name != class_name {
+            return;
+        }
+        if let Ok(hooks) = HOOKS.lock() {
+            if let Some(hooks) = hooks.as_ref().and_then(|hooks| hooks.get(class_name)) {
+                (hooks.load)(user_data, pending.state);
+            }
+        }
+    }
+}
+
+/// Takes the states saved when the library was last unloaded, called by
+/// `godot_gdnative_init!`.
+#[doc(hidden)]
+pub fn take_reload_state() {
+    let library_path = match info::library_path() {
+        Some(path) => Variant::from_str(path),
+        None => return,
+    };
+
+    let mut engine = _Engine::godot_singleton();
+    if !engine.has_meta(GodotString::from_str(RELOAD_STATE_META)) {
+        return;
+    }
+
+    let mut libraries = engine.get_meta(GodotString::from_str(RELOAD_STATE_META)).to_dictionary();
+    if !libraries.contains(&library_path) {
+        return;
+    }
+
+    let states = libraries.get(&library_path).to_dictionary();
+    libraries.erase(&library_path);
+    engine.set_meta(GodotString::from_str(RELOAD_STATE_META), Variant::from_dictionary(&libraries));
+
+    let mut pending = HashMap::new();
+    let mut ids = states.keys();
+    for idx in 0..ids.len() {
+        let id = ids.get_val(idx);
+        let mut entry = states.get(&id).to_array();
+        pending.insert(id.to_i64(), PendingState {
+            class_name: entry.get_val(0).to_string(),
+            state: entry.get_val(1),
+        });
+    }
+
+    if let Ok(mut current) = PENDING.lock() {
+        *current = Some(pending);
+    }
+}
+
+/// Saves the states of the instances if the library is unloaded by the editor, runs
+/// the unload callbacks and forgets the hooks, called by `godot_gdnative_terminate!`.
+#[doc(hidden)]
+pub fn unload(in_editor: bool) {
+    if in_editor {
+        save_states();
+    }
+
+    let callbacks = match UNLOAD_CALLBACKS.lock() {
+        Ok(mut callbacks) => mem::replace(&mut *callbacks, Vec::new()),
+        Err(_) => Vec::new(),
+    };
+    for callback in callbacks {
+        callback();
+    }
+
+    HAS_HOOKS.store(false, Ordering::Release);
+    if let Ok(mut hooks) = HOOKS.lock() {
+        *hooks = None;
+    }
+    if let Ok(mut instances) = INSTANCES.lock() {
+        *instances = None;
+    }
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = None;
+    }
+}
+
+fn save_states() {
+    let library_path = match info::library_path() {
+        Some(path) => Variant::from_str(path),
+        None => return,
+    };
+
+    let instances = match INSTANCES.lock() {
+        Ok(mut instances) => instances.take().unwrap_or_default(),
+        Err(_) => return,
+    };
+    if instances.is_empty() {
+        return;
+    }
+
+    let mut states = Dictionary::new();
+    if let Ok(hooks) = HOOKS.lock() {
+        let hooks = match hooks.as_ref() {
+            Some(hooks) => hooks,
+            None => return,
+        };
+
+        for (&user_data, instance) in instances.iter() {
+            if let Some(hooks) = hooks.get(&instance.class_name) {
+                let instance_id = unsafe { Object::from_sys(instance.this).get_instance_id() };
+                let mut entry = VariantArray::new();
+                entry.push(&Variant::from_str(&instance.class_name));
+                entry.push(&(hooks.save)(user_data as *const libc::c_void));
+                states.set(&Variant::from_i64(instance_id), &Variant::from_array(&entry));
+            }
+        }
+    }
+
+    let mut engine = _Engine::godot_singleton();
+    let mut libraries = if engine.has_meta(GodotString::from_str(RELOAD_STATE_META)) {
+        engine.get_meta(GodotString::from_str(RELOAD_STATE_META)).to_dictionary()
+    } else {
+        Dictionary::new()
+    };
+    libraries.set(&library_path, &Variant::from_dictionary(&states));
+    engine.set_meta(GodotString::from_str(RELOAD_STATE_META), Variant::from_dictionary(&libraries));
+}
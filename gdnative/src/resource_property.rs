@@ -0,0 +1,172 @@
+//! Exported resources, filtered by class in the inspector.
+//!
+//! `ResourceProperty<T>` holds an optional resource of class `T`, like a `Texture`
+//! or an `AudioStream`. Registered with `ResourceProperty::<T>::hint()`, the resource
+//! picker of the inspector only offers resources of that class:
+//!
+//! ```ignore
+//! builder.add_property(Property {
+//!     name: "icon",
+//!     default: ResourceProperty::<Texture>::preload("res://icon.png"),
+//!     hint: ResourceProperty::<Texture>::hint(),
+//!     getter: |this: &mut Item| this.icon.clone(),
+//!     setter: |this: &mut Item, icon| this.icon = icon,
+//!     usage: PropertyUsage::DEFAULT,
+//!     documentation: None,
+//! });
+//! ```
+//!
+//! The property is empty when no resource is assigned, which the inspector shows
+//! as `[empty]` and scripts as `null`. Assigning a resource of another class from a
+//! script is refused like any value of the wrong type.
+
+use std::fmt;
+use init::PropertyHint;
+use FromVariant;
+use GodotObject;
+use GodotString;
+use ToVariant;
+use Variant;
+use _ResourceLoader;
+
+/// An optional resource of class `T` exported as a property, see the
+/// `resource_property` module.
+pub struct ResourceProperty<T: GodotObject> {
+    resource: Option<T>,
+}
+
+impl<T: GodotObject> ResourceProperty<T> {
+    /// Creates an empty property.
+    pub fn none() -> Self {
+        ResourceProperty { resource: None }
+    }
+
+    pub fn new(resource: T) -> Self {
+        ResourceProperty { resource: Some(resource) }
+    }
+
+    /// Loads the resource at `path`, for the default value of the property.
+    ///
+    /// Prints an error and returns an empty property if the resource can't be loaded
+    /// as a `T`.
+    pub fn preload(path: &str) -> Self {
+        let resource = _ResourceLoader::godot_singleton()
+            .load(GodotString::from_str(path), GodotString::from_str(T::class_name()), false)
+            .and_then(|resource| resource.cast::<T>());
+
+        if resource.is_none() {
+            godot_error!("could not load {:?} as {}", path, T::class_name());
+        }
+
+        ResourceProperty { resource }
+    }
+
+    /// The hint restricting the resources offered by the inspector to `T`.
+    pub fn hint() -> PropertyHint<'static> {
+        PropertyHint::ResourceType { class: T::class_name() }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.resource.as_ref()
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.resource.as_mut()
+    }
+
+    pub fn set(&mut self, resource: Option<T>) {
+        self.resource = resource;
+    }
+
+    pub fn take(&mut self) -> Option<T> {
+        self.resource.take()
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.resource.is_none()
+    }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.resource
+    }
+}
+
+// Creating the handle from the pointer adds a reference to reference counted objects.
+fn new_handle<T: GodotObject>(resource: &T) -> T {
+    unsafe { T::from_sys(resource.to_sys()) }
+}
+
+impl<T: GodotObject> Default for ResourceProperty<T> {
+    fn default() -> Self {
+        ResourceProperty::none()
+    }
+}
+
+impl<T: GodotObject> Clone for ResourceProperty<T> {
+    fn clone(&self) -> Self {
+        ResourceProperty { resource: self.resource.as_ref().map(new_handle) }
+    }
+}
+
+impl<T: GodotObject> From<Option<T>> for ResourceProperty<T> {
+    fn from(resource: Option<T>) -> Self {
+        ResourceProperty { resource }
+    }
+}
+
+/// An empty property is converted to nil.
+impl<T: GodotObject> ToVariant for ResourceProperty<T> {
+    fn to_variant(&self) -> Variant {
+        match self.resource {
+            Some(ref resource) => Variant::from_object(new_handle(resource)),
+            None => Variant::new(),
+        }
+    }
+}
+
+/// Nil is converted to an empty property, objects of another class fail.
+impl<T: GodotObject> FromVariant for ResourceProperty<T> {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        if variant.is_nil() {
+            Some(ResourceProperty::none())
+        } else {
+            variant.try_to_object::<T>().map(ResourceProperty::new)
+        }
+    }
+}
+
+impl<T: GodotObject> fmt::Debug for ResourceProperty<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.resource {
+            Some(ref resource) => write!(f, "ResourceProperty<{}>({:p})", T::class_name(), unsafe { resource.to_sys() }),
+            None => write!(f, "ResourceProperty<{}>(None)", T::class_name()),
+        }
+    }
+}
+
+godot_test!(test_resource_property {
+    use ImageTexture;
+    use Texture;
+    use init::PropertyHint;
+
+    match ResourceProperty::<Texture>::hint() {
+        PropertyHint::ResourceType { class } => assert_eq!(class, "Texture"),
+        _ => panic!("expected a resource type hint"),
+    }
+    assert_eq!(ResourceProperty::<Texture>::hint().to_hint_string().to_string(), "Texture");
+
+    let empty = ResourceProperty::<Texture>::none();
+    assert!(empty.to_variant().is_nil());
+    assert!(ResourceProperty::<Texture>::from_variant(&Variant::new()).unwrap().is_none());
+
+    let texture = ImageTexture::new();
+    let variant = Variant::from_object(texture.new_ref());
+    let property = ResourceProperty::<Texture>::from_variant(&variant).unwrap();
+    assert_eq!(property.get().unwrap().get_width(), 0);
+    assert!(property.clone().to_variant().try_to_object::<ImageTexture>().is_some());
+
+    assert!(ResourceProperty::<Texture>::from_variant(&Variant::from_str("icon.png")).is_none());
+
+    let missing = ResourceProperty::<Texture>::preload("res://gdnative_missing_texture.png");
+    assert!(missing.is_none());
+});
@@ -38,6 +38,8 @@ impl_basic_traits!{
     }
 }
 
+impl_sys_access!(for Rid as godot_rid without forget);
+
 impl PartialOrd for Rid {
     fn partial_cmp(&self, other: &Rid) -> Option<Ordering> {
         unsafe {
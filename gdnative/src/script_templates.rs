@@ -0,0 +1,322 @@
+//! Scaffolding of rust classes for the scripts created in the editor.
+//!
+//! The "Attach Script" dialog of the editor creates an empty `.gdns` file for
+//! NativeScript. The editor plugin registered with
+//! `InitHandle::add_script_template_plugin` watches the project for new `.gdns`
+//! files and, for each of them:
+//!
+//! - sets the class name of the script from its file name
+//!   (`player_controller.gdns` becomes `PlayerController`),
+//! - writes a rust file with the matching struct and impl to the source directory of
+//!   the crate, unless it already exists,
+//! - prints the remaining steps (declaring the module, registering the class and
+//!   setting the library of the script).
+//!
+//! ```ignore
+//! fn init(handle: init::InitHandle) {
+//!     handle.add_script_template_plugin("RustScriptTemplates", ScriptTemplateOptions {
+//!         src_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/src"),
+//!         base_class: "Node",
+//!     });
+//! }
+//! ```
+//!
+//! The plugin is enabled like any other editor plugin, with a `plugin.cfg` file in
+//! the `addons` directory of the project pointing to a `.gdns` script of the plugin
+//! class.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use init::{ClassDescriptor, InitHandle};
+use info;
+use EditorFileSystemDirectory;
+use EditorPlugin;
+use GodotString;
+use NativeClass;
+use LocalCellData;
+use NativeInstanceHeader;
+use NativeScript;
+use VariantArray;
+use _ResourceLoader;
+use _ResourceSaver;
+
+/// The options of the editor plugin registered with
+/// `InitHandle::add_script_template_plugin`.
+#[derive(Copy, Clone, Debug)]
+pub struct ScriptTemplateOptions {
+    /// The absolute path of the directory the rust files are written to, usually
+    /// `concat!(env!("CARGO_MANIFEST_DIR"), "/src")`.
+    pub src_dir: &'static str,
+    /// The class the generated structs inherit.
+    pub base_class: &'static str,
+}
+
+static OPTIONS: Mutex<Option<ScriptTemplateOptions>> = Mutex::new(None);
+
+/// The rust code of a new class.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptTemplate {
+    pub class_name: String,
+    pub base_class: String,
+}
+
+impl ScriptTemplate {
+    pub fn new<N: Into<String>, B: Into<String>>(class_name: N, base_class: B) -> Self {
+        ScriptTemplate {
+            class_name: class_name.into(),
+            base_class: base_class.into(),
+        }
+    }
+
+    /// Creates the template of the class of a script, named after the file name of
+    /// the script.
+    pub fn from_script_path<B: Into<String>>(path: &str, base_class: B) -> Self {
+        let stem = Path::new(path)
+            .file_stem()
+            .map_or("", |stem| stem.to_str().unwrap_or(""));
+
+        ScriptTemplate::new(to_camel_case(stem), base_class)
+    }
+
+    /// The name of the rust module of the class.
+    pub fn module_name(&self) -> String {
+        to_snake_case(&self.class_name)
+    }
+
+    /// The source of the rust module of the class, using `#[derive(NativeClass)]`.
+    pub fn rust_source(&self) -> String {
+        format!(
+r#"use gdnative::*;
+
+#[derive(NativeClass)]
+#[inherit({base})]
+pub struct {name} {{
+    header: NativeInstanceHeader,
+}}
+
+#[methods]
+impl {name} {{
+    fn _init(header: NativeInstanceHeader) -> Self {{
+        {name} {{ header }}
+    }}
+
+    #[export]
+    fn _ready(&mut self) {{
+    }}
+}}
+"#,
+            name = self.class_name,
+            base = self.base_class,
+        )
+    }
+
+    /// The steps left to use the class once its module is written.
+    pub fn instructions(&self, script_path: &str) -> String {
+        format!(
+            "Created the rust class {name} for {script}. To finish setting it up:\n\
+             - declare the module with `mod {module};` in the crate,\n\
+             - register the class with `handle.add_native_class::<{module}::{name}>();`,\n\
+             - rebuild the library and set it as the library of {script}.",
+            name = self.class_name,
+            module = self.module_name(),
+            script = script_path,
+        )
+    }
+}
+
+fn to_camel_case(name: &str) -> String {
+    name.split(|c: char| c == '_' || c == '-' || c == ' ')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap();
+            first.to_uppercase().chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if prev_lower {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+            prev_lower = false;
+        } else {
+            snake.push(c);
+            prev_lower = c.is_lowercase();
+        }
+    }
+    snake
+}
+
+impl<'a> InitHandle<'a> {
+    /// Registers an editor plugin scaffolding rust classes for the NativeScripts
+    /// created in the editor, see the `script_templates` module.
+    pub fn add_script_template_plugin(&self, class_name: &str, options: ScriptTemplateOptions) {
+        if let Ok(mut current) = OPTIONS.lock() {
+            *current = Some(options);
+        }
+
+        let class = self.add_tool_class::<ScriptTemplatePlugin>(ClassDescriptor {
+            name: class_name,
+            base_class: "EditorPlugin",
+            constructor: Some(godot_wrap_constructor!(ScriptTemplatePlugin, ScriptTemplatePlugin::new)),
+            destructor: Some(godot_wrap_destructor!(ScriptTemplatePlugin)),
+        });
+
+        class.add_method("_enter_tree", godot_wrap_method!(
+            ScriptTemplatePlugin,
+            fn _enter_tree(&mut self, #[owner] owner: EditorPlugin) -> ()
+        ));
+        class.add_method("_on_filesystem_changed", godot_wrap_method!(
+            ScriptTemplatePlugin,
+            fn _on_filesystem_changed(&mut self, #[owner] owner: EditorPlugin) -> ()
+        ));
+    }
+}
+
+#[doc(hidden)]
+pub struct ScriptTemplatePlugin {
+    header: NativeInstanceHeader,
+    known_scripts: HashSet<String>,
+}
+
+impl NativeClass for ScriptTemplatePlugin {
+    type UserData = LocalCellData<ScriptTemplatePlugin>;
+
+    fn class_name() -> &'static str {
+        "ScriptTemplatePlugin"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+impl ScriptTemplatePlugin {
+    fn new(header: NativeInstanceHeader) -> Self {
+        ScriptTemplatePlugin {
+            header,
+            known_scripts: HashSet::new(),
+        }
+    }
+
+    fn _enter_tree(&mut self, mut owner: EditorPlugin) {
+        // The scripts already in the project are not new.
+        self.known_scripts = native_scripts(&mut owner);
+
+        let target = owner.as_node().as_object();
+        if let Some(mut filesystem) = owner.get_editor_interface()
+            .and_then(|mut editor| editor.get_resource_filesystem()) {
+            let connected = filesystem.connect(
+                GodotString::from_str("filesystem_changed"),
+                Some(target),
+                GodotString::from_str("_on_filesystem_changed"),
+                VariantArray::new(),
+                0
+            );
+            if let Err(err) = connected {
+                godot_error!("Failed to watch the project files: {:?}", err);
+            }
+        }
+    }
+
+    fn _on_filesystem_changed(&mut self, mut owner: EditorPlugin) {
+        let options = match OPTIONS.lock().ok().and_then(|options| *options) {
+            Some(options) => options,
+            None => return,
+        };
+
+        for path in native_scripts(&mut owner) {
+            if self.known_scripts.insert(path.clone()) {
+                scaffold(&path, &options);
+            }
+        }
+    }
+}
+
+// Returns the paths of the `.gdns` files of the project.
+fn native_scripts(owner: &mut EditorPlugin) -> HashSet<String> {
+    fn collect(dir: &mut EditorFileSystemDirectory, paths: &mut HashSet<String>) {
+        for idx in 0..dir.get_file_count() {
+            if dir.get_file_type(idx).to_string() == "NativeScript" {
+                paths.insert(dir.get_file_path(idx).to_string());
+            }
+        }
+
+        for idx in 0..dir.get_subdir_count() {
+            if let Some(mut subdir) = dir.get_subdir(idx) {
+                collect(&mut subdir, paths);
+            }
+        }
+    }
+
+    let mut paths = HashSet::new();
+    let root = owner.get_editor_interface()
+        .and_then(|mut editor| editor.get_resource_filesystem())
+        .and_then(|mut filesystem| filesystem.get_filesystem());
+
+    if let Some(mut root) = root {
+        collect(&mut root, &mut paths);
+    }
+
+    paths
+}
+
+fn scaffold(path: &str, options: &ScriptTemplateOptions) {
+    let mut script = match _ResourceLoader::godot_singleton()
+        .load(GodotString::from_str(path), GodotString::from_str("NativeScript"), false)
+        .and_then(|resource| resource.cast::<NativeScript>()) {
+        Some(script) => script,
+        None => return,
+    };
+
+    // Only the scripts just created by the editor have no class name.
+    if !script.get_class_name().is_empty() {
+        return;
+    }
+
+    let template = ScriptTemplate::from_script_path(path, options.base_class);
+    if template.class_name.is_empty() || info::is_class_registered(&template.class_name) {
+        return;
+    }
+
+    script.set_class_name(GodotString::from_str(&template.class_name));
+    let saved = _ResourceSaver::godot_singleton().save(
+        GodotString::from_str(path),
+        script.cast(),
+        0
+    );
+    if let Err(err) = saved {
+        godot_error!("Failed to save {}: {:?}", path, err);
+        return;
+    }
+
+    let rust_path = Path::new(options.src_dir).join(format!("{}.rs", template.module_name()));
+    if rust_path.exists() {
+        godot_print!("{} already exists, it was not overwritten.", rust_path.display());
+    } else if let Err(err) = fs::write(&rust_path, template.rust_source()) {
+        godot_error!("Failed to write {}: {}", rust_path.display(), err);
+        return;
+    }
+
+    godot_print!("{}", template.instructions(path));
+}
+
+#[test]
+fn script_template_names() {
+    let template = ScriptTemplate::from_script_path("res://player/player_controller.gdns", "KinematicBody");
+    assert_eq!(template.class_name, "PlayerController");
+    assert_eq!(template.module_name(), "player_controller");
+    assert!(template.rust_source().contains("#[inherit(KinematicBody)]\npub struct PlayerController {"));
+
+    assert_eq!(to_snake_case("HUD2D"), "hud2d");
+    assert_eq!(to_snake_case("Hud2dOverlay"), "hud2d_overlay");
+    assert_eq!(to_camel_case("enemy-spawner"), "EnemySpawner");
+}
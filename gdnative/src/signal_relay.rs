@@ -0,0 +1,219 @@
+//! Signals connected to rust closures.
+//!
+//! `Object::connect` calls a method of the target object, so connecting a signal
+//! to rust code usually means exporting a method for it. `ConnectSignal::connect_fn`
+//! connects a closure instead, and the generated classes have a `connect_<signal>`
+//! method for each of their signals:
+//!
+//! ```ignore
+//! button.connect_pressed(|_args| godot_print!("pressed"))?;
+//!
+//! timer.connect_fn("timeout", ConnectFlags::ONE_SHOT, move |_args| {
+//!     godot_print!("done");
+//! })?;
+//! ```
+//!
+//! The closure receives the arguments of the signal. It is stored in a small relay
+//! object bound to the connection, which is freed with the connection: when the
+//! source object is freed, or after the first emission of a `ONE_SHOT` connection.
+//! A closure holding a reference to the source object keeps both alive.
+
+use std::slice;
+use std::sync::Mutex;
+use diagnostics::catch_panic;
+use init::{register_after_init, ClassDescriptor, InitHandle, ScriptMethod, ScriptMethodAttributes};
+use info;
+use instance_borrow::with_instance_mut;
+use libc;
+use sys;
+use GodotError;
+use GodotObject;
+use GodotResult;
+use GodotString;
+use Instance;
+use LocalCellData;
+use NativeClass;
+use NativeInstanceHeader;
+use NativeScript;
+use Object;
+use Reference;
+use Variant;
+use VariantArray;
+
+bitflags! {
+    /// The flags of a signal connection.
+    pub struct ConnectFlags: u32 {
+        /// The closure is called at the end of the frame instead of during the
+        /// emission.
+        const DEFERRED = 1;
+        /// The connection is saved with the scene. Closures can't be saved, this
+        /// only matters for the editor.
+        const PERSIST = 2;
+        /// The connection is removed after the first emission.
+        const ONE_SHOT = 4;
+    }
+}
+
+type Callback = dyn FnMut(&[&Variant]);
+
+/// Connects signals of any object to rust closures, see the `signal_relay` module.
+pub trait ConnectSignal: GodotObject {
+    /// Calls `callback` with the arguments of the signal each time it is emitted.
+    ///
+    /// Fails if the object has no such signal, or if the library is not initialized.
+    /// The first closure of the process must be connected from the main thread,
+    /// which registers the relay class.
+    fn connect_fn<F>(&self, signal: &str, flags: ConnectFlags, callback: F) -> GodotResult
+    where F: FnMut(&[&Variant]) + 'static {
+        connect_fn(self, signal, flags, callback)
+    }
+}
+
+impl<T: GodotObject> ConnectSignal for T {}
+
+pub(crate) fn connect_fn<O, F>(source: &O, signal: &str, flags: ConnectFlags, callback: F) -> GodotResult
+where
+    O: GodotObject + ?Sized,
+    F: FnMut(&[&Variant]) + 'static,
+{
+    let script = relay_script().ok_or(GodotError::Unconfigured)?;
+
+    let relay = Reference::new();
+    relay.as_object().set_script(script.cast());
+    Instance::<SignalRelay>::try_from_base(&relay)
+        .ok_or(GodotError::Unconfigured)?
+        .map_mut(|instance| instance.callback = Some(Box::new(callback)))
+        .map_err(|_| GodotError::Failed)?;
+
+    // The connection holds the only reference to the relay.
+    let mut binds = VariantArray::new();
+    binds.push(&Variant::from_object(relay.as_object()));
+
+    let mut source = unsafe { Object::from_sys(source.to_sys()) };
+    source.connect(
+        GodotString::from_str(signal),
+        Some(relay.as_object()),
+        GodotString::from_str("_relay"),
+        binds,
+        flags.bits() as i64,
+    )
+}
+
+// Scripts are resources, their reference count is atomic.
+struct RelayScript(NativeScript);
+unsafe impl Send for RelayScript {}
+
+// Shared by the threads, the class is only registered once, from the main thread.
+static RELAY_SCRIPT: Mutex<Option<RelayScript>> = Mutex::new(None);
+
+// Registers the relay class the first time a closure is connected, and returns a
+// script of that class.
+fn relay_script() -> Option<NativeScript> {
+    let mut script = RELAY_SCRIPT.lock().ok()?;
+    if let Some(ref script) = *script {
+        return Some(script.0.new_ref());
+    }
+
+    let library = info::library_resource()?;
+    register_after_init(register).ok()?;
+
+    let mut new_script = NativeScript::new();
+    new_script.set_class_name(GodotString::from_str(SignalRelay::class_name()));
+    new_script.set_library(Some(library));
+
+    *script = Some(RelayScript(new_script.new_ref()));
+    Some(new_script)
+}
+
+fn register(handle: &InitHandle) {
+    let class = handle.add_class::<SignalRelay>(ClassDescriptor {
+        name: SignalRelay::class_name(),
+        base_class: "Reference",
+        constructor: Some(godot_wrap_constructor!(SignalRelay, SignalRelay::new)),
+        destructor: Some(godot_wrap_destructor!(SignalRelay)),
+    });
+
+    class.add_method_advanced(ScriptMethod {
+        name: "_relay",
+        method_ptr: Some(invoke),
+        attributes: ScriptMethodAttributes::default(),
+        documentation: None,
+        method_data: ::std::ptr::null_mut(),
+        free_func: None,
+    });
+}
+
+struct SignalRelay {
+    header: NativeInstanceHeader,
+    callback: Option<Box<Callback>>,
+}
+
+impl SignalRelay {
+    fn new(header: NativeInstanceHeader) -> Self {
+        SignalRelay { header, callback: None }
+    }
+}
+
+impl NativeClass for SignalRelay {
+    type UserData = LocalCellData<SignalRelay>;
+
+    fn class_name() -> &'static str {
+        "SignalRelay"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+extern "C" fn invoke(
+    _this: *mut sys::godot_object,
+    _method: *mut libc::c_void,
+    user_data: *mut libc::c_void,
+    num_args: libc::c_int,
+    args: *mut *mut sys::godot_variant
+) -> sys::godot_variant {
+    unsafe {
+        // The last argument is the relay itself, bound to the connection.
+        let args: Vec<&Variant> = if args.is_null() || num_args < 1 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(args, num_args as usize - 1)
+                .iter()
+                .map(|&arg| Variant::cast_ref(arg))
+                .collect()
+        };
+
+        // A closure emitting its own signal is reported as a borrow conflict.
+        with_instance_mut::<SignalRelay, _, _>(user_data, "SignalRelay::_relay", |relay| {
+            if let Some(ref mut callback) = relay.callback {
+                catch_panic("SignalRelay::_relay", || callback(&args));
+            }
+        });
+
+        Variant::new().forget()
+    }
+}
+
+godot_test!(test_signal_relay {
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    let mut object = Reference::new();
+    let calls = Rc::new(Cell::new(0));
+    let arguments = Rc::new(Cell::new(0));
+
+    let counter = calls.clone();
+    let received = arguments.clone();
+    let connected = object.connect_fn("script_changed", ConnectFlags::ONE_SHOT, move |args| {
+        counter.set(counter.get() + 1);
+        received.set(args.len());
+    });
+    assert!(connected.is_ok());
+    assert!(object.connect_fn("not_a_signal", ConnectFlags::empty(), |_| {}).is_err());
+
+    object.emit_signal(GodotString::from_str("script_changed"), &[]);
+    object.emit_signal(GodotString::from_str("script_changed"), &[]);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(arguments.get(), 0);
+});
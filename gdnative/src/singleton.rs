@@ -0,0 +1,153 @@
+//! Global game state written in rust, reachable from rust and GDScript.
+//!
+//! A manager of the state shared by the scenes, like the save data or the settings,
+//! is a node at the root of the scene tree. The usual way to put it there is a
+//! project autoload: the `.gdns` script of the class is added as a singleton in the
+//! project settings, and GDScript reaches it by its name. Rust code gets its rust
+//! instance with `singleton`:
+//!
+//! ```ignore
+//! let settings = singleton::<Settings>().expect("the Settings autoload is missing");
+//! settings.map(|settings| settings.volume).unwrap();
+//! ```
+//!
+//! `singleton` finds the node at `/root/<class name>`, the name of the autoload
+//! unless `add_singleton` registered the class under another one, and caches it
+//! until the node leaves the tree. `autoload` finds the other autoloads, by name.
+//!
+//! A class registered with `InitHandle::add_singleton` doesn't need the autoload:
+//! the first call to `singleton` which doesn't find it creates the instance and adds
+//! it to the root at the end of the frame. GDScript reaches that node with
+//! `get_node("/root/<name>")`, the bare name is only declared for the autoloads of
+//! the project.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use init::{NativeClassInit, NativeClassMethods, InitHandle};
+use info;
+use signal_relay::{connect_fn, ConnectFlags};
+use sys;
+use GodotObject;
+use GodotString;
+use Instance;
+use NativeClass;
+use NativeScript;
+use Node;
+use NodePath;
+use SceneTree;
+use Variant;
+use _Engine;
+
+// The singleton names of the classes registered with `add_singleton`, by class name.
+static REGISTERED: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+thread_local! {
+    // The singletons found or created by `singleton`, by name.
+    static CACHE: RefCell<HashMap<String, *mut sys::godot_object>> = RefCell::new(HashMap::new());
+}
+
+impl<'a> InitHandle<'a> {
+    /// Registers the class `C` as a singleton named `name`, created by the first
+    /// call to `singleton` if the project has no autoload of this name, see the
+    /// `singleton` module. The class must extend `Node`.
+    pub fn add_singleton<C>(&self, name: &str)
+    where C: NativeClassInit + NativeClassMethods {
+        self.add_native_class::<C>();
+        if let Ok(mut registered) = REGISTERED.lock() {
+            registered.get_or_insert_with(HashMap::new).insert(C::class_name().to_string(), name.to_string());
+        }
+    }
+}
+
+/// The instance of the singleton of class `C`, see the `singleton` module. Must be
+/// called from the main thread.
+///
+/// Returns `None` if there is no such node at the root of the tree and the class
+/// wasn't registered with `add_singleton`, or if the node has another script.
+pub fn singleton<C: NativeClass>() -> Option<Instance<C>> {
+    let name = singleton_name::<C>();
+    if let Some(this) = CACHE.with(|cache| cache.borrow().get(&name).cloned()) {
+        return unsafe { Instance::try_from_sys(this) };
+    }
+
+    let node = match autoload::<Node>(&name) {
+        Some(node) => node,
+        None if is_registered::<C>() => create_singleton::<C>(&name)?,
+        None => return None,
+    };
+    let instance = Instance::try_from_base(&node)?;
+    cache(&name, &node);
+    Some(instance)
+}
+
+/// The autoload `name` of the project, or any node at `/root/<name>`, cast to `T`.
+/// Must be called from the main thread.
+pub fn autoload<T: GodotObject>(name: &str) -> Option<T> {
+    let root = scene_tree()?.get_root()?;
+    let path = NodePath::from_str(name);
+    if !root.has_node(path.new_ref()) {
+        return None;
+    }
+    root.get_node(path).and_then(|node| node.cast::<T>())
+}
+
+fn singleton_name<C: NativeClass>() -> String {
+    REGISTERED.lock().ok()
+        .and_then(|registered| registered.as_ref().and_then(|names| names.get(C::class_name()).cloned()))
+        .unwrap_or_else(|| C::class_name().to_string())
+}
+
+fn is_registered<C: NativeClass>() -> bool {
+    REGISTERED.lock().ok()
+        .map_or(false, |registered| registered.as_ref().map_or(false, |names| names.contains_key(C::class_name())))
+}
+
+// Creates the instance, added to the root at the end of the frame: the tree can be
+// busy when `singleton` is called.
+fn create_singleton<C: NativeClass>(name: &str) -> Option<Node> {
+    let mut root = scene_tree()?.get_root()?;
+
+    let mut script = NativeScript::new();
+    script.set_class_name(GodotString::from_str(C::class_name()));
+    script.set_library(Some(info::library_resource()?));
+    let mut node = script._new(&[])?.cast::<Node>()?;
+    node.set_name(GodotString::from_str(name));
+    root.call_deferred(
+        GodotString::from_str("add_child"),
+        &[Variant::from_object(unsafe { Node::from_sys(node.to_sys()) })],
+    );
+    Some(node)
+}
+
+// The node is forgotten when it leaves the tree or is freed, which both drop the
+// one shot connection.
+fn cache(name: &str, node: &Node) {
+    struct Forget {
+        name: String,
+        this: *mut sys::godot_object,
+    }
+
+    impl Drop for Forget {
+        fn drop(&mut self) {
+            let _ = CACHE.try_with(|cache| {
+                let mut cache = cache.borrow_mut();
+                if cache.get(&self.name) == Some(&self.this) {
+                    cache.remove(&self.name);
+                }
+            });
+        }
+    }
+
+    let forget = Forget { name: name.to_string(), this: unsafe { node.to_sys() } };
+    let connected = connect_fn(node, "tree_exiting", ConnectFlags::ONE_SHOT, move |_args| {
+        let _ = &forget;
+    });
+    if connected.is_ok() {
+        CACHE.with(|cache| cache.borrow_mut().insert(name.to_string(), unsafe { node.to_sys() }));
+    }
+}
+
+fn scene_tree() -> Option<SceneTree> {
+    _Engine::godot_singleton().get_main_loop().and_then(|main_loop| main_loop.cast::<SceneTree>())
+}
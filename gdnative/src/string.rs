@@ -1,8 +1,11 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 
+use libc;
+use std::collections::HashMap;
 use std::ffi::CStr;
 use std::ops::Range;
 use std::str;
@@ -10,8 +13,26 @@ use std::slice;
 use std::cmp::Ordering;
 use std::mem::{transmute, forget};
 use std::fmt;
+use std::sync::Mutex;
+
+// ASCII strings up to this length are converted through a buffer on the stack, which
+// skips the UTF-8 decoding done by the engine.
+const SMALL_STRING_LEN: usize = 64;
+
+/// The maximum number of strings cached by `GodotString::from_static`.
+pub const STRING_CACHE_CAPACITY: usize = 1024;
+
+// Godot strings are immutable once shared (copy on write) and their reference count is
+// atomic.
+struct CachedString(GodotString);
+unsafe impl Send for CachedString {}
+
+// Keyed by the address and length of the static strings, hashing the addresses is
+// cheaper than hashing the contents.
+static STRING_CACHE: Mutex<Option<HashMap<(usize, usize), CachedString>>> = Mutex::new(None);
 
 /// Godot's reference-counted string type.
+#[repr(C)]
 pub struct GodotString(pub(crate) sys::godot_string);
 
 macro_rules! impl_methods {
@@ -46,9 +67,13 @@ impl GodotString {
     pub fn from_str<S>(s: S) -> Self
         where S: AsRef<str>
     {
+        let val = s.as_ref();
+        if val.len() <= SMALL_STRING_LEN && val.bytes().all(|b| b != 0 && b < 0x80) {
+            return GodotString::from_small_ascii(val);
+        }
+
         unsafe {
             let api = get_api();
-            let val = s.as_ref();
             let godot_s = (api.godot_string_chars_to_utf8_with_len)(
                 val.as_ptr() as *const _,
                 val.len() as _
@@ -58,6 +83,45 @@ impl GodotString {
         }
     }
 
+    /// Converts a string that lives for the whole program, usually a literal used as
+    /// a name or a key, reusing the result of the previous conversions.
+    ///
+    /// The converted strings are cached until the library is terminated, up to
+    /// `STRING_CACHE_CAPACITY` strings, after which this behaves like `from_str`.
+    /// Strings built at runtime should use `from_str` instead: the cache is keyed by
+    /// address and doesn't deduplicate equal strings.
+    pub fn from_static(s: &'static str) -> Self {
+        let key = (s.as_ptr() as usize, s.len());
+        let mut cache = match STRING_CACHE.lock() {
+            Ok(cache) => cache,
+            Err(_) => return GodotString::from_str(s),
+        };
+        let cache = cache.get_or_insert_with(HashMap::new);
+
+        if let Some(cached) = cache.get(&key) {
+            return cached.0.new_ref();
+        }
+
+        let string = GodotString::from_str(s);
+        if cache.len() < STRING_CACHE_CAPACITY {
+            cache.insert(key, CachedString(string.new_ref()));
+        }
+        string
+    }
+
+    fn from_small_ascii(val: &str) -> Self {
+        let mut wide: [libc::wchar_t; SMALL_STRING_LEN] = [0; SMALL_STRING_LEN];
+        for (dst, &byte) in wide.iter_mut().zip(val.as_bytes()) {
+            *dst = byte as libc::wchar_t;
+        }
+
+        unsafe {
+            let mut dest = sys::godot_string::default();
+            (get_api().godot_string_new_with_wide_string)(&mut dest, wide.as_ptr(), val.len() as _);
+            GodotString(dest)
+        }
+    }
+
     pub fn len(&self) -> usize {
         unsafe {
             (get_api().godot_string_length)(&self.0) as usize
@@ -167,6 +231,50 @@ impl GodotString {
         }
     }
 
+    /// The number with up to 14 decimals and without trailing zeros, like
+    /// `String.num` in GDScript.
+    pub fn num(value: f64) -> Self {
+        unsafe { GodotString((get_api().godot_string_num)(value)) }
+    }
+
+    /// The number rounded to `decimals` decimals, without trailing zeros.
+    pub fn num_with_decimals(value: f64, decimals: i32) -> Self {
+        unsafe { GodotString((get_api().godot_string_num_with_decimals)(value, decimals as _)) }
+    }
+
+    /// The number like `str()` in GDScript, with the shortest decimals giving the
+    /// same value.
+    pub fn num_real(value: f64) -> Self {
+        unsafe { GodotString((get_api().godot_string_num_real)(value)) }
+    }
+
+    /// The number in scientific notation, like `1e+20`.
+    pub fn num_scientific(value: f64) -> Self {
+        unsafe { GodotString((get_api().godot_string_num_scientific)(value)) }
+    }
+
+    /// The integer in `base`, from 2 to 36, with lowercase digits.
+    pub fn num_int64(value: i64, base: i32) -> Self {
+        unsafe { GodotString((get_api().godot_string_num_int64)(value, base as _)) }
+    }
+
+    /// A byte count in the largest unit it reaches, like `1.5 MiB`.
+    pub fn humanize_size(size: usize) -> Self {
+        unsafe { GodotString((get_api().godot_string_humanize_size)(size)) }
+    }
+
+    /// The number in the string with exactly `digits` decimals, truncated or padded
+    /// with zeros.
+    pub fn pad_decimals(&self, digits: i32) -> Self {
+        unsafe { GodotString((get_api().godot_string_pad_decimals)(&self.0, digits as _)) }
+    }
+
+    /// The number in the string with at least `digits` digits before the decimal
+    /// point, padded with leading zeros.
+    pub fn pad_zeros(&self, digits: i32) -> Self {
+        unsafe { GodotString((get_api().godot_string_pad_zeros)(&self.0, digits as _)) }
+    }
+
     /// Returns the internal ffi representation of the string and consumes
     /// the rust object without running the destructor.
     ///
@@ -203,8 +311,13 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for GodotString {
+impl_sys_access!(for GodotString as godot_string without forget);
+
+impl ToVariant for GodotString {
     fn to_variant(&self) -> Variant { Variant::from_godot_string(self) }
+}
+
+impl FromVariant for GodotString {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_godot_string() }
 }
 
@@ -253,6 +366,8 @@ impl_basic_traits!(
     }
 );
 
+impl_sys_access!(for Utf8String as godot_char_string);
+
 impl fmt::Debug for Utf8String {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         self.to_string().fmt(f)
@@ -311,6 +426,8 @@ impl_basic_traits! {
     }
 }
 
+impl_sys_access!(for StringName as godot_string_name);
+
 impl fmt::Debug for StringName {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         self.get_name().to_string().fmt(f)
@@ -331,6 +448,15 @@ impl PartialOrd for StringName {
     }
 }
 
+/// Drops the strings cached by `GodotString::from_static`, called by
+/// `godot_gdnative_terminate!`.
+#[doc(hidden)]
+pub fn clear_string_cache() {
+    if let Ok(mut cache) = STRING_CACHE.lock() {
+        *cache = None;
+    }
+}
+
 godot_test!(test_string {
     use VariantType;
     let foo = GodotString::from_str("foo");
@@ -350,4 +476,21 @@ godot_test!(test_string {
     } else {
         panic!("variant should be a GodotString");
     }
+
+    // The small ASCII strings don't go through the UTF-8 decoding.
+    assert_eq!(GodotString::from_str("position").to_string(), "position");
+    assert_eq!(GodotString::from_str("").len(), 0);
+    assert_eq!(GodotString::from_str("pösition").to_string(), "pösition");
+    let long = "a".repeat(SMALL_STRING_LEN + 1);
+    assert_eq!(GodotString::from_str(&long).to_string(), long);
+
+    let cached = GodotString::from_static("velocity");
+    assert!(cached == GodotString::from_static("velocity"));
+    assert!(cached == GodotString::from_str("velocity"));
+
+    assert_eq!(GodotString::num(0.5).to_string(), "0.5");
+    assert_eq!(GodotString::num_with_decimals(1.23456, 2).to_string(), "1.23");
+    assert_eq!(GodotString::num_int64(255, 16).to_string(), "ff");
+    assert_eq!(GodotString::num(1.5).pad_decimals(3).to_string(), "1.500");
+    assert_eq!(GodotString::from_str("7").pad_zeros(3).to_string(), "007");
 });
@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 use GodotString;
 
@@ -101,7 +102,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for StringArray {
+impl_sys_access!(for StringArray as godot_pool_string_array);
+
+impl ToVariant for StringArray {
     fn to_variant(&self) -> Variant { Variant::from_string_array(self) }
+}
+
+impl FromVariant for StringArray {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_string_array() }
 }
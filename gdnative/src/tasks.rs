@@ -0,0 +1,351 @@
+//! Async functions driven by the engine.
+//!
+//! Game logic spanning several frames, like a cutscene or the phases of a boss, reads
+//! best as a sequence of steps. GDScript writes them with `yield(object, "signal")`;
+//! rust code spawns an async block waiting for the signals with `SignalFuture`:
+//!
+//! ```ignore
+//! async fn intro(mut door: AnimationPlayer, mut tween: Tween) -> Result<(), GodotError> {
+//!     door.play("open".into(), -1.0, 1.0, false);
+//!     SignalFuture::new(&door, "animation_finished")?.await;
+//!
+//!     timer(0.5)?.await;
+//!     tween.start();
+//!     SignalFuture::new(&tween, "tween_completed")?.await;
+//!     Ok(())
+//! }
+//!
+//! spawn(async move {
+//!     if let Err(err) = intro(door, tween).await {
+//!         godot_error!("intro: {}", err);
+//!     }
+//! });
+//! ```
+//!
+//! The spawned tasks run on the main thread. The first call to `spawn` adds a node
+//! running them from its `_process` method to the root of the scene tree, so a
+//! task continues in the frame after the signal it waits for is emitted. Code
+//! driving the tasks itself, in tests for example, calls `run_tasks`.
+//!
+//! A task waiting for a signal of an object which is freed before the signal is
+//! emitted gets `None` instead of the arguments of the signal.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use init::{register_after_init, ClassDescriptor};
+use info;
+use is_api_bound;
+use signal_relay::{connect_fn, ConnectFlags};
+use GodotError;
+use GodotObject;
+use GodotString;
+use LocalCellData;
+use NativeClass;
+use NativeInstanceHeader;
+use NativeScript;
+use Node;
+use NodePauseMode;
+use SceneTree;
+use Variant;
+use _Engine;
+
+pub(crate) type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+thread_local! {
+    static TASKS: RefCell<HashMap<u64, Task>> = RefCell::new(HashMap::new());
+    static NEXT_TASK: Cell<u64> = Cell::new(0);
+}
+
+// The driver node is added once per process, its script is registered once.
+static DRIVER_ADDED: AtomicBool = AtomicBool::new(false);
+
+// The tasks to poll, the wakers can be called from any thread.
+static WOKEN: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+struct TaskWaker(u64);
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        if let Ok(mut woken) = WOKEN.lock() {
+            woken.push(self.0);
+        }
+    }
+}
+
+/// Runs the future as a task of the main thread, see the `tasks` module. Must be
+/// called from the main thread.
+///
+/// The task is polled for the first time by the next call to `run_tasks`.
+pub fn spawn<F>(future: F)
+where F: Future<Output = ()> + 'static {
+    spawn_task(Box::pin(future));
+}
+
+// Returns the id of the task, for `task_waker`.
+pub(crate) fn spawn_task(task: Task) -> u64 {
+    let id = NEXT_TASK.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+
+    TASKS.with(|tasks| tasks.borrow_mut().insert(id, task));
+    if let Ok(mut woken) = WOKEN.lock() {
+        woken.push(id);
+    }
+
+    if is_api_bound() && !DRIVER_ADDED.swap(true, Ordering::AcqRel) {
+        add_driver();
+    }
+    id
+}
+
+/// A waker scheduling the task to be polled by the next `run_tasks`.
+pub(crate) fn task_waker(id: u64) -> Waker {
+    Waker::from(Arc::new(TaskWaker(id)))
+}
+
+/// Polls the tasks woken since the last call and returns their number. Called once
+/// per frame by the node added by `spawn`.
+///
+/// Tasks woken while the tasks run are polled by the next call.
+pub fn run_tasks() -> usize {
+    let woken = match WOKEN.lock() {
+        Ok(mut woken) => mem::replace(&mut *woken, Vec::new()),
+        Err(_) => return 0,
+    };
+
+    let mut polled = 0;
+    for id in woken {
+        // The task is taken out of the map while it runs, so that it can spawn
+        // other tasks. A task woken several times is only polled once.
+        let mut task = match TASKS.with(|tasks| tasks.borrow_mut().remove(&id)) {
+            Some(task) => task,
+            None => continue,
+        };
+
+        let waker = task_waker(id);
+        polled += 1;
+        if task.as_mut().poll(&mut Context::from_waker(&waker)).is_pending() {
+            TASKS.with(|tasks| tasks.borrow_mut().insert(id, task));
+        }
+    }
+    polled
+}
+
+/// The number of tasks which haven't finished.
+pub fn pending_tasks() -> usize {
+    TASKS.with(|tasks| tasks.borrow().len())
+}
+
+/// A future resolved with the arguments of a signal the next time it is emitted, or
+/// with `None` if the object is freed before that.
+pub struct SignalFuture {
+    state: Rc<RefCell<SignalState>>,
+}
+
+#[derive(Default)]
+struct SignalState {
+    args: Option<Vec<Variant>>,
+    disconnected: bool,
+    waker: Option<Waker>,
+}
+
+// Owned by the connection, which is dropped with the source object or after the
+// emission.
+struct SignalListener {
+    state: Rc<RefCell<SignalState>>,
+}
+
+impl Drop for SignalListener {
+    fn drop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.disconnected = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl SignalFuture {
+    /// Waits for the signal `signal` of `source`.
+    ///
+    /// Fails if the object has no such signal, or if the library is not initialized.
+    pub fn new<O: GodotObject>(source: &O, signal: &str) -> Result<Self, GodotError> {
+        let state = Rc::new(RefCell::new(SignalState::default()));
+        let listener = SignalListener { state: state.clone() };
+
+        connect_fn(source, signal, ConnectFlags::ONE_SHOT, move |args| {
+            let mut state = listener.state.borrow_mut();
+            state.args = Some(args.iter().map(|&arg| arg.clone()).collect());
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        })?;
+
+        Ok(SignalFuture { state })
+    }
+}
+
+impl Future for SignalFuture {
+    type Output = Option<Vec<Variant>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if let Some(args) = state.args.take() {
+            return Poll::Ready(Some(args));
+        }
+        if state.disconnected {
+            return Poll::Ready(None);
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+// Owned by a connection to the object, which is dropped when the object is freed.
+struct FreeListener {
+    freed: Rc<Cell<bool>>,
+}
+
+impl Drop for FreeListener {
+    fn drop(&mut self) {
+        self.freed.set(true);
+    }
+}
+
+/// A flag set when `object` is freed, for the tasks keeping a pointer to it without
+/// keeping it alive.
+pub(crate) fn freed_flag<O: GodotObject>(object: &O) -> Result<Rc<Cell<bool>>, GodotError> {
+    let freed = Rc::new(Cell::new(false));
+
+    // Every object has the `script_changed` signal, the connection is only used to
+    // know when the object is freed.
+    let listener = FreeListener { freed: freed.clone() };
+    connect_fn(object, "script_changed", ConnectFlags::empty(), move |_args| {
+        let _ = &listener;
+    })?;
+    Ok(freed)
+}
+
+/// Waits for `seconds`, like `yield(get_tree().create_timer(seconds), "timeout")`.
+/// The timer doesn't run while the game is paused.
+pub fn timer(seconds: f64) -> Result<SignalFuture, GodotError> {
+    let mut tree = scene_tree().ok_or(GodotError::Unconfigured)?;
+    let timer = tree.create_timer(seconds, false).ok_or(GodotError::Failed)?;
+    SignalFuture::new(&timer, "timeout")
+}
+
+pub(crate) fn scene_tree() -> Option<SceneTree> {
+    _Engine::godot_singleton().get_main_loop().and_then(|main_loop| main_loop.cast::<SceneTree>())
+}
+
+// Adds a node running the tasks to the root of the tree. The node is added at the
+// end of the frame, the tree can be busy when the first task is spawned.
+fn add_driver() {
+    let script = match driver_script() {
+        Some(script) => script,
+        None => return,
+    };
+    let mut root = match scene_tree().and_then(|tree| tree.get_root()) {
+        Some(root) => root,
+        None => return,
+    };
+
+    let mut node = Node::new();
+    node.set_name(GodotString::from_str(TaskDriver::class_name()));
+    node.set_pause_mode(NodePauseMode::PauseModeProcess as i64);
+    node.set_script(script.cast());
+    root.call_deferred(GodotString::from_str("add_child"), &[Variant::from_object(node)]);
+}
+
+fn driver_script() -> Option<NativeScript> {
+    let library = info::library_resource()?;
+    register_after_init(|handle| {
+        let class = handle.add_class::<TaskDriver>(ClassDescriptor {
+            name: TaskDriver::class_name(),
+            base_class: "Node",
+            constructor: Some(godot_wrap_constructor!(TaskDriver, TaskDriver::new)),
+            destructor: Some(godot_wrap_destructor!(TaskDriver)),
+        });
+        class.add_method("_process", godot_wrap_method!(
+            TaskDriver,
+            fn _process(&self, _delta: f64) -> ()
+        ));
+    }).ok()?;
+
+    let mut script = NativeScript::new();
+    script.set_class_name(GodotString::from_str(TaskDriver::class_name()));
+    script.set_library(Some(library));
+    Some(script)
+}
+
+struct TaskDriver {
+    header: NativeInstanceHeader,
+}
+
+impl TaskDriver {
+    fn new(header: NativeInstanceHeader) -> Self {
+        TaskDriver { header }
+    }
+
+    fn _process(&self, _delta: f64) {
+        run_tasks();
+    }
+}
+
+impl NativeClass for TaskDriver {
+    type UserData = LocalCellData<TaskDriver>;
+
+    fn class_name() -> &'static str {
+        "AsyncTasks"
+    }
+
+    fn get_header(&self) -> &NativeInstanceHeader {
+        &self.header
+    }
+}
+
+#[test]
+fn task_polling() {
+    // Pending until it was polled `count` times, like a task waiting for signals.
+    struct Steps {
+        count: u32,
+        log: Rc<RefCell<Vec<u32>>>,
+    }
+
+    impl Future for Steps {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            self.log.borrow_mut().push(self.count);
+            if self.count == 0 {
+                return Poll::Ready(());
+            }
+            self.count -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    spawn(Steps { count: 2, log: log.clone() });
+    assert_eq!(pending_tasks(), 1);
+    assert!(log.borrow().is_empty());
+
+    assert_eq!(run_tasks(), 1);
+    assert_eq!(run_tasks(), 1);
+    assert_eq!(pending_tasks(), 1);
+    assert_eq!(run_tasks(), 1);
+    assert_eq!(pending_tasks(), 0);
+    assert_eq!(run_tasks(), 0);
+    assert_eq!(*log.borrow(), vec![2, 1, 0]);
+}
@@ -0,0 +1,86 @@
+//! Cleanup when the library is terminated.
+//!
+//! Caches to flush, threads to join or singletons to free are released by callbacks
+//! registered with `on_terminate`, usually from the init callbacks:
+//!
+//! ```ignore
+//! let worker = Worker::spawn();
+//! let handle = worker.handle();
+//! on_terminate(move |termination| {
+//!     handle.stop_and_join();
+//!     if termination == Termination::Game {
+//!         save_statistics();
+//!     }
+//! });
+//! ```
+//!
+//! `godot_gdnative_terminate!` runs the callbacks after the terminate callback of
+//! the library and the `reload::on_unload` callbacks, while the engine API is still
+//! available. They run in the reverse order of their registration, so that what was
+//! set up last is released first.
+
+use std::mem;
+use std::sync::Mutex;
+
+/// Why the library is terminated, see `on_terminate`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    /// The editor unloads the library, because it quits or, for reloadable
+    /// libraries, because it lost the focus. The library may be loaded again.
+    Editor,
+    /// The game quits.
+    Game,
+}
+
+static TERMINATE_CALLBACKS: Mutex<Vec<Box<dyn FnOnce(Termination) + Send>>> = Mutex::new(Vec::new());
+
+/// Registers a callback invoked once when the library is terminated, see the
+/// `terminate` module.
+pub fn on_terminate<F>(callback: F)
+where F: FnOnce(Termination) + Send + 'static {
+    if let Ok(mut callbacks) = TERMINATE_CALLBACKS.lock() {
+        callbacks.push(Box::new(callback));
+    }
+}
+
+/// Runs the callbacks registered with `on_terminate`, last registered first.
+/// Called by `godot_gdnative_terminate!`.
+#[doc(hidden)]
+pub fn run_terminate_callbacks(in_editor: bool) {
+    let termination = if in_editor { Termination::Editor } else { Termination::Game };
+
+    // Callbacks registered by a callback run after the others.
+    loop {
+        let callbacks = match TERMINATE_CALLBACKS.lock() {
+            Ok(mut callbacks) => mem::replace(&mut *callbacks, Vec::new()),
+            Err(_) => return,
+        };
+        if callbacks.is_empty() {
+            return;
+        }
+
+        for callback in callbacks.into_iter().rev() {
+            callback(termination);
+        }
+    }
+}
+
+#[test]
+fn terminate_order() {
+    use std::sync::Arc;
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for idx in 0..3 {
+        let order = order.clone();
+        on_terminate(move |termination| {
+            assert_eq!(termination, Termination::Game);
+            order.lock().unwrap().push(idx);
+        });
+    }
+
+    run_terminate_callbacks(false);
+    assert_eq!(*order.lock().unwrap(), vec![2, 1, 0]);
+
+    run_terminate_callbacks(false);
+    assert_eq!(order.lock().unwrap().len(), 3);
+}
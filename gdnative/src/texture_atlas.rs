@@ -0,0 +1,235 @@
+//! Helpers to draw sprites from texture atlases.
+//!
+//! The engine batches consecutive 2D draw calls using the same texture, so drawing
+//! many sprites from regions of a single atlas texture is much cheaper than drawing
+//! them from separate textures.
+//!
+//! A `SpriteAtlas` maps sprite names to regions of an atlas texture. The regions can
+//! come from any rust-side packer implementing `AtlasLayout`, for example the simple
+//! `ShelfPacker`:
+//!
+//! ```ignore
+//! let mut packer = ShelfPacker::new(1024, 2);
+//! for (name, image) in &images {
+//!     packer.add(name, image.get_width() as u32, image.get_height() as u32);
+//! }
+//! let layout = packer.pack().expect("The sprites don't fit in the atlas");
+//! // ... blit the images at their regions into `texture` ...
+//! let atlas = SpriteAtlas::from_layout(texture, &layout);
+//!
+//! canvas_item.draw_sprite(&atlas, "player", position_rect);
+//! ```
+
+use std::collections::HashMap;
+use geom::euclid::rect;
+use AtlasTexture;
+use CanvasItem;
+use Color;
+use GodotObject;
+use Rect2;
+use Texture;
+
+// `Texture` is abstract and has no `new_ref`, `from_sys` takes a new reference.
+fn texture_ref(texture: &Texture) -> Texture {
+    unsafe { Texture::from_sys(texture.to_sys()) }
+}
+
+impl AtlasTexture {
+    /// Creates a texture showing a region of `atlas`.
+    pub fn from_region(atlas: &Texture, region: Rect2) -> Self {
+        let mut texture = AtlasTexture::new();
+        texture.set_atlas(Some(texture_ref(atlas)));
+        texture.set_region(region);
+        texture
+    }
+}
+
+impl CanvasItem {
+    /// Draws a region of a texture, see `draw_texture_rect_region`.
+    pub fn draw_region(&mut self, texture: &Texture, rect: Rect2, src_rect: Rect2) {
+        self.draw_texture_rect_region(
+            Some(texture_ref(texture)), rect, src_rect,
+            Color::rgb(1.0, 1.0, 1.0), false, None, true
+        );
+    }
+
+    /// Draws a region of a texture with a color modulation.
+    pub fn draw_region_modulated(&mut self, texture: &Texture, rect: Rect2, src_rect: Rect2, modulate: Color) {
+        self.draw_texture_rect_region(
+            Some(texture_ref(texture)), rect, src_rect, modulate, false, None, true
+        );
+    }
+
+    /// Draws a sprite of an atlas. Returns `false` if the atlas has no sprite with
+    /// this name.
+    pub fn draw_sprite(&mut self, atlas: &SpriteAtlas, name: &str, rect: Rect2) -> bool {
+        match atlas.region(name) {
+            Some(region) => {
+                self.draw_region(&atlas.texture, rect, region);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draws several sprites of an atlas. All the draw calls use the atlas texture so
+    /// the engine can batch them.
+    pub fn draw_sprites<'a, I>(&mut self, atlas: &SpriteAtlas, sprites: I)
+    where
+        I: IntoIterator<Item = (&'a str, Rect2)>,
+    {
+        for (name, rect) in sprites {
+            self.draw_sprite(atlas, name, rect);
+        }
+    }
+}
+
+/// The placement of named sprites in an atlas texture, as computed by a packer.
+pub trait AtlasLayout {
+    /// Returns the name and the region of each sprite, in pixels.
+    fn regions(&self) -> Vec<(String, Rect2)>;
+}
+
+impl AtlasLayout for Vec<(String, Rect2)> {
+    fn regions(&self) -> Vec<(String, Rect2)> {
+        self.clone()
+    }
+}
+
+impl AtlasLayout for HashMap<String, Rect2> {
+    fn regions(&self) -> Vec<(String, Rect2)> {
+        self.iter().map(|(name, region)| (name.clone(), *region)).collect()
+    }
+}
+
+/// A texture containing several named sprites.
+pub struct SpriteAtlas {
+    texture: Texture,
+    regions: HashMap<String, Rect2>,
+}
+
+impl SpriteAtlas {
+    pub fn new(texture: Texture) -> Self {
+        SpriteAtlas {
+            texture,
+            regions: HashMap::new(),
+        }
+    }
+
+    pub fn from_layout<L: AtlasLayout>(texture: Texture, layout: &L) -> Self {
+        let mut atlas = SpriteAtlas::new(texture);
+        for (name, region) in layout.regions() {
+            atlas.add_region(name, region);
+        }
+        atlas
+    }
+
+    pub fn add_region<S: Into<String>>(&mut self, name: S, region: Rect2) {
+        self.regions.insert(name.into(), region);
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn region(&self, name: &str) -> Option<Rect2> {
+        self.regions.get(name).cloned()
+    }
+
+    /// Creates an `AtlasTexture` for a sprite, to be used where the engine expects a
+    /// texture (for example by a `Sprite` node).
+    pub fn atlas_texture(&self, name: &str) -> Option<AtlasTexture> {
+        self.region(name).map(|region| AtlasTexture::from_region(&self.texture, region))
+    }
+}
+
+/// A simple packer placing the sprites on horizontal shelves, tallest first.
+#[derive(Clone, Debug)]
+pub struct ShelfPacker {
+    width: u32,
+    padding: u32,
+    sprites: Vec<(String, u32, u32)>,
+}
+
+impl ShelfPacker {
+    /// Creates a packer for an atlas of the given width, with `padding` pixels
+    /// between the sprites.
+    pub fn new(width: u32, padding: u32) -> Self {
+        ShelfPacker {
+            width,
+            padding,
+            sprites: Vec::new(),
+        }
+    }
+
+    pub fn add<S: Into<String>>(&mut self, name: S, width: u32, height: u32) {
+        self.sprites.push((name.into(), width, height));
+    }
+
+    /// Computes the regions of the sprites, or returns `None` if a sprite is wider
+    /// than the atlas.
+    ///
+    /// The height of the atlas is the `bottom` of the returned layout.
+    pub fn pack(&self) -> Option<PackedLayout> {
+        let mut sprites: Vec<&(String, u32, u32)> = self.sprites.iter().collect();
+        sprites.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+
+        let mut regions = Vec::with_capacity(sprites.len());
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for &&(ref name, width, height) in &sprites {
+            if width > self.width {
+                return None;
+            }
+
+            if x + width > self.width {
+                x = 0;
+                y += shelf_height + self.padding;
+                shelf_height = 0;
+            }
+
+            regions.push((name.clone(), rect(x as f32, y as f32, width as f32, height as f32)));
+            x += width + self.padding;
+            shelf_height = shelf_height.max(height);
+        }
+
+        Some(PackedLayout {
+            regions,
+            bottom: y + shelf_height,
+        })
+    }
+}
+
+/// The result of `ShelfPacker::pack`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedLayout {
+    pub regions: Vec<(String, Rect2)>,
+    pub bottom: u32,
+}
+
+impl AtlasLayout for PackedLayout {
+    fn regions(&self) -> Vec<(String, Rect2)> {
+        self.regions.clone()
+    }
+}
+
+#[test]
+fn shelf_packing() {
+    let mut packer = ShelfPacker::new(10, 1);
+    packer.add("a", 4, 2);
+    packer.add("b", 4, 5);
+    packer.add("c", 4, 3);
+    packer.add("d", 10, 1);
+
+    let layout = packer.pack().unwrap();
+    assert_eq!(layout.regions, vec![
+        ("b".to_string(), rect(0.0, 0.0, 4.0, 5.0)),
+        ("c".to_string(), rect(5.0, 0.0, 4.0, 3.0)),
+        ("a".to_string(), rect(0.0, 6.0, 4.0, 2.0)),
+        ("d".to_string(), rect(0.0, 9.0, 10.0, 1.0)),
+    ]);
+    assert_eq!(layout.bottom, 10);
+
+    packer.add("too_wide", 11, 1);
+    assert_eq!(packer.pack(), None);
+}
@@ -0,0 +1,148 @@
+//! Startup time instrumentation.
+//!
+//! With the `startup_timing` feature, the time spent registering each class, method,
+//! property and signal, as well as the time spent lazily initializing the generated
+//! bindings of each engine class, is recorded and a report is printed at the end of
+//! `godot_nativescript_init`. This helps finding out why the editor takes long to
+//! load a library.
+//!
+//! Without the feature nothing is recorded and the instrumentation compiles down to
+//! nothing.
+
+#[cfg(feature = "startup_timing")]
+use std::sync::Mutex;
+#[cfg(feature = "startup_timing")]
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TimingKind {
+    Class,
+    Method,
+    Property,
+    Signal,
+    Bindings,
+}
+
+#[cfg(feature = "startup_timing")]
+struct Record {
+    kind: TimingKind,
+    name: String,
+    duration: Duration,
+}
+
+#[cfg(feature = "startup_timing")]
+static RECORDS: Mutex<Vec<Record>> = Mutex::new(Vec::new());
+
+/// Runs `f`, recording the time it took under the name returned by `name`.
+#[cfg(feature = "startup_timing")]
+pub(crate) fn time<N, F, R>(kind: TimingKind, name: N, f: F) -> R
+where
+    N: FnOnce() -> String,
+    F: FnOnce() -> R,
+{
+    let start = Instant::now();
+    let ret = f();
+    let duration = start.elapsed();
+
+    if let Ok(mut records) = RECORDS.lock() {
+        records.push(Record { kind, name: name(), duration });
+    }
+
+    ret
+}
+
+#[cfg(not(feature = "startup_timing"))]
+#[inline(always)]
+pub(crate) fn time<N, F, R>(_kind: TimingKind, _name: N, f: F) -> R
+where
+    N: FnOnce() -> String,
+    F: FnOnce() -> R,
+{
+    f()
+}
+
+/// Returns a human readable report of the time spent during registration and in
+/// the initialization of the bindings so far.
+///
+/// Classes are listed with the total time spent registering them and their members,
+/// slowest first. Returns `None` unless the `startup_timing` feature is enabled.
+pub fn startup_timing_report() -> Option<String> {
+    report()
+}
+
+#[cfg(not(feature = "startup_timing"))]
+fn report() -> Option<String> {
+    None
+}
+
+#[cfg(feature = "startup_timing")]
+fn report() -> Option<String> {
+    use std::fmt::Write;
+
+    let records = match RECORDS.lock() {
+        Ok(records) => records,
+        Err(_) => return None,
+    };
+
+    // Members are recorded as "Class::member", group them under their class.
+    let mut classes: Vec<(&str, Duration, Vec<&Record>)> = Vec::new();
+    let mut bindings: Vec<&Record> = Vec::new();
+    for record in records.iter() {
+        if record.kind == TimingKind::Bindings {
+            bindings.push(record);
+            continue;
+        }
+
+        let class = record.name.split("::").next().unwrap_or("");
+        let idx = match classes.iter().position(|&(name, _, _)| name == class) {
+            Some(idx) => idx,
+            None => {
+                classes.push((class, Duration::new(0, 0), Vec::new()));
+                classes.len() - 1
+            }
+        };
+
+        classes[idx].1 += record.duration;
+        if record.kind != TimingKind::Class {
+            classes[idx].2.push(record);
+        }
+    }
+
+    classes.sort_by(|a, b| b.1.cmp(&a.1));
+    bindings.sort_by(|a, b| b.duration.cmp(&a.duration));
+
+    let total: Duration = records.iter().map(|r| r.duration).sum();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "Startup timing report ({}):", format_duration(total));
+    for &(name, duration, ref members) in &classes {
+        let _ = writeln!(out, "  class {}: {}", name, format_duration(duration));
+        for member in members {
+            let _ = writeln!(out, "    {:?} {}: {}",
+                member.kind, member.name, format_duration(member.duration)
+            );
+        }
+    }
+
+    if !bindings.is_empty() {
+        let bindings_total: Duration = bindings.iter().map(|r| r.duration).sum();
+        let _ = writeln!(out, "  bindings initialization: {}", format_duration(bindings_total));
+        for record in bindings {
+            let _ = writeln!(out, "    {}: {}", record.name, format_duration(record.duration));
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(feature = "startup_timing")]
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 / 1_000_000.0)
+}
+
+#[doc(hidden)]
+pub fn print_startup_timing_report() {
+    if let Some(report) = startup_timing_report() {
+        godot_print!("{}", report);
+    }
+}
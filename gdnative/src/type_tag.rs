@@ -0,0 +1,299 @@
+//! Type tags to share rust instances between several GDNative libraries.
+//!
+//! `Instance<T>` casts the user data of a script to `T::UserData`, which is only
+//! correct if the script is an instance of `T`. When the script comes from the
+//! library doing the lookup, comparing the class names is enough. When it comes from
+//! another library, the lookup only succeeds if that library registered the class
+//! with the same `TypeTag` as `T`.
+//!
+//! Every library exposes the instances of its classes through a C interface,
+//! `TypeTagInterface`, whose address is stored in the metadata of the `Engine`
+//! singleton under the path of the library:
+//!
+//! ```text
+//! Engine.get_meta("__gdnative_type_tags") == {
+//!     "res://bin/libgame.so": 140235..., # the address of its interface
+//!     "res://bin/libui.so": 140237...,
+//! }
+//! ```
+//!
+//! The interface is versioned: a library only uses the interface of another
+//! library with the same `TYPE_TAG_INTERFACE_VERSION`, and otherwise treats its
+//! scripts as unknown classes. The entry of a library is removed when it is
+//! terminated. Code which isn't built with these bindings, for example a C++
+//! library, gets the interface from the symbol declared by
+//! `godot_type_tag_interface!`.
+//!
+//! Sharing instances this way requires both libraries to be built with the same
+//! compiler from the same definition of the class, usually a crate they both depend
+//! on. The default tag includes the class name, the version of this crate and the
+//! size and alignment of the class, which catches most accidental mismatches. Classes
+//! can override `NativeClass::type_tag` to include their own version.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use libc;
+use sys;
+use get_api;
+use on_terminate;
+use GDNativeLibrary;
+use GodotObject;
+use GodotString;
+use Dictionary;
+use NativeClass;
+use NativeScript;
+use Object;
+use Variant;
+use _Engine;
+use info;
+
+const TYPE_TAGS_META: &str = "__gdnative_type_tags";
+
+/// The version of `TypeTagInterface`, changed when its layout or the meaning of
+/// its functions changes. `version` is the first field in every version.
+pub const TYPE_TAG_INTERFACE_VERSION: u32 = 1;
+
+/// A stable identifier for a rust class, see `NativeClass::type_tag`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeTag(pub u64);
+
+impl TypeTag {
+    /// Computes a tag by hashing a string (with 64 bit FNV-1a, so that the tag
+    /// doesn't depend on the compiler or the platform).
+    pub fn from_name(name: &str) -> Self {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in name.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        TypeTag(hash)
+    }
+
+    /// The default tag of a class, computed from its name and the size and alignment
+    /// of the wrapper its instances are stored in.
+    pub fn of<T: NativeClass>() -> Self {
+        TypeTag::from_name(&format!(
+            "{}/{}/{}/{}",
+            T::class_name(),
+            env!("CARGO_PKG_VERSION"),
+            mem::size_of::<T::UserData>(),
+            mem::align_of::<T::UserData>(),
+        ))
+    }
+}
+
+/// The C interface of a library to the instances of its classes, see the
+/// `type_tag` module.
+#[repr(C)]
+pub struct TypeTagInterface {
+    /// `TYPE_TAG_INTERFACE_VERSION` of the library.
+    pub version: u32,
+    /// Returns the tag of a class registered by the library, or 0 if it has no
+    /// such class. `class_name` is a nul-terminated UTF-8 string.
+    pub type_tag: unsafe extern "C" fn(class_name: *const libc::c_char) -> u64,
+    /// Returns the user data of `object` (a pointer to `NativeClass::UserData`) if
+    /// its script is a class of the library with the tag `tag`, or null.
+    pub user_data: unsafe extern "C" fn(object: *mut sys::godot_object, tag: u64) -> *const libc::c_void,
+}
+
+static INTERFACE: TypeTagInterface = TypeTagInterface {
+    version: TYPE_TAG_INTERFACE_VERSION,
+    type_tag: interface_type_tag,
+    user_data: interface_user_data,
+};
+
+// The tags of the classes registered by this library.
+static CLASS_TAGS: Mutex<Option<HashMap<String, u64>>> = Mutex::new(None);
+static PUBLISHED: AtomicBool = AtomicBool::new(false);
+
+/// The interface of this library, returned by the function declared with
+/// `godot_type_tag_interface!`.
+#[doc(hidden)]
+pub fn type_tag_interface() -> *const TypeTagInterface {
+    &INTERFACE
+}
+
+/// Declare an extern function returning the `TypeTagInterface` of the library,
+/// for the code looking it up with the symbols of the library instead of the
+/// metadata of the `Engine`, see the `type_tag` module.
+///
+/// By default the function is named `godot_rust_type_tag_interface`, which can be
+/// overridden:
+///
+/// ```ignore
+/// godot_type_tag_interface!(as my_library_type_tag_interface);
+/// ```
+#[macro_export]
+macro_rules! godot_type_tag_interface {
+    () => {
+        godot_type_tag_interface!(as godot_rust_type_tag_interface);
+    };
+    (as $fn_name:ident) => {
+        #[no_mangle]
+        #[doc(hidden)]
+        pub extern "C" fn $fn_name() -> *const $crate::TypeTagInterface {
+            $crate::type_tag_interface()
+        }
+    };
+}
+
+unsafe extern "C" fn interface_type_tag(class_name: *const libc::c_char) -> u64 {
+    if class_name.is_null() {
+        return 0;
+    }
+    let class_name = match CStr::from_ptr(class_name).to_str() {
+        Ok(class_name) => class_name,
+        Err(_) => return 0,
+    };
+    class_tag(class_name).unwrap_or(0)
+}
+
+unsafe extern "C" fn interface_user_data(object: *mut sys::godot_object, tag: u64) -> *const libc::c_void {
+    if object.is_null() {
+        return ptr::null();
+    }
+    let script = match Object::from_sys(object).get_script().and_then(|script| script.cast::<NativeScript>()) {
+        Some(script) => script,
+        None => return ptr::null(),
+    };
+    let own_path = info::library_path();
+    if own_path.is_none() || script_library_path(&script) != own_path {
+        return ptr::null();
+    }
+    if class_tag(&script.get_class_name().to_string()) != Some(tag) {
+        return ptr::null();
+    }
+    (get_api().godot_nativescript_get_userdata)(object) as *const libc::c_void
+}
+
+fn class_tag(class_name: &str) -> Option<u64> {
+    CLASS_TAGS.lock().ok()?.as_ref()?.get(class_name).cloned()
+}
+
+fn script_library_path(script: &NativeScript) -> Option<String> {
+    script.get_library()
+        .map(|library: GDNativeLibrary| library.get_current_library_path().to_string())
+}
+
+/// Records the tag of a class registered by this library, and publishes the
+/// interface of the library with the first class.
+pub(crate) fn register<C: NativeClass>(class_name: &str) {
+    if let Ok(mut tags) = CLASS_TAGS.lock() {
+        tags.get_or_insert_with(HashMap::new).insert(class_name.to_string(), C::type_tag().0);
+    }
+
+    let path = match info::library_path() {
+        Some(path) => path,
+        None => return,
+    };
+    if PUBLISHED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let address = type_tag_interface() as usize as i64;
+    update_libraries(|libraries| libraries.set(&Variant::from_str(&path), &Variant::from_i64(address)));
+    on_terminate(move |_| {
+        update_libraries(|libraries| libraries.erase(&Variant::from_str(&path)));
+        PUBLISHED.store(false, Ordering::Release);
+        if let Ok(mut tags) = CLASS_TAGS.lock() {
+            *tags = None;
+        }
+    });
+}
+
+fn update_libraries<F>(update: F)
+where F: FnOnce(&mut Dictionary) {
+    let mut engine = _Engine::godot_singleton();
+    let mut libraries = if engine.has_meta(GodotString::from_str(TYPE_TAGS_META)) {
+        engine.get_meta(GodotString::from_str(TYPE_TAGS_META)).to_dictionary()
+    } else {
+        Dictionary::new()
+    };
+    update(&mut libraries);
+    engine.set_meta(GodotString::from_str(TYPE_TAGS_META), Variant::from_dictionary(&libraries));
+}
+
+// The interface published by the library of `path`, if it has the same version.
+fn library_interface(path: &str) -> Option<&'static TypeTagInterface> {
+    let engine = _Engine::godot_singleton();
+    if !engine.has_meta(GodotString::from_str(TYPE_TAGS_META)) {
+        return None;
+    }
+    let libraries = engine.get_meta(GodotString::from_str(TYPE_TAGS_META)).to_dictionary();
+    let path = Variant::from_str(path);
+    if !libraries.contains(&path) {
+        return None;
+    }
+
+    let interface = libraries.get(&path).to_i64() as usize as *const TypeTagInterface;
+    if interface.is_null() {
+        return None;
+    }
+    // The version is the first field in every version, the rest is only read if
+    // it matches.
+    unsafe {
+        if *(interface as *const u32) != TYPE_TAG_INTERFACE_VERSION {
+            godot_warn!(
+                "the GDNative library {} uses another version of the type tag interface, its instances can't be shared",
+                path.to_string()
+            );
+            return None;
+        }
+        Some(&*interface)
+    }
+}
+
+/// Returns the user data of `this`, whose script is `script`, if its instances
+/// can be accessed as `T` by this library.
+///
+/// The object pointer must be valid.
+pub(crate) unsafe fn user_data<T: NativeClass>(this: *mut sys::godot_object, script: &NativeScript) -> *const T::UserData {
+    let own_user_data = || (get_api().godot_nativescript_get_userdata)(this) as *const T::UserData;
+
+    let (script_path, own_path) = match (script_library_path(script), info::library_path()) {
+        (Some(script_path), Some(own_path)) => (script_path, own_path),
+        // Without paths the script can't be attributed to a library, keep the
+        // behavior of a single library setup.
+        _ => return own_user_data(),
+    };
+
+    if script_path == own_path {
+        return own_user_data();
+    }
+
+    match library_interface(&script_path) {
+        Some(interface) => (interface.user_data)(this, T::type_tag().0) as *const T::UserData,
+        None => ptr::null(),
+    }
+}
+
+#[test]
+fn type_tag_hash() {
+    assert_eq!(TypeTag::from_name(""), TypeTag(0xcbf29ce484222325));
+    assert_eq!(TypeTag::from_name("a"), TypeTag(0xaf63dc4c8601ec8c));
+    assert_eq!(TypeTag::from_name("foobar"), TypeTag(0x85944171f73967e8));
+}
+
+#[test]
+fn type_tag_interface_tags() {
+    use std::ffi::CString;
+
+    if let Ok(mut tags) = CLASS_TAGS.lock() {
+        tags.get_or_insert_with(HashMap::new).insert("TaggedPlayer".to_string(), 42);
+    }
+
+    let interface = unsafe { &*type_tag_interface() };
+    assert_eq!(interface.version, TYPE_TAG_INTERFACE_VERSION);
+    unsafe {
+        let name = CString::new("TaggedPlayer").unwrap();
+        assert_eq!((interface.type_tag)(name.as_ptr()), 42);
+        let name = CString::new("Unknown").unwrap();
+        assert_eq!((interface.type_tag)(name.as_ptr()), 0);
+        assert_eq!((interface.type_tag)(ptr::null()), 0);
+        assert!((interface.user_data)(ptr::null_mut(), 42).is_null());
+    }
+}
@@ -0,0 +1,311 @@
+//! Wrappers storing the rust instances of the scripts.
+//!
+//! The engine keeps a pointer to the instance of each script and every callback
+//! (methods, properties, reload hooks...) accesses the instance through it. The
+//! wrapper the instance is stored in is chosen per class by `NativeClass::UserData`:
+//!
+//! - `LocalCellData` stores the instance in a `RefCell`. It is the cheapest, but the
+//!   instance can only be accessed from the thread that created it.
+//! - `MutexData` stores the instance in a `Mutex`, for classes used from several
+//!   threads, for example by a `Thread` or by the physics server.
+//! - `RwLockData` stores the instance in a `RwLock`, which lets several threads run
+//!   `&self` methods at the same time.
+//!
+//! With any wrapper, an access that conflicts with another access from the same
+//! thread fails with `AccessError::Borrowed` instead of panicking or deadlocking.
+//! This happens when the engine calls back into the script while one of its methods
+//! is running, for example through a signal. `LocalCellData` lets shared accesses
+//! nest, so `&self` methods can be called back; the locks can't be entered twice by
+//! the same thread.
+//!
+//! With `#[derive(NativeClass)]` the wrapper is selected with the `user_data`
+//! attribute, `LocalCellData` is used without it:
+//!
+//! ```ignore
+//! #[derive(NativeClass)]
+//! #[inherit(Node)]
+//! #[user_data(MutexData<Loader>)]
+//! struct Loader {
+//!     header: NativeInstanceHeader,
+//! }
+//! ```
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::error::Error;
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+use libc;
+
+/// Stores the rust instance of a script and controls the access to it.
+///
+/// Unsafe to implement: `map_mut` must never give out a mutable reference while
+/// another reference to the value is alive.
+pub unsafe trait UserData: Sized {
+    type Target;
+
+    /// Wraps a new instance.
+    fn new(value: Self::Target) -> Self;
+
+    /// Calls `op` with a shared reference to the instance.
+    fn map<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&Self::Target) -> U;
+
+    /// Calls `op` with a mutable reference to the instance.
+    fn map_mut<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&mut Self::Target) -> U;
+}
+
+/// The reason an instance couldn't be accessed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessError {
+    /// The instance is already accessed in a conflicting way by the same thread.
+    Borrowed,
+    /// A `LocalCellData` instance was accessed from another thread than the one
+    /// that created it.
+    WrongThread,
+    /// A method panicked while holding the lock of the instance.
+    Poisoned,
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccessError::Borrowed => write!(f, "the instance is already borrowed"),
+            AccessError::WrongThread => write!(f, "the instance belongs to another thread"),
+            AccessError::Poisoned => write!(f, "the lock of the instance is poisoned"),
+        }
+    }
+}
+
+impl Error for AccessError {}
+
+/// Stores the instance in a `RefCell`, see the `user_data` module.
+///
+/// The instance can only be accessed from the thread that created it, other threads
+/// get `AccessError::WrongThread`. The threads are identified by the operating
+/// system, so another library reaching the instance through `Instance<T>` is allowed
+/// on the same thread.
+pub struct LocalCellData<T> {
+    thread: u64,
+    cell: RefCell<T>,
+}
+
+unsafe impl<T> UserData for LocalCellData<T> {
+    type Target = T;
+
+    fn new(value: T) -> Self {
+        LocalCellData {
+            thread: current_thread(),
+            cell: RefCell::new(value),
+        }
+    }
+
+    fn map<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&T) -> U {
+        if self.thread != current_thread() {
+            return Err(AccessError::WrongThread);
+        }
+        let value = self.cell.try_borrow().map_err(|_| AccessError::Borrowed)?;
+        Ok(op(&*value))
+    }
+
+    fn map_mut<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&mut T) -> U {
+        if self.thread != current_thread() {
+            return Err(AccessError::WrongThread);
+        }
+        let mut value = self.cell.try_borrow_mut().map_err(|_| AccessError::Borrowed)?;
+        Ok(op(&mut *value))
+    }
+}
+
+impl<T> LocalCellData<T> {
+    /// Borrows the instance like the `RefCell` the previous versions stored it in.
+    ///
+    /// Panics if the instance is mutably borrowed or belongs to another thread.
+    #[deprecated(note = "use `UserData::map` instead, see the `migration` module")]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        assert!(self.thread == current_thread(), "{}", AccessError::WrongThread);
+        self.cell.borrow()
+    }
+
+    /// Mutably borrows the instance like the `RefCell` the previous versions stored
+    /// it in.
+    ///
+    /// Panics if the instance is borrowed or belongs to another thread.
+    #[deprecated(note = "use `UserData::map_mut` instead, see the `migration` module")]
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        assert!(self.thread == current_thread(), "{}", AccessError::WrongThread);
+        self.cell.borrow_mut()
+    }
+}
+
+/// Stores the instance in a `Mutex`, see the `user_data` module.
+///
+/// Accesses from other threads wait for the lock.
+pub struct MutexData<T> {
+    lock: Mutex<T>,
+}
+
+unsafe impl<T: Send> UserData for MutexData<T> {
+    type Target = T;
+
+    fn new(value: T) -> Self {
+        MutexData { lock: Mutex::new(value) }
+    }
+
+    fn map<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&T) -> U {
+        self.map_mut(|value| op(value))
+    }
+
+    fn map_mut<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&mut T) -> U {
+        let _held = HeldLock::acquire(self as *const _ as usize)?;
+        let mut value = self.lock.lock().map_err(|_| AccessError::Poisoned)?;
+        Ok(op(&mut *value))
+    }
+}
+
+/// Stores the instance in a `RwLock`, see the `user_data` module.
+///
+/// Shared accesses from several threads run at the same time, mutable accesses wait
+/// for the other accesses to end.
+pub struct RwLockData<T> {
+    lock: RwLock<T>,
+}
+
+unsafe impl<T: Send + Sync> UserData for RwLockData<T> {
+    type Target = T;
+
+    fn new(value: T) -> Self {
+        RwLockData { lock: RwLock::new(value) }
+    }
+
+    fn map<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&T) -> U {
+        // A thread taking the read lock twice can deadlock when a writer is waiting.
+        let _held = HeldLock::acquire(self as *const _ as usize)?;
+        let value = self.lock.read().map_err(|_| AccessError::Poisoned)?;
+        Ok(op(&*value))
+    }
+
+    fn map_mut<F, U>(&self, op: F) -> Result<U, AccessError>
+    where F: FnOnce(&mut T) -> U {
+        let _held = HeldLock::acquire(self as *const _ as usize)?;
+        let mut value = self.lock.write().map_err(|_| AccessError::Poisoned)?;
+        Ok(op(&mut *value))
+    }
+}
+
+thread_local! {
+    // The addresses of the locks held by the current thread.
+    static HELD_LOCKS: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+}
+
+// The id of the current thread given by the operating system, the same in all the
+// libraries of the process. The thread locals of a library are its own, and their
+// addresses can be reused by the next threads.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn current_thread() -> u64 {
+    unsafe { libc::syscall(libc::SYS_gettid) as u64 }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn current_thread() -> u64 {
+    // Unlike `pthread_self`, never reused during the life of the process.
+    let mut id = 0;
+    unsafe {
+        libc::pthread_threadid_np(0, &mut id);
+    }
+    id
+}
+
+#[cfg(windows)]
+fn current_thread() -> u64 {
+    extern "system" {
+        fn GetCurrentThreadId() -> u32;
+    }
+    unsafe { GetCurrentThreadId() as u64 }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios", windows)))]
+fn current_thread() -> u64 {
+    unsafe { libc::pthread_self() as usize as u64 }
+}
+
+struct HeldLock(usize);
+
+impl HeldLock {
+    fn acquire(lock: usize) -> Result<Self, AccessError> {
+        HELD_LOCKS.with(|held| {
+            let mut held = held.borrow_mut();
+            if held.contains(&lock) {
+                return Err(AccessError::Borrowed);
+            }
+            held.push(lock);
+            Ok(HeldLock(lock))
+        })
+    }
+}
+
+impl Drop for HeldLock {
+    fn drop(&mut self) {
+        HELD_LOCKS.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(idx) = held.iter().rposition(|&lock| lock == self.0) {
+                held.remove(idx);
+            }
+        });
+    }
+}
+
+#[test]
+fn user_data_reentrance() {
+    let cell = LocalCellData::new(1);
+    assert_eq!(cell.map(|a| cell.map(|b| a + b)), Ok(Ok(2)));
+    assert_eq!(cell.map(|_| cell.map_mut(|_| ())), Ok(Err(AccessError::Borrowed)));
+    assert_eq!(cell.map_mut(|_| cell.map(|_| ())), Ok(Err(AccessError::Borrowed)));
+
+    let mutex = MutexData::new(1);
+    assert_eq!(mutex.map_mut(|value| { *value += 1; *value }), Ok(2));
+    assert_eq!(mutex.map(|_| mutex.map(|_| ())), Ok(Err(AccessError::Borrowed)));
+    assert_eq!(mutex.map(|value| *value), Ok(2));
+
+    let rw_lock = RwLockData::new(1);
+    assert_eq!(rw_lock.map(|_| rw_lock.map_mut(|_| ())), Ok(Err(AccessError::Borrowed)));
+    assert_eq!(rw_lock.map(|value| *value), Ok(1));
+}
+
+#[test]
+#[allow(deprecated)]
+fn local_cell_data_borrow_shims() {
+    let cell = LocalCellData::new(1);
+    *cell.borrow_mut() += 1;
+    assert_eq!(*cell.borrow(), 2);
+    let _borrowed = cell.borrow();
+    assert_eq!(cell.map_mut(|_| ()), Err(AccessError::Borrowed));
+}
+
+#[test]
+fn user_data_threads() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let cell = Arc::new(Mutex::new(LocalCellData::new(0)));
+    let other = cell.clone();
+    let result = thread::spawn(move || other.lock().unwrap().map(|value| *value)).join().unwrap();
+    assert_eq!(result, Err(AccessError::WrongThread));
+    assert_eq!(cell.lock().unwrap().map(|value| *value), Ok(0));
+    assert_ne!(thread::spawn(current_thread).join().unwrap(), current_thread());
+
+    let mutex = Arc::new(MutexData::new(0));
+    let workers: Vec<_> = (0..4).map(|_| {
+        let mutex = mutex.clone();
+        thread::spawn(move || mutex.map_mut(|value| *value += 1).unwrap())
+    }).collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(mutex.map(|value| *value), Ok(4));
+}
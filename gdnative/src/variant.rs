@@ -2,6 +2,7 @@ use super::*;
 use std::mem::{transmute, forget};
 use std::default::Default;
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // TODO: implement Debug, PartialEq, etc.
 
@@ -11,6 +12,14 @@ use std::fmt;
 /// dependning on the size of the type and whether the it is trivially copyable.
 pub struct Variant(pub(crate) sys::godot_variant);
 
+// The number of variants owned by rust wrappers, see `MemoryReport`.
+static LIVE_VARIANTS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg_attr(not(gdnative_helper = "memory_report"), allow(dead_code))]
+pub(crate) fn live_variants() -> usize {
+    LIVE_VARIANTS.load(Ordering::Relaxed)
+}
+
 macro_rules! variant_constructors_transmute {
     (
         $(
@@ -26,7 +35,7 @@ macro_rules! variant_constructors_transmute {
                     let mut dest = sys::godot_variant::default();
                     let gd_val: $GdType = transmute(*val);
                     (api.$gd_method)(&mut dest, &gd_val);
-                    Variant(dest)
+                    Variant::from_sys(dest)
                 }
             }
         )*
@@ -47,7 +56,7 @@ macro_rules! variant_constructors_wrap {
                     let api = get_api();
                     let mut dest = sys::godot_variant::default();
                     (api.$gd_method)(&mut dest, &val.0);
-                    Variant(dest)
+                    Variant::from_sys(dest)
                 }
             }
         )*
@@ -223,7 +232,7 @@ impl Variant {
             let api = get_api();
             let mut dest = sys::godot_variant::default();
             (api.godot_variant_new_nil)(&mut dest);
-            Variant(dest)
+            Variant::from_sys(dest)
         }
     }
 
@@ -238,7 +247,7 @@ impl Variant {
             let mut godot_s = (api.godot_string_chars_to_utf8_with_len)(val.as_ptr() as *const _, val.len() as _);
             (api.godot_variant_new_string)(&mut dest, &godot_s);
             (api.godot_string_destroy)(&mut godot_s);
-            Variant(dest)
+            Variant::from_sys(dest)
         }
     }
 
@@ -250,7 +259,7 @@ impl Variant {
             let api = get_api();
             let mut dest = sys::godot_variant::default();
             (api.godot_variant_new_object)(&mut dest, val.to_sys());
-            Variant(dest)
+            Variant::from_sys(dest)
         }
     }
 
@@ -260,7 +269,7 @@ impl Variant {
             let api = get_api();
             let mut dest = sys::godot_variant::default();
             (api.godot_variant_new_int)(&mut dest, v);
-            Variant(dest)
+            Variant::from_sys(dest)
         }
     }
 
@@ -270,7 +279,7 @@ impl Variant {
             let api = get_api();
             let mut dest = sys::godot_variant::default();
             (api.godot_variant_new_uint)(&mut dest, v);
-            Variant(dest)
+            Variant::from_sys(dest)
         }
     }
 
@@ -280,7 +289,7 @@ impl Variant {
             let api = get_api();
             let mut dest = sys::godot_variant::default();
             (api.godot_variant_new_bool)(&mut dest, v);
-            Variant(dest)
+            Variant::from_sys(dest)
         }
     }
 
@@ -455,37 +464,10 @@ impl Variant {
         }
     }
 
-    // TODO: return a proper error.
+    /// Calls the method `method` of the value and drops its result, see `try_call`
+    /// for the result and the reason of the failures.
     pub fn call(&mut self, method: &GodotString, args: &[Variant]) -> Result<(), ()> {
-        unsafe {
-            let api = get_api();
-            let mut err = sys::godot_variant_call_error::default();
-            if args.is_empty() {
-                let mut first = ::std::ptr::null() as *const sys::godot_variant;
-                (api.godot_variant_call)(
-                    &mut self.0,
-                    &method.0,
-                    &mut first, 0,
-                    &mut err
-                );
-            } else {
-                // TODO: double check that this is safe.
-                let gd_args: &[sys::godot_variant] = transmute(args);
-                let mut first = &gd_args[0] as *const sys::godot_variant;
-                (api.godot_variant_call)(
-                    &mut self.0,
-                    &method.0,
-                    &mut first, args.len() as i32,
-                    &mut err
-                );
-            }
-
-            if err.error == sys::godot_variant_call_error_error::GODOT_CALL_ERROR_CALL_OK {
-                Ok(())
-            } else {
-                Err(())
-            }
-        }
+        self.try_call(method, args).map(|_| ()).map_err(|_| ())
     }
 
     pub(crate) fn cast_ref<'l>(ptr: *const sys::godot_variant) -> &'l Variant {
@@ -503,11 +485,42 @@ impl Variant {
     /// responsible for running the destructor for the object, otherwise
     /// it is leaked.
     pub fn forget(self) -> sys::godot_variant {
+        LIVE_VARIANTS.fetch_sub(1, Ordering::Relaxed);
         let v = self.0;
         forget(self);
         v
     }
 
+    /// The raw value, to pass to the functions of `sys_api()`.
+    ///
+    /// The variant remains owned by the wrapper, the functions must not
+    /// destroy it.
+    pub fn as_sys(&self) -> &sys::godot_variant {
+        &self.0
+    }
+
+    /// The raw value, to pass to the functions of `sys_api()` modifying it in
+    /// place.
+    ///
+    /// The variant remains owned by the wrapper, the functions must not
+    /// destroy it or overwrite it without destroying it first.
+    pub fn as_mut_sys(&mut self) -> &mut sys::godot_variant {
+        &mut self.0
+    }
+
+    /// Takes ownership of a raw variant, the wrapper destroys it when dropped.
+    ///
+    /// # Safety
+    ///
+    /// `v` must have been initialized by a constructor of the API, or returned
+    /// by a function of the API passing ownership to the caller. Nothing else may
+    /// destroy it afterwards, including the wrapper it was taken from with
+    /// `as_sys`.
+    pub unsafe fn from_sys(v: sys::godot_variant) -> Variant {
+        LIVE_VARIANTS.fetch_add(1, Ordering::Relaxed);
+        Variant(v)
+    }
+
     // Returns a copy of the internal ffi representation of the variant.
     //
     // The variant remains owned by the rust wrapper and the receiver of
@@ -520,12 +533,29 @@ impl Variant {
 
 impl_basic_traits!(
     for Variant as godot_variant {
-        Drop => godot_variant_destroy;
-        Clone => godot_variant_new_copy;
         PartialEq => godot_variant_operator_equal;
     }
 );
 
+impl Drop for Variant {
+    fn drop(&mut self) {
+        LIVE_VARIANTS.fetch_sub(1, Ordering::Relaxed);
+        unsafe {
+            (get_api().godot_variant_destroy)(&mut self.0)
+        }
+    }
+}
+
+impl Clone for Variant {
+    fn clone(&self) -> Self {
+        unsafe {
+            let mut result = sys::godot_variant::default();
+            (get_api().godot_variant_new_copy)(&mut result, &self.0);
+            Variant::from_sys(result)
+        }
+    }
+}
+
 impl Default for Variant {
     fn default() -> Self { Variant::new() }
 }
@@ -644,4 +674,28 @@ godot_test!(
         assert!(v_m1.try_to_f64().is_none());
         assert!(v_m1.try_to_array().is_none());
     }
+
+    test_sys_access {
+        use ::sys_api;
+
+        let api = sys_api();
+        let mut v = Variant::from_i64(1);
+        unsafe {
+            (api.godot_variant_destroy)(v.as_mut_sys());
+            (api.godot_variant_new_int)(v.as_mut_sys(), 2);
+            assert_eq!((api.godot_variant_as_int)(v.as_sys()), 2);
+        }
+
+        let string = GodotString::from_str("raw");
+        let copy = unsafe {
+            let mut raw = sys::godot_variant::default();
+            (api.godot_variant_new_string)(&mut raw, string.as_sys());
+            Variant::from_sys(raw)
+        };
+        assert_eq!(copy.to_string(), "raw");
+
+        let raw = copy.forget();
+        let copy = unsafe { Variant::from_sys(raw) };
+        assert_eq!(copy.to_godot_string(), string);
+    }
 );
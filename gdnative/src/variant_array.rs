@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 
 /// A reference-counted `Variant` vector. Godot's generic array data type.
 /// Negative indices can be used to count from the right.
@@ -21,7 +22,7 @@ impl VariantArray {
     /// Returns a copy of the element at the given offset.
     pub fn get_val(&mut self, idx: i32) -> Variant {
         unsafe {
-            Variant((get_api().godot_array_get)(&self.0, idx))
+            Variant::from_sys((get_api().godot_array_get)(&self.0, idx))
         }
     }
 
@@ -90,7 +91,7 @@ impl VariantArray {
     /// Removes an element at the end of the array.
     pub fn pop(&mut self) -> Variant {
         unsafe {
-            Variant((get_api().godot_array_pop_back)(&mut   self.0))
+            Variant::from_sys((get_api().godot_array_pop_back)(&mut   self.0))
         }
     }
 
@@ -104,7 +105,7 @@ impl VariantArray {
     /// Removes an element at the front of the array.
     pub fn pop_front(&mut self) -> Variant {
         unsafe {
-            Variant((get_api().godot_array_pop_front)(&mut self.0))
+            Variant::from_sys((get_api().godot_array_pop_front)(&mut self.0))
         }
     }
 
@@ -202,8 +203,13 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for VariantArray {
+impl_sys_access!(for VariantArray as godot_array);
+
+impl ToVariant for VariantArray {
     fn to_variant(&self) -> Variant { Variant::from_array(self) }
+}
+
+impl FromVariant for VariantArray {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_array() }
 }
 
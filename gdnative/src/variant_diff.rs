@@ -0,0 +1,331 @@
+//! Readable printing and structural comparison of variants, for tests.
+//!
+//! Comparing nested dictionaries and arrays produced by the engine with `assert_eq!`
+//! only shows that they differ. `variant_diff` lists the differences with the path
+//! of each value, and `assert_variant_eq!` panics with that list:
+//!
+//! ```text
+//! variants differ:
+//!   $.items[2].name: expected "sword", got "shield"
+//!   $.stats.hp: missing, expected 10
+//!   $.stats.mp: unexpected 4
+//! ```
+//!
+//! Pool arrays are compared element by element like arrays. Other values are
+//! compared with the equality operator of the engine.
+
+use std::fmt::{self, Write};
+use Dictionary;
+use Variant;
+use VariantArray;
+use VariantType;
+
+/// The differences between two variants, see `variant_diff`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffReport {
+    pub differences: Vec<VariantDifference>,
+}
+
+/// A value that differs between two variants.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantDifference {
+    /// The path of the value from the root, like `$.items[2].name`.
+    pub path: String,
+    /// The expected value, `None` if the value is unexpected.
+    pub expected: Option<String>,
+    /// The actual value, `None` if the value is missing.
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for VariantDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.expected, &self.actual) {
+            (&Some(ref expected), &Some(ref actual)) => write!(f, "{}: expected {}, got {}", self.path, expected, actual),
+            (&Some(ref expected), &None) => write!(f, "{}: missing, expected {}", self.path, expected),
+            (&None, &Some(ref actual)) => write!(f, "{}: unexpected {}", self.path, actual),
+            (&None, &None) => write!(f, "{}", self.path),
+        }
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "variants differ:")?;
+        for difference in &self.differences {
+            write!(f, "\n  {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two variants recursively, returns `None` if they are equal.
+pub fn variant_diff(expected: &Variant, actual: &Variant) -> Option<DiffReport> {
+    let mut differences = Vec::new();
+    diff_values(&mut "$".to_string(), expected, actual, &mut differences);
+
+    if differences.is_empty() {
+        None
+    } else {
+        Some(DiffReport { differences })
+    }
+}
+
+/// Formats a variant on several lines, with the content of dictionaries and arrays
+/// indented.
+pub fn pretty_variant(value: &Variant) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, Some(0));
+    out
+}
+
+fn diff_values(path: &mut String, expected: &Variant, actual: &Variant, differences: &mut Vec<VariantDifference>) {
+    if let (Some(expected), Some(actual)) = (as_array(expected), as_array(actual)) {
+        return diff_arrays(path, &expected, &actual, differences);
+    }
+
+    if expected.get_type() == VariantType::Dictionary && actual.get_type() == VariantType::Dictionary {
+        return diff_dictionaries(path, &expected.to_dictionary(), &actual.to_dictionary(), differences);
+    }
+
+    if expected != actual {
+        let mut expected_str = compact_variant(expected);
+        let mut actual_str = compact_variant(actual);
+        if expected_str == actual_str {
+            expected_str = format!("{} ({:?})", expected_str, expected.get_type());
+            actual_str = format!("{} ({:?})", actual_str, actual.get_type());
+        }
+
+        differences.push(VariantDifference {
+            path: path.clone(),
+            expected: Some(expected_str),
+            actual: Some(actual_str),
+        });
+    }
+}
+
+fn diff_arrays(path: &mut String, expected: &VariantArray, actual: &VariantArray, differences: &mut Vec<VariantDifference>) {
+    let len = path.len();
+    for idx in 0..expected.len().max(actual.len()) {
+        write!(path, "[{}]", idx).unwrap();
+
+        if idx >= actual.len() {
+            differences.push(missing(path, expected.get_ref(idx)));
+        } else if idx >= expected.len() {
+            differences.push(unexpected(path, actual.get_ref(idx)));
+        } else {
+            diff_values(path, expected.get_ref(idx), actual.get_ref(idx), differences);
+        }
+
+        path.truncate(len);
+    }
+}
+
+fn diff_dictionaries(path: &mut String, expected: &Dictionary, actual: &Dictionary, differences: &mut Vec<VariantDifference>) {
+    let len = path.len();
+
+    let expected_keys = expected.keys();
+    for idx in 0..expected_keys.len() {
+        let key = expected_keys.get_ref(idx);
+        write_key(path, key);
+
+        if actual.contains(key) {
+            diff_values(path, expected.get_ref(key), actual.get_ref(key), differences);
+        } else {
+            differences.push(missing(path, expected.get_ref(key)));
+        }
+
+        path.truncate(len);
+    }
+
+    let actual_keys = actual.keys();
+    for idx in 0..actual_keys.len() {
+        let key = actual_keys.get_ref(idx);
+        if !expected.contains(key) {
+            write_key(path, key);
+            differences.push(unexpected(path, actual.get_ref(key)));
+            path.truncate(len);
+        }
+    }
+}
+
+fn missing(path: &str, expected: &Variant) -> VariantDifference {
+    VariantDifference {
+        path: path.to_string(),
+        expected: Some(compact_variant(expected)),
+        actual: None,
+    }
+}
+
+fn unexpected(path: &str, actual: &Variant) -> VariantDifference {
+    VariantDifference {
+        path: path.to_string(),
+        expected: None,
+        actual: Some(compact_variant(actual)),
+    }
+}
+
+// Keys that are identifiers are written like fields, other keys like indices.
+fn write_key(path: &mut String, key: &Variant) {
+    if key.get_type() == VariantType::GodotString {
+        let key = key.to_string();
+        if is_identifier(&key) {
+            write!(path, ".{}", key).unwrap();
+        } else {
+            write!(path, "[{:?}]", key).unwrap();
+        }
+    } else {
+        write!(path, "[{}]", compact_variant(key)).unwrap();
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_alphabetic() => chars.all(|c| c == '_' || c.is_alphanumeric()),
+        _ => false,
+    }
+}
+
+// Arrays and pool arrays.
+fn as_array(value: &Variant) -> Option<VariantArray> {
+    match value.get_type() {
+        VariantType::VariantArray
+        | VariantType::ByteArray
+        | VariantType::Int32Array
+        | VariantType::Float32Array
+        | VariantType::StringArray
+        | VariantType::Vector2Array
+        | VariantType::Vector3Array
+        | VariantType::ColorArray => Some(value.to_array()),
+        _ => None,
+    }
+}
+
+fn compact_variant(value: &Variant) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, None);
+    out
+}
+
+// Writes on one line if `indent` is `None`.
+fn write_value(out: &mut String, value: &Variant, indent: Option<usize>) {
+    if let Some(array) = as_array(value) {
+        let items: Vec<&Variant> = (0..array.len()).map(|idx| array.get_ref(idx)).collect();
+        return write_collection(out, '[', ']', items.len(), indent, |out, idx, indent| {
+            write_value(out, items[idx], indent);
+        });
+    }
+
+    match value.get_type() {
+        VariantType::Dictionary => {
+            let dictionary = value.to_dictionary();
+            let keys = dictionary.keys();
+            write_collection(out, '{', '}', keys.len() as usize, indent, |out, idx, indent| {
+                let key = keys.get_ref(idx as i32);
+                write_value(out, key, None);
+                out.push_str(": ");
+                write_value(out, dictionary.get_ref(key), indent);
+            });
+        }
+        VariantType::Nil => out.push_str("null"),
+        VariantType::Bool => write!(out, "{}", value.to_bool()).unwrap(),
+        VariantType::I64 => write!(out, "{}", value.to_i64()).unwrap(),
+        VariantType::F64 => write!(out, "{:?}", value.to_f64()).unwrap(),
+        VariantType::GodotString => write!(out, "{:?}", value.to_string()).unwrap(),
+        _ => write!(out, "{:?}", value).unwrap(),
+    }
+}
+
+fn write_collection<F>(out: &mut String, open: char, close: char, len: usize, indent: Option<usize>, mut write_item: F)
+where F: FnMut(&mut String, usize, Option<usize>) {
+    out.push(open);
+    if len == 0 {
+        out.push(close);
+        return;
+    }
+
+    for idx in 0..len {
+        match indent {
+            Some(indent) => {
+                out.push('\n');
+                push_indent(out, indent + 1);
+                write_item(out, idx, Some(indent + 1));
+                if idx + 1 < len {
+                    out.push(',');
+                }
+            }
+            None => {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                write_item(out, idx, None);
+            }
+        }
+    }
+
+    if let Some(indent) = indent {
+        out.push('\n');
+        push_indent(out, indent);
+    }
+    out.push(close);
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("  ");
+    }
+}
+
+#[test]
+fn variant_diff_identifiers() {
+    assert!(is_identifier("hp"));
+    assert!(is_identifier("_max_hp2"));
+    assert!(!is_identifier("2d"));
+    assert!(!is_identifier("max hp"));
+    assert!(!is_identifier(""));
+}
+
+godot_test!(test_variant_diff {
+    use ToVariant;
+
+    let mut stats = Dictionary::new();
+    stats.set(&Variant::from_str("hp"), &Variant::from_i64(10));
+    stats.set(&Variant::from_str("max hp"), &Variant::from_i64(10));
+
+    let mut items = VariantArray::new();
+    items.push(&Variant::from_str("sword"));
+    items.push(&Variant::from_i64(3));
+
+    let mut expected = Dictionary::new();
+    expected.set(&Variant::from_str("stats"), &Variant::from_dictionary(&stats));
+    expected.set(&Variant::from_str("items"), &Variant::from_array(&items));
+    let expected = Variant::from_dictionary(&expected);
+
+    assert!(variant_diff(&expected, &expected.clone()).is_none());
+    assert_variant_eq!(expected, expected.clone());
+
+    // Dictionaries and arrays are shared, the actual value needs new ones.
+    let mut stats = Dictionary::new();
+    stats.set(&Variant::from_str("max hp"), &Variant::from_i64(10));
+    stats.set(&Variant::from_str("mp"), &Variant::from_i64(4));
+
+    let mut items = VariantArray::new();
+    items.push(&Variant::from_str("sword"));
+    items.push(&ToVariant::to_variant(&3.0f64));
+    items.push(&Variant::new());
+
+    let mut actual = Dictionary::new();
+    actual.set(&Variant::from_str("stats"), &Variant::from_dictionary(&stats));
+    actual.set(&Variant::from_str("items"), &Variant::from_array(&items));
+    let actual = Variant::from_dictionary(&actual);
+
+    let report = variant_diff(&expected, &actual).unwrap();
+    let lines: Vec<String> = report.differences.iter().map(|difference| difference.to_string()).collect();
+    assert!(lines.contains(&"$.stats.hp: missing, expected 10".to_string()));
+    assert!(lines.contains(&"$.stats.mp: unexpected 4".to_string()));
+    assert!(lines.contains(&"$.items[1]: expected 3, got 3.0".to_string()));
+    assert!(lines.contains(&"$.items[2]: unexpected null".to_string()));
+    assert_eq!(lines.len(), 4);
+
+    assert_eq!(pretty_variant(&Variant::from_array(&items)), "[\n  \"sword\",\n  3.0,\n  null\n]");
+});
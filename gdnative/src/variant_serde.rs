@@ -0,0 +1,777 @@
+//! Serde support for variants, with the `serde` feature.
+//!
+//! `Variant`, `Dictionary`, `VariantArray`, `Color` and the math types of `geom`
+//! implement `Serialize` and `Deserialize`, so they can be saved with any serde
+//! format. In the other direction, `to_variant` and `from_variant` are a serde data
+//! format converting rust values to variants and back, which turns game data into
+//! values the engine can store with `File::store_var` or `ConfigFile`:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct SaveGame {
+//!     level: String,
+//!     position: Vector2,
+//!     inventory: HashMap<String, u32>,
+//! }
+//!
+//! file.store_var(gdnative::to_variant(&save)?);
+//! let save: SaveGame = gdnative::from_variant(&file.get_var())?;
+//! ```
+//!
+//! Structs and maps become dictionaries, sequences and tuples become arrays and unit
+//! enum variants become their name. The other enum variants become a dictionary with
+//! the name of the variant as only key, like in JSON. Math types become arrays of
+//! their components, or dictionaries for the structs of `geom`.
+//!
+//! Integers are read from floats without a fractional part, since the numbers parsed
+//! by the engine from JSON are all floats. The integers of the engine are signed 64-bit
+//! integers, serializing a `u64` above `i64::max_value()` is an error. Objects and
+//! `Rid`s can't be serialized.
+//!
+//! Deserializing a `Variant` builds it from the data model of serde, which has no
+//! math types: a `Vector2`, `Transform` or `Color` variant serialized and deserialized
+//! back to a `Variant` becomes the array or dictionary of its components, and the pool
+//! arrays become `VariantArray`s. The types are kept by deserializing to the typed
+//! fields instead, like `position: Vector2` in `SaveGame` above.
+
+use std::error::Error;
+use std::fmt;
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess,
+    IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::ser::{
+    self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use ByteArray;
+use Dictionary;
+use ToVariant;
+use Variant;
+use VariantArray;
+use VariantType;
+
+/// An error converting a value to or from a variant with serde.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariantSerdeError(String);
+
+impl fmt::Display for VariantSerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for VariantSerdeError {}
+
+impl ser::Error for VariantSerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        VariantSerdeError(msg.to_string())
+    }
+}
+
+impl de::Error for VariantSerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        VariantSerdeError(msg.to_string())
+    }
+}
+
+/// Converts a value to a variant, see the `variant_serde` module.
+pub fn to_variant<T: Serialize + ?Sized>(value: &T) -> Result<Variant, VariantSerdeError> {
+    value.serialize(VariantSerializer)
+}
+
+/// Converts a variant to a value, see the `variant_serde` module.
+pub fn from_variant<T: DeserializeOwned>(variant: &Variant) -> Result<T, VariantSerdeError> {
+    T::deserialize(VariantDeserializer(variant))
+}
+
+impl Serialize for Variant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.get_type() {
+            VariantType::Nil => serializer.serialize_unit(),
+            VariantType::Bool => serializer.serialize_bool(self.to_bool()),
+            VariantType::I64 => serializer.serialize_i64(self.to_i64()),
+            VariantType::F64 => serializer.serialize_f64(self.to_f64()),
+            VariantType::GodotString => serializer.serialize_str(&self.to_string()),
+            VariantType::NodePath => serializer.serialize_str(&self.to_node_path().to_string()),
+            VariantType::Vector2 => self.to_vector2().serialize(serializer),
+            VariantType::Rect2 => self.to_rect2().serialize(serializer),
+            VariantType::Vector3 => self.to_vector3().serialize(serializer),
+            VariantType::Transform2D => self.to_transform2d().serialize(serializer),
+            VariantType::Plane => self.to_plane().serialize(serializer),
+            VariantType::Quat => self.to_quat().serialize(serializer),
+            VariantType::Aabb => self.to_aabb().serialize(serializer),
+            VariantType::Basis => self.to_basis().serialize(serializer),
+            VariantType::Transform => self.to_transform().serialize(serializer),
+            VariantType::Color => self.to_color().serialize(serializer),
+            VariantType::Dictionary => self.to_dictionary().serialize(serializer),
+            VariantType::ByteArray => serializer.serialize_bytes(&self.to_byte_array().read()),
+            VariantType::VariantArray
+            | VariantType::Int32Array
+            | VariantType::Float32Array
+            | VariantType::StringArray
+            | VariantType::Vector2Array
+            | VariantType::Vector3Array
+            | VariantType::ColorArray => self.to_array().serialize(serializer),
+            ty @ VariantType::Rid | ty @ VariantType::Object => {
+                Err(ser::Error::custom(format!("{:?} variants can't be serialized", ty)))
+            }
+        }
+    }
+}
+
+impl Serialize for VariantArray {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len() as usize))?;
+        for element in self {
+            seq.serialize_element(element)?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for Dictionary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len() as usize))?;
+        for (key, value) in self {
+            map.serialize_entry(&key, &value)?;
+        }
+        map.end()
+    }
+}
+
+// Loses the math types and the types of the pool arrays, see the module.
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Variant, D::Error> {
+        deserializer.deserialize_any(VariantVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for VariantArray {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<VariantArray, D::Error> {
+        let variant = Variant::deserialize(deserializer)?;
+        match variant.get_type() {
+            VariantType::VariantArray => Ok(variant.to_array()),
+            ty => Err(de::Error::custom(format!("expected an array, found {:?}", ty))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Dictionary {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Dictionary, D::Error> {
+        let variant = Variant::deserialize(deserializer)?;
+        match variant.get_type() {
+            VariantType::Dictionary => Ok(variant.to_dictionary()),
+            ty => Err(de::Error::custom(format!("expected a dictionary, found {:?}", ty))),
+        }
+    }
+}
+
+struct VariantVisitor;
+
+impl<'de> Visitor<'de> for VariantVisitor {
+    type Value = Variant;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a value that can be stored in a variant")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Variant, E> {
+        Ok(Variant::from_bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Variant, E> {
+        Ok(Variant::from_i64(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Variant, E> {
+        if v > i64::max_value() as u64 {
+            return Err(E::invalid_value(de::Unexpected::Unsigned(v), &"a signed 64-bit integer"));
+        }
+        Ok(Variant::from_i64(v as i64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Variant, E> {
+        Ok(v.to_variant())
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Variant, E> {
+        Ok(Variant::from_str(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Variant, E> {
+        Ok(Variant::from_byte_array(&ByteArray::from_slice(v)))
+    }
+
+    fn visit_none<E>(self) -> Result<Variant, E> {
+        Ok(Variant::new())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Variant, D::Error> {
+        Variant::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Variant, E> {
+        Ok(Variant::new())
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Variant, D::Error> {
+        Variant::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Variant, A::Error> {
+        let mut array = VariantArray::new();
+        while let Some(element) = seq.next_element::<Variant>()? {
+            array.push(&element);
+        }
+        Ok(Variant::from_array(&array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Variant, A::Error> {
+        let mut dictionary = Dictionary::new();
+        while let Some((key, value)) = map.next_entry::<Variant, Variant>()? {
+            dictionary.set(&key, &value);
+        }
+        Ok(Variant::from_dictionary(&dictionary))
+    }
+}
+
+struct VariantSerializer;
+
+// Externally tagged enum variants, `{ "Variant": value }`.
+fn tagged(variant: &'static str, value: Variant) -> Variant {
+    let mut dictionary = Dictionary::new();
+    dictionary.set(&Variant::from_str(variant), &value);
+    Variant::from_dictionary(&dictionary)
+}
+
+impl Serializer for VariantSerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = ArraySerializer;
+    type SerializeTupleVariant = ArraySerializer;
+    type SerializeMap = DictionarySerializer;
+    type SerializeStruct = DictionarySerializer;
+    type SerializeStructVariant = DictionarySerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_i64(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_i64(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_i64(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_i64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_u64(v as u64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_u64(v as u64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_u64(v as u64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Variant, VariantSerdeError> {
+        if v > i64::max_value() as u64 {
+            return Err(ser::Error::custom(format!("{} doesn't fit in the signed 64-bit integers of the engine", v)));
+        }
+        Ok(Variant::from_i64(v as i64))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Variant, VariantSerdeError> {
+        Ok((v as f64).to_variant())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Variant, VariantSerdeError> {
+        Ok(v.to_variant())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_str(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_str(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_byte_array(&ByteArray::from_slice(v)))
+    }
+
+    fn serialize_none(self) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::new())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Variant, VariantSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::new())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _idx: u32, variant: &'static str) -> Result<Variant, VariantSerdeError> {
+        Ok(Variant::from_str(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Variant, VariantSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Variant, VariantSerdeError> {
+        Ok(tagged(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<ArraySerializer, VariantSerdeError> {
+        Ok(ArraySerializer { array: VariantArray::new(), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArraySerializer, VariantSerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ArraySerializer, VariantSerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<ArraySerializer, VariantSerdeError> {
+        Ok(ArraySerializer { array: VariantArray::new(), variant: Some(variant) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<DictionarySerializer, VariantSerdeError> {
+        Ok(DictionarySerializer { dictionary: Dictionary::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<DictionarySerializer, VariantSerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<DictionarySerializer, VariantSerdeError> {
+        Ok(DictionarySerializer { dictionary: Dictionary::new(), key: None, variant: Some(variant) })
+    }
+}
+
+struct ArraySerializer {
+    array: VariantArray,
+    variant: Option<&'static str>,
+}
+
+impl ArraySerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), VariantSerdeError> {
+        self.array.push(&to_variant(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Variant, VariantSerdeError> {
+        let array = Variant::from_array(&self.array);
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, array),
+            None => array,
+        })
+    }
+}
+
+impl SerializeSeq for ArraySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), VariantSerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+impl SerializeTuple for ArraySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), VariantSerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+impl SerializeTupleStruct for ArraySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), VariantSerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+impl SerializeTupleVariant for ArraySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), VariantSerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+struct DictionarySerializer {
+    dictionary: Dictionary,
+    key: Option<Variant>,
+    variant: Option<&'static str>,
+}
+
+impl DictionarySerializer {
+    fn finish(self) -> Result<Variant, VariantSerdeError> {
+        let dictionary = Variant::from_dictionary(&self.dictionary);
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, dictionary),
+            None => dictionary,
+        })
+    }
+}
+
+impl SerializeMap for DictionarySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), VariantSerdeError> {
+        self.key = Some(to_variant(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), VariantSerdeError> {
+        let key = self.key.take().ok_or_else(|| ser::Error::custom("value serialized before its key"))?;
+        self.dictionary.set(&key, &to_variant(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+impl SerializeStruct for DictionarySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), VariantSerdeError> {
+        self.dictionary.set(&Variant::from_str(key), &to_variant(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+impl SerializeStructVariant for DictionarySerializer {
+    type Ok = Variant;
+    type Error = VariantSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), VariantSerdeError> {
+        self.dictionary.set(&Variant::from_str(key), &to_variant(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Variant, VariantSerdeError> {
+        self.finish()
+    }
+}
+
+struct VariantDeserializer<'a>(&'a Variant);
+
+macro_rules! deserialize_integers {
+    ($($method:ident)*) => (
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VariantSerdeError> {
+                if self.0.get_type() == VariantType::F64 {
+                    let v = self.0.to_f64();
+                    if v.fract() == 0.0 && v >= i64::min_value() as f64 && v < i64::max_value() as f64 {
+                        return visitor.visit_i64(v as i64);
+                    }
+                }
+                self.deserialize_any(visitor)
+            }
+        )*
+    )
+}
+
+impl<'de, 'a> Deserializer<'de> for VariantDeserializer<'a> {
+    type Error = VariantSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VariantSerdeError> {
+        match self.0.get_type() {
+            VariantType::Nil => visitor.visit_unit(),
+            VariantType::Bool => visitor.visit_bool(self.0.to_bool()),
+            VariantType::I64 => visitor.visit_i64(self.0.to_i64()),
+            VariantType::F64 => visitor.visit_f64(self.0.to_f64()),
+            VariantType::GodotString => visitor.visit_string(self.0.to_string()),
+            VariantType::NodePath => visitor.visit_string(self.0.to_node_path().to_string()),
+            VariantType::Dictionary => visitor.visit_map(DictionaryAccess::new(self.0.to_dictionary())),
+            VariantType::ByteArray => visitor.visit_byte_buf(self.0.to_byte_array().read().to_vec()),
+            VariantType::VariantArray
+            | VariantType::Int32Array
+            | VariantType::Float32Array
+            | VariantType::StringArray
+            | VariantType::Vector2Array
+            | VariantType::Vector3Array
+            | VariantType::ColorArray => visitor.visit_seq(ArrayAccess { array: self.0.to_array(), next: 0 }),
+            VariantType::Rid | VariantType::Object => {
+                Err(de::Error::custom(format!("{:?} variants can't be deserialized", self.0.get_type())))
+            }
+            // Math types, in the form they are serialized to.
+            _ => {
+                let components = to_variant(self.0)?;
+                VariantDeserializer(&components).deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VariantSerdeError> {
+        if self.0.get_type() == VariantType::Nil {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, VariantSerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, VariantSerdeError> {
+        match self.0.get_type() {
+            VariantType::GodotString => visitor.visit_enum(self.0.to_string().into_deserializer()),
+            VariantType::Dictionary => {
+                let dictionary = self.0.to_dictionary();
+                let mut entries = dictionary.iter();
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => visitor.visit_enum(EnumDeserializer { variant, value }),
+                    _ => Err(de::Error::custom("expected a dictionary with a single key for an enum")),
+                }
+            }
+            ty => Err(de::Error::custom(format!("expected a string or a dictionary for an enum, found {:?}", ty))),
+        }
+    }
+
+    deserialize_integers! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct ArrayAccess {
+    array: VariantArray,
+    next: i32,
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess {
+    type Error = VariantSerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, VariantSerdeError> {
+        if self.next >= self.array.len() {
+            return Ok(None);
+        }
+        self.next += 1;
+        seed.deserialize(VariantDeserializer(self.array.get_ref(self.next - 1))).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.array.len() - self.next) as usize)
+    }
+}
+
+struct DictionaryAccess {
+    dictionary: Dictionary,
+    keys: VariantArray,
+    next: i32,
+}
+
+impl DictionaryAccess {
+    fn new(dictionary: Dictionary) -> Self {
+        let keys = dictionary.keys();
+        DictionaryAccess { dictionary, keys, next: 0 }
+    }
+}
+
+impl<'de> MapAccess<'de> for DictionaryAccess {
+    type Error = VariantSerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, VariantSerdeError> {
+        if self.next >= self.keys.len() {
+            return Ok(None);
+        }
+        self.next += 1;
+        seed.deserialize(VariantDeserializer(self.keys.get_ref(self.next - 1))).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, VariantSerdeError> {
+        let key = self.keys.get_ref(self.next - 1);
+        seed.deserialize(VariantDeserializer(self.dictionary.get_ref(key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.keys.len() - self.next) as usize)
+    }
+}
+
+struct EnumDeserializer {
+    variant: Variant,
+    value: Variant,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = VariantSerdeError;
+    type Variant = EnumValue;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, EnumValue), VariantSerdeError> {
+        let variant = seed.deserialize(VariantDeserializer(&self.variant))?;
+        Ok((variant, EnumValue(self.value)))
+    }
+}
+
+struct EnumValue(Variant);
+
+impl<'de> VariantAccess<'de> for EnumValue {
+    type Error = VariantSerdeError;
+
+    fn unit_variant(self) -> Result<(), VariantSerdeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, VariantSerdeError> {
+        seed.deserialize(VariantDeserializer(&self.0))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, VariantSerdeError> {
+        VariantDeserializer(&self.0).deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, VariantSerdeError> {
+        VariantDeserializer(&self.0).deserialize_map(visitor)
+    }
+}
+
+godot_test!(test_variant_serde {
+    use std::collections::HashMap;
+    use Color;
+    use Vector2;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Item {
+        Potion,
+        Key(u32),
+        Sword { damage: f32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct SaveGame {
+        level: String,
+        position: Vector2,
+        tint: Color,
+        inventory: Vec<Item>,
+        gold: HashMap<String, u32>,
+        checkpoint: Option<i64>,
+    }
+
+    let mut gold = HashMap::new();
+    gold.insert("bank".to_string(), 250);
+
+    let save = SaveGame {
+        level: "forest".to_string(),
+        position: Vector2::new(1.5, -2.0),
+        tint: Color::rgb(1.0, 0.5, 0.0),
+        inventory: vec![Item::Potion, Item::Key(3), Item::Sword { damage: 7.5 }],
+        gold,
+        checkpoint: None,
+    };
+
+    let variant = to_variant(&save).unwrap();
+    assert_eq!(variant.get_type(), VariantType::Dictionary);
+    let dictionary = variant.to_dictionary();
+    assert_eq!(dictionary.get_ref(&Variant::from_str("level")).to_string(), "forest");
+    let inventory = dictionary.get_ref(&Variant::from_str("inventory")).to_array();
+    assert_eq!(inventory.get_ref(0).to_string(), "Potion");
+
+    assert_eq!(from_variant::<SaveGame>(&variant).unwrap(), save);
+
+    // Numbers parsed from JSON are floats.
+    let mut parsed = Dictionary::new();
+    parsed.set(&Variant::from_str("Key"), &3.0f64.to_variant());
+    assert_eq!(from_variant::<Item>(&Variant::from_dictionary(&parsed)).unwrap(), Item::Key(3));
+
+    assert_eq!(from_variant::<Vector2>(&Variant::from_vector2(&Vector2::new(1.0, 2.0))).unwrap(), Vector2::new(1.0, 2.0));
+    assert!(from_variant::<u32>(&Variant::from_str("three")).is_err());
+
+    let max = i64::max_value() as u64;
+    assert_eq!(from_variant::<u64>(&to_variant(&max).unwrap()).unwrap(), max);
+
+    // The math types become their components.
+    let vector = Variant::from_vector2(&Vector2::new(1.0, 2.0));
+    let round_trip: Variant = from_variant(&vector).unwrap();
+    assert_ne!(round_trip.get_type(), VariantType::Vector2);
+});
+
+#[test]
+fn serialize_u64_out_of_range() {
+    assert!(to_variant(&(i64::max_value() as u64 + 1)).is_err());
+    assert!(to_variant(&u64::max_value()).is_err());
+}
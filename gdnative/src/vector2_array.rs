@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 use Vector2;
 
@@ -103,7 +104,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for Vector2Array {
+impl_sys_access!(for Vector2Array as godot_pool_vector2_array);
+
+impl ToVariant for Vector2Array {
     fn to_variant(&self) -> Variant { Variant::from_vector2_array(self) }
+}
+
+impl FromVariant for Vector2Array {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_vector2_array() }
 }
@@ -1,7 +1,8 @@
 use sys;
 use get_api;
 use Variant;
-use GodotType;
+use FromVariant;
+use ToVariant;
 use VariantArray;
 use Vector3;
 
@@ -103,7 +104,12 @@ impl_basic_traits!(
     }
 );
 
-impl GodotType for Vector3Array {
+impl_sys_access!(for Vector3Array as godot_pool_vector3_array);
+
+impl ToVariant for Vector3Array {
     fn to_variant(&self) -> Variant { Variant::from_vector3_array(self) }
+}
+
+impl FromVariant for Vector3Array {
     fn from_variant(variant: &Variant) -> Option<Self> { variant.try_to_vector3_array() }
 }
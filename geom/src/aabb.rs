@@ -1,9 +1,136 @@
+use {Plane, Vector3};
 
-use Vector3;
-
+/// An axis-aligned box, the `AABB` of the engine. The methods expect a positive
+/// size, see `abs`.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Aabb {
     pub position: Vector3,
     pub size: Vector3,
 }
+
+impl Aabb {
+    pub fn new(position: Vector3, size: Vector3) -> Self {
+        Aabb { position, size }
+    }
+
+    /// The corner opposite to `position`.
+    pub fn end(&self) -> Vector3 {
+        self.position + self.size
+    }
+
+    pub fn center(&self) -> Vector3 {
+        self.position + self.size * 0.5
+    }
+
+    /// The volume, the `get_area` of the engine.
+    pub fn volume(&self) -> f32 {
+        self.size.x * self.size.y * self.size.z
+    }
+
+    pub fn has_no_area(&self) -> bool {
+        self.size.x <= 0.0 || self.size.y <= 0.0 || self.size.z <= 0.0
+    }
+
+    pub fn has_no_surface(&self) -> bool {
+        self.size.x <= 0.0 && self.size.y <= 0.0 && self.size.z <= 0.0
+    }
+
+    /// Whether the box contains `point`, faces included.
+    pub fn has_point(&self, point: Vector3) -> bool {
+        let end = self.end();
+        point.x >= self.position.x && point.y >= self.position.y && point.z >= self.position.z
+            && point.x <= end.x && point.y <= end.y && point.z <= end.z
+    }
+
+    /// Whether the boxes overlap, touching faces excluded.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        let (end, other_end) = (self.end(), other.end());
+        self.position.x < other_end.x && other.position.x < end.x
+            && self.position.y < other_end.y && other.position.y < end.y
+            && self.position.z < other_end.z && other.position.z < end.z
+    }
+
+    /// Whether the box contains `other` entirely, faces included.
+    pub fn encloses(&self, other: &Aabb) -> bool {
+        let (end, other_end) = (self.end(), other.end());
+        other.position.x >= self.position.x && other.position.y >= self.position.y
+            && other.position.z >= self.position.z
+            && other_end.x <= end.x && other_end.y <= end.y && other_end.z <= end.z
+    }
+
+    /// The overlap of the boxes, `None` if they don't intersect.
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let position = self.position.max(other.position);
+        Some(Aabb::new(position, self.end().min(other.end()) - position))
+    }
+
+    /// The smallest box containing both.
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        let position = self.position.min(other.position);
+        Aabb::new(position, self.end().max(other.end()) - position)
+    }
+
+    /// The box grown by `by` on every side, shrunk if it is negative.
+    pub fn grow(&self, by: f32) -> Aabb {
+        let by = Vector3::new(by, by, by);
+        Aabb::new(self.position - by, self.size + by * 2.0)
+    }
+
+    /// The smallest box containing the box and `point`.
+    pub fn expand(&self, point: Vector3) -> Aabb {
+        self.merge(&Aabb::new(point, Vector3::zero()))
+    }
+
+    /// The same box with a positive size.
+    pub fn abs(&self) -> Aabb {
+        let end = self.end();
+        let position = self.position.min(end);
+        Aabb::new(position, self.position.max(end) - position)
+    }
+
+    /// Whether the plane goes through the box.
+    pub fn intersects_plane(&self, plane: &Plane) -> bool {
+        let (min, max) = (self.position, self.end());
+        let (mut over, mut under) = (false, false);
+        for &x in &[min.x, max.x] {
+            for &y in &[min.y, max.y] {
+                for &z in &[min.z, max.z] {
+                    if plane.distance_to(Vector3::new(x, y, z)) > 0.0 {
+                        over = true;
+                    } else {
+                        under = true;
+                    }
+                }
+            }
+        }
+        over && under
+    }
+}
+
+#[test]
+fn aabb_math() {
+    let unit = Aabb::new(Vector3::zero(), Vector3::new(1.0, 1.0, 1.0));
+    let other = Aabb::new(Vector3::new(0.5, 0.5, -1.0), Vector3::new(1.0, 1.0, 1.5));
+    assert_eq!(unit.center(), Vector3::new(0.5, 0.5, 0.5));
+    assert_eq!(other.volume(), 1.5);
+    assert!(unit.has_point(Vector3::new(1.0, 0.0, 0.5)));
+    assert!(!unit.has_point(Vector3::new(1.5, 0.0, 0.5)));
+
+    assert!(unit.intersects(&other));
+    assert!(!unit.intersects(&Aabb::new(Vector3::new(1.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0))));
+    assert_eq!(unit.intersection(&other), Some(Aabb::new(Vector3::new(0.5, 0.5, 0.0), Vector3::new(0.5, 0.5, 0.5))));
+    assert_eq!(unit.merge(&other), Aabb::new(Vector3::new(0.0, 0.0, -1.0), Vector3::new(1.5, 1.5, 2.0)));
+    assert!(unit.merge(&other).encloses(&unit));
+    assert!(!unit.encloses(&other));
+    assert_eq!(unit.grow(1.0), Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(3.0, 3.0, 3.0)));
+    assert_eq!(unit.expand(Vector3::new(2.0, 0.5, 0.5)), Aabb::new(Vector3::zero(), Vector3::new(2.0, 1.0, 1.0)));
+    assert_eq!(Aabb::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(-1.0, -1.0, -1.0)).abs(), unit);
+
+    assert!(unit.intersects_plane(&Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.5)));
+    assert!(!unit.intersects_plane(&Plane::new(Vector3::new(0.0, 1.0, 0.0), 2.0)));
+}
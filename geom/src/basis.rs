@@ -1,7 +1,239 @@
-use Vector3;
+use std::ops::Mul;
+use {Quat, Vector3, Vector3Godot};
 
+/// A 3x3 matrix, the rotation and scale of a `Transform`.
+///
+/// `elements` are the rows of the matrix, the axes are its columns. The methods
+/// follow the conventions of the `Basis` of the engine, including the YXZ order
+/// of the Euler angles.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Basis {
     pub elements: [Vector3; 3],
 }
+
+impl Default for Basis {
+    fn default() -> Self {
+        Basis::identity()
+    }
+}
+
+impl Basis {
+    pub fn identity() -> Self {
+        Basis::from_diagonal(Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    /// A basis scaling by `scale`.
+    pub fn from_diagonal(scale: Vector3) -> Self {
+        Basis {
+            elements: [
+                Vector3::new(scale.x, 0.0, 0.0),
+                Vector3::new(0.0, scale.y, 0.0),
+                Vector3::new(0.0, 0.0, scale.z),
+            ],
+        }
+    }
+
+    /// A basis with the given axes, its columns.
+    pub fn from_axes(x: Vector3, y: Vector3, z: Vector3) -> Self {
+        Basis {
+            elements: [
+                Vector3::new(x.x, y.x, z.x),
+                Vector3::new(x.y, y.y, z.y),
+                Vector3::new(x.z, y.z, z.z),
+            ],
+        }
+    }
+
+    /// The rotation by `phi` radians around `axis`, which must be normalized.
+    pub fn from_axis_angle(axis: Vector3, phi: f32) -> Self {
+        let (sin, cos) = phi.sin_cos();
+        let t = 1.0 - cos;
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        Basis {
+            elements: [
+                Vector3::new(x * x + cos * (1.0 - x * x), x * y * t - z * sin, x * z * t + y * sin),
+                Vector3::new(x * y * t + z * sin, y * y + cos * (1.0 - y * y), y * z * t - x * sin),
+                Vector3::new(x * z * t - y * sin, y * z * t + x * sin, z * z + cos * (1.0 - z * z)),
+            ],
+        }
+    }
+
+    /// The rotation by the Euler angles `euler`, in radians, applied in the YXZ
+    /// order: first around Z, then X, then Y.
+    pub fn from_euler(euler: Vector3) -> Self {
+        let (sin, cos) = euler.x.sin_cos();
+        let x = Basis::from_rows(1.0, 0.0, 0.0, 0.0, cos, -sin, 0.0, sin, cos);
+        let (sin, cos) = euler.y.sin_cos();
+        let y = Basis::from_rows(cos, 0.0, sin, 0.0, 1.0, 0.0, -sin, 0.0, cos);
+        let (sin, cos) = euler.z.sin_cos();
+        let z = Basis::from_rows(cos, -sin, 0.0, sin, cos, 0.0, 0.0, 0.0, 1.0);
+        y * x * z
+    }
+
+    /// The rotation of a unit quaternion.
+    pub fn from_quat(quat: Quat) -> Self {
+        let s = 2.0 / (quat.i * quat.i + quat.j * quat.j + quat.k * quat.k + quat.r * quat.r);
+        let (xs, ys, zs) = (quat.i * s, quat.j * s, quat.k * s);
+        let (wx, wy, wz) = (quat.r * xs, quat.r * ys, quat.r * zs);
+        let (xx, xy, xz) = (quat.i * xs, quat.i * ys, quat.i * zs);
+        let (yy, yz, zz) = (quat.j * ys, quat.j * zs, quat.k * zs);
+        Basis::from_rows(
+            1.0 - (yy + zz), xy - wz, xz + wy,
+            xy + wz, 1.0 - (xx + zz), yz - wx,
+            xz - wy, yz + wx, 1.0 - (xx + yy),
+        )
+    }
+
+    fn from_rows(xx: f32, xy: f32, xz: f32, yx: f32, yy: f32, yz: f32, zx: f32, zy: f32, zz: f32) -> Self {
+        Basis {
+            elements: [Vector3::new(xx, xy, xz), Vector3::new(yx, yy, yz), Vector3::new(zx, zy, zz)],
+        }
+    }
+
+    /// The X axis, the first column.
+    pub fn x(&self) -> Vector3 {
+        Vector3::new(self.elements[0].x, self.elements[1].x, self.elements[2].x)
+    }
+
+    /// The Y axis, the second column.
+    pub fn y(&self) -> Vector3 {
+        Vector3::new(self.elements[0].y, self.elements[1].y, self.elements[2].y)
+    }
+
+    /// The Z axis, the third column.
+    pub fn z(&self) -> Vector3 {
+        Vector3::new(self.elements[0].z, self.elements[1].z, self.elements[2].z)
+    }
+
+    /// The length of the axes.
+    pub fn scale(&self) -> Vector3 {
+        Vector3::new(self.x().length(), self.y().length(), self.z().length())
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let e = &self.elements;
+        e[0].x * (e[1].y * e[2].z - e[2].y * e[1].z)
+            - e[1].x * (e[0].y * e[2].z - e[2].y * e[0].z)
+            + e[2].x * (e[0].y * e[1].z - e[1].y * e[0].z)
+    }
+
+    pub fn transposed(&self) -> Self {
+        Basis::from_axes(self.elements[0], self.elements[1], self.elements[2])
+    }
+
+    /// The inverse matrix. Like in the engine, the result is undefined if the
+    /// determinant is 0.
+    pub fn inverse(&self) -> Self {
+        let e = &self.elements;
+        let co = [
+            e[1].y * e[2].z - e[1].z * e[2].y,
+            e[1].z * e[2].x - e[1].x * e[2].z,
+            e[1].x * e[2].y - e[1].y * e[2].x,
+        ];
+        let s = 1.0 / (e[0].x * co[0] + e[0].y * co[1] + e[0].z * co[2]);
+        Basis::from_rows(
+            co[0] * s, (e[0].z * e[2].y - e[0].y * e[2].z) * s, (e[0].y * e[1].z - e[0].z * e[1].y) * s,
+            co[1] * s, (e[0].x * e[2].z - e[0].z * e[2].x) * s, (e[0].z * e[1].x - e[0].x * e[1].z) * s,
+            co[2] * s, (e[0].y * e[2].x - e[0].x * e[2].y) * s, (e[0].x * e[1].y - e[0].y * e[1].x) * s,
+        )
+    }
+
+    /// The axes made orthogonal and of length 1, with the Gram-Schmidt process.
+    pub fn orthonormalized(&self) -> Self {
+        let x = self.x().normalized();
+        let y = (self.y() - x * x.dot(self.y())).normalized();
+        let z = (self.z() - x * x.dot(self.z()) - y * y.dot(self.z())).normalized();
+        Basis::from_axes(x, y, z)
+    }
+
+    /// The Euler angles of the rotation, in the YXZ order, see `from_euler`. The basis
+    /// must be a rotation, see `orthonormalized`.
+    pub fn to_euler(&self) -> Vector3 {
+        use std::f32::consts::FRAC_PI_2;
+
+        let e = &self.elements;
+        let m12 = e[1].z;
+        if m12 >= 1.0 {
+            Vector3::new(-FRAC_PI_2, -(-e[0].y).atan2(e[0].x), 0.0)
+        } else if m12 <= -1.0 {
+            Vector3::new(FRAC_PI_2, -(-e[0].y).atan2(e[0].x), 0.0)
+        } else {
+            Vector3::new((-m12).asin(), e[0].z.atan2(e[2].z), e[1].x.atan2(e[1].y))
+        }
+    }
+
+    /// The unit quaternion of the rotation. The basis must be a rotation, see
+    /// `orthonormalized`.
+    pub fn to_quat(&self) -> Quat {
+        let m = &self.elements;
+        let m = [[m[0].x, m[0].y, m[0].z], [m[1].x, m[1].y, m[1].z], [m[2].x, m[2].y, m[2].z]];
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let mut q = [0.0; 4];
+
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt();
+            q[3] = s * 0.5;
+            let s = 0.5 / s;
+            q[0] = (m[2][1] - m[1][2]) * s;
+            q[1] = (m[0][2] - m[2][0]) * s;
+            q[2] = (m[1][0] - m[0][1]) * s;
+        } else {
+            let i = if m[0][0] < m[1][1] {
+                if m[1][1] < m[2][2] { 2 } else { 1 }
+            } else if m[0][0] < m[2][2] {
+                2
+            } else {
+                0
+            };
+            let j = (i + 1) % 3;
+            let k = (i + 2) % 3;
+
+            let s = (m[i][i] - m[j][j] - m[k][k] + 1.0).sqrt();
+            q[i] = s * 0.5;
+            let s = 0.5 / s;
+            q[3] = (m[k][j] - m[j][k]) * s;
+            q[j] = (m[j][i] + m[i][j]) * s;
+            q[k] = (m[k][i] + m[i][k]) * s;
+        }
+
+        Quat::quaternion(q[0], q[1], q[2], q[3])
+    }
+
+    /// The vector transformed by the matrix.
+    pub fn xform(&self, v: Vector3) -> Vector3 {
+        Vector3::new(self.elements[0].dot(v), self.elements[1].dot(v), self.elements[2].dot(v))
+    }
+
+    /// The vector transformed by the transposed matrix, which is the inverse of
+    /// rotations.
+    pub fn xform_inv(&self, v: Vector3) -> Vector3 {
+        Vector3::new(self.x().dot(v), self.y().dot(v), self.z().dot(v))
+    }
+
+    /// Whether the elements are equal within the tolerance of the engine.
+    pub fn approx_eq(&self, other: &Basis) -> bool {
+        (0..3).all(|idx| self.elements[idx].approx_eq(other.elements[idx]))
+    }
+}
+
+/// The basis applying `rhs`, then `self`.
+impl Mul<Basis> for Basis {
+    type Output = Basis;
+
+    fn mul(self, rhs: Basis) -> Basis {
+        let row = |row: Vector3| Vector3::new(rhs.x().dot(row), rhs.y().dot(row), rhs.z().dot(row));
+        Basis {
+            elements: [row(self.elements[0]), row(self.elements[1]), row(self.elements[2])],
+        }
+    }
+}
+
+impl Mul<Vector3> for Basis {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        self.xform(rhs)
+    }
+}
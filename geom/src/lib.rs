@@ -1,9 +1,16 @@
 pub extern crate euclid;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
 
 mod plane;
 mod aabb;
 mod basis;
 mod transform;
+mod transform2d;
+mod quat;
+mod rect2;
+mod vector;
 
 pub type Vector3 = euclid::Vector3D<f32>;
 pub type Vector2 = euclid::Vector2D<f32>;
@@ -15,3 +22,7 @@ pub use plane::Plane;
 pub use aabb::Aabb;
 pub use basis::Basis;
 pub use transform::Transform;
+pub use transform2d::Transform2DGodot;
+pub use quat::QuatGodot;
+pub use rect2::Rect2Godot;
+pub use vector::{Vector2Godot, Vector3Godot};
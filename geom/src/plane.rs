@@ -1,8 +1,126 @@
-use Vector3;
+use vector::CMP_EPSILON;
+use {Vector3, Vector3Godot};
 
+/// The plane of the points `p` where `normal.dot(p) == d`. The points with a
+/// positive distance are over the plane.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Plane {
     pub normal: Vector3,
     pub d: f32,
 }
+
+impl Plane {
+    pub fn new(normal: Vector3, d: f32) -> Self {
+        Plane { normal, d }
+    }
+
+    /// The plane through `point`, with a normalized `normal`.
+    pub fn from_point_normal(point: Vector3, normal: Vector3) -> Self {
+        Plane { normal, d: normal.dot(point) }
+    }
+
+    /// The plane through the three points, facing the side from which they are
+    /// clockwise, like in the engine.
+    pub fn from_points(a: Vector3, b: Vector3, c: Vector3) -> Self {
+        let normal = (a - c).cross(a - b).normalized();
+        Plane { normal, d: normal.dot(a) }
+    }
+
+    /// The plane with a normal of length 1, or the zero plane if the normal is zero.
+    pub fn normalized(&self) -> Self {
+        let length = self.normal.length();
+        if length == 0.0 {
+            Plane::new(Vector3::zero(), 0.0)
+        } else {
+            Plane::new(self.normal / length, self.d / length)
+        }
+    }
+
+    /// The point of the plane closest to the origin.
+    pub fn center(&self) -> Vector3 {
+        self.normal * self.d
+    }
+
+    /// The signed distance of `point`, positive over the plane.
+    pub fn distance_to(&self, point: Vector3) -> f32 {
+        self.normal.dot(point) - self.d
+    }
+
+    pub fn is_point_over(&self, point: Vector3) -> bool {
+        self.distance_to(point) > CMP_EPSILON
+    }
+
+    /// Whether `point` is at most `epsilon` away from the plane.
+    pub fn has_point(&self, point: Vector3, epsilon: f32) -> bool {
+        self.distance_to(point).abs() <= epsilon
+    }
+
+    /// The orthogonal projection of `point` on the plane.
+    pub fn project(&self, point: Vector3) -> Vector3 {
+        point - self.normal * self.distance_to(point)
+    }
+
+    /// The intersection with the ray from `from` in the direction `dir`, `None` if
+    /// they are parallel or the plane is behind.
+    pub fn intersects_ray(&self, from: Vector3, dir: Vector3) -> Option<Vector3> {
+        let den = self.normal.dot(dir);
+        if den.abs() <= CMP_EPSILON {
+            return None;
+        }
+
+        let dist = (self.normal.dot(from) - self.d) / den;
+        if dist > CMP_EPSILON {
+            return None;
+        }
+        Some(from - dir * dist)
+    }
+
+    /// The intersection with the segment from `begin` to `end`, `None` if the
+    /// segment doesn't cross the plane.
+    pub fn intersects_segment(&self, begin: Vector3, end: Vector3) -> Option<Vector3> {
+        let segment = begin - end;
+        let den = self.normal.dot(segment);
+        if den.abs() <= CMP_EPSILON {
+            return None;
+        }
+
+        let dist = (self.normal.dot(begin) - self.d) / den;
+        if dist < -CMP_EPSILON || dist > 1.0 + CMP_EPSILON {
+            return None;
+        }
+        Some(begin - segment * dist)
+    }
+
+    /// Whether the normals and distances are equal within the tolerance of the engine.
+    pub fn approx_eq(&self, other: &Plane) -> bool {
+        self.normal.approx_eq(other.normal) && (self.d - other.d).abs() < CMP_EPSILON
+    }
+}
+
+#[test]
+fn plane_math() {
+    let floor = Plane::from_points(
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(1.0, 1.0, 0.0),
+        Vector3::new(0.0, 1.0, 1.0),
+    );
+    assert!(floor.approx_eq(&Plane::new(Vector3::new(0.0, 1.0, 0.0), 1.0)));
+    assert_eq!(Plane::new(Vector3::new(0.0, 2.0, 0.0), 2.0).normalized(), floor);
+    assert_eq!(floor.center(), Vector3::new(0.0, 1.0, 0.0));
+    assert_eq!(floor.distance_to(Vector3::new(5.0, 3.0, 2.0)), 2.0);
+    assert!(floor.is_point_over(Vector3::new(0.0, 1.5, 0.0)));
+    assert!(floor.has_point(Vector3::new(7.0, 1.0, -3.0), CMP_EPSILON));
+    assert_eq!(floor.project(Vector3::new(5.0, 3.0, 2.0)), Vector3::new(5.0, 1.0, 2.0));
+
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    assert_eq!(floor.intersects_ray(Vector3::new(2.0, 4.0, 0.0), down), Some(Vector3::new(2.0, 1.0, 0.0)));
+    assert_eq!(floor.intersects_ray(Vector3::new(2.0, 0.0, 0.0), down), None);
+    assert_eq!(floor.intersects_ray(Vector3::new(2.0, 4.0, 0.0), Vector3::new(1.0, 0.0, 0.0)), None);
+    assert_eq!(
+        floor.intersects_segment(Vector3::new(0.0, 3.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+        Some(Vector3::new(0.0, 1.0, 0.0))
+    );
+    assert_eq!(floor.intersects_segment(Vector3::new(0.0, 3.0, 0.0), Vector3::new(0.0, 2.0, 0.0)), None);
+}
@@ -0,0 +1,34 @@
+use {Basis, Quat, Vector3};
+
+/// The methods of the `Quat` of the engine which euclid doesn't provide, with the
+/// same semantics.
+///
+/// `i`, `j`, `k` and `r` are the `x`, `y`, `z` and `w` of the engine, and the
+/// product `a * b` of the engine is `a.pre_rotate(&b)`.
+pub trait QuatGodot: Sized {
+    /// The rotation by the Euler angles `euler`, in radians, in the YXZ order of
+    /// `Basis::from_euler`.
+    fn from_euler(euler: Vector3) -> Self;
+
+    /// The Euler angles of the rotation, in the YXZ order.
+    fn to_euler(&self) -> Vector3;
+
+    /// The vector rotated, the quaternion must be normalized.
+    fn xform(&self, v: Vector3) -> Vector3;
+}
+
+impl QuatGodot for Quat {
+    fn from_euler(euler: Vector3) -> Self {
+        Basis::from_euler(euler).to_quat()
+    }
+
+    fn to_euler(&self) -> Vector3 {
+        Basis::from_quat(*self).to_euler()
+    }
+
+    fn xform(&self, v: Vector3) -> Vector3 {
+        let u = self.vector_part();
+        let t = u.cross(v) * 2.0;
+        v + t * self.r + u.cross(t)
+    }
+}
@@ -0,0 +1,131 @@
+use {Rect2, Vector2};
+use euclid::{Point2D, Size2D};
+
+/// The methods of the `Rect2` of the engine which euclid doesn't provide, with the
+/// same semantics. Euclid provides `intersects`, `intersection`, `contains_rect`,
+/// `translate` and `is_empty`.
+///
+/// The rectangles must have a positive size, see `abs`.
+pub trait Rect2Godot: Sized {
+    /// The corner opposite to `origin`.
+    fn end(&self) -> Vector2;
+
+    fn center(&self) -> Vector2;
+
+    fn area(&self) -> f32;
+
+    fn has_no_area(&self) -> bool;
+
+    /// Whether the rectangle contains `point`, the left and top edges included, the
+    /// right and bottom edges excluded.
+    fn has_point(&self, point: Vector2) -> bool;
+
+    /// Whether the rectangle contains `other` entirely, edges included.
+    fn encloses(&self, other: &Self) -> bool;
+
+    /// The smallest rectangle containing both. Unlike `union` of euclid, empty
+    /// rectangles aren't ignored.
+    fn merge(&self, other: &Self) -> Self;
+
+    /// The intersection, or an empty rectangle if they don't intersect.
+    fn clip(&self, other: &Self) -> Self;
+
+    /// The rectangle grown by `by` on every side, shrunk if it is negative.
+    fn grow(&self, by: f32) -> Self;
+
+    fn grow_individual(&self, left: f32, top: f32, right: f32, bottom: f32) -> Self;
+
+    /// The smallest rectangle containing the rectangle and `point`.
+    fn expand(&self, point: Vector2) -> Self;
+
+    /// The same rectangle with a positive size.
+    fn abs(&self) -> Self;
+}
+
+impl Rect2Godot for Rect2 {
+    fn end(&self) -> Vector2 {
+        Vector2::new(self.origin.x + self.size.width, self.origin.y + self.size.height)
+    }
+
+    fn center(&self) -> Vector2 {
+        Vector2::new(self.origin.x + self.size.width * 0.5, self.origin.y + self.size.height * 0.5)
+    }
+
+    fn area(&self) -> f32 {
+        self.size.width * self.size.height
+    }
+
+    fn has_no_area(&self) -> bool {
+        self.size.width <= 0.0 || self.size.height <= 0.0
+    }
+
+    fn has_point(&self, point: Vector2) -> bool {
+        let end = self.end();
+        point.x >= self.origin.x && point.y >= self.origin.y && point.x < end.x && point.y < end.y
+    }
+
+    fn encloses(&self, other: &Self) -> bool {
+        let (end, other_end) = (self.end(), other.end());
+        other.origin.x >= self.origin.x
+            && other.origin.y >= self.origin.y
+            && other_end.x <= end.x
+            && other_end.y <= end.y
+    }
+
+    fn merge(&self, other: &Self) -> Self {
+        let (end, other_end) = (self.end(), other.end());
+        let origin = Point2D::new(self.origin.x.min(other.origin.x), self.origin.y.min(other.origin.y));
+        Rect2::new(
+            origin,
+            Size2D::new(end.x.max(other_end.x) - origin.x, end.y.max(other_end.y) - origin.y),
+        )
+    }
+
+    fn clip(&self, other: &Self) -> Self {
+        self.intersection(other).unwrap_or_else(Rect2::zero)
+    }
+
+    fn grow(&self, by: f32) -> Self {
+        self.grow_individual(by, by, by, by)
+    }
+
+    fn grow_individual(&self, left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Rect2::new(
+            Point2D::new(self.origin.x - left, self.origin.y - top),
+            Size2D::new(self.size.width + left + right, self.size.height + top + bottom),
+        )
+    }
+
+    fn expand(&self, point: Vector2) -> Self {
+        self.merge(&Rect2::new(point.to_point(), Size2D::zero()))
+    }
+
+    fn abs(&self) -> Self {
+        Rect2::new(
+            Point2D::new(
+                self.origin.x + self.size.width.min(0.0),
+                self.origin.y + self.size.height.min(0.0),
+            ),
+            Size2D::new(self.size.width.abs(), self.size.height.abs()),
+        )
+    }
+}
+
+#[test]
+fn rect2_math() {
+    let rect = Rect2::new(Point2D::new(0.0, 0.0), Size2D::new(4.0, 2.0));
+    assert_eq!(rect.end(), Vector2::new(4.0, 2.0));
+    assert_eq!(rect.area(), 8.0);
+    assert!(rect.has_point(Vector2::new(0.0, 0.0)));
+    assert!(!rect.has_point(Vector2::new(4.0, 1.0)));
+    assert!(rect.encloses(&Rect2::new(Point2D::new(2.0, 0.0), Size2D::new(2.0, 2.0))));
+    assert!(!rect.encloses(&Rect2::new(Point2D::new(3.0, 0.0), Size2D::new(2.0, 2.0))));
+
+    let other = Rect2::new(Point2D::new(3.0, 1.0), Size2D::new(2.0, 2.0));
+    assert_eq!(rect.merge(&other), Rect2::new(Point2D::new(0.0, 0.0), Size2D::new(5.0, 3.0)));
+    assert_eq!(rect.clip(&other), Rect2::new(Point2D::new(3.0, 1.0), Size2D::new(1.0, 1.0)));
+    assert_eq!(rect.clip(&rect.translate(&Vector2::new(5.0, 0.0))), Rect2::zero());
+    assert_eq!(rect.grow(1.0), Rect2::new(Point2D::new(-1.0, -1.0), Size2D::new(6.0, 4.0)));
+    assert_eq!(rect.expand(Vector2::new(-1.0, 3.0)), Rect2::new(Point2D::new(-1.0, 0.0), Size2D::new(5.0, 3.0)));
+    assert_eq!(Rect2::new(Point2D::new(4.0, 2.0), Size2D::new(-4.0, -2.0)).abs(), rect);
+}
@@ -1,8 +1,116 @@
+use std::ops::Mul;
 use {Vector3, Basis};
 
+/// A 3D transform: a `Basis` for the rotation and scale, followed by a translation
+/// to `origin`. The methods follow the `Transform` of the engine.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Transform {
     pub basis: Basis,
     pub origin: Vector3,
 }
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform::new(Basis::identity(), Vector3::zero())
+    }
+
+    pub fn new(basis: Basis, origin: Vector3) -> Self {
+        Transform { basis, origin }
+    }
+
+    /// The transform translated by `offset`, in the parent space.
+    pub fn translated(&self, offset: Vector3) -> Self {
+        Transform::new(self.basis, self.origin + offset)
+    }
+
+    /// The inverse of a transform without scale.
+    pub fn inverse(&self) -> Self {
+        let basis = self.basis.transposed();
+        Transform::new(basis, basis.xform(-self.origin))
+    }
+
+    /// The inverse of any transform with a non-zero determinant.
+    pub fn affine_inverse(&self) -> Self {
+        let basis = self.basis.inverse();
+        Transform::new(basis, basis.xform(-self.origin))
+    }
+
+    /// The point transformed to the parent space.
+    pub fn xform(&self, point: Vector3) -> Vector3 {
+        self.basis.xform(point) + self.origin
+    }
+
+    /// The point of the parent space transformed back, for transforms without scale.
+    /// See `affine_inverse` for the others.
+    pub fn xform_inv(&self, point: Vector3) -> Vector3 {
+        self.basis.xform_inv(point - self.origin)
+    }
+
+    /// Whether the elements are equal within the tolerance of the engine.
+    pub fn approx_eq(&self, other: &Transform) -> bool {
+        use Vector3Godot;
+        self.basis.approx_eq(&other.basis) && self.origin.approx_eq(other.origin)
+    }
+}
+
+/// The transform applying `rhs`, then `self`.
+impl Mul<Transform> for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform::new(self.basis * rhs.basis, self.xform(rhs.origin))
+    }
+}
+
+/// The point transformed, like `xform`.
+impl Mul<Vector3> for Transform {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        self.xform(rhs)
+    }
+}
+
+#[test]
+fn transform_math() {
+    use std::f32::consts::FRAC_PI_2;
+    use {Quat, QuatGodot, Transform2D, Transform2DGodot, Vector2, Vector2Godot, Vector3Godot};
+
+    let euler = Vector3::new(0.3, -1.2, 2.5);
+    let basis = Basis::from_euler(euler);
+    assert!(basis.to_euler().approx_eq(euler));
+    assert!(Basis::from_quat(basis.to_quat()).approx_eq(&basis));
+    assert!(Quat::from_euler(euler).to_euler().approx_eq(euler));
+    assert!((basis.determinant() - 1.0).abs() < 0.0001);
+    assert!((basis * basis.inverse()).approx_eq(&Basis::identity()));
+
+    // Rotations are counterclockwise around the axis.
+    let yaw = Basis::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+    assert!(yaw.xform(Vector3::new(1.0, 0.0, 0.0)).approx_eq(Vector3::new(0.0, 0.0, -1.0)));
+    assert!(yaw.to_quat().xform(Vector3::new(1.0, 0.0, 0.0)).approx_eq(Vector3::new(0.0, 0.0, -1.0)));
+    assert!(Basis::from_euler(Vector3::new(0.0, FRAC_PI_2, 0.0)).approx_eq(&yaw));
+
+    let transform = Transform::new(yaw, Vector3::new(1.0, 2.0, 3.0));
+    let point = Vector3::new(4.0, 5.0, 6.0);
+    assert!(transform.xform_inv(transform.xform(point)).approx_eq(point));
+    assert!((transform * transform.inverse()).approx_eq(&Transform::identity()));
+    let scaled = Transform::new(yaw * Basis::from_diagonal(Vector3::new(2.0, 3.0, 4.0)), Vector3::new(1.0, 0.0, 0.0));
+    assert!(scaled.basis.scale().approx_eq(Vector3::new(2.0, 3.0, 4.0)));
+    assert!(scaled.affine_inverse().xform(scaled.xform(point)).approx_eq(point));
+
+    let transform2d = Transform2D::from_rotation_origin(FRAC_PI_2, Vector2::new(1.0, 2.0));
+    assert!(transform2d.xform(Vector2::new(1.0, 0.0)).approx_eq(Vector2::new(1.0, 3.0)));
+    assert!(transform2d.xform_inv(Vector2::new(1.0, 3.0)).approx_eq(Vector2::new(1.0, 0.0)));
+    assert!((transform2d.rotation() - FRAC_PI_2).abs() < 0.0001);
+    let scaled2d = transform2d.pre_mul(&Transform2D::create_scale(2.0, 3.0));
+    assert!(scaled2d.affine_inverse().xform(scaled2d.xform(Vector2::new(4.0, 5.0))).approx_eq(Vector2::new(4.0, 5.0)));
+    assert!(scaled2d.xform(Vector2::new(1.0, 0.0)).approx_eq(transform2d.xform(Vector2::new(2.0, 0.0))));
+}
@@ -0,0 +1,100 @@
+use {Transform2D, Vector2};
+
+/// The methods of the `Transform2D` of the engine which euclid doesn't provide, with
+/// the same semantics.
+///
+/// `m11`, `m12` are the X axis, `m21`, `m22` the Y axis and `m31`, `m32` the origin.
+/// `transform_point` and `transform_vector` of euclid are the `xform` and
+/// `basis_xform` of the engine, and the product `a * b` of the engine is
+/// `a.pre_mul(&b)`.
+pub trait Transform2DGodot: Sized {
+    /// The rotation by `rotation` radians, followed by a translation to `origin`.
+    fn from_rotation_origin(rotation: f32, origin: Vector2) -> Self;
+
+    fn x_axis(&self) -> Vector2;
+
+    fn y_axis(&self) -> Vector2;
+
+    fn origin(&self) -> Vector2;
+
+    /// The rotation of the X axis, in radians.
+    fn rotation(&self) -> f32;
+
+    /// The length of the axes.
+    fn scale(&self) -> Vector2;
+
+    /// The point transformed to the parent space.
+    fn xform(&self, point: Vector2) -> Vector2;
+
+    /// The point of the parent space transformed back, for transforms without scale.
+    fn xform_inv(&self, point: Vector2) -> Vector2;
+
+    /// The vector rotated and scaled, without the translation.
+    fn basis_xform(&self, v: Vector2) -> Vector2;
+
+    /// The vector rotated back, for transforms without scale.
+    fn basis_xform_inv(&self, v: Vector2) -> Vector2;
+
+    /// The inverse of a transform without scale.
+    fn orthonormal_inverse(&self) -> Self;
+
+    /// The inverse of any transform with a non-zero determinant. Unlike `inverse`
+    /// of euclid, a zero determinant isn't checked.
+    fn affine_inverse(&self) -> Self;
+}
+
+impl Transform2DGodot for Transform2D {
+    fn from_rotation_origin(rotation: f32, origin: Vector2) -> Self {
+        let (sin, cos) = rotation.sin_cos();
+        Transform2D::row_major(cos, sin, -sin, cos, origin.x, origin.y)
+    }
+
+    fn x_axis(&self) -> Vector2 {
+        Vector2::new(self.m11, self.m12)
+    }
+
+    fn y_axis(&self) -> Vector2 {
+        Vector2::new(self.m21, self.m22)
+    }
+
+    fn origin(&self) -> Vector2 {
+        Vector2::new(self.m31, self.m32)
+    }
+
+    fn rotation(&self) -> f32 {
+        self.m12.atan2(self.m11)
+    }
+
+    fn scale(&self) -> Vector2 {
+        Vector2::new(self.x_axis().length(), self.y_axis().length())
+    }
+
+    fn xform(&self, point: Vector2) -> Vector2 {
+        self.basis_xform(point) + self.origin()
+    }
+
+    fn xform_inv(&self, point: Vector2) -> Vector2 {
+        self.basis_xform_inv(point - self.origin())
+    }
+
+    fn basis_xform(&self, v: Vector2) -> Vector2 {
+        self.x_axis() * v.x + self.y_axis() * v.y
+    }
+
+    fn basis_xform_inv(&self, v: Vector2) -> Vector2 {
+        Vector2::new(self.x_axis().dot(v), self.y_axis().dot(v))
+    }
+
+    fn orthonormal_inverse(&self) -> Self {
+        let basis = Transform2D::row_major(self.m11, self.m21, self.m12, self.m22, 0.0, 0.0);
+        let origin = basis.basis_xform(-self.origin());
+        Transform2D::row_major(basis.m11, basis.m12, basis.m21, basis.m22, origin.x, origin.y)
+    }
+
+    fn affine_inverse(&self) -> Self {
+        let idet = 1.0 / (self.m11 * self.m22 - self.m12 * self.m21);
+        let basis = Transform2D::row_major(self.m22 * idet, -self.m12 * idet, -self.m21 * idet, self.m11 * idet, 0.0, 0.0);
+        let origin = basis.basis_xform(-self.origin());
+        Transform2D::row_major(basis.m11, basis.m12, basis.m21, basis.m22, origin.x, origin.y)
+    }
+}
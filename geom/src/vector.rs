@@ -0,0 +1,290 @@
+use {Vector2, Vector3};
+
+// The tolerances of the engine, see `math_defs.h`.
+pub(crate) const CMP_EPSILON: f32 = 0.00001;
+const UNIT_EPSILON: f32 = 0.001;
+
+// Like `Math::is_equal_approx`, the tolerance grows with the magnitude of `a`.
+fn is_equal_approx(a: f32, b: f32) -> bool {
+    if a == b {
+        return true;
+    }
+    let tolerance = (CMP_EPSILON * a.abs()).max(CMP_EPSILON);
+    (a - b).abs() < tolerance
+}
+
+// Like `Math::stepify`, a step of 0 leaves the value unchanged.
+fn stepify(value: f32, step: f32) -> f32 {
+    if step != 0.0 {
+        (value / step + 0.5).floor() * step
+    } else {
+        value
+    }
+}
+
+/// The methods of the `Vector2` of the engine which euclid doesn't provide, with the
+/// same semantics. Euclid provides `dot`, `cross`, `length`, `lerp`, `abs`, `floor`,
+/// `ceil`, `round` and the operators with vectors and scalars.
+///
+/// The angles are in radians.
+pub trait Vector2Godot: Sized {
+    fn length_squared(self) -> f32;
+
+    /// The vector scaled to a length of 1, or the zero vector unchanged. Unlike
+    /// `normalize`, which divides by zero.
+    fn normalized(self) -> Self;
+
+    fn is_normalized(self) -> bool;
+
+    fn distance_to(self, to: Self) -> f32;
+
+    fn distance_squared_to(self, to: Self) -> f32;
+
+    /// The angle from the positive X axis, between -π and π.
+    fn angle(self) -> f32;
+
+    /// The signed angle to `to`, between -π and π.
+    fn angle_to(self, to: Self) -> f32;
+
+    /// The vector rotated by `phi`.
+    fn rotated(self, phi: f32) -> Self;
+
+    /// The spherical interpolation to `to` by `t`, from 0 to 1. Both vectors must be
+    /// normalized.
+    fn slerp(self, to: Self, t: f32) -> Self;
+
+    /// The projection on `onto`.
+    fn project(self, onto: Self) -> Self;
+
+    /// The vector sliding along the plane of `normal`.
+    fn slide(self, normal: Self) -> Self;
+
+    /// The mirror image of the vector, across the line of `normal`.
+    fn reflect(self, normal: Self) -> Self;
+
+    /// The vector bouncing off the plane of `normal`.
+    fn bounce(self, normal: Self) -> Self;
+
+    /// Every component rounded to the nearest multiple of the matching component of
+    /// `step`.
+    fn snapped(self, step: Self) -> Self;
+
+    /// The vector rotated by 90 degrees, `(y, -x)`.
+    fn tangent(self) -> Self;
+
+    /// Whether the components are equal within the tolerance of the engine.
+    fn approx_eq(self, other: Self) -> bool;
+}
+
+impl Vector2Godot for Vector2 {
+    fn length_squared(self) -> f32 {
+        self.square_length()
+    }
+
+    fn normalized(self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self / length
+        }
+    }
+
+    fn is_normalized(self) -> bool {
+        (self.length_squared() - 1.0).abs() < UNIT_EPSILON
+    }
+
+    fn distance_to(self, to: Self) -> f32 {
+        (to - self).length()
+    }
+
+    fn distance_squared_to(self, to: Self) -> f32 {
+        (to - self).square_length()
+    }
+
+    fn angle(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+
+    fn angle_to(self, to: Self) -> f32 {
+        self.cross(to).atan2(self.dot(to))
+    }
+
+    fn rotated(self, phi: f32) -> Self {
+        let (sin, cos) = phi.sin_cos();
+        Vector2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    fn slerp(self, to: Self, t: f32) -> Self {
+        self.rotated(self.angle_to(to) * t)
+    }
+
+    fn project(self, onto: Self) -> Self {
+        onto * (self.dot(onto) / onto.square_length())
+    }
+
+    fn slide(self, normal: Self) -> Self {
+        self - normal * self.dot(normal)
+    }
+
+    fn reflect(self, normal: Self) -> Self {
+        normal * (2.0 * self.dot(normal)) - self
+    }
+
+    fn bounce(self, normal: Self) -> Self {
+        -self.reflect(normal)
+    }
+
+    fn snapped(self, step: Self) -> Self {
+        Vector2::new(stepify(self.x, step.x), stepify(self.y, step.y))
+    }
+
+    fn tangent(self) -> Self {
+        Vector2::new(self.y, -self.x)
+    }
+
+    fn approx_eq(self, other: Self) -> bool {
+        is_equal_approx(self.x, other.x) && is_equal_approx(self.y, other.y)
+    }
+}
+
+/// The methods of the `Vector3` of the engine which euclid doesn't provide, with the
+/// same semantics, see `Vector2Godot`.
+pub trait Vector3Godot: Sized {
+    fn length_squared(self) -> f32;
+
+    /// The vector scaled to a length of 1, or the zero vector unchanged.
+    fn normalized(self) -> Self;
+
+    fn is_normalized(self) -> bool;
+
+    fn distance_to(self, to: Self) -> f32;
+
+    fn distance_squared_to(self, to: Self) -> f32;
+
+    /// The unsigned angle to `to`, between 0 and π.
+    fn angle_to(self, to: Self) -> f32;
+
+    /// The vector rotated by `phi` around `axis`, which must be normalized.
+    fn rotated(self, axis: Self, phi: f32) -> Self;
+
+    /// The spherical interpolation to `to` by `t`, from 0 to 1. Both vectors must be
+    /// normalized.
+    fn slerp(self, to: Self, t: f32) -> Self;
+
+    /// The projection on `onto`.
+    fn project(self, onto: Self) -> Self;
+
+    /// The vector sliding along the plane of `normal`.
+    fn slide(self, normal: Self) -> Self;
+
+    /// The mirror image of the vector, across the line of `normal`.
+    fn reflect(self, normal: Self) -> Self;
+
+    /// The vector bouncing off the plane of `normal`.
+    fn bounce(self, normal: Self) -> Self;
+
+    /// Every component rounded to the nearest multiple of the matching component of
+    /// `step`.
+    fn snapped(self, step: Self) -> Self;
+
+    /// Whether the components are equal within the tolerance of the engine.
+    fn approx_eq(self, other: Self) -> bool;
+}
+
+impl Vector3Godot for Vector3 {
+    fn length_squared(self) -> f32 {
+        self.square_length()
+    }
+
+    fn normalized(self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self / length
+        }
+    }
+
+    fn is_normalized(self) -> bool {
+        (self.length_squared() - 1.0).abs() < UNIT_EPSILON
+    }
+
+    fn distance_to(self, to: Self) -> f32 {
+        (to - self).length()
+    }
+
+    fn distance_squared_to(self, to: Self) -> f32 {
+        (to - self).square_length()
+    }
+
+    fn angle_to(self, to: Self) -> f32 {
+        self.cross(to).length().atan2(self.dot(to))
+    }
+
+    // Rodrigues' rotation formula, which gives the same result as rotating with the
+    // basis built from the axis in the engine.
+    fn rotated(self, axis: Self, phi: f32) -> Self {
+        let (sin, cos) = phi.sin_cos();
+        self * cos + axis.cross(self) * sin + axis * (axis.dot(self) * (1.0 - cos))
+    }
+
+    fn slerp(self, to: Self, t: f32) -> Self {
+        let theta = self.angle_to(to);
+        self.rotated(self.cross(to).normalized(), theta * t)
+    }
+
+    fn project(self, onto: Self) -> Self {
+        onto * (self.dot(onto) / onto.square_length())
+    }
+
+    fn slide(self, normal: Self) -> Self {
+        self - normal * self.dot(normal)
+    }
+
+    fn reflect(self, normal: Self) -> Self {
+        normal * (2.0 * self.dot(normal)) - self
+    }
+
+    fn bounce(self, normal: Self) -> Self {
+        -self.reflect(normal)
+    }
+
+    fn snapped(self, step: Self) -> Self {
+        Vector3::new(stepify(self.x, step.x), stepify(self.y, step.y), stepify(self.z, step.z))
+    }
+
+    fn approx_eq(self, other: Self) -> bool {
+        is_equal_approx(self.x, other.x)
+            && is_equal_approx(self.y, other.y)
+            && is_equal_approx(self.z, other.z)
+    }
+}
+
+#[test]
+fn vector_math() {
+    use std::f32::consts::FRAC_PI_2;
+
+    assert_eq!(Vector2::zero().normalized(), Vector2::zero());
+    assert!(Vector2::new(3.0, 4.0).normalized().approx_eq(Vector2::new(0.6, 0.8)));
+    assert!(Vector2::new(3.0, 4.0).normalized().is_normalized());
+    assert!((Vector2::new(1.0, 0.0).angle_to(Vector2::new(0.0, 1.0)) - FRAC_PI_2).abs() < CMP_EPSILON);
+    assert!((Vector2::new(0.0, 1.0).angle_to(Vector2::new(1.0, 0.0)) + FRAC_PI_2).abs() < CMP_EPSILON);
+    assert!(Vector2::new(1.0, 0.0).slerp(Vector2::new(0.0, 1.0), 0.5)
+        .approx_eq(Vector2::new(0.5f32.sqrt(), 0.5f32.sqrt())));
+    assert_eq!(Vector2::new(1.0, -1.0).bounce(Vector2::new(0.0, 1.0)), Vector2::new(1.0, 1.0));
+    assert_eq!(Vector2::new(1.0, -1.0).reflect(Vector2::new(0.0, 1.0)), Vector2::new(-1.0, -1.0));
+    assert_eq!(Vector2::new(1.0, -1.0).slide(Vector2::new(0.0, 1.0)), Vector2::new(1.0, 0.0));
+    assert_eq!(Vector2::new(7.4, -2.6).snapped(Vector2::new(2.0, 0.0)), Vector2::new(8.0, -2.6));
+
+    assert_eq!(Vector3::zero().normalized(), Vector3::zero());
+    assert_eq!(Vector3::new(1.0, 2.0, 2.0).length_squared(), 9.0);
+    assert!((Vector3::new(1.0, 0.0, 0.0).angle_to(Vector3::new(0.0, 0.0, -1.0)) - FRAC_PI_2).abs() < CMP_EPSILON);
+    assert!(Vector3::new(1.0, 0.0, 0.0).rotated(Vector3::new(0.0, 1.0, 0.0), FRAC_PI_2)
+        .approx_eq(Vector3::new(0.0, 0.0, -1.0)));
+    assert!(Vector3::new(1.0, 0.0, 0.0).slerp(Vector3::new(0.0, 1.0, 0.0), 0.5)
+        .approx_eq(Vector3::new(0.5f32.sqrt(), 0.5f32.sqrt(), 0.0)));
+    assert_eq!(Vector3::new(1.0, -1.0, 2.0).bounce(Vector3::new(0.0, 1.0, 0.0)), Vector3::new(1.0, 1.0, 2.0));
+    assert_eq!(Vector3::new(0.3, 1.2, -0.6).snapped(Vector3::new(0.5, 0.5, 0.5)), Vector3::new(0.5, 1.0, -0.5));
+    assert!(!Vector3::new(1.0, 0.0, 0.0).approx_eq(Vector3::new(1.001, 0.0, 0.0)));
+}
@@ -9,6 +9,8 @@ func _ready():
 
     if gdn.initialize():
         status = gdn.call_native("standard_varcall", "run_tests", [])
+        if OS.get_cmdline_args().has("--bench"):
+            gdn.call_native("standard_varcall", "run_benchmarks", [])
         gdn.terminate()
     else:
         print(" -- Could not load the gdnative library.")
@@ -11,19 +11,122 @@ pub extern "C" fn run_tests(
     status &= gdnative::test_string();
 
     status &= gdnative::test_dictionary();
+    status &= gdnative::test_dictionary_iter();
     // status &= gdnative::test_dictionary_clone_clear();
 
     status &= gdnative::test_array();
+    status &= gdnative::test_array_iter();
+    status &= gdnative::test_pool_access();
+    status &= gdnative::test_pool_stream();
+    status &= gdnative::test_node_path_property();
+    status &= gdnative::test_node_access();
+    status &= gdnative::test_resource_property();
     // status &= gdnative::test_array_clone_clear();
 
     status &= gdnative::test_variant_nil();
     status &= gdnative::test_variant_i64();
+    status &= gdnative::test_sys_access();
+    status &= gdnative::test_variant_diff();
+    status &= gdnative::test_variant_serde();
+    status &= gdnative::test_fuzz_corpus();
+
+    status &= gdnative::test_checked_get_node();
+    status &= gdnative::test_checked_connect();
+    status &= gdnative::test_return_value();
+    status &= gdnative::test_method_args();
+    status &= gdnative::test_call();
+    status &= gdnative::test_native_function();
+    status &= gdnative::test_collection_conversions();
+    status &= gdnative::test_from_variant_error();
+    status &= gdnative::test_adhoc_script();
+    status &= gdnative::test_signal_relay();
+    status &= gdnative::test_features();
+    status &= gdnative::test_input_bindings();
+    status &= gdnative::test_function_state();
+    status &= gdnative::test_property_observer();
+    status &= gdnative::test_deferred_init();
+    status &= gdnative::test_object_debug();
 
     status &= test_constructor();
+    status &= test_wrap_method_optional_args();
+    status &= test_wrap_method_owner();
+    status &= test_wrap_method_borrow();
+    status &= test_wrap_method_errors();
+    status &= test_derive_variant();
+    status &= test_derive_export();
 
     gdnative::Variant::from_bool(status).forget()
 }
 
+/// Prints the time taken by operations whose cost matters in call-heavy code.
+///
+/// Run with `godot --path test/project -- --bench`.
+#[no_mangle]
+pub extern "C" fn run_benchmarks(
+    _data: *mut gdnative::libc::c_void,
+    _args: *mut gdnative::sys::godot_array
+) -> gdnative::sys::godot_variant {
+    bench_string_conversion();
+    bench_variadic_calls();
+
+    gdnative::Variant::new().forget()
+}
+
+fn bench<R, F: Fn() -> R>(name: &str, iterations: u32, f: F) {
+    use std::time::Instant;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let _ = f();
+    }
+    let elapsed = start.elapsed();
+    let nanos = elapsed.as_secs() as f64 * 1e9 + elapsed.subsec_nanos() as f64;
+    println!("    {:<40} {:>10.1} ns/iter", name, nanos / iterations as f64);
+}
+
+fn bench_string_conversion() {
+    println!(" -- bench_string_conversion");
+
+    use gdnative::GodotString;
+
+    const ITERATIONS: u32 = 1_000_000;
+
+    // Non-ASCII strings go through the UTF-8 decoding of the engine, like every
+    // string did before the small string fast path.
+    bench("from_str, utf-8 decoding", ITERATIONS, || GodotString::from_str("pösition"));
+    bench("from_str, small ascii", ITERATIONS, || GodotString::from_str("position"));
+    bench("from_static", ITERATIONS, || GodotString::from_static("position"));
+}
+
+fn bench_variadic_calls() {
+    println!(" -- bench_variadic_calls");
+
+    use gdnative::{GodotObject, GodotString, Object, Reference, ToVariant, VariantArray};
+
+    const ITERATIONS: u32 = 100_000;
+
+    // The arrays of pointers to the arguments are taken from the pool of the thread,
+    // only the first call allocates one.
+    let reference = Reference::new();
+    let this = reference.as_sys();
+    let signal = GodotString::from_str("hit");
+    unsafe { Object::from_sys(this) }.add_user_signal(signal.new_ref(), VariantArray::new());
+
+    let args = [1.to_variant(), 2.5.to_variant(), GodotString::from_str("critical").to_variant()];
+    bench("emit_signal, no arguments", ITERATIONS, || {
+        unsafe { Object::from_sys(this) }.emit_signal(signal.new_ref(), &[])
+    });
+    bench("emit_signal, 3 arguments", ITERATIONS, || {
+        unsafe { Object::from_sys(this) }.emit_signal(signal.new_ref(), &args)
+    });
+
+    let method = GodotString::from_str("has_signal");
+    let method_args = [signal.to_variant()];
+    bench("call, 1 argument", ITERATIONS, || {
+        unsafe { Object::from_sys(this) }.call(method.new_ref(), &method_args)
+    });
+}
+
 fn test_constructor() -> bool {
     println!(" -- test_constructor");
 
@@ -41,6 +144,361 @@ fn test_constructor() -> bool {
     return true;
 }
 
+fn test_wrap_method_optional_args() -> bool {
+    println!(" -- test_wrap_method_optional_args");
+
+    use std::ptr;
+    use gdnative::*;
+
+    struct Foo {
+        header: NativeInstanceHeader,
+    }
+
+    impl NativeClass for Foo {
+        type UserData = LocalCellData<Foo>;
+        fn class_name() -> &'static str { "Foo" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+    }
+
+    impl Foo {
+        fn sum(&mut self, a: i64, b: i64, rest: &[Variant]) -> i64 {
+            a + b + rest.iter().map(|v| v.to_i64()).sum::<i64>()
+        }
+
+        fn name(&mut self) -> &str {
+            Self::class_name()
+        }
+    }
+
+    // Borrowed return values are copied into engine types by the wrapper.
+    let _ = godot_wrap_method!(Foo, fn name(&mut self) -> &str);
+
+    let method = godot_wrap_method!(
+        Foo,
+        fn sum(&mut self, a: i64, #[opt] b: i64 = 10, #[rest] rest: &[Variant]) -> i64
+    );
+
+    let foo = LocalCellData::new(Foo { header: NativeInstanceHeader { this: ptr::null_mut() } });
+    let call = |args: &[Variant]| -> Variant {
+        let mut sys_args: Vec<sys::godot_variant> = args.iter().map(|arg| arg.to_sys()).collect();
+        let mut ptrs: Vec<*mut sys::godot_variant> = sys_args.iter_mut().map(|arg| arg as *mut _).collect();
+        unsafe {
+            let ret = method(
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &foo as *const _ as *mut _,
+                ptrs.len() as _,
+                ptrs.as_mut_ptr(),
+            );
+            Variant::from_sys_variant(&ret).unwrap()
+        }
+    };
+
+    let ok = call(&[Variant::from_i64(1)]).to_i64() == 11
+        && call(&[Variant::from_i64(1), Variant::from_i64(2)]).to_i64() == 3
+        && call(&[Variant::from_i64(1), Variant::from_i64(2), Variant::from_i64(3), Variant::from_i64(4)]).to_i64() == 10
+        && call(&[]).is_nil();
+
+    let arguments = godot_method_arguments!(
+        Foo,
+        fn sum(&mut self, a: i64, #[opt] b: i64 = 10, #[rest] rest: &[Variant]) -> i64
+    );
+    let ok = ok
+        && arguments.iter().map(|arg| arg.name).collect::<Vec<_>>() == ["a", "b"]
+        && arguments[0].default.is_none()
+        && arguments[1].default == Some(Variant::from_i64(10));
+
+    if !ok {
+        godot_error!("   !!! Test test_wrap_method_optional_args failed");
+    }
+
+    ok
+}
+
+fn test_wrap_method_owner() -> bool {
+    println!(" -- test_wrap_method_owner");
+
+    use std::ptr;
+    use gdnative::*;
+
+    struct Foo {
+        header: NativeInstanceHeader,
+    }
+
+    impl NativeClass for Foo {
+        type UserData = LocalCellData<Foo>;
+        fn class_name() -> &'static str { "Foo" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+    }
+
+    impl Foo {
+        fn owner_id(&mut self, owner: Node, offset: i64) -> i64 {
+            owner.get_instance_id() + offset
+        }
+    }
+
+    let method = godot_wrap_method!(
+        Foo,
+        fn owner_id(&mut self, #[owner] owner: Node, offset: i64) -> i64
+    );
+
+    let node = Node::new();
+    let foo = LocalCellData::new(Foo { header: NativeInstanceHeader { this: ptr::null_mut() } });
+    let mut arg = Variant::from_i64(1).to_sys();
+    let mut args = [&mut arg as *mut _];
+    let ret = unsafe {
+        let ret = method(
+            node.to_sys(),
+            ptr::null_mut(),
+            &foo as *const _ as *mut _,
+            1,
+            args.as_mut_ptr(),
+        );
+        Variant::from_sys_variant(&ret).unwrap()
+    };
+
+    let ok = ret.to_i64() == node.get_instance_id() + 1;
+    unsafe { node.free(); }
+
+    if !ok {
+        godot_error!("   !!! Test test_wrap_method_owner failed");
+    }
+
+    ok
+}
+
+fn test_wrap_method_borrow() -> bool {
+    println!(" -- test_wrap_method_borrow");
+
+    use std::ptr;
+    use gdnative::*;
+
+    struct Foo {
+        header: NativeInstanceHeader,
+        value: i64,
+    }
+
+    impl NativeClass for Foo {
+        type UserData = LocalCellData<Foo>;
+        fn class_name() -> &'static str { "Foo" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+    }
+
+    impl Foo {
+        fn get_value(&self) -> i64 {
+            self.value
+        }
+
+        fn set_value(&mut self, value: i64) {
+            self.value = value;
+        }
+    }
+
+    let get_value = godot_wrap_method!(Foo, fn get_value(&self) -> i64);
+    let set_value = godot_wrap_method!(Foo, fn set_value(&mut self, value: i64) -> ());
+
+    let foo = LocalCellData::new(Foo { header: NativeInstanceHeader { this: ptr::null_mut() }, value: 42 });
+    let user_data = &foo as *const _ as *mut _;
+    let mut arg = Variant::from_i64(1).to_sys();
+    let mut args = [&mut arg as *mut _];
+
+    // Simulates a method of the instance running while the engine calls back into
+    // the script.
+    let (shared, exclusive) = foo.map(|_| unsafe {
+        let shared = get_value(ptr::null_mut(), ptr::null_mut(), user_data, 0, ptr::null_mut());
+        let exclusive = set_value(ptr::null_mut(), ptr::null_mut(), user_data, 1, args.as_mut_ptr());
+        (Variant::from_sys_variant(&shared).unwrap(), Variant::from_sys_variant(&exclusive).unwrap())
+    }).unwrap();
+
+    let ok = shared.to_i64() == 42 && exclusive.is_nil() && foo.map(|foo| foo.value) == Ok(42);
+
+    if !ok {
+        godot_error!("   !!! Test test_wrap_method_borrow failed");
+    }
+
+    ok
+}
+
+fn test_wrap_method_errors() -> bool {
+    println!(" -- test_wrap_method_errors");
+
+    use std::ptr;
+    use gdnative::*;
+
+    struct Returned {
+        header: NativeInstanceHeader,
+    }
+
+    impl NativeClass for Returned {
+        type UserData = LocalCellData<Returned>;
+        fn class_name() -> &'static str { "Returned" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+    }
+
+    struct Logged {
+        header: NativeInstanceHeader,
+    }
+
+    impl NativeClass for Logged {
+        type UserData = LocalCellData<Logged>;
+        fn class_name() -> &'static str { "Logged" }
+        fn get_header(&self) -> &NativeInstanceHeader { &self.header }
+        fn method_errors() -> MethodErrors { MethodErrors::Log }
+    }
+
+    impl Returned {
+        fn parse(&self, text: String) -> Result<i64, String> {
+            text.parse().map_err(|_| format!("not a number: {}", text))
+        }
+    }
+
+    impl Logged {
+        fn parse(&self, text: String) -> Result<i64, String> {
+            text.parse().map_err(|_| format!("not a number: {}", text))
+        }
+    }
+
+    let returned = LocalCellData::new(Returned { header: NativeInstanceHeader { this: ptr::null_mut() } });
+    let logged = LocalCellData::new(Logged { header: NativeInstanceHeader { this: ptr::null_mut() } });
+
+    type Method = unsafe extern "C" fn(
+        *mut sys::godot_object,
+        *mut libc::c_void,
+        *mut libc::c_void,
+        libc::c_int,
+        *mut *mut sys::godot_variant,
+    ) -> sys::godot_variant;
+
+    let call = |method: Method, user_data: *mut libc::c_void, text: &str| unsafe {
+        let mut arg = Variant::from_str(text).to_sys();
+        let mut args = [&mut arg as *mut _];
+        let ret = method(ptr::null_mut(), ptr::null_mut(), user_data, 1, args.as_mut_ptr());
+        Variant::from_sys_variant(&ret).unwrap()
+    };
+    let returned_parse: Method = godot_wrap_method!(Returned, fn parse(&self, text: String) -> Result<i64, String>);
+    let logged_parse: Method = godot_wrap_method!(Logged, fn parse(&self, text: String) -> Result<i64, String>);
+    let returned = &returned as *const _ as *mut _;
+    let logged = &logged as *const _ as *mut _;
+
+    let error = call(returned_parse, returned, "x").try_to_dictionary()
+        .map(|dictionary| dictionary.get(&Variant::from_str("error")).to_string());
+
+    let ok = call(returned_parse, returned, "3").to_i64() == 3
+        && error == Some("not a number: x".to_string())
+        && call(logged_parse, logged, "4").to_i64() == 4
+        && call(logged_parse, logged, "x").is_nil();
+
+    if !ok {
+        godot_error!("   !!! Test test_wrap_method_errors failed");
+    }
+
+    ok
+}
+
+fn test_derive_variant() -> bool {
+    println!(" -- test_derive_variant");
+
+    use gdnative::*;
+
+    #[derive(Debug, PartialEq, ToVariant, FromVariant)]
+    struct Hit {
+        damage: i64,
+        position: Vector2,
+        critical: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, ToVariant, FromVariant)]
+    struct Pair<T>(T, T);
+
+    #[derive(Debug, PartialEq, ToVariant, FromVariant)]
+    enum State {
+        Idle,
+        Running = 4,
+    }
+
+    #[derive(Debug, PartialEq, ToVariant, FromVariant)]
+    enum Command {
+        Move(Vector2),
+        Attack { target: String, hits: Vec<Hit> },
+        Stop,
+    }
+
+    let hit = Hit { damage: 3, position: Vector2::new(1.0, 2.0), critical: None };
+    let dictionary = hit.to_variant().try_to_dictionary().unwrap();
+
+    // A missing `Option` field is `None`.
+    let mut partial = Dictionary::new();
+    partial.set(&Variant::from_str("damage"), &Variant::from_i64(3));
+    partial.set(&Variant::from_str("position"), &Variant::from_vector2(&Vector2::new(1.0, 2.0)));
+
+    let command = Command::Attack { target: "boss".to_string(), hits: vec![hit] };
+
+    // The error names the field that failed to convert.
+    let mut wrong = Dictionary::new();
+    wrong.set(&Variant::from_str("Attack"), &Variant::from_dictionary(&partial));
+    let error = Command::try_from_variant(&Variant::from_dictionary(&wrong)).err().map(|err| err.path);
+
+    let ok = dictionary.len() == 3
+        && dictionary.get(&Variant::from_str("damage")).to_i64() == 3
+        && Hit::from_variant(&Variant::from_dictionary(&partial)).map_or(false, |hit| hit.critical.is_none())
+        && Hit::from_variant(&Variant::from_i64(3)).is_none()
+        && Pair::from_variant(&Pair(1i64, 2).to_variant()) == Some(Pair(1, 2))
+        && State::Running.to_variant().to_i64() == 4
+        && State::from_variant(&Variant::from_i64(0)) == Some(State::Idle)
+        && State::from_variant(&Variant::from_i64(1)).is_none()
+        && Command::from_variant(&command.to_variant()) == Some(command)
+        && error == Some(vec![VariantPath::Key("Attack".to_string()), VariantPath::Field("target")])
+        && Command::from_variant(&Command::Stop.to_variant()) == Some(Command::Stop)
+        && Command::from_variant(&Command::Move(Vector2::new(1.0, 0.0)).to_variant()) == Some(Command::Move(Vector2::new(1.0, 0.0)));
+
+    if !ok {
+        godot_error!("   !!! Test test_derive_variant failed");
+    }
+
+    ok
+}
+
+fn test_derive_export() -> bool {
+    println!(" -- test_derive_export");
+
+    use gdnative::*;
+    use gdnative::init::{Export, PropertyHint};
+
+    #[derive(Debug, PartialEq, Export)]
+    enum Team {
+        Neutral,
+        Red,
+        Blue,
+    }
+
+    #[derive(Debug, PartialEq, Export)]
+    enum Layer {
+        Ground = 1,
+        Air,
+        Water = 8,
+    }
+
+    fn values<T: Export>() -> Vec<&'static str> {
+        match T::export_hint() {
+            PropertyHint::Enum { values } => values.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    let ok = values::<Team>() == vec!["Neutral", "Red", "Blue"]
+        && values::<Layer>() == vec!["Ground:1", "Air:2", "Water:8"]
+        && Team::from_variant(&Team::Blue.to_variant()) == Some(Team::Blue)
+        && Layer::Water.to_variant().to_i64() == 8
+        && Layer::from_variant(&Variant::from_i64(3)).is_none();
+
+    if !ok {
+        godot_error!("   !!! Test test_derive_export failed");
+    }
+
+    ok
+}
+
 godot_gdnative_init!();
 godot_nativescript_init!();
 godot_gdnative_terminate!();
+godot_type_tag_interface!();